@@ -0,0 +1,596 @@
+//! `#[derive(Command)]`, which implements `rust_begin::command::Command`, `Display` and
+//! `FromStr` for an enum. See the `command` module of `rust-begin` for what the generated code
+//! does; this crate only reads the enum and writes the impls.
+//!
+//! There are no dependencies: the enum is read straight from the token trees, which is enough
+//! for the shapes that are supported (an enum without generics, with unit, tuple and struct
+//! variants) and for pointing at the ones that are not.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+const RUNTIME: &str = "::rust_begin::command";
+
+#[proc_macro_derive(Command, attributes(command))]
+pub fn derive_command(input: TokenStream) -> TokenStream {
+    match parse_enum(input) {
+        Ok(item) => expand(&item)
+            .parse()
+            .expect("the generated impls are valid Rust"),
+        Err(e) => e.into_compile_error(),
+    }
+}
+
+struct Error {
+    span: Span,
+    message: String,
+}
+
+impl Error {
+    fn new(span: Span, message: impl Into<String>) -> Error {
+        Error {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// `::core::compile_error! { "message" }`, pointing at the offending tokens.
+    fn into_compile_error(self) -> TokenStream {
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+        let tokens = [
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("core", self.span)),
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("compile_error", self.span)),
+            TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+            TokenTree::Group(Group::new(
+                Delimiter::Brace,
+                TokenTree::Literal(message).into(),
+            )),
+        ];
+        tokens
+            .into_iter()
+            .map(|mut token| {
+                token.set_span(self.span);
+                token
+            })
+            .collect()
+    }
+}
+
+/// How variant names are spelled when there is no `rename`.
+#[derive(Clone, Copy)]
+enum Case {
+    Snake,
+    Kebab,
+    Lower,
+    Upper,
+    Pascal,
+}
+
+impl Case {
+    fn from_name(name: &str) -> Option<Case> {
+        match name {
+            "snake_case" => Some(Case::Snake),
+            "kebab-case" => Some(Case::Kebab),
+            "lowercase" => Some(Case::Lower),
+            "UPPERCASE" => Some(Case::Upper),
+            "PascalCase" => Some(Case::Pascal),
+            _ => None,
+        }
+    }
+
+    /// Spells a variant name, `r#` already stripped. A word starts at each capital, except that
+    /// a run of capitals is one word, so `HttpGet` and `HTTPGet` are both `http_get` in snake
+    /// case.
+    fn apply(self, ident: &str) -> String {
+        let chars: Vec<char> = ident.chars().collect();
+        let separated = |separator: char| {
+            let mut out = String::new();
+            for (i, &c) in chars.iter().enumerate() {
+                let starts_word = i > 0
+                    && c.is_uppercase()
+                    && (!chars[i - 1].is_uppercase()
+                        || chars.get(i + 1).is_some_and(|next| next.is_lowercase()));
+                if starts_word {
+                    out.push(separator);
+                }
+                out.extend(c.to_lowercase());
+            }
+            out
+        };
+        match self {
+            Case::Snake => separated('_'),
+            Case::Kebab => separated('-'),
+            Case::Lower => ident.to_lowercase(),
+            Case::Upper => ident.to_uppercase(),
+            Case::Pascal => ident.to_string(),
+        }
+    }
+}
+
+struct Enum {
+    ident: Ident,
+    variants: Vec<Variant>,
+}
+
+enum Shape {
+    Unit,
+    Tuple,
+    Struct,
+}
+
+struct Variant {
+    ident: Ident,
+    name: String,
+    shape: Shape,
+    fields: Vec<Field>,
+}
+
+struct Field {
+    /// `None` in tuple variants.
+    ident: Option<Ident>,
+    /// Used in error messages about a missing or invalid argument.
+    name: String,
+    ty: TokenStream,
+}
+
+/// One `key = "value"` out of `#[command(...)]`.
+struct Setting {
+    key: Ident,
+    value: String,
+    span: Span,
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+fn is_ident(token: Option<&TokenTree>, name: &str) -> bool {
+    matches!(token, Some(TokenTree::Ident(i)) if i.to_string() == name)
+}
+
+/// Reads the attributes at `tokens[*at..]`, returning the settings of the `command` ones.
+fn take_attributes(tokens: &[TokenTree], at: &mut usize) -> Result<Vec<Setting>, Error> {
+    let mut settings = Vec::new();
+    while is_punct(tokens.get(*at), '#') {
+        let Some(TokenTree::Group(group)) = tokens.get(*at + 1) else {
+            return Err(Error::new(tokens[*at].span(), "expected an attribute"));
+        };
+        *at += 2;
+        let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+        if !is_ident(inner.first(), "command") {
+            continue;
+        }
+        match inner.get(1) {
+            Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis => {
+                settings.extend(parse_settings(args)?);
+            }
+            _ => {
+                return Err(Error::new(
+                    group.span(),
+                    "expected `#[command(key = \"value\", ...)]`",
+                ))
+            }
+        }
+    }
+    Ok(settings)
+}
+
+fn parse_settings(args: &Group) -> Result<Vec<Setting>, Error> {
+    let tokens: Vec<TokenTree> = args.stream().into_iter().collect();
+    let mut settings = Vec::new();
+    for part in split_commas(&tokens) {
+        let (key, value) = match part {
+            [TokenTree::Ident(key), TokenTree::Punct(eq), TokenTree::Literal(value)]
+                if eq.as_char() == '=' =>
+            {
+                (key, value)
+            }
+            [] => continue,
+            _ => return Err(Error::new(part[0].span(), "expected `key = \"value\"`")),
+        };
+        let text = value.to_string();
+        let plain = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .filter(|text| !text.contains('\\'));
+        let Some(plain) = plain else {
+            return Err(Error::new(
+                value.span(),
+                "expected a string without escapes",
+            ));
+        };
+        settings.push(Setting {
+            key: key.clone(),
+            value: plain.to_string(),
+            span: value.span(),
+        });
+    }
+    Ok(settings)
+}
+
+/// The one `rename` among `settings`, refusing any other key.
+fn rename(settings: Vec<Setting>, what: &str) -> Result<Option<Setting>, Error> {
+    let mut found = None;
+    for setting in settings {
+        if setting.key.to_string() != "rename" {
+            return Err(Error::new(
+                setting.key.span(),
+                format!(
+                    "unknown {} attribute `{}`, expected `rename`",
+                    what, setting.key
+                ),
+            ));
+        }
+        found = Some(setting);
+    }
+    Ok(found)
+}
+
+/// A name that can be written as one word of the text syntax.
+fn check_name(name: &str, span: Span) -> Result<(), Error> {
+    if name.is_empty()
+        || name
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '#')
+    {
+        return Err(Error::new(
+            span,
+            format!("`{}` cannot be used as a name: it has to be one word", name),
+        ));
+    }
+    Ok(())
+}
+
+/// Splits at commas outside `<...>`; groups are single tokens, so their commas do not count.
+fn split_commas(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if let TokenTree::Punct(p) = token {
+            match p.as_char() {
+                '<' => depth += 1,
+                // the `>` of `->` does not close anything
+                '>' if !(i > 0 && is_punct(tokens.get(i - 1), '-')) => {
+                    depth = depth.saturating_sub(1)
+                }
+                ',' if depth == 0 => {
+                    parts.push(&tokens[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+    parts
+}
+
+/// Skips `pub`, `pub(crate)` and the like.
+fn skip_visibility(tokens: &[TokenTree], at: &mut usize) {
+    if is_ident(tokens.get(*at), "pub") {
+        *at += 1;
+        if matches!(tokens.get(*at), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis)
+        {
+            *at += 1;
+        }
+    }
+}
+
+fn parse_enum(input: TokenStream) -> Result<Enum, Error> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut at = 0;
+    let settings = take_attributes(&tokens, &mut at)?;
+    skip_visibility(&tokens, &mut at);
+
+    match tokens.get(at) {
+        Some(TokenTree::Ident(keyword)) if keyword.to_string() == "enum" => {}
+        Some(token) => {
+            return Err(Error::new(
+                token.span(),
+                "`Command` can only be derived for enums",
+            ))
+        }
+        None => return Err(Error::new(Span::call_site(), "expected an enum")),
+    }
+    let Some(TokenTree::Ident(ident)) = tokens.get(at + 1) else {
+        return Err(Error::new(tokens[at].span(), "expected the enum's name"));
+    };
+    let body = match tokens.get(at + 2) {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        Some(token) if is_punct(Some(token), '<') || is_ident(Some(token), "where") => {
+            return Err(Error::new(
+                token.span(),
+                "`Command` cannot be derived for generic enums",
+            ))
+        }
+        _ => return Err(Error::new(ident.span(), "expected the enum's variants")),
+    };
+
+    let mut case = Case::Snake;
+    for setting in settings {
+        if setting.key.to_string() != "rename_all" {
+            return Err(Error::new(
+                setting.key.span(),
+                format!(
+                    "unknown enum attribute `{}`, expected `rename_all`",
+                    setting.key
+                ),
+            ));
+        }
+        case = Case::from_name(&setting.value).ok_or_else(|| {
+            Error::new(
+                setting.span,
+                "expected one of \"snake_case\", \"kebab-case\", \"lowercase\", \"UPPERCASE\" \
+                 or \"PascalCase\"",
+            )
+        })?;
+    }
+
+    let body: Vec<TokenTree> = body.stream().into_iter().collect();
+    let mut variants: Vec<Variant> = Vec::new();
+    for part in split_commas(&body) {
+        if part.is_empty() {
+            continue;
+        }
+        let variant = parse_variant(part, case)?;
+        if variants.iter().any(|other| other.name == variant.name) {
+            return Err(Error::new(
+                variant.ident.span(),
+                format!("two variants are named `{}`", variant.name),
+            ));
+        }
+        variants.push(variant);
+    }
+    if variants.is_empty() {
+        return Err(Error::new(
+            ident.span(),
+            "`Command` cannot be derived for an enum without variants",
+        ));
+    }
+    Ok(Enum {
+        ident: ident.clone(),
+        variants,
+    })
+}
+
+fn parse_variant(tokens: &[TokenTree], case: Case) -> Result<Variant, Error> {
+    let mut at = 0;
+    let settings = take_attributes(tokens, &mut at)?;
+    let Some(TokenTree::Ident(ident)) = tokens.get(at) else {
+        return Err(Error::new(
+            tokens[at.min(tokens.len() - 1)].span(),
+            "expected a variant",
+        ));
+    };
+    let (name, span) = match rename(settings, "variant")? {
+        Some(setting) => (setting.value, setting.span),
+        None => {
+            let raw = ident.to_string();
+            (case.apply(raw.trim_start_matches("r#")), ident.span())
+        }
+    };
+    check_name(&name, span)?;
+
+    let (shape, fields) = match tokens.get(at + 1) {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            (Shape::Tuple, parse_fields(group, false)?)
+        }
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            (Shape::Struct, parse_fields(group, true)?)
+        }
+        // a discriminant, `= 3`, changes nothing
+        _ => (Shape::Unit, Vec::new()),
+    };
+    Ok(Variant {
+        ident: ident.clone(),
+        name,
+        shape,
+        fields,
+    })
+}
+
+fn parse_fields(group: &Group, named: bool) -> Result<Vec<Field>, Error> {
+    let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+    let mut fields = Vec::new();
+    for (index, part) in split_commas(&tokens).into_iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        let mut at = 0;
+        let settings = take_attributes(part, &mut at)?;
+        skip_visibility(part, &mut at);
+        let ident = if named {
+            match (part.get(at), part.get(at + 1)) {
+                (Some(TokenTree::Ident(ident)), colon) if is_punct(colon, ':') => {
+                    at += 2;
+                    Some(ident.clone())
+                }
+                _ => return Err(Error::new(part[0].span(), "expected a field name")),
+            }
+        } else {
+            None
+        };
+        if at >= part.len() {
+            return Err(Error::new(part[0].span(), "expected a type"));
+        }
+        let name = match rename(settings, "field")? {
+            Some(setting) => {
+                check_name(&setting.value, setting.span)?;
+                setting.value
+            }
+            None => match &ident {
+                Some(ident) => ident.to_string().trim_start_matches("r#").to_string(),
+                None => index.to_string(),
+            },
+        };
+        fields.push(Field {
+            ident,
+            name,
+            ty: part[at..].iter().cloned().collect(),
+        });
+    }
+    Ok(fields)
+}
+
+/// `Enum::Variant`, `Enum::Variant(__f0, __f1)` or `Enum::Variant { a: __f0, b: __f1 }`.
+fn pattern(item: &Enum, variant: &Variant, bind: bool) -> String {
+    let path = format!("{}::{}", item.ident, variant.ident);
+    let binding = |i: usize| {
+        if bind {
+            format!("__f{}", i)
+        } else {
+            "_".to_string()
+        }
+    };
+    match variant.shape {
+        Shape::Unit => path,
+        Shape::Tuple => {
+            let fields: Vec<String> = (0..variant.fields.len()).map(binding).collect();
+            format!("{}({})", path, fields.join(", "))
+        }
+        Shape::Struct => {
+            let fields: Vec<String> = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| format!("{}: {}", field.ident.as_ref().unwrap(), binding(i)))
+                .collect();
+            format!("{} {{ {} }}", path, fields.join(", "))
+        }
+    }
+}
+
+/// `Enum::Variant(a, b)` or `Enum::Variant { x: a, y: b }` built from one expression per field.
+fn construct(item: &Enum, variant: &Variant, field: impl Fn(&Field) -> String) -> String {
+    let path = format!("{}::{}", item.ident, variant.ident);
+    match variant.shape {
+        Shape::Unit => path,
+        Shape::Tuple => {
+            let fields: Vec<String> = variant.fields.iter().map(field).collect();
+            format!("{}({})", path, fields.join(", "))
+        }
+        Shape::Struct => {
+            let fields: Vec<String> = variant
+                .fields
+                .iter()
+                .map(|f| format!("{}: {}", f.ident.as_ref().unwrap(), field(f)))
+                .collect();
+            format!("{} {{ {} }}", path, fields.join(", "))
+        }
+    }
+}
+
+fn expand(item: &Enum) -> String {
+    let rt = RUNTIME;
+    let name = item.ident.to_string();
+    let names: Vec<String> = item
+        .variants
+        .iter()
+        .map(|v| format!("{:?}", v.name))
+        .collect();
+
+    let mut index_arms = String::new();
+    let mut parse_arms = String::new();
+    let mut write_arms = String::new();
+    let mut encode_arms = String::new();
+    let mut decode_arms = String::new();
+    for (i, variant) in item.variants.iter().enumerate() {
+        index_arms += &format!("{} => {},\n", pattern(item, variant, false), i);
+
+        let parsed = construct(item, variant, |field| {
+            format!(
+                "<{} as {rt}::Field>::parse_field(__tokens, {:?})?",
+                field.ty, field.name
+            )
+        });
+        parse_arms += &format!("{} => ::core::result::Result::Ok({}),\n", i, parsed);
+
+        let mut write = format!("__f.write_str({:?})?;\n", variant.name);
+        let mut encode = format!("{rt}::Field::encode_field(&{}u64, __out);\n", i);
+        for j in 0..variant.fields.len() {
+            write += &format!(
+                "__f.write_str(\" \")?;\n{rt}::Field::write_field(__f{}, __f)?;\n",
+                j
+            );
+            encode += &format!("{rt}::Field::encode_field(__f{}, __out);\n", j);
+        }
+        let bound = pattern(item, variant, true);
+        write_arms += &format!(
+            "{} => {{\n{}::core::result::Result::Ok(())\n}}\n",
+            bound, write
+        );
+        encode_arms += &format!("{} => {{\n{}}}\n", bound, encode);
+
+        let decoded = construct(item, variant, |field| {
+            format!("<{} as {rt}::Field>::decode_field(__input)?", field.ty)
+        });
+        decode_arms += &format!("{}u64 => ::core::result::Result::Ok({}),\n", i, decoded);
+    }
+
+    format!(
+        r#"
+#[automatically_derived]
+impl {rt}::Command for {name} {{
+    const VARIANTS: &'static [&'static str] = &[{names}];
+
+    fn variant_index(&self) -> usize {{
+        match self {{
+            {index_arms}
+        }}
+    }}
+
+    fn parse_tokens(
+        __tokens: &mut {rt}::Tokens<'_>,
+    ) -> ::core::result::Result<Self, {rt}::ParseError> {{
+        match __tokens.variant(<Self as {rt}::Command>::VARIANTS)? {{
+            {parse_arms}
+            _ => ::core::unreachable!(),
+        }}
+    }}
+
+    fn write_tokens(&self, __f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {{
+        match self {{
+            {write_arms}
+        }}
+    }}
+
+    fn encode(&self, __out: &mut ::std::vec::Vec<u8>) {{
+        match self {{
+            {encode_arms}
+        }}
+    }}
+
+    fn decode(__input: &mut &[u8]) -> ::core::result::Result<Self, {rt}::DecodeError> {{
+        match <u64 as {rt}::Field>::decode_field(__input)? {{
+            {decode_arms}
+            __tag => ::core::result::Result::Err({rt}::DecodeError::UnknownVariant(__tag)),
+        }}
+    }}
+}}
+
+#[automatically_derived]
+impl ::core::fmt::Display for {name} {{
+    fn fmt(&self, __f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {{
+        <Self as {rt}::Command>::write_tokens(self, __f)
+    }}
+}}
+
+#[automatically_derived]
+impl ::core::str::FromStr for {name} {{
+    type Err = {rt}::ParseError;
+
+    fn from_str(__text: &str) -> ::core::result::Result<Self, Self::Err> {{
+        <Self as {rt}::Command>::parse_command(__text)
+    }}
+}}
+"#,
+        names = names.join(", "),
+    )
+}
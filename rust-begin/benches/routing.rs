@@ -0,0 +1,122 @@
+//! Loads a full-table-sized set of routes into a `RoutingTable` and times the operations.
+//!
+//! Run with `cargo bench --bench routing`. The prefixes are random but shaped like a real
+//! IPv4 table (mostly /24s, then /22 and /23 and a tail of shorter ones), seeded so every run
+//! measures the same table.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rust_begin::enums::{IpAddr, Ipv6Addr};
+use rust_begin::net::{IpNetwork, RoutingTable};
+
+const V4_ROUTES: usize = 1_000_000;
+const V6_ROUTES: usize = 200_000;
+const LOOKUPS: usize = 1_000_000;
+
+/// xorshift64, good enough to spread prefixes around.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn v4(&mut self) -> IpAddr {
+        let [a, b, c, d] = (self.next() as u32).to_be_bytes();
+        IpAddr::V4(a, b, c, d)
+    }
+
+    fn v6(&mut self) -> IpAddr {
+        // keep it inside 2000::/3 like the global unicast table
+        let bits = (u128::from(self.next()) << 64) | u128::from(self.next());
+        IpAddr::V6(Ipv6Addr::from_bits(bits >> 3 | 1 << 125))
+    }
+
+    fn v4_prefix_len(&mut self) -> u8 {
+        match self.next() % 100 {
+            0..=59 => 24,
+            60..=74 => 23,
+            75..=86 => 22,
+            n => 8 + (n % 14) as u8,
+        }
+    }
+
+    fn v6_prefix_len(&mut self) -> u8 {
+        match self.next() % 100 {
+            0..=49 => 48,
+            50..=69 => 32,
+            70..=84 => 44,
+            n => 29 + (n % 36) as u8,
+        }
+    }
+}
+
+fn report(name: &str, ops: usize, elapsed: Duration) {
+    println!(
+        "{:<24} {:>9} ops {:>10.1} ms {:>8.0} ns/op",
+        name,
+        ops,
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_nanos() as f64 / ops as f64
+    );
+}
+
+fn main() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    let mut networks = Vec::with_capacity(V4_ROUTES + V6_ROUTES);
+    for _ in 0..V4_ROUTES {
+        let len = rng.v4_prefix_len();
+        networks.push(IpNetwork::new(rng.v4(), len).unwrap());
+    }
+    for _ in 0..V6_ROUTES {
+        let len = rng.v6_prefix_len();
+        networks.push(IpNetwork::new(rng.v6(), len).unwrap());
+    }
+    let addrs: Vec<IpAddr> = (0..LOOKUPS)
+        .map(|i| if i % 5 == 0 { rng.v6() } else { rng.v4() })
+        .collect();
+
+    let mut table = RoutingTable::new();
+    let start = Instant::now();
+    for (i, network) in networks.iter().enumerate() {
+        table.insert(*network, i as u32);
+    }
+    report("insert", networks.len(), start.elapsed());
+    println!("{:<24} {:>9} distinct routes", "", table.len());
+
+    let start = Instant::now();
+    let mut found = 0usize;
+    for addr in &addrs {
+        if black_box(table.lookup(addr)).is_some() {
+            found += 1;
+        }
+    }
+    report("lookup", addrs.len(), start.elapsed());
+    println!("{:<24} {:>9} matched", "", found);
+
+    let start = Instant::now();
+    for (i, network) in networks.iter().enumerate().step_by(10) {
+        black_box(table.update(network, i as u32 + 1));
+    }
+    report("update", networks.len() / 10, start.elapsed());
+
+    let start = Instant::now();
+    let dump = table
+        .iter()
+        .map(|(network, hop)| format!("{} via {}\n", network, hop))
+        .collect::<String>();
+    report("dump", table.len(), start.elapsed());
+    black_box(dump);
+
+    let start = Instant::now();
+    for network in &networks {
+        table.remove(network);
+    }
+    report("remove", networks.len(), start.elapsed());
+    assert!(table.is_empty());
+}
@@ -0,0 +1,94 @@
+//! Replacing a file whole, so that a crash part way through a save leaves the old contents.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Writes `bytes` to a new file next to `path`, syncs it, renames it over `path` and syncs the
+/// directory, so that the rename itself is on disk when this returns.
+///
+/// The temporary file is named `path` plus `.<pid>-<count>.tmp`, so two saves of the same file
+/// never write to one temporary file, and it is removed again if anything fails before the
+/// rename.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut temp = PathBuf::from(path);
+    temp.as_mut_os_string()
+        .push(format!(".{}-{}.tmp", process::id(), count));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)?;
+    let written = file
+        .write_all(bytes)
+        .and_then(|()| file.sync_all())
+        .and_then(|()| fs::rename(&temp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    sync_dir(path)
+}
+
+/// Temporary files `write_atomic` left next to `path` when it did not finish, named `path`
+/// plus `.<pid>-<count>.tmp`, and `path` plus `.tmp` as older saves named theirs. Other files
+/// that merely start with the name, like `users.db.notes.tmp`, are not included. Sorted by
+/// name.
+pub(crate) fn leftover_temp_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let (Some(dir), Some(name)) = (parent(path), path.file_name()) else {
+        return Ok(Vec::new());
+    };
+    let Some(name) = name.to_str() else {
+        return Ok(Vec::new());
+    };
+    let prefix = format!("{}.", name);
+    let mut temps = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        let is_temp = file_name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix("tmp"))
+            .is_some_and(|middle| middle.is_empty() || is_pid_and_count(middle));
+        if is_temp && entry.file_type()?.is_file() {
+            temps.push(entry.path());
+        }
+    }
+    temps.sort();
+    Ok(temps)
+}
+
+/// Whether `middle` is `<pid>-<count>.` as `write_atomic` names its temporary files.
+fn is_pid_and_count(middle: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    middle
+        .strip_suffix('.')
+        .and_then(|middle| middle.split_once('-'))
+        .is_some_and(|(pid, count)| digits(pid) && digits(count))
+}
+
+/// The directory `path` is in, `.` for a bare file name.
+fn parent(path: &Path) -> Option<&Path> {
+    match path.parent() {
+        Some(dir) if dir.as_os_str().is_empty() => Some(Path::new(".")),
+        dir => dir,
+    }
+}
+
+/// Makes a rename in the directory of `path` durable. Directories can only be opened and
+/// synced like this on Unix; elsewhere the rename is left to the file system.
+fn sync_dir(path: &Path) -> io::Result<()> {
+    if cfg!(unix) {
+        if let Some(dir) = parent(path) {
+            File::open(dir)?.sync_all()?;
+        }
+    }
+    Ok(())
+}
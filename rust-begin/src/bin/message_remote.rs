@@ -0,0 +1,111 @@
+//! Runs a `Message` server on loopback, or sends commands to one.
+//!
+//! `message-remote serve` listens on 127.0.0.1 until it is killed. `message-remote send` takes
+//! commands in the REPL syntax, each argument one command or else one per line on stdin, and
+//! prints the server's reply to each.
+
+use std::env;
+use std::io::{self, BufRead};
+use std::process;
+use std::time::Duration;
+
+use rust_begin::message::{parse_script, Client, ClientError, Server};
+
+const DEFAULT_PORT: u16 = 7878;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: message-remote serve [--port <n>] [--max-connections <n>] [--timeout <secs>]"
+    );
+    eprintln!("       message-remote send [--port <n>] [<command>...]");
+    process::exit(2);
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(format!("not a number: {}", value)))
+}
+
+fn serve(mut args: &[&str]) {
+    let mut port = DEFAULT_PORT;
+    let mut builder = Server::builder();
+    loop {
+        match args {
+            [] => break,
+            ["--port", n, rest @ ..] => {
+                port = number(n);
+                args = rest;
+            }
+            ["--max-connections", n, rest @ ..] => {
+                builder = builder.max_connections(number(n));
+                args = rest;
+            }
+            ["--timeout", secs, rest @ ..] => {
+                builder = builder.read_timeout(Some(Duration::from_secs(number(secs))));
+                args = rest;
+            }
+            _ => usage(),
+        }
+    }
+    let server = builder
+        .bind(("127.0.0.1", port))
+        .unwrap_or_else(|e| fail(format!("could not listen on port {}: {}", port, e)));
+    if let Ok(addr) = server.local_addr() {
+        println!("listening on {}", addr);
+    }
+    if let Err(e) = server.run() {
+        fail(e);
+    }
+}
+
+fn send(args: &[&str]) {
+    let (port, commands) = match args {
+        ["--port", n, rest @ ..] => (number(n), rest),
+        [first, ..] if first.starts_with("--") => usage(),
+        rest => (DEFAULT_PORT, rest),
+    };
+    let lines: Vec<String> = if commands.is_empty() {
+        io::stdin()
+            .lock()
+            .lines()
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|e| fail(e))
+    } else {
+        commands.iter().map(|command| command.to_string()).collect()
+    };
+    let messages = parse_script(&lines.join("\n")).unwrap_or_else(|e| fail(e));
+
+    let mut client = Client::connect(("127.0.0.1", port))
+        .unwrap_or_else(|e| fail(format!("could not connect to port {}: {}", port, e)));
+    let mut failed = false;
+    for message in &messages {
+        match client.send(message) {
+            Ok(outcome) => println!("{}: {:?}", message, outcome),
+            Err(e @ (ClientError::Rejected(_) | ClientError::Malformed(_))) => {
+                failed = true;
+                eprintln!("{}: {}", message, e);
+            }
+            Err(e) => fail(format!("{}: {}", message, e)),
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["serve", rest @ ..] => serve(rest),
+        ["send", rest @ ..] => send(rest),
+        _ => usage(),
+    }
+}
@@ -0,0 +1,173 @@
+//! An interactive prompt that parses each line as a `Message` and calls it on one `DrawState`.
+//!
+//! Besides the commands themselves (`move 3 -4`, `write "hello"`, `color 255 0 128`, `quit`):
+//!
+//! - `:state` shows the drawing state, `:reset` starts a fresh one
+//! - `:history` lists earlier commands, `!!` runs the last one again and `!n` runs number `n`
+//! - `:help` lists all of this
+//!
+//! With `--history <file>` the history is loaded from the file at start and every new command
+//! is appended to it.
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use rust_begin::enums::Message;
+use rust_begin::message::{DrawState, Outcome};
+
+const HELP: &str = "\
+commands:
+  move <x> <y>          move the cursor by x and y
+  write \"<text>\"        append text to the buffer
+  color <r> <g> <b>     set the pen color, each 0 to 255
+  quit                  stop and leave
+prompt:
+  :state  :reset  :history  !!  !<n>  :help";
+
+fn usage() -> ! {
+    eprintln!("usage: message-repl [--history <file>]");
+    process::exit(2);
+}
+
+struct Repl {
+    state: DrawState,
+    history: Vec<String>,
+    history_file: Option<File>,
+}
+
+impl Repl {
+    fn remember(&mut self, line: &str) {
+        self.history.push(line.to_string());
+        if let Some(file) = &mut self.history_file {
+            if let Err(e) = writeln!(file, "{}", line) {
+                eprintln!("could not save history: {}", e);
+                self.history_file = None;
+            }
+        }
+    }
+
+    /// Looks up `!!` and `!n`. An entry that is itself a `!` line, which only a hand-edited
+    /// history file can hold, is refused rather than followed.
+    fn recall(&self, line: &str) -> Result<String, String> {
+        let entry = match &line[1..] {
+            "!" => self.history.last(),
+            n => {
+                let n: usize = n.parse().map_err(|_| format!("no such entry: {}", line))?;
+                n.checked_sub(1).and_then(|i| self.history.get(i))
+            }
+        };
+        let entry = entry.ok_or_else(|| format!("no such entry: {}", line))?;
+        if entry.trim_start().starts_with('!') {
+            return Err(format!("{} recalls another entry: {}", line, entry));
+        }
+        Ok(entry.clone())
+    }
+
+    /// Handles one line of input; false once the program has quit.
+    fn handle(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        match line {
+            "" => return true,
+            ":help" => println!("{}", HELP),
+            ":state" => println!("{:?}", self.state),
+            ":reset" => {
+                self.state = DrawState::new();
+                println!("{:?}", self.state);
+            }
+            ":history" => {
+                for (i, entry) in self.history.iter().enumerate() {
+                    println!("{:>4}  {}", i + 1, entry);
+                }
+            }
+            _ if line.starts_with('!') => match self.recall(line) {
+                Ok(entry) => {
+                    println!("{}", entry);
+                    return self.handle(&entry);
+                }
+                Err(e) => eprintln!("{}", e),
+            },
+            _ if line.starts_with(':') => eprintln!("unknown prompt command {}, try :help", line),
+            _ => {
+                self.remember(line);
+                return self.run(line);
+            }
+        }
+        true
+    }
+
+    fn run(&mut self, line: &str) -> bool {
+        let message: Message = match line.parse() {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("  {}", line);
+                eprintln!("  {}", e.caret());
+                eprintln!("error at column {}: {}", e.column, e.kind);
+                return true;
+            }
+        };
+        match message.call(&mut self.state) {
+            Ok(Outcome::Stopped) => return false,
+            Ok(Outcome::Moved { to, .. }) => println!("cursor at {:?}", to),
+            Ok(Outcome::Wrote { .. }) => println!("text is {:?}", self.state.text),
+            Ok(Outcome::ColorChanged { to, .. }) => println!("color is {:?}", to),
+            Err(e) => eprintln!("{}: {}", message, e),
+        }
+        true
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let mut repl = Repl {
+        state: DrawState::new(),
+        history: Vec::new(),
+        history_file: None,
+    };
+    match args.as_slice() {
+        [] => {}
+        ["--history", path] => {
+            match fs::read_to_string(path) {
+                Ok(text) => repl.history = text.lines().map(str::to_string).collect(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    eprintln!("could not read {}: {}", path, e);
+                    process::exit(1);
+                }
+            }
+            match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => repl.history_file = Some(file),
+                Err(e) => {
+                    eprintln!("could not open {}: {}", path, e);
+                    process::exit(1);
+                }
+            }
+        }
+        _ => usage(),
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+        match lines.next() {
+            Some(Ok(line)) => {
+                if !repl.handle(&line) {
+                    break;
+                }
+            }
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+            None => {
+                println!();
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Looks inside a file written by `UserRegistry::save`, and repairs it.
+//!
+//! `user-store inspect <file>` lists the users and everything wrong with the file, and exits
+//! with 1 if `repair` would change it. `user-store repair <file>` leaves out what cannot be
+//! read, migrates the rest to the current version and writes it back, keeping the old file
+//! as `<file>.bak`.
+
+use std::env;
+use std::process;
+use std::time::SystemTime;
+
+use rust_begin::users::{inspect_store, repair_store, StoreReport, STORE_VERSION};
+
+fn usage() -> ! {
+    eprintln!("usage: user-store inspect <file>");
+    eprintln!("       user-store repair <file>");
+    process::exit(2);
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn list(report: &StoreReport) {
+    let registry = &report.registry;
+    let now = SystemTime::now();
+    for user in registry.iter() {
+        let mut notes = vec![if user.active { "active" } else { "inactive" }];
+        match registry.credential(&user.username) {
+            Some(credential) if credential.locked_until(now).is_some() => notes.push("locked"),
+            Some(_) => notes.push("password"),
+            None => notes.push("no password"),
+        }
+        println!(
+            "  {:<20} {:<32} {:>5}  {}",
+            user.username,
+            user.email,
+            user.sign_in_count,
+            notes.join(", ")
+        );
+    }
+}
+
+fn inspect(path: &str) {
+    let report = inspect_store(path).unwrap_or_else(|e| fail(e));
+    print!("{}: version {}", path, report.version);
+    if report.needs_migration() {
+        print!(", repair migrates it to {}", STORE_VERSION);
+    }
+    println!();
+    println!("{} users", report.registry.len());
+    list(&report);
+    if !report.problems.is_empty() {
+        println!("problems:");
+        for problem in &report.problems {
+            println!("  {}", problem);
+        }
+    }
+    for temp in &report.temp_files {
+        println!("{} is left from a save that did not finish", temp.display());
+    }
+    if !report.is_clean() {
+        process::exit(1);
+    }
+}
+
+fn repair(path: &str) {
+    let report = repair_store(path).unwrap_or_else(|e| fail(e));
+    if report.is_clean() {
+        println!("{}: nothing to repair", path);
+        return;
+    }
+    for problem in &report.problems {
+        println!("{}", problem);
+    }
+    for temp in &report.temp_files {
+        println!("removed {}", temp.display());
+    }
+    if !report.problems.is_empty() || report.needs_migration() {
+        println!(
+            "{}: wrote {} users in version {}, the old file is {}.bak",
+            path,
+            report.registry.len(),
+            STORE_VERSION,
+            path
+        );
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args[..] {
+        ["inspect", path] => inspect(path),
+        ["repair", path] => repair(path),
+        _ => usage(),
+    }
+}
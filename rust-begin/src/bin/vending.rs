@@ -0,0 +1,193 @@
+//! A vending machine simulation driven from the prompt.
+//!
+//! The machine starts stocked with a few snacks and a float of coins for change, and refuses
+//! pennies. Coins are written the way `Coin` prints them (`quarter alaska`, `dime`), so
+//! `insert quarter alaska` then `select A1` buys something. `:help` lists everything.
+
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use rust_begin::change::CoinInventory;
+use rust_begin::enums::{Coin, CoinKind, UsState};
+use rust_begin::vending::{dollars, Event, Outcome, State, VendingMachine};
+
+const HELP: &str = "\
+customer:
+  insert <coin>          put a coin in, like `insert dime` or `insert quarter alaska`
+  select <slot>          buy from a slot, like `select A1`
+  refund                 get the coins back
+operator:
+  service                open service mode
+  restock <slot> <n>     add n to a slot
+  load <n> <coin>        put n coins in the till
+  collect                empty the till
+  done                   close service mode
+prompt:
+  :status  :log  :help  :quit";
+
+fn machine() -> VendingMachine {
+    VendingMachine::builder()
+        .slot("A1", "Pretzels", 125, 3)
+        .slot("A2", "Trail mix", 150, 2)
+        .slot("B1", "Mints", 65, 1)
+        .slot("B2", "Gum", 40, 0)
+        .coins(Coin::Quarter(UsState::Alabama), 4)
+        .coins(Coin::Dime, 5)
+        .coins(Coin::Nickel, 2)
+        .reject(CoinKind::Penny)
+        .build()
+}
+
+fn status(machine: &VendingMachine) {
+    match machine.state() {
+        State::Idle => println!("idle"),
+        State::Paying { credit } => println!("credit {}", dollars(credit)),
+        State::Service => println!("in service"),
+    }
+    for slot in machine.slots() {
+        let left = match slot.count {
+            0 => "sold out".to_string(),
+            n => format!("{} left", n),
+        };
+        println!(
+            "  {:<4}{:<12}{:>7}  {}",
+            slot.code,
+            slot.product,
+            dollars(slot.price),
+            left
+        );
+    }
+    let till: Vec<String> = machine
+        .till()
+        .iter()
+        .map(|(coin, count)| format!("{} x {}", count, coin))
+        .collect();
+    println!(
+        "  till {} ({})",
+        dollars(machine.till().total_cents() as u32),
+        till.join(", ")
+    );
+}
+
+/// Reads a line into an event, or says what is wrong with it.
+fn parse(line: &str) -> Result<Event, String> {
+    let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    let coin = |text: &str| {
+        text.parse::<Coin>()
+            .map_err(|e| format!("not a coin: {}: {}", text, e.kind))
+    };
+    let count = |text: &str| {
+        text.parse::<u32>()
+            .map_err(|_| format!("not a count: {}", text))
+    };
+    match (word, rest) {
+        ("insert", coin_text) => Ok(Event::Insert(coin(coin_text)?)),
+        ("select", slot) if !slot.is_empty() => Ok(Event::Select(slot.to_string())),
+        ("refund", "") => Ok(Event::Refund),
+        ("service", "") => Ok(Event::OpenService),
+        ("done", "") => Ok(Event::CloseService),
+        ("collect", "") => Ok(Event::Collect),
+        ("restock", args) => match args.split_whitespace().collect::<Vec<_>>()[..] {
+            [slot, n] => Ok(Event::Restock {
+                slot: slot.to_string(),
+                count: count(n)?,
+            }),
+            _ => Err("usage: restock <slot> <n>".to_string()),
+        },
+        ("load", args) => {
+            let (n, coin_text) = args.split_once(' ').ok_or("usage: load <n> <coin>")?;
+            Ok(Event::Load {
+                count: count(n)?,
+                coin: coin(coin_text.trim())?,
+            })
+        }
+        _ => Err(format!("unknown command {}, try :help", line)),
+    }
+}
+
+fn coins(coins: &[Coin]) -> String {
+    if coins.is_empty() {
+        return "nothing".to_string();
+    }
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn till(till: &CoinInventory) -> String {
+    if till.is_empty() {
+        return "nothing".to_string();
+    }
+    till.iter()
+        .map(|(coin, count)| format!("{} x {}", count, coin))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn run(machine: &mut VendingMachine, line: &str) {
+    let event = match parse(line) {
+        Ok(event) => event,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    match machine.handle(event) {
+        Ok(Outcome::Credited { credit }) => println!("credit {}", dollars(credit)),
+        Ok(Outcome::Sold(sale)) => {
+            println!("here is your {}", sale.product);
+            if !sale.change.is_empty() {
+                println!("change: {}", coins(&sale.change));
+            }
+        }
+        Ok(Outcome::Refunded(refund)) => println!("refunded {}", coins(&refund)),
+        Ok(Outcome::ServiceOpened) => println!("service mode"),
+        Ok(Outcome::ServiceClosed) => println!("back in operation"),
+        Ok(Outcome::Restocked { slot, count }) => println!("added {} to {}", count, slot),
+        Ok(Outcome::Loaded { coin, count }) => println!("loaded {} x {}", count, coin),
+        Ok(Outcome::Collected(collected)) => println!("collected {}", till(&collected)),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn main() {
+    if std::env::args().len() > 1 {
+        eprintln!("usage: vending");
+        process::exit(2);
+    }
+    let mut machine = machine();
+    status(&machine);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+            None => {
+                println!();
+                break;
+            }
+        };
+        match line.trim() {
+            "" => {}
+            ":quit" => break,
+            ":help" => println!("{}", HELP),
+            ":status" => status(&machine),
+            ":log" => {
+                for transaction in machine.log() {
+                    println!("{}", transaction);
+                }
+            }
+            line => run(&mut machine, line),
+        }
+    }
+}
@@ -0,0 +1,517 @@
+//! Making change, the reverse of `value_in_cents`: which coins add up to an amount.
+//!
+//! For the US coins the greedy way (as many of the largest coin as fit, then of the next one,
+//! and so on) always uses the fewest coins, but that does not hold for every set. With coins
+//! of 1, 3 and 4 greedy pays 6 as 4 + 1 + 1 where 3 + 3 will do. `Denominations` finds out
+//! whether greedy is safe for its set (whether the set is *canonical*) and uses dynamic
+//! programming when it is not, or when only so many of each coin are available.
+//!
+//! `CoinInventory` holds actual `Coin`s, such as a till, and pays amounts out of them.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::enums::{Coin, CoinKind};
+
+/// The largest amount the dynamic programming here takes, about six hundred and fifty dollars.
+/// It keeps a table entry per cent up to the amount, of up to 16 bytes, so a table is at most
+/// a megabyte. When coins are limited it also keeps a bit per cent for each bundle of coins,
+/// no more than 17 bundles and so about 140 kilobytes per denomination.
+pub const MAX_AMOUNT: u32 = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeError {
+    /// `Denominations::new` was given no values.
+    NoDenominations,
+    /// A coin worth nothing.
+    ZeroDenomination,
+    /// No combination of the coins available adds up to the amount.
+    CannotMakeChange { amount: u32 },
+    /// More than `MAX_AMOUNT`, for a method that needs a table that long.
+    AmountTooLarge { amount: u32 },
+}
+
+impl fmt::Display for ChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeError::NoDenominations => write!(f, "there are no denominations"),
+            ChangeError::ZeroDenomination => write!(f, "a denomination is worth nothing"),
+            ChangeError::CannotMakeChange { amount } => {
+                write!(f, "cannot make change for {} cents", amount)
+            }
+            ChangeError::AmountTooLarge { amount } => write!(
+                f,
+                "{} cents is more than the {} change can be worked out for",
+                amount, MAX_AMOUNT
+            ),
+        }
+    }
+}
+
+impl Error for ChangeError {}
+
+/// A set of coin values, largest first. Counts of coins passed to and returned from its
+/// methods line up with `values()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denominations {
+    values: Vec<u32>,
+    canonical: bool,
+}
+
+impl Denominations {
+    /// The values in any order; duplicates are dropped.
+    pub fn new(values: &[u32]) -> Result<Denominations, ChangeError> {
+        if values.is_empty() {
+            return Err(ChangeError::NoDenominations);
+        }
+        if values.contains(&0) {
+            return Err(ChangeError::ZeroDenomination);
+        }
+        let mut values = values.to_vec();
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        values.dedup();
+        let canonical = is_canonical(&values);
+        Ok(Denominations { values, canonical })
+    }
+
+    /// Quarter, dime, nickel and penny.
+    pub fn us() -> Denominations {
+        let values: Vec<u32> = CoinKind::ALL
+            .iter()
+            .rev()
+            .map(|kind| u32::from(kind.value_in_cents()))
+            .collect();
+        Denominations::new(&values).expect("the US coins are valid denominations")
+    }
+
+    pub fn values(&self) -> &[u32] {
+        &self.values
+    }
+
+    /// Whether greedy gives the fewest coins for every amount. Sets without a coin of 1 are
+    /// never taken to be canonical.
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// What the coins in `counts` add up to.
+    pub fn total(&self, counts: &[u32]) -> u64 {
+        self.values
+            .iter()
+            .zip(counts)
+            .map(|(&value, &count)| u64::from(value) * u64::from(count))
+            .sum()
+    }
+
+    /// As many of each coin as fit, largest first. `None` if that leaves a remainder.
+    pub fn greedy(&self, amount: u32) -> Option<Vec<u32>> {
+        let (counts, rest) = greedy(&self.values, u64::from(amount));
+        (rest == 0).then(|| counts.into_iter().map(|count| count as u32).collect())
+    }
+
+    /// The fewest coins adding up to `amount`. A set that is not canonical takes amounts up to
+    /// `MAX_AMOUNT`.
+    pub fn optimal(&self, amount: u32) -> Result<Vec<u32>, ChangeError> {
+        let cannot = ChangeError::CannotMakeChange { amount };
+        if self.canonical {
+            return self.greedy(amount).ok_or(cannot);
+        }
+        let amount = check_amount(amount)?;
+        // fewest[a] is the fewest coins for a, and last[a] the coin taken last to get there
+        let mut fewest = vec![u32::MAX; amount + 1];
+        let mut last = vec![0; amount + 1];
+        fewest[0] = 0;
+        for a in 1..=amount {
+            for (i, &value) in self.values.iter().enumerate() {
+                let Some(before) = a.checked_sub(value as usize) else {
+                    continue;
+                };
+                if fewest[before] != u32::MAX && fewest[before] + 1 < fewest[a] {
+                    fewest[a] = fewest[before] + 1;
+                    last[a] = i;
+                }
+            }
+        }
+        if fewest[amount] == u32::MAX {
+            return Err(cannot);
+        }
+        let mut counts = vec![0; self.values.len()];
+        let mut a = amount;
+        while a > 0 {
+            counts[last[a]] += 1;
+            a -= self.values[last[a]] as usize;
+        }
+        Ok(counts)
+    }
+
+    /// The fewest coins adding up to `amount` with no more than `limits[i]` of coin `i`, for
+    /// amounts up to `MAX_AMOUNT`.
+    ///
+    /// # Panics
+    ///
+    /// If `limits` is not as long as `values()`.
+    pub fn optimal_with_limits(
+        &self,
+        amount: u32,
+        limits: &[u32],
+    ) -> Result<Vec<u32>, ChangeError> {
+        assert_eq!(
+            limits.len(),
+            self.values.len(),
+            "one limit per denomination"
+        );
+        let cannot = ChangeError::CannotMakeChange { amount };
+        let amount = check_amount(amount)?;
+        // Bounded knapsack: `k` of a coin become bundles of 1, 2, 4, ... coins, each taken
+        // at most once, which between them still make every count up to `k`.
+        let mut bundles = Vec::new();
+        for (i, (&value, &limit)) in self.values.iter().zip(limits).enumerate() {
+            let mut left = limit.min((amount / value as usize) as u32);
+            let mut size = 1;
+            while left > 0 {
+                let take = size.min(left);
+                bundles.push((i, take, value as usize * take as usize));
+                left -= take;
+                size *= 2;
+            }
+        }
+        let mut fewest = vec![u32::MAX; amount + 1];
+        fewest[0] = 0;
+        // bit a of row b: bundle b is part of the best way found for a after considering it
+        let row = (amount + 1).div_ceil(64);
+        let mut taken = vec![0u64; row * bundles.len()];
+        let is_taken =
+            |taken: &[u64], b: usize, a: usize| taken[b * row + a / 64] >> (a % 64) & 1 == 1;
+        for (b, &(_, coins, worth)) in bundles.iter().enumerate() {
+            for a in (worth..=amount).rev() {
+                let before = fewest[a - worth];
+                if before != u32::MAX && before + coins < fewest[a] {
+                    fewest[a] = before + coins;
+                    taken[b * row + a / 64] |= 1 << (a % 64);
+                }
+            }
+        }
+        if fewest[amount] == u32::MAX {
+            return Err(cannot);
+        }
+        let mut counts = vec![0; self.values.len()];
+        let mut a = amount;
+        for (b, &(i, coins, worth)) in bundles.iter().enumerate().rev() {
+            if is_taken(&taken, b, a) {
+                counts[i] += coins;
+                a -= worth;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// How many combinations of coins add up to `amount`, saturating at `u128::MAX`, for
+    /// amounts up to `MAX_AMOUNT`.
+    pub fn count_ways(&self, amount: u32) -> Result<u128, ChangeError> {
+        let amount = check_amount(amount)?;
+        let mut ways = vec![0u128; amount + 1];
+        ways[0] = 1;
+        for &value in &self.values {
+            for a in value as usize..=amount {
+                ways[a] = ways[a].saturating_add(ways[a - value as usize]);
+            }
+        }
+        Ok(ways[amount])
+    }
+
+    /// Every combination of coins adding up to `amount`, those with more of the larger coins
+    /// first.
+    pub fn combinations(&self, amount: u32) -> Combinations {
+        Combinations::new(
+            self.values.clone(),
+            vec![u32::MAX; self.values.len()],
+            amount,
+        )
+    }
+
+    /// Every combination with no more than `limits[i]` of coin `i`.
+    ///
+    /// # Panics
+    ///
+    /// If `limits` is not as long as `values()`.
+    pub fn combinations_with_limits(&self, amount: u32, limits: &[u32]) -> Combinations {
+        assert_eq!(
+            limits.len(),
+            self.values.len(),
+            "one limit per denomination"
+        );
+        Combinations::new(self.values.clone(), limits.to_vec(), amount)
+    }
+}
+
+/// `amount` as a table length, if it is no more than `MAX_AMOUNT`.
+fn check_amount(amount: u32) -> Result<usize, ChangeError> {
+    if amount > MAX_AMOUNT {
+        return Err(ChangeError::AmountTooLarge { amount });
+    }
+    Ok(amount as usize)
+}
+
+/// Greedy counts for `amount` and what is left over.
+fn greedy(values: &[u32], mut amount: u64) -> (Vec<u64>, u64) {
+    let counts = values
+        .iter()
+        .map(|&value| {
+            let count = amount / u64::from(value);
+            amount %= u64::from(value);
+            count
+        })
+        .collect();
+    (counts, amount)
+}
+
+/// Pearson's test: if greedy is ever beaten, it is beaten at an amount built from the greedy
+/// counts for one less than some coin, so only O(n²) amounts need checking.
+fn is_canonical(values: &[u32]) -> bool {
+    if values.last() != Some(&1) {
+        return false;
+    }
+    let n = values.len();
+    for i in 1..n {
+        let (below, _) = greedy(values, u64::from(values[i - 1]) - 1);
+        for j in i..n {
+            // the counts for below up to coin j, one more of coin j, none of the rest
+            let mut counts = below.clone();
+            counts[j] += 1;
+            counts[j + 1..].iter_mut().for_each(|count| *count = 0);
+            let amount: u64 = counts
+                .iter()
+                .zip(values)
+                .map(|(&count, &value)| count * u64::from(value))
+                .sum();
+            let (greedy_counts, _) = greedy(values, amount);
+            if greedy_counts.iter().sum::<u64>() > counts.iter().sum::<u64>() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Combinations of coins adding up to an amount, from `Denominations::combinations`.
+#[derive(Debug, Clone)]
+pub struct Combinations {
+    values: Vec<u32>,
+    limits: Vec<u32>,
+    amount: u32,
+    counts: Vec<u32>,
+    started: bool,
+}
+
+impl Combinations {
+    fn new(values: Vec<u32>, limits: Vec<u32>, amount: u32) -> Combinations {
+        Combinations {
+            counts: vec![0; values.len()],
+            values,
+            limits,
+            amount,
+            started: false,
+        }
+    }
+
+    /// Takes as many of coins `from..` as fit in what the earlier coins leave, and returns
+    /// what is left after that.
+    fn fill(&mut self, from: usize) -> u64 {
+        let paid: u64 = self.values[..from]
+            .iter()
+            .zip(&self.counts)
+            .map(|(&value, &count)| u64::from(value) * u64::from(count))
+            .sum();
+        let mut left = u64::from(self.amount) - paid;
+        for i in from..self.values.len() {
+            let value = u64::from(self.values[i]);
+            let count = (left / value).min(u64::from(self.limits[i]));
+            self.counts[i] = count as u32;
+            left -= count * value;
+        }
+        left
+    }
+}
+
+impl Iterator for Combinations {
+    type Item = Vec<u32>;
+
+    fn next(&mut self) -> Option<Vec<u32>> {
+        loop {
+            let left = if !self.started {
+                self.started = true;
+                self.fill(0)
+            } else {
+                // one fewer of the smallest coin that can give one up, then refill the rest;
+                // the last coin only ever takes up what is left, so it is not a choice
+                let last = self.values.len() - 1;
+                let i = (0..last).rev().find(|&i| self.counts[i] > 0)?;
+                self.counts[i] -= 1;
+                self.fill(i + 1)
+            };
+            if left == 0 {
+                return Some(self.counts.clone());
+            }
+        }
+    }
+}
+
+/// Pairs the counts for `Denominations::us()` with their coins, leaving out the zeros.
+fn us_coins(counts: &[u32]) -> Vec<(CoinKind, u32)> {
+    CoinKind::ALL
+        .iter()
+        .rev()
+        .zip(counts)
+        .filter(|(_, &count)| count > 0)
+        .map(|(&kind, &count)| (kind, count))
+        .collect()
+}
+
+/// The fewest US coins adding up to `amount`, largest first.
+pub fn make_change(amount: u32) -> Vec<(CoinKind, u32)> {
+    let counts = Denominations::us()
+        .optimal(amount)
+        .expect("pennies make any amount");
+    us_coins(&counts)
+}
+
+/// Every way to make `amount` out of US coins, those with more of the larger coins first.
+pub fn all_change(amount: u32) -> impl Iterator<Item = Vec<(CoinKind, u32)>> {
+    Denominations::us()
+        .combinations(amount)
+        .map(|counts| us_coins(&counts))
+}
+
+/// Coins on hand, kept in the order they came in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoinInventory {
+    /// No zero counts, and each coin at most once.
+    coins: Vec<(Coin, u32)>,
+}
+
+impl CoinInventory {
+    pub fn new() -> CoinInventory {
+        CoinInventory::default()
+    }
+
+    pub fn add(&mut self, coin: Coin) {
+        self.add_many(coin, 1);
+    }
+
+    /// Adds `count` of `coin`, stopping at `u32::MAX` of it.
+    pub fn add_many(&mut self, coin: Coin, count: u32) {
+        let total = self.count(coin).saturating_add(count);
+        self.set_count(coin, total);
+    }
+
+    /// Adds `count` of `coin` and returns how many of it there are now, or `None`, with
+    /// nothing added, if that would be more than `u32::MAX`.
+    pub fn checked_add_many(&mut self, coin: Coin, count: u32) -> Option<u32> {
+        let total = self.count(coin).checked_add(count)?;
+        self.set_count(coin, total);
+        Some(total)
+    }
+
+    fn set_count(&mut self, coin: Coin, total: u32) {
+        if total == 0 {
+            return;
+        }
+        match self.coins.iter_mut().find(|(c, _)| *c == coin) {
+            Some((_, n)) => *n = total,
+            None => self.coins.push((coin, total)),
+        }
+    }
+
+    /// Takes one `coin` out; false if there is none.
+    pub fn remove(&mut self, coin: Coin) -> bool {
+        let Some(at) = self.coins.iter().position(|(c, _)| *c == coin) else {
+            return false;
+        };
+        self.coins[at].1 -= 1;
+        if self.coins[at].1 == 0 {
+            self.coins.remove(at);
+        }
+        true
+    }
+
+    pub fn count(&self, coin: Coin) -> u32 {
+        self.coins
+            .iter()
+            .find(|(c, _)| *c == coin)
+            .map_or(0, |&(_, n)| n)
+    }
+
+    /// Coins of this kind, quarters of every state together, saturating at `u32::MAX`.
+    pub fn count_kind(&self, kind: CoinKind) -> u32 {
+        self.coins
+            .iter()
+            .filter(|(c, _)| c.kind() == kind)
+            .map(|&(_, n)| n)
+            .fold(0, u32::saturating_add)
+    }
+
+    /// How many coins there are. Counted in `u64`, since each coin's count can reach
+    /// `u32::MAX`.
+    pub fn len(&self) -> u64 {
+        self.coins.iter().map(|&(_, n)| u64::from(n)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coins.is_empty()
+    }
+
+    pub fn total_cents(&self) -> u64 {
+        self.coins
+            .iter()
+            .map(|(c, n)| u64::from(c.kind().value_in_cents()) * u64::from(*n))
+            .sum()
+    }
+
+    /// Each coin with how many of it there are.
+    pub fn iter(&self) -> impl Iterator<Item = (Coin, u32)> + '_ {
+        self.coins.iter().copied()
+    }
+
+    /// The fewest coins from the inventory adding up to `amount`, largest first, without
+    /// taking them out. Coins of a kind are picked in the order they came in.
+    pub fn plan_change(&self, amount: u32) -> Result<Vec<Coin>, ChangeError> {
+        let limits: Vec<u32> = CoinKind::ALL
+            .iter()
+            .rev()
+            .map(|&kind| self.count_kind(kind))
+            .collect();
+        let counts = Denominations::us().optimal_with_limits(amount, &limits)?;
+        let mut change = Vec::new();
+        for (kind, count) in us_coins(&counts) {
+            let of_kind = self.coins.iter().filter(|(c, _)| c.kind() == kind);
+            let coins = of_kind.flat_map(|&(c, n)| std::iter::repeat_n(c, n as usize));
+            change.extend(coins.take(count as usize));
+        }
+        Ok(change)
+    }
+
+    /// Takes the coins `plan_change` picks out of the inventory. On error nothing is taken.
+    pub fn make_change(&mut self, amount: u32) -> Result<Vec<Coin>, ChangeError> {
+        let change = self.plan_change(amount)?;
+        for &coin in &change {
+            self.remove(coin);
+        }
+        Ok(change)
+    }
+}
+
+impl Extend<Coin> for CoinInventory {
+    fn extend<I: IntoIterator<Item = Coin>>(&mut self, coins: I) {
+        for coin in coins {
+            self.add(coin);
+        }
+    }
+}
+
+impl FromIterator<Coin> for CoinInventory {
+    fn from_iter<I: IntoIterator<Item = Coin>>(coins: I) -> Self {
+        let mut inventory = CoinInventory::new();
+        inventory.extend(coins);
+        inventory
+    }
+}
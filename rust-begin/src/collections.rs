@@ -0,0 +1,98 @@
+//! Vectors and Hash Maps
+//!
+//! A vector (`Vec<T>`) stores more than one value of the 👉 same type next to each other in memory.
+//! A hash map (`HashMap<K, V>`) stores a mapping of keys of type K to values of type V, like an
+//! Object in JavaScript or a Dictionary in Python.
+
+use std::collections::HashMap;
+
+use crate::command::Command;
+
+/// Using an Enum to Store Multiple Types in a vector. Rust needs to know what types will be in the
+/// vector at compile time, so every element is a `SpreadsheetCell`.
+#[derive(Debug, Clone, PartialEq, Command)]
+pub enum SpreadsheetCell {
+    Int(i32),
+    Float(f64),
+    Text(String),
+}
+
+/// Adds `amount` to every element through a mutable reference.
+pub fn add_to_all(v: &mut [i32], amount: i32) {
+    for i in v.iter_mut() {
+        *i += amount; // the dereference operator (*) gets the value in i before we can add to it
+    }
+}
+
+/// Pairs each team with its initial score by zipping the two vectors and collecting the tuples.
+pub fn team_scores(teams: &[String], initial_scores: &[i32]) -> HashMap<String, i32> {
+    teams
+        .iter()
+        .cloned()
+        .zip(initial_scores.iter().copied())
+        .collect()
+}
+
+/// Counts how many times each word appears. `or_insert` returns a mutable reference (&mut V) to
+/// the value for this key, so we can update it based on the old value.
+pub fn word_count(text: &str) -> HashMap<&str, usize> {
+    let mut map = HashMap::new();
+
+    for word in text.split_whitespace() {
+        let count = map.entry(word).or_insert(0);
+        *count += 1;
+    }
+
+    map
+}
+
+pub fn demo() {
+    // 💡 Reading Elements of Vectors
+    let v = [1, 2, 3, 4, 5];
+    let third: &i32 = &v[2];
+    println!("The third element is {}", third);
+
+    match v.get(2) {
+        // the get method returns an Option<&T>
+        Some(third) => println!("The third element is {}", third),
+        None => println!("There is no third element."),
+    }
+    println!("v.get(100) is {:?}", v.get(100)); // None without panic, &v[100] would panic
+
+    let mut v = vec![100, 32, 57];
+    add_to_all(&mut v, 50);
+    for i in &v {
+        println!("{}", i);
+    }
+
+    let row = vec![
+        SpreadsheetCell::Int(3),
+        SpreadsheetCell::Text(String::from("blue")),
+        SpreadsheetCell::Float(10.12),
+    ];
+    println!("row is {:?}", row);
+    let cells: Vec<String> = row.iter().map(|cell| cell.to_string()).collect();
+    println!("as commands: {}", cells.join("; ")); // int 3; text "blue"; float 10.12
+
+    // ! Hash Maps
+    let teams = vec![String::from("Blue"), String::from("Yellow")];
+    let mut scores = team_scores(&teams, &[10, 50]);
+
+    let team_name = String::from("Blue");
+    println!("Blue has {:?}", scores.get(&team_name)); // Some(&10)
+
+    scores.insert(String::from("Blue"), 25); // Overwriting a value
+    scores.entry(String::from("Red")).or_insert(50); // Only inserting a value if the key has no value
+    scores.entry(String::from("Blue")).or_insert(50);
+
+    let mut sorted: Vec<_> = scores.iter().collect();
+    sorted.sort();
+    for (key, value) in sorted {
+        println!("{}: {}", key, value);
+    }
+
+    let counts = word_count("hello world wonderful world");
+    let mut sorted: Vec<_> = counts.into_iter().collect();
+    sorted.sort();
+    println!("{:?}", sorted); // [("hello", 1), ("wonderful", 1), ("world", 2)]
+}
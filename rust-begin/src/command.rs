@@ -0,0 +1,620 @@
+//! Text and binary forms for enums, generated with `#[derive(Command)]`.
+//!
+//! Writing a parser, a `Display` and an encoder by hand for each lesson enum, as `message`
+//! does for `Message`, gets repetitive. The derive writes them from the enum's definition:
+//!
+//! ```
+//! use rust_begin::command::Command;
+//!
+//! #[derive(Debug, PartialEq, Command)]
+//! enum Shape {
+//!     Dot,
+//!     Circle(u32),
+//!     #[command(rename = "rect")]
+//!     Rectangle { width: u32, height: u32 },
+//!     Label(String),
+//! }
+//!
+//! let shape: Shape = "rect 3 4".parse().unwrap();
+//! assert_eq!(shape, Shape::Rectangle { width: 3, height: 4 });
+//! assert_eq!(Shape::Label("a \"b\"".to_string()).to_string(), r#"label "a \"b\"""#);
+//! assert_eq!(Shape::VARIANTS, ["dot", "circle", "rect", "label"]);
+//! assert_eq!(Shape::from_bytes(&shape.to_bytes()).unwrap(), shape);
+//! ```
+//!
+//! # Text
+//!
+//! The variant's name followed by its fields in order, separated by spaces, whether the
+//! variant is a tuple or a struct. Names are the variant's in snake_case unless renamed, with
+//! a run of capitals kept as one word (`HTTPGet` is `http_get`) and a raw `r#` left off.
+//! Strings and chars go in double quotes with the escapes of `message` (`\"`, `\\`, `\n`, `\t`,
+//! `\r`, `\0`, `\u{1F980}`); a `#` outside quotes starts a comment. A field that is itself a
+//! `Command` is written the same way in place, so `Coin::Quarter(UsState::Alaska)` is
+//! `quarter alaska`.
+//!
+//! # Binary
+//!
+//! The variant's position in the enum as a varint, then the fields: the same encoding as
+//! `message::wire` uses for `Message`. Signed integers are zigzag varints, unsigned integers
+//! varints, `bool` one byte, floats their little-endian bytes, chars their code point as a
+//! varint and strings a varint byte length then UTF-8.
+//!
+//! # Attributes
+//!
+//! - `#[command(rename_all = "...")]` on the enum picks the spelling of variant names:
+//!   `"snake_case"` (the default), `"kebab-case"`, `"lowercase"`, `"UPPERCASE"` or
+//!   `"PascalCase"`.
+//! - `#[command(rename = "...")]` on a variant sets its name outright.
+//! - `#[command(rename = "...")]` on a field sets the name used in error messages, which is
+//!   otherwise the field's name, or its position in a tuple variant.
+//!
+//! # Unsupported shapes
+//!
+//! Only enums can derive `Command`:
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! struct Point { x: i32, y: i32 }
+//! ```
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! union Bits { int: u32, float: f32 }
+//! ```
+//!
+//! and not generic ones, since the text form of a type parameter is not known:
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! enum Either<L, R> { Left(L), Right(R) }
+//! ```
+//!
+//! An enum without variants has nothing to parse:
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! enum Never {}
+//! ```
+//!
+//! Two variants cannot end up with the same name:
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! enum Direction { Up, #[command(rename = "up")] North }
+//! ```
+//!
+//! Names have to be single words, and attributes have to be known ones:
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! enum Greeting { #[command(rename = "good morning")] Morning }
+//! ```
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! enum Greeting { #[command(alias = "hi")] Hello }
+//! ```
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! #[command(rename_all = "camelCase")]
+//! enum Greeting { Hello }
+//! ```
+//!
+//! Every field has to implement `Field`:
+//!
+//! ```compile_fail
+//! # use rust_begin::command::Command;
+//! #[derive(Command)]
+//! enum Batch { Items(Vec<u32>) }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+use crate::message::{unescape, wire, BadEscape};
+
+pub use rust_begin_derive::Command;
+
+/// An enum with a text and a binary form. Implement it with `#[derive(Command)]`, which also
+/// implements `Display` and `FromStr` in the text form.
+pub trait Command: Sized {
+    /// The variants' names as they are written, in declaration order.
+    const VARIANTS: &'static [&'static str];
+
+    /// The position of this value's variant in the enum.
+    fn variant_index(&self) -> usize;
+
+    /// Reads a value from the front of `tokens`.
+    fn parse_tokens(tokens: &mut Tokens<'_>) -> Result<Self, ParseError>;
+
+    /// Writes the text form.
+    fn write_tokens(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Appends the binary form to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Reads a value from the front of `input` and advances past it.
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError>;
+
+    fn variant_name(&self) -> &'static str {
+        Self::VARIANTS[self.variant_index()]
+    }
+
+    /// Parses a whole line. Surrounding spaces and a trailing comment are allowed.
+    fn parse_command(text: &str) -> Result<Self, ParseError> {
+        let mut tokens = Tokens::new(text);
+        let value = Self::parse_tokens(&mut tokens)?;
+        tokens.finish()?;
+        Ok(value)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    /// Decodes a value that takes up all of `bytes`.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut input = bytes;
+        let value = Self::decode(&mut input)?;
+        if !input.is_empty() {
+            return Err(DecodeError::TrailingBytes(input.len()));
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Nothing where a command should be.
+    Empty,
+    UnknownVariant {
+        found: String,
+        expected: &'static [&'static str],
+    },
+    /// The text ended where this field should be.
+    MissingArgument(&'static str),
+    /// A field whose text does not parse as its type.
+    InvalidArgument {
+        name: &'static str,
+        found: String,
+    },
+    /// A string or char field not in double quotes.
+    ExpectedString(&'static str),
+    UnterminatedString,
+    InvalidEscape(String),
+    /// More than the command takes.
+    TrailingInput,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "expected a command"),
+            ParseErrorKind::UnknownVariant { found, expected } => write!(
+                f,
+                "unknown command `{}`, expected one of {}",
+                found,
+                expected.join(", ")
+            ),
+            ParseErrorKind::MissingArgument(name) => write!(f, "missing argument `{}`", name),
+            ParseErrorKind::InvalidArgument { name, found } => {
+                write!(f, "`{}` is not a valid `{}`", found, name)
+            }
+            ParseErrorKind::ExpectedString(name) => {
+                write!(f, "expected `{}` in double quotes", name)
+            }
+            ParseErrorKind::UnterminatedString => write!(f, "missing closing quote"),
+            ParseErrorKind::InvalidEscape(escape) => write!(f, "invalid escape `{}`", escape),
+            ParseErrorKind::TrailingInput => write!(f, "unexpected extra argument"),
+        }
+    }
+}
+
+/// What went wrong and where; `column` counts characters from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.kind)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended in the middle of a value.
+    Truncated,
+    /// A varint longer than 64 bits.
+    VarintOverflow,
+    /// An integer too large for its field.
+    OutOfRange(i128),
+    InvalidUtf8,
+    /// A `char` field holding something that is not a code point.
+    InvalidChar(u32),
+    /// A `bool` field holding something other than 0 or 1.
+    InvalidBool(u8),
+    /// A variant tag past the end of the enum.
+    UnknownVariant(u64),
+    /// Bytes left over after the value, from `Command::from_bytes`.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "input is truncated"),
+            DecodeError::VarintOverflow => write!(f, "varint does not fit in 64 bits"),
+            DecodeError::OutOfRange(value) => write!(f, "{} is out of range for its field", value),
+            DecodeError::InvalidUtf8 => write!(f, "text is not valid UTF-8"),
+            DecodeError::InvalidChar(value) => write!(f, "{:#x} is not a char", value),
+            DecodeError::InvalidBool(value) => write!(f, "{} is not a bool", value),
+            DecodeError::UnknownVariant(tag) => write!(f, "unknown variant tag {}", tag),
+            DecodeError::TrailingBytes(len) => write!(f, "{} bytes left over", len),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+impl From<wire::DecodeError> for DecodeError {
+    fn from(e: wire::DecodeError) -> Self {
+        match e {
+            wire::DecodeError::VarintOverflow => DecodeError::VarintOverflow,
+            wire::DecodeError::InvalidUtf8 => DecodeError::InvalidUtf8,
+            // the helpers used here only fail in the two ways above, or by running out
+            _ => DecodeError::Truncated,
+        }
+    }
+}
+
+/// The words and quoted strings of a line of text, read from the front.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+/// One word, or one quoted string with its escapes resolved.
+enum Token<'a> {
+    Word(&'a str),
+    Quoted(String),
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(text: &'a str) -> Tokens<'a> {
+        Tokens { text, pos: 0 }
+    }
+
+    /// An error at byte offset `at`.
+    pub fn error_at(&self, at: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            column: self.text[..at.min(self.text.len())].chars().count() + 1,
+            kind,
+        }
+    }
+
+    /// Skips spaces and a comment; true if nothing else is left.
+    pub fn at_end(&mut self) -> bool {
+        self.skip_space();
+        self.text[self.pos..].is_empty() || self.text[self.pos..].starts_with('#')
+    }
+
+    fn skip_space(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// The next token and the byte offset it starts at.
+    fn next(&mut self) -> Result<Option<(Token<'a>, usize)>, ParseError> {
+        if self.at_end() {
+            return Ok(None);
+        }
+        let start = self.pos;
+        if self.text[start..].starts_with('"') {
+            return self
+                .quoted(start)
+                .map(|text| Some((Token::Quoted(text), start)));
+        }
+        let rest = &self.text[start..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || c == '"' || c == '#')
+            .unwrap_or(rest.len());
+        self.pos += len;
+        Ok(Some((Token::Word(&rest[..len]), start)))
+    }
+
+    /// Reads the string whose opening quote is at `start`. Escapes are those of the message
+    /// syntax.
+    fn quoted(&mut self, start: usize) -> Result<String, ParseError> {
+        let mut out = String::new();
+        let mut at = start + 1;
+        while let Some(c) = self.text[at..].chars().next() {
+            match c {
+                '"' => {
+                    self.pos = at + 1;
+                    return Ok(out);
+                }
+                '\\' => match unescape(&self.text[at..]) {
+                    Ok((unescaped, len)) => {
+                        out.push(unescaped);
+                        at += len;
+                    }
+                    Err(BadEscape::Unterminated) => break,
+                    Err(BadEscape::Invalid(len)) => {
+                        let escape = self.text[at..at + len].to_string();
+                        return Err(self.error_at(at, ParseErrorKind::InvalidEscape(escape)));
+                    }
+                },
+                c => {
+                    out.push(c);
+                    at += c.len_utf8();
+                }
+            }
+        }
+        Err(self.error_at(start, ParseErrorKind::UnterminatedString))
+    }
+
+    /// The next token as a variant name, returning its index in `names`.
+    pub fn variant(&mut self, names: &'static [&'static str]) -> Result<usize, ParseError> {
+        let end = self.text.trim_end().len();
+        let (found, start) = match self.next()? {
+            None => return Err(self.error_at(end, ParseErrorKind::Empty)),
+            Some((Token::Word(word), start)) => (word.to_string(), start),
+            Some((Token::Quoted(_), start)) => (self.text[start..self.pos].to_string(), start),
+        };
+        names.iter().position(|name| *name == found).ok_or_else(|| {
+            let kind = ParseErrorKind::UnknownVariant {
+                found,
+                expected: names,
+            };
+            self.error_at(start, kind)
+        })
+    }
+
+    /// The next token, which has to be a bare word, for field `name`. Returns it with its
+    /// offset.
+    pub fn word(&mut self, name: &'static str) -> Result<(&'a str, usize), ParseError> {
+        let end = self.text.trim_end().len();
+        match self.next()? {
+            None => Err(self.error_at(end, ParseErrorKind::MissingArgument(name))),
+            Some((Token::Word(word), start)) => Ok((word, start)),
+            Some((Token::Quoted(_), start)) => {
+                let found = self.text[start..self.pos].to_string();
+                Err(self.error_at(start, ParseErrorKind::InvalidArgument { name, found }))
+            }
+        }
+    }
+
+    /// The next token, which has to be a quoted string, for field `name`. Returns it with
+    /// its offset.
+    pub fn string(&mut self, name: &'static str) -> Result<(String, usize), ParseError> {
+        let end = self.text.trim_end().len();
+        match self.next()? {
+            None => Err(self.error_at(end, ParseErrorKind::MissingArgument(name))),
+            Some((Token::Quoted(text), start)) => Ok((text, start)),
+            Some((Token::Word(_), start)) => {
+                Err(self.error_at(start, ParseErrorKind::ExpectedString(name)))
+            }
+        }
+    }
+
+    /// Checks that nothing but spaces and a comment is left.
+    pub fn finish(&mut self) -> Result<(), ParseError> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.error_at(self.pos, ParseErrorKind::TrailingInput))
+        }
+    }
+}
+
+/// A type that can be a field of a `#[derive(Command)]` enum.
+pub trait Field: Sized {
+    /// Reads the field from the front of `tokens`; `name` is for error messages.
+    fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError>;
+
+    fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    fn encode_field(&self, out: &mut Vec<u8>);
+
+    fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// Parses a bare word with `FromStr`.
+fn parse_word<T: std::str::FromStr>(
+    tokens: &mut Tokens<'_>,
+    name: &'static str,
+) -> Result<T, ParseError> {
+    let (word, start) = tokens.word(name)?;
+    word.parse().map_err(|_| {
+        let found = word.to_string();
+        tokens.error_at(start, ParseErrorKind::InvalidArgument { name, found })
+    })
+}
+
+macro_rules! signed_field {
+    ($($ty:ty),*) => {$(
+        impl Field for $ty {
+            fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError> {
+                parse_word(tokens, name)
+            }
+
+            fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self)
+            }
+
+            fn encode_field(&self, out: &mut Vec<u8>) {
+                let value = *self as i64;
+                wire::put_varint(out, ((value << 1) ^ (value >> 63)) as u64);
+            }
+
+            fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                let zigzag = wire::take_varint(input)?;
+                let value = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+                <$ty>::try_from(value).map_err(|_| DecodeError::OutOfRange(value.into()))
+            }
+        }
+    )*};
+}
+
+macro_rules! unsigned_field {
+    ($($ty:ty),*) => {$(
+        impl Field for $ty {
+            fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError> {
+                parse_word(tokens, name)
+            }
+
+            fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self)
+            }
+
+            fn encode_field(&self, out: &mut Vec<u8>) {
+                wire::put_varint(out, *self as u64);
+            }
+
+            fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                let value = wire::take_varint(input)?;
+                <$ty>::try_from(value).map_err(|_| DecodeError::OutOfRange(value.into()))
+            }
+        }
+    )*};
+}
+
+macro_rules! float_field {
+    ($($ty:ty),*) => {$(
+        impl Field for $ty {
+            fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError> {
+                parse_word(tokens, name)
+            }
+
+            /// `{:?}` rather than `{}` so that whole numbers keep their `.0`.
+            fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{:?}", self)
+            }
+
+            fn encode_field(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                let bytes = wire::take_bytes(input, std::mem::size_of::<$ty>() as u64)?;
+                Ok(<$ty>::from_le_bytes(bytes.try_into().expect("took the size of the type")))
+            }
+        }
+    )*};
+}
+
+signed_field!(i8, i16, i32, i64, isize);
+unsigned_field!(u8, u16, u32, u64, usize);
+float_field!(f32, f64);
+
+impl Field for bool {
+    fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError> {
+        parse_word(tokens, name)
+    }
+
+    fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+
+    fn encode_field(&self, out: &mut Vec<u8>) {
+        out.push(u8::from(*self));
+    }
+
+    fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        match wire::take_bytes(input, 1)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            [other] => Err(DecodeError::InvalidBool(*other)),
+            _ => unreachable!("took one byte"),
+        }
+    }
+}
+
+impl Field for String {
+    fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError> {
+        tokens.string(name).map(|(text, _)| text)
+    }
+
+    fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::message::write_quoted(f, self)
+    }
+
+    fn encode_field(&self, out: &mut Vec<u8>) {
+        wire::put_varint(out, self.len() as u64);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = wire::take_varint(input)?;
+        let bytes = wire::take_bytes(input, len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+    }
+}
+
+impl Field for char {
+    fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError> {
+        let (text, start) = tokens.string(name)?;
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => {
+                let found = format!("{:?}", text);
+                Err(tokens.error_at(start, ParseErrorKind::InvalidArgument { name, found }))
+            }
+        }
+    }
+
+    fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::message::write_quoted(f, self.encode_utf8(&mut [0; 4]))
+    }
+
+    fn encode_field(&self, out: &mut Vec<u8>) {
+        wire::put_varint(out, u64::from(*self as u32));
+    }
+
+    fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let value = wire::take_varint(input)?;
+        let value = u32::try_from(value).map_err(|_| DecodeError::OutOfRange(value.into()))?;
+        char::from_u32(value).ok_or(DecodeError::InvalidChar(value))
+    }
+}
+
+/// Commands nest: the field is written as the inner command, name and all.
+impl<T: Command> Field for T {
+    fn parse_field(tokens: &mut Tokens<'_>, name: &'static str) -> Result<Self, ParseError> {
+        if tokens.at_end() {
+            let end = tokens.text.trim_end().len();
+            return Err(tokens.error_at(end, ParseErrorKind::MissingArgument(name)));
+        }
+        T::parse_tokens(tokens)
+    }
+
+    fn write_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_tokens(f)
+    }
+
+    fn encode_field(&self, out: &mut Vec<u8>) {
+        self.encode(out);
+    }
+
+    fn decode_field(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        T::decode(input)
+    }
+}
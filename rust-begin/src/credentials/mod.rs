@@ -0,0 +1,482 @@
+//! Passwords for `User`s, and what happens when someone keeps getting one wrong.
+//!
+//! Passwords are stored as scrypt hashes (RFC 7914), salted and memory-hard: checking a guess
+//! needs `128 * r * N` bytes of memory, 32 MiB with the default `Params`, which is what makes
+//! guessing on graphics cards and custom chips expensive. Everything down to SHA-256 is built
+//! here and checked against the test vectors of the RFCs that define it.
+//!
+//! A stored hash is a string in the PHC format that other scrypt implementations read:
+//!
+//! ```
+//! use rust_begin::credentials::{Params, PasswordHash};
+//!
+//! let params = Params::new(4, 1, 1).unwrap(); // cheap enough for an example
+//! let hash = PasswordHash::new("correct horse battery staple", params);
+//! assert!(hash.to_string().starts_with("$scrypt$ln=4,r=1,p=1$"));
+//!
+//! let stored: PasswordHash = hash.to_string().parse()?;
+//! assert!(stored.verify("correct horse battery staple"));
+//! assert!(!stored.verify("Tr0ub4dor&3"));
+//! # Ok::<(), rust_begin::credentials::ParseHashError>(())
+//! ```
+//!
+//! `users::UserRegistry::sign_in` keeps a `Credential` per user to count failed attempts and
+//! lock the account for a while after too many of them.
+
+mod scrypt;
+mod sha256;
+
+pub use sha256::{hmac_sha256, pbkdf2_hmac_sha256, sha256, Sha256};
+
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The cost of an scrypt hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Params {
+    log_n: u8,
+    r: u32,
+    p: u32,
+}
+
+impl Params {
+    /// `N = 2^15`, `r = 8`, `p = 1`: 32 MiB and a few tenths of a second to check a password.
+    pub const DEFAULT: Params = Params {
+        log_n: 15,
+        r: 8,
+        p: 1,
+    };
+
+    /// 1 GiB, the most memory a hash read from a string may take.
+    pub const MAX_MEMORY: usize = 1 << 30;
+    /// The most parallel lanes a hash read from a string may have.
+    pub const MAX_P: u32 = 16;
+
+    /// `None` unless RFC 7914's limits hold: `N = 2^log_n` above 1 and below `2^(16 * r)`,
+    /// `r` and `p` at least 1, `r * p` below `2^30`, and the memory within `usize`.
+    pub fn new(log_n: u8, r: u32, p: u32) -> Option<Params> {
+        let memory = 1usize
+            .checked_shl(u32::from(log_n))
+            .and_then(|n| n.checked_mul(r as usize)?.checked_mul(128));
+        let valid = log_n > 0
+            && r > 0
+            && p > 0
+            && u64::from(log_n) < 16 * u64::from(r)
+            && u64::from(r) * u64::from(p) < 1 << 30
+            && memory.is_some();
+        valid.then_some(Params { log_n, r, p })
+    }
+
+    pub fn log_n(self) -> u8 {
+        self.log_n
+    }
+
+    pub fn r(self) -> u32 {
+        self.r
+    }
+
+    pub fn p(self) -> u32 {
+        self.p
+    }
+
+    /// Bytes of memory one hash takes.
+    pub fn memory(self) -> usize {
+        128 * self.r as usize * (1 << self.log_n)
+    }
+
+    /// Whether a hash read from a string may ask for this cost: the table and the `p` blocks
+    /// beside it each take at most `MAX_MEMORY`, and `p` is at most `MAX_P`. Anything more is
+    /// more likely an edited file than a real hash, and checking it could exhaust the machine.
+    fn storable(self) -> bool {
+        self.p <= Params::MAX_P
+            && self.memory() <= Params::MAX_MEMORY
+            && (128 * self.r as usize).saturating_mul(self.p as usize) <= Params::MAX_MEMORY
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params::DEFAULT
+    }
+}
+
+/// Fills `out` with the key scrypt derives from `password` and `salt` at the cost of
+/// `params`.
+pub fn scrypt(password: &[u8], salt: &[u8], params: Params, out: &mut [u8]) {
+    scrypt::scrypt(password, salt, params.log_n, params.r, params.p, out);
+}
+
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+/// Sixteen bytes no other call returns. std has no secure random numbers, but a salt only
+/// needs to be unique, not secret: each `RandomState` is keyed from the operating system's
+/// random source, and the counter and clock keep two calls apart even if the keys repeat.
+fn new_salt() -> [u8; SALT_LEN] {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut salt = [0; SALT_LEN];
+    for (i, chunk) in salt.chunks_exact_mut(8).enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(count);
+        hasher.write_u128(now);
+        hasher.write_usize(i);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    salt
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 without the `=` padding, as the PHC format writes it.
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &b)| bits | u32::from(b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            text.push(BASE64[(bits >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    text
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64.iter().position(|&b| b == c)? as u32;
+            bits |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    // the unused low bits must be zero, so each hash has one spelling
+    (base64_encode(&bytes) == text).then_some(bytes)
+}
+
+/// Whether the two are equal, taking as long to find out wherever they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// A salted scrypt hash of a password, and the cost it was made at.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PasswordHash {
+    params: Params,
+    salt: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+impl PasswordHash {
+    /// Hashes `password` with a new salt.
+    pub fn new(password: &str, params: Params) -> PasswordHash {
+        PasswordHash::with_salt(password, &new_salt(), params)
+    }
+
+    pub fn with_salt(password: &str, salt: &[u8], params: Params) -> PasswordHash {
+        let mut hash = vec![0; HASH_LEN];
+        scrypt(password.as_bytes(), salt, params, &mut hash);
+        PasswordHash {
+            params,
+            salt: salt.to_vec(),
+            hash,
+        }
+    }
+
+    /// A hash no password matches, made without hashing anything, so that checking against it
+    /// takes as long as checking a real one at `params`.
+    pub(crate) fn dummy(params: Params) -> PasswordHash {
+        PasswordHash {
+            params,
+            salt: vec![0; SALT_LEN],
+            hash: vec![0; HASH_LEN],
+        }
+    }
+
+    pub fn params(&self) -> Params {
+        self.params
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Whether `password` is the one that was hashed.
+    pub fn verify(&self, password: &str) -> bool {
+        let mut hash = vec![0; self.hash.len()];
+        scrypt(password.as_bytes(), &self.salt, self.params, &mut hash);
+        constant_time_eq(&hash, &self.hash)
+    }
+}
+
+/// `$scrypt$ln=15,r=8,p=1$<salt>$<hash>`, with the salt and hash in base64.
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "$scrypt$ln={},r={},p={}${}${}",
+            self.params.log_n,
+            self.params.r,
+            self.params.p,
+            base64_encode(&self.salt),
+            base64_encode(&self.hash)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseHashError {
+    pub found: String,
+}
+
+impl fmt::Display for ParseHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not an scrypt password hash: {:?}", self.found)
+    }
+}
+
+impl Error for ParseHashError {}
+
+impl FromStr for PasswordHash {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<PasswordHash, ParseHashError> {
+        let invalid = || ParseHashError {
+            found: s.to_string(),
+        };
+        let fields: Vec<&str> = s.split('$').collect();
+        let ["", "scrypt", params, salt, hash] = fields[..] else {
+            return Err(invalid());
+        };
+        let mut values = params.split(',').map(|param| param.split_once('='));
+        let (Some(Some(("ln", log_n))), Some(Some(("r", r))), Some(Some(("p", p))), None) =
+            (values.next(), values.next(), values.next(), values.next())
+        else {
+            return Err(invalid());
+        };
+        let params = Params::new(
+            log_n.parse().map_err(|_| invalid())?,
+            r.parse().map_err(|_| invalid())?,
+            p.parse().map_err(|_| invalid())?,
+        )
+        .filter(|params| params.storable())
+        .ok_or_else(invalid)?;
+        let salt = base64_decode(salt).ok_or_else(invalid)?;
+        let hash = base64_decode(hash)
+            .filter(|h| !h.is_empty())
+            .ok_or_else(invalid)?;
+        Ok(PasswordHash { params, salt, hash })
+    }
+}
+
+/// When to lock an account, and for how long.
+///
+/// After `threshold` failed attempts in a row the account is locked for `base`, and every
+/// further failure after a lock runs out doubles that, up to `max`. A threshold of 0 never
+/// locks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lockout {
+    pub threshold: u32,
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl Lockout {
+    /// Five attempts, then 30 seconds, doubling to an hour at most.
+    pub const DEFAULT: Lockout = Lockout {
+        threshold: 5,
+        base: Duration::from_secs(30),
+        max: Duration::from_secs(60 * 60),
+    };
+
+    /// How long to lock for after `failed` failed attempts in a row; `None` below the
+    /// threshold.
+    pub fn delay(&self, failed: u32) -> Option<Duration> {
+        if self.threshold == 0 || failed < self.threshold {
+            return None;
+        }
+        let doublings = failed - self.threshold;
+        let delay = 2u32
+            .checked_pow(doublings)
+            .and_then(|factor| self.base.checked_mul(factor))
+            .unwrap_or(self.max);
+        Some(delay.min(self.max))
+    }
+}
+
+impl Default for Lockout {
+    fn default() -> Self {
+        Lockout::DEFAULT
+    }
+}
+
+/// A user's password and the record of signing in with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credential {
+    hash: PasswordHash,
+    failed_attempts: u32,
+    locked_until: Option<SystemTime>,
+    last_sign_in: Option<SystemTime>,
+    last_failed_sign_in: Option<SystemTime>,
+}
+
+impl Credential {
+    pub fn new(hash: PasswordHash) -> Credential {
+        Credential {
+            hash,
+            failed_attempts: 0,
+            locked_until: None,
+            last_sign_in: None,
+            last_failed_sign_in: None,
+        }
+    }
+
+    /// A credential as it was saved, for `crate::users` to load.
+    pub(crate) fn restore(
+        hash: PasswordHash,
+        failed_attempts: u32,
+        locked_until: Option<SystemTime>,
+        last_sign_in: Option<SystemTime>,
+        last_failed_sign_in: Option<SystemTime>,
+    ) -> Credential {
+        Credential {
+            hash,
+            failed_attempts,
+            locked_until,
+            last_sign_in,
+            last_failed_sign_in,
+        }
+    }
+
+    pub fn hash(&self) -> &PasswordHash {
+        &self.hash
+    }
+
+    /// When the last lock runs out, or ran out; unlike `locked_until` this is kept once past,
+    /// so that saving and loading a credential gives back the same one.
+    pub(crate) fn lock_expiry(&self) -> Option<SystemTime> {
+        self.locked_until
+    }
+
+    /// Failed attempts since the last successful one.
+    pub fn failed_attempts(&self) -> u32 {
+        self.failed_attempts
+    }
+
+    /// When the lock runs out, if the account is locked at `now`.
+    pub fn locked_until(&self, now: SystemTime) -> Option<SystemTime> {
+        self.locked_until.filter(|&until| until > now)
+    }
+
+    pub fn last_sign_in(&self) -> Option<SystemTime> {
+        self.last_sign_in
+    }
+
+    pub fn last_failed_sign_in(&self) -> Option<SystemTime> {
+        self.last_failed_sign_in
+    }
+
+    /// A new password. The record of signing in stays, but any lock is lifted.
+    pub fn set_hash(&mut self, hash: PasswordHash) {
+        self.hash = hash;
+        self.unlock();
+    }
+
+    /// Lifts the lock and forgets the failed attempts.
+    pub fn unlock(&mut self) {
+        self.failed_attempts = 0;
+        self.locked_until = None;
+    }
+
+    /// Checks `password` at `now`. A wrong one is a failed attempt and may lock the account,
+    /// and then the error says until when; the right one clears the failed attempts. Nothing
+    /// is checked while the account is locked. A lock too long for the clock lasts until the
+    /// year 10000.
+    pub fn check(
+        &mut self,
+        password: &str,
+        now: SystemTime,
+        lockout: &Lockout,
+    ) -> Result<(), SignInError> {
+        if let Some(until) = self.locked_until(now) {
+            return Err(SignInError::Locked { until });
+        }
+        if self.hash.verify(password) {
+            self.unlock();
+            return Ok(());
+        }
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+        self.last_failed_sign_in = Some(now);
+        match lockout.delay(self.failed_attempts) {
+            Some(delay) => {
+                let until = now
+                    .checked_add(delay)
+                    .unwrap_or_else(|| far_future().max(now));
+                self.locked_until = Some(until);
+                Err(SignInError::Locked { until })
+            }
+            None => Err(SignInError::InvalidCredentials),
+        }
+    }
+
+    pub fn record_sign_in(&mut self, now: SystemTime) {
+        self.last_sign_in = Some(now);
+    }
+}
+
+/// Seconds from the epoch to the start of the year 10000.
+const FAR_FUTURE_SECS: u64 = 253_402_300_800;
+
+/// Where a lock ends when `now` plus the delay is past what `SystemTime` can hold: later than
+/// anyone will wait, but still a time the user store can write and read back.
+fn far_future() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(FAR_FUTURE_SECS)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignInError {
+    /// No such user, no password set, or the wrong password. Which one is not told, and each
+    /// takes one hash to find out, so that neither the answer nor how long it takes says who
+    /// has an account. Only an account that exists can be `Locked`, though.
+    InvalidCredentials,
+    /// Too many failed attempts; no password is checked until the lock runs out.
+    Locked { until: SystemTime },
+    /// The password was right but the user is deactivated.
+    Inactive,
+}
+
+impl fmt::Display for SignInError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignInError::InvalidCredentials => write!(f, "wrong username or password"),
+            SignInError::Locked { until } => {
+                let secs = until.duration_since(UNIX_EPOCH).unwrap_or_default();
+                write!(
+                    f,
+                    "too many failed attempts, locked until {} seconds after the epoch",
+                    secs.as_secs()
+                )
+            }
+            SignInError::Inactive => write!(f, "the account is deactivated"),
+        }
+    }
+}
+
+impl Error for SignInError {}
@@ -0,0 +1,90 @@
+//! scrypt (RFC 7914): PBKDF2 around a mixing step that needs a large table in memory, so that
+//! guessing passwords in parallel costs memory as well as time.
+
+use super::sha256::pbkdf2_hmac_sha256;
+
+/// Salsa20/8, the Salsa20 core with 8 rounds instead of 20, on 16 words in place.
+fn salsa20_8(block: &mut [u32; 16]) {
+    let mut x = *block;
+    let quarter = |x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize| {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    };
+    for _ in 0..4 {
+        // columns
+        quarter(&mut x, 0, 4, 8, 12);
+        quarter(&mut x, 5, 9, 13, 1);
+        quarter(&mut x, 10, 14, 2, 6);
+        quarter(&mut x, 15, 3, 7, 11);
+        // rows
+        quarter(&mut x, 0, 1, 2, 3);
+        quarter(&mut x, 5, 6, 7, 4);
+        quarter(&mut x, 10, 11, 8, 9);
+        quarter(&mut x, 15, 12, 13, 14);
+    }
+    for (word, mixed) in block.iter_mut().zip(x) {
+        *word = word.wrapping_add(mixed);
+    }
+}
+
+/// scryptBlockMix on `2 * r` blocks of 16 words, from `input` into `output`.
+fn block_mix(input: &[u32], output: &mut [u32]) {
+    let blocks = input.len() / 16;
+    let mut x: [u32; 16] = input[input.len() - 16..].try_into().expect("16 words");
+    for (i, block) in input.chunks_exact(16).enumerate() {
+        for (word, b) in x.iter_mut().zip(block) {
+            *word ^= b;
+        }
+        salsa20_8(&mut x);
+        // even blocks go to the first half of the output, odd ones to the second
+        let at = (i / 2 + (i % 2) * blocks / 2) * 16;
+        output[at..at + 16].copy_from_slice(&x);
+    }
+}
+
+/// scryptROMix on one block of `32 * r` words, with a table of `n` copies of it.
+fn ro_mix(block: &mut [u32], n: usize) {
+    let len = block.len();
+    let mut table = vec![0u32; n * len];
+    let mut x = block.to_vec();
+    let mut y = vec![0u32; len];
+    for i in 0..n {
+        table[i * len..(i + 1) * len].copy_from_slice(&x);
+        block_mix(&x, &mut y);
+        std::mem::swap(&mut x, &mut y);
+    }
+    for _ in 0..n {
+        // Integerify: the first 64 bits of the last 16-word block, modulo n
+        let last = u64::from(x[len - 16]) | u64::from(x[len - 15]) << 32;
+        let j = (last % n as u64) as usize;
+        for (word, v) in x.iter_mut().zip(&table[j * len..(j + 1) * len]) {
+            *word ^= v;
+        }
+        block_mix(&x, &mut y);
+        std::mem::swap(&mut x, &mut y);
+    }
+    block.copy_from_slice(&x);
+}
+
+/// Fills `out` with the key scrypt derives from `password` and `salt`. `log_n` is the base-2
+/// logarithm of the CPU/memory cost `N`, `r` the block size and `p` the parallelization;
+/// `Params` checks them.
+pub(super) fn scrypt(password: &[u8], salt: &[u8], log_n: u8, r: u32, p: u32, out: &mut [u8]) {
+    let n = 1usize << log_n;
+    let block_len = 128 * r as usize;
+    let mut bytes = vec![0u8; p as usize * block_len];
+    pbkdf2_hmac_sha256(password, salt, 1, &mut bytes);
+    let mut words = vec![0u32; block_len / 4];
+    for block in bytes.chunks_exact_mut(block_len) {
+        for (word, b) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes(b.try_into().expect("four bytes"));
+        }
+        ro_mix(&mut words, n);
+        for (b, word) in block.chunks_exact_mut(4).zip(&words) {
+            b.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+    pbkdf2_hmac_sha256(password, &bytes, 1, out);
+}
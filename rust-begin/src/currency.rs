@@ -0,0 +1,553 @@
+//! Coins and banknotes of several currencies, and converting amounts between them.
+//!
+//! `Coin` only knows the four US coins and `value_in_cents`. Here every currency has its own
+//! coins and banknotes in one table, `DENOMINATIONS`, each worth a whole number of the
+//! currency's minor units: cents, pence, centimes, or whole yen, as the yen has none. `Coin`
+//! stays the US set and takes its values from the same table through `Denominated`.
+//!
+//! Exchange rates come from a text file, one rate per line:
+//!
+//! ```
+//! use rust_begin::currency::ExchangeRates;
+//!
+//! let rates = ExchangeRates::parse("# 1 of the first buys this many of the second\nUSD EUR 0.92\nUSD JPY 150\n")?;
+//! // $10.00 in euro cents, and in yen through the dollar
+//! assert_eq!(rates.convert(1000, "USD", "EUR"), Ok(920));
+//! assert_eq!(rates.convert(920, "EUR", "JPY"), Ok(1500));
+//! # Ok::<(), rust_begin::currency::RatesFileError>(())
+//! ```
+//!
+//! Rates are kept as exact fractions, so converting is exact until the last step, which
+//! rounds to the nearest minor unit with halves rounding up.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::change::Denominations;
+use crate::command::Command;
+use crate::enums::{Coin, CoinKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    /// The ISO 4217 code, like `USD`.
+    pub code: &'static str,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    /// Digits after the decimal point: 2 when there are 100 minor units to the major one,
+    /// 0 when there are no minor units.
+    pub exponent: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Form {
+    Coin,
+    Banknote,
+}
+
+/// One coin or banknote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Denomination {
+    /// The code of its currency.
+    pub currency: &'static str,
+    pub name: &'static str,
+    pub form: Form,
+    /// In minor units of the currency.
+    pub value: u32,
+}
+
+const fn currency(
+    code: &'static str,
+    name: &'static str,
+    symbol: &'static str,
+    exponent: u8,
+) -> Currency {
+    Currency {
+        code,
+        name,
+        symbol,
+        exponent,
+    }
+}
+
+const fn coin(currency: &'static str, name: &'static str, value: u32) -> Denomination {
+    Denomination {
+        currency,
+        name,
+        form: Form::Coin,
+        value,
+    }
+}
+
+const fn note(currency: &'static str, name: &'static str, value: u32) -> Denomination {
+    Denomination {
+        currency,
+        name,
+        form: Form::Banknote,
+        value,
+    }
+}
+
+/// Every currency this module knows.
+#[rustfmt::skip]
+pub static CURRENCIES: &[Currency] = &[
+    currency("USD", "US dollar", "$", 2),
+    currency("EUR", "Euro", "€", 2),
+    currency("GBP", "Pound sterling", "£", 2),
+    currency("JPY", "Japanese yen", "¥", 0),
+    currency("CHF", "Swiss franc", "CHF", 2),
+];
+
+/// The coins and banknotes in circulation for each of `CURRENCIES`, smallest first within a
+/// currency. The US coins are named as `Coin` prints them.
+#[rustfmt::skip]
+pub static DENOMINATIONS: &[Denomination] = &[
+    coin("USD", "penny", 1),
+    coin("USD", "nickel", 5),
+    coin("USD", "dime", 10),
+    coin("USD", "quarter", 25),
+    coin("USD", "half dollar", 50),
+    coin("USD", "dollar coin", 100),
+    note("USD", "1 dollar", 100),
+    note("USD", "2 dollars", 200),
+    note("USD", "5 dollars", 500),
+    note("USD", "10 dollars", 1_000),
+    note("USD", "20 dollars", 2_000),
+    note("USD", "50 dollars", 5_000),
+    note("USD", "100 dollars", 10_000),
+    coin("EUR", "1 cent", 1),
+    coin("EUR", "2 cent", 2),
+    coin("EUR", "5 cent", 5),
+    coin("EUR", "10 cent", 10),
+    coin("EUR", "20 cent", 20),
+    coin("EUR", "50 cent", 50),
+    coin("EUR", "1 euro", 100),
+    coin("EUR", "2 euro", 200),
+    note("EUR", "5 euro", 500),
+    note("EUR", "10 euro", 1_000),
+    note("EUR", "20 euro", 2_000),
+    note("EUR", "50 euro", 5_000),
+    note("EUR", "100 euro", 10_000),
+    note("EUR", "200 euro", 20_000),
+    note("EUR", "500 euro", 50_000),
+    coin("GBP", "1p", 1),
+    coin("GBP", "2p", 2),
+    coin("GBP", "5p", 5),
+    coin("GBP", "10p", 10),
+    coin("GBP", "20p", 20),
+    coin("GBP", "50p", 50),
+    coin("GBP", "1 pound", 100),
+    coin("GBP", "2 pounds", 200),
+    note("GBP", "5 pounds", 500),
+    note("GBP", "10 pounds", 1_000),
+    note("GBP", "20 pounds", 2_000),
+    note("GBP", "50 pounds", 5_000),
+    coin("JPY", "1 yen", 1),
+    coin("JPY", "5 yen", 5),
+    coin("JPY", "10 yen", 10),
+    coin("JPY", "50 yen", 50),
+    coin("JPY", "100 yen", 100),
+    coin("JPY", "500 yen", 500),
+    note("JPY", "1000 yen", 1_000),
+    note("JPY", "2000 yen", 2_000),
+    note("JPY", "5000 yen", 5_000),
+    note("JPY", "10000 yen", 10_000),
+    coin("CHF", "5 centimes", 5),
+    coin("CHF", "10 centimes", 10),
+    coin("CHF", "20 centimes", 20),
+    coin("CHF", "half franc", 50),
+    coin("CHF", "1 franc", 100),
+    coin("CHF", "2 francs", 200),
+    coin("CHF", "5 francs", 500),
+    note("CHF", "10 francs", 1_000),
+    note("CHF", "20 francs", 2_000),
+    note("CHF", "50 francs", 5_000),
+    note("CHF", "100 francs", 10_000),
+    note("CHF", "200 francs", 20_000),
+    note("CHF", "1000 francs", 100_000),
+];
+
+impl Currency {
+    /// Looks a currency up by its code, in any case.
+    pub fn from_code(code: &str) -> Option<&'static Currency> {
+        CURRENCIES
+            .iter()
+            .find(|currency| currency.code.eq_ignore_ascii_case(code))
+    }
+
+    /// Minor units in one major unit: 100 for cents, 1 for yen.
+    pub fn minor_per_major(&self) -> u64 {
+        10u64.pow(u32::from(self.exponent))
+    }
+
+    /// Its coins and banknotes, smallest first.
+    pub fn denominations(&self) -> impl Iterator<Item = &'static Denomination> {
+        let code = self.code;
+        DENOMINATIONS
+            .iter()
+            .filter(move |denomination| denomination.currency == code)
+    }
+
+    pub fn coins(&self) -> impl Iterator<Item = &'static Denomination> {
+        self.denominations()
+            .filter(|denomination| denomination.form == Form::Coin)
+    }
+
+    pub fn banknotes(&self) -> impl Iterator<Item = &'static Denomination> {
+        self.denominations()
+            .filter(|denomination| denomination.form == Form::Banknote)
+    }
+
+    /// Looks a coin or banknote up by its name, in any case.
+    pub fn denomination(&self, name: &str) -> Option<&'static Denomination> {
+        self.denominations()
+            .find(|denomination| denomination.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The values of its coins and banknotes, for making change. A coin and a banknote worth
+    /// the same count as one.
+    pub fn change_denominations(&self) -> Denominations {
+        let values: Vec<u32> = self.denominations().map(|d| d.value).collect();
+        Denominations::new(&values).expect("every currency has denominations")
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code)
+    }
+}
+
+/// Anything worth a fixed amount of one currency. `value_in_cents`, for every currency.
+pub trait Denominated {
+    fn currency(&self) -> &'static Currency;
+
+    /// The value in minor units of `currency()`.
+    fn value_in_minor_units(&self) -> u32;
+}
+
+impl Denominated for Denomination {
+    fn currency(&self) -> &'static Currency {
+        Currency::from_code(self.currency).expect("denominations are of known currencies")
+    }
+
+    fn value_in_minor_units(&self) -> u32 {
+        self.value
+    }
+}
+
+impl CoinKind {
+    /// Its entry in `DENOMINATIONS`.
+    pub fn denomination(self) -> &'static Denomination {
+        let name = Coin::VARIANTS[self as usize];
+        DENOMINATIONS
+            .iter()
+            .find(|d| d.currency == "USD" && d.form == Form::Coin && d.name == name)
+            .expect("every US coin is in the table")
+    }
+}
+
+impl Denominated for CoinKind {
+    fn currency(&self) -> &'static Currency {
+        self.denomination().currency()
+    }
+
+    fn value_in_minor_units(&self) -> u32 {
+        self.denomination().value
+    }
+}
+
+impl Denominated for Coin {
+    fn currency(&self) -> &'static Currency {
+        self.kind().currency()
+    }
+
+    fn value_in_minor_units(&self) -> u32 {
+        self.kind().value_in_minor_units()
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// How many units of one currency one unit of another buys, as an exact fraction.
+///
+/// Parsed from a decimal like `0.9215` or `150`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rate {
+    numer: u128,
+    denom: u128,
+}
+
+impl Rate {
+    /// `None` if either is zero.
+    pub fn new(numer: u128, denom: u128) -> Option<Rate> {
+        if numer == 0 || denom == 0 {
+            return None;
+        }
+        let divisor = gcd(numer, denom);
+        Some(Rate {
+            numer: numer / divisor,
+            denom: denom / divisor,
+        })
+    }
+
+    /// The numerator and denominator, in lowest terms.
+    pub fn parts(self) -> (u128, u128) {
+        (self.numer, self.denom)
+    }
+
+    /// The rate the other way round.
+    pub fn inverse(self) -> Rate {
+        Rate {
+            numer: self.denom,
+            denom: self.numer,
+        }
+    }
+
+    /// From `a` to `b` and then from `b` to `c`; `None` if it grows too large to hold.
+    pub fn then(self, next: Rate) -> Option<Rate> {
+        // cross-cancel first to keep the products small
+        let g1 = gcd(self.numer, next.denom);
+        let g2 = gcd(next.numer, self.denom);
+        Some(Rate {
+            numer: (self.numer / g1).checked_mul(next.numer / g2)?,
+            denom: (self.denom / g2).checked_mul(next.denom / g1)?,
+        })
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+}
+
+impl std::str::FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rate, String> {
+        let invalid = || format!("not a rate: {}", s);
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !digits(whole) || !digits(fraction) || fraction.len() > 30 {
+            return Err(invalid());
+        }
+        let numer: u128 = format!("{}{}", whole, fraction)
+            .parse()
+            .map_err(|_| invalid())?;
+        Rate::new(numer, 10u128.pow(fraction.len() as u32))
+            .ok_or_else(|| format!("a rate of zero: {}", s))
+    }
+}
+
+#[derive(Debug)]
+pub enum RatesFileError {
+    Io(io::Error),
+    /// A line of the file could not be understood.
+    Parse {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for RatesFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RatesFileError::Io(e) => write!(f, "rates file: {}", e),
+            RatesFileError::Parse { line, reason } => {
+                write!(f, "rates file line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl Error for RatesFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RatesFileError::Io(e) => Some(e),
+            RatesFileError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for RatesFileError {
+    fn from(e: io::Error) -> Self {
+        RatesFileError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConvertError {
+    UnknownCurrency(String),
+    /// No rate between the two, directly, the other way round or through a third currency.
+    NoRate {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// The result, or a step on the way to it, does not fit.
+    Overflow,
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::UnknownCurrency(code) => write!(f, "unknown currency {}", code),
+            ConvertError::NoRate { from, to } => {
+                write!(f, "no exchange rate from {} to {}", from, to)
+            }
+            ConvertError::Overflow => write!(f, "the converted amount is too large"),
+        }
+    }
+}
+
+impl Error for ConvertError {}
+
+/// Exchange rates between the currencies in `CURRENCIES`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExchangeRates {
+    rates: Vec<(&'static Currency, &'static Currency, Rate)>,
+}
+
+impl ExchangeRates {
+    pub fn new() -> ExchangeRates {
+        ExchangeRates::default()
+    }
+
+    /// One unit of `from` buys `rate` units of `to`. Replaces an earlier rate for the pair.
+    pub fn insert(&mut self, from: &'static Currency, to: &'static Currency, rate: Rate) {
+        match self
+            .rates
+            .iter_mut()
+            .find(|(f, t, _)| f.code == from.code && t.code == to.code)
+        {
+            Some(entry) => entry.2 = rate,
+            None => self.rates.push((from, to, rate)),
+        }
+    }
+
+    /// Reads lines of `FROM TO RATE`, like `USD EUR 0.92`. Blank lines and lines starting with
+    /// `#` are skipped. Each pair of currencies takes one rate, given one way round.
+    pub fn parse(text: &str) -> Result<ExchangeRates, RatesFileError> {
+        let mut rates = ExchangeRates::new();
+        for (i, line) in text.lines().enumerate() {
+            let parse_error = |reason: String| RatesFileError::Parse {
+                line: i + 1,
+                reason,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [from, to, rate] = fields[..] else {
+                return Err(parse_error(format!(
+                    "expected FROM TO RATE, found {:?}",
+                    line
+                )));
+            };
+            let currency = |code: &str| {
+                Currency::from_code(code)
+                    .ok_or_else(|| parse_error(format!("unknown currency {}", code)))
+            };
+            let (from, to) = (currency(from)?, currency(to)?);
+            if from == to {
+                return Err(parse_error(format!("a rate from {} to itself", from)));
+            }
+            if rates.direct(from, to).is_some() {
+                return Err(parse_error(format!(
+                    "a second rate from {} to {}",
+                    from, to
+                )));
+            }
+            if rates.direct(to, from).is_some() {
+                return Err(parse_error(format!(
+                    "a rate from {} to {} as well as one from {} to {}",
+                    from, to, to, from
+                )));
+            }
+            let rate = rate.parse().map_err(parse_error)?;
+            rates.insert(from, to, rate);
+        }
+        Ok(rates)
+    }
+
+    /// Reads a file in the form `parse` takes.
+    pub fn load(path: impl AsRef<Path>) -> Result<ExchangeRates, RatesFileError> {
+        ExchangeRates::parse(&fs::read_to_string(path)?)
+    }
+
+    fn direct(&self, from: &Currency, to: &Currency) -> Option<Rate> {
+        self.rates
+            .iter()
+            .find(|(f, t, _)| f.code == from.code && t.code == to.code)
+            .map(|&(_, _, rate)| rate)
+    }
+
+    /// A rate as given, or the inverse of the one given the other way.
+    fn either_way(&self, from: &Currency, to: &Currency) -> Option<Rate> {
+        self.direct(from, to)
+            .or_else(|| self.direct(to, from).map(Rate::inverse))
+    }
+
+    /// How many units of `to` one unit of `from` buys: the rate given for the pair, the
+    /// inverse of the one given the other way, or else through one other currency.
+    pub fn rate(&self, from: &str, to: &str) -> Result<Rate, ConvertError> {
+        let known = |code: &str| {
+            Currency::from_code(code).ok_or_else(|| ConvertError::UnknownCurrency(code.to_string()))
+        };
+        let (from, to) = (known(from)?, known(to)?);
+        if from == to {
+            return Ok(Rate { numer: 1, denom: 1 });
+        }
+        if let Some(rate) = self.either_way(from, to) {
+            return Ok(rate);
+        }
+        let mut overflowed = false;
+        for via in CURRENCIES {
+            let (Some(first), Some(second)) =
+                (self.either_way(from, via), self.either_way(via, to))
+            else {
+                continue;
+            };
+            match first.then(second) {
+                Some(rate) => return Ok(rate),
+                None => overflowed = true,
+            }
+        }
+        Err(if overflowed {
+            ConvertError::Overflow
+        } else {
+            ConvertError::NoRate {
+                from: from.code,
+                to: to.code,
+            }
+        })
+    }
+
+    /// `amount` minor units of `from` in minor units of `to`, to the nearest one, halves up.
+    pub fn convert(&self, amount: u64, from: &str, to: &str) -> Result<u64, ConvertError> {
+        let rate = self.rate(from, to)?;
+        let from = Currency::from_code(from).expect("rate checked the code");
+        let to = Currency::from_code(to).expect("rate checked the code");
+        let numer = u128::from(amount)
+            .checked_mul(rate.numer)
+            .and_then(|n| n.checked_mul(u128::from(to.minor_per_major())))
+            .ok_or(ConvertError::Overflow)?;
+        let denom = rate
+            .denom
+            .checked_mul(u128::from(from.minor_per_major()))
+            .ok_or(ConvertError::Overflow)?;
+        let (quotient, remainder) = (numer / denom, numer % denom);
+        let rounded = if remainder >= denom - remainder {
+            quotient + 1
+        } else {
+            quotient
+        };
+        u64::try_from(rounded).map_err(|_| ConvertError::Overflow)
+    }
+}
@@ -0,0 +1,502 @@
+//! Enum and Pattern Matching
+//!
+//! Enums allow you to define a type by enumerating its possible values.
+//! Enumerating is useful when we know all the possibilities.
+
+use crate::command::Command;
+use crate::currency::Denominated;
+use crate::message::{CallError, DrawState, Outcome};
+use crate::net::{IpNetwork, NextHop, RoutingTable};
+use crate::structs::Color;
+
+/// Any IP address can be either a version four or six address, but not at the same time.
+/// That property of IP addresses makes an enum appropriate, because an enum value can only be one
+/// of its variants. As they are still fundamentally IP addresses, they are treated as the same type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Command)]
+#[command(rename_all = "lowercase")]
+pub enum IpAddrKind {
+    V4,
+    V6,
+}
+
+/// Both `IpAddrKind::V4` and `IpAddrKind::V6` are of the same type, so a function can take any
+/// `IpAddrKind` and we can call it with either variant.
+///
+/// Returns the default route for that kind (`0.0.0.0/0` or `::/0`), the network a
+/// `RoutingTable` falls back to when nothing more specific matches.
+pub fn route(ip_kind: IpAddrKind) -> IpNetwork {
+    let unspecified = match ip_kind {
+        IpAddrKind::V4 => IpAddr::V4(0, 0, 0, 0),
+        IpAddrKind::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    IpNetwork::new(unspecified, 0).expect("a /0 fits every kind")
+}
+
+/// Each variant can have a different type of data.
+///
+/// Both variants can be parsed from and printed as text, see the `net` module.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IpAddr {
+    V4(u8, u8, u8, u8),
+    V6(Ipv6Addr),
+}
+
+/// A 128-bit IPv6 address stored as eight 16-bit groups, plus the zone (`%eth0`) it was scoped
+/// to, if any.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ipv6Addr {
+    segments: [u16; 8],
+    zone: Option<String>,
+}
+
+impl Ipv6Addr {
+    /// `::1`
+    pub const LOCALHOST: Ipv6Addr = Ipv6Addr::from_segments([0, 0, 0, 0, 0, 0, 0, 1]);
+    /// `::`
+    pub const UNSPECIFIED: Ipv6Addr = Ipv6Addr::from_segments([0; 8]);
+
+    pub const fn from_segments(segments: [u16; 8]) -> Ipv6Addr {
+        Ipv6Addr {
+            segments,
+            zone: None,
+        }
+    }
+
+    pub const fn from_bits(bits: u128) -> Ipv6Addr {
+        let mut segments = [0u16; 8];
+        let mut i = 0;
+        while i < 8 {
+            segments[i] = (bits >> (112 - 16 * i)) as u16;
+            i += 1;
+        }
+        Ipv6Addr::from_segments(segments)
+    }
+
+    pub fn from_octets(octets: [u8; 16]) -> Ipv6Addr {
+        Ipv6Addr::from_bits(u128::from_be_bytes(octets))
+    }
+
+    /// Scopes the address to a zone, e.g. the interface a link-local address belongs to.
+    pub fn with_zone(mut self, zone: impl Into<String>) -> Ipv6Addr {
+        self.zone = Some(zone.into());
+        self
+    }
+
+    /// The same address without its zone.
+    pub fn without_zone(mut self) -> Ipv6Addr {
+        self.zone = None;
+        self
+    }
+
+    pub const fn segments(&self) -> [u16; 8] {
+        self.segments
+    }
+
+    pub fn octets(&self) -> [u8; 16] {
+        self.to_bits().to_be_bytes()
+    }
+
+    pub fn to_bits(&self) -> u128 {
+        self.segments
+            .iter()
+            .fold(0, |bits, &segment| (bits << 16) | u128::from(segment))
+    }
+
+    pub fn zone(&self) -> Option<&str> {
+        self.zone.as_deref()
+    }
+
+    /// The IPv4 address inside an IPv4-mapped address (`::ffff:a.b.c.d`).
+    pub fn to_ipv4_mapped(&self) -> Option<Ipv4Addr> {
+        match self.octets() {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => {
+                Some(Ipv4Addr::new(a, b, c, d))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl IpAddr {
+    /// The kind of address stored in this variant.
+    pub fn kind(&self) -> IpAddrKind {
+        match self {
+            IpAddr::V4(..) => IpAddrKind::V4,
+            IpAddr::V6(_) => IpAddrKind::V6,
+        }
+    }
+
+    /// An IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) turned back into the IPv4 address it
+    /// stands for; every other address is returned as it is.
+    pub fn to_canonical(&self) -> IpAddr {
+        match self {
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(v4) => IpAddr::from(v4),
+                None => self.clone(),
+            },
+            IpAddr::V4(..) => self.clone(),
+        }
+    }
+}
+
+// The standard library IpAddr keeps a struct in each variant instead, this one is the V4 data on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ipv4Addr {
+    octets: [u8; 4],
+}
+
+impl Ipv4Addr {
+    /// `127.0.0.1`
+    pub const LOCALHOST: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+    /// `0.0.0.0`
+    pub const UNSPECIFIED: Ipv4Addr = Ipv4Addr::new(0, 0, 0, 0);
+    /// `255.255.255.255`
+    pub const BROADCAST: Ipv4Addr = Ipv4Addr::new(255, 255, 255, 255);
+
+    pub const fn new(a: u8, b: u8, c: u8, d: u8) -> Ipv4Addr {
+        Ipv4Addr {
+            octets: [a, b, c, d],
+        }
+    }
+
+    pub const fn from_octets(octets: [u8; 4]) -> Ipv4Addr {
+        Ipv4Addr { octets }
+    }
+
+    pub const fn from_bits(bits: u32) -> Ipv4Addr {
+        Ipv4Addr::from_octets(bits.to_be_bytes())
+    }
+
+    pub const fn octets(&self) -> [u8; 4] {
+        self.octets
+    }
+
+    pub const fn to_bits(&self) -> u32 {
+        u32::from_be_bytes(self.octets)
+    }
+
+    /// `::ffff:a.b.c.d`
+    pub const fn to_ipv6_mapped(&self) -> Ipv6Addr {
+        let [a, b, c, d] = self.octets;
+        Ipv6Addr::from_segments([
+            0,
+            0,
+            0,
+            0,
+            0,
+            0xffff,
+            u16::from_be_bytes([a, b]),
+            u16::from_be_bytes([c, d]),
+        ])
+    }
+}
+
+impl From<Ipv4Addr> for IpAddr {
+    fn from(ip: Ipv4Addr) -> IpAddr {
+        let [a, b, c, d] = ip.octets();
+        IpAddr::V4(a, b, c, d)
+    }
+}
+
+impl From<Ipv6Addr> for IpAddr {
+    fn from(ip: Ipv6Addr) -> IpAddr {
+        IpAddr::V6(ip)
+    }
+}
+
+// 💡 You can put any kind of data inside an enum variant: String, Numerics type or Structs
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Quit,
+    Move { x: i32, y: i32 },
+    Write(String),
+    ChangeColor(i32, i32, i32),
+}
+
+/// Which variant a `Message` is, without its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MessageKind {
+    Quit,
+    Move,
+    Write,
+    ChangeColor,
+}
+
+impl MessageKind {
+    pub const ALL: [MessageKind; 4] = [
+        MessageKind::Quit,
+        MessageKind::Move,
+        MessageKind::Write,
+        MessageKind::ChangeColor,
+    ];
+}
+
+impl Message {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Message::Quit => MessageKind::Quit,
+            Message::Move { .. } => MessageKind::Move,
+            Message::Write(_) => MessageKind::Write,
+            Message::ChangeColor(..) => MessageKind::ChangeColor,
+        }
+    }
+
+    /// Applies the message to `state`. `Move` is relative to the cursor.
+    pub fn call(&self, state: &mut DrawState) -> Result<Outcome, CallError> {
+        if !state.running {
+            return Err(CallError::Stopped);
+        }
+        // 💡 match binds the data inside each variant, so every arm gets its own fields
+        match self {
+            Message::Quit => {
+                state.running = false;
+                Ok(Outcome::Stopped)
+            }
+            Message::Move { x, y } => {
+                let from = state.cursor;
+                let to = from
+                    .0
+                    .checked_add(*x)
+                    .zip(from.1.checked_add(*y))
+                    .ok_or(CallError::CursorOverflow { dx: *x, dy: *y })?;
+                state.cursor = to;
+                Ok(Outcome::Moved { from, to })
+            }
+            Message::Write(text) => {
+                state.text.push_str(text);
+                Ok(Outcome::Wrote { len: text.len() })
+            }
+            Message::ChangeColor(r, g, b) => {
+                for (component, &value) in [r, g, b].into_iter().enumerate() {
+                    if !(0..=255).contains(&value) {
+                        return Err(CallError::ColorOutOfRange { component, value });
+                    }
+                }
+                let from = state.color;
+                state.color = Color(*r, *g, *b);
+                Ok(Outcome::ColorChanged {
+                    from,
+                    to: state.color,
+                })
+            }
+        }
+    }
+}
+
+// ! Patterns that bind to Values
+/// The 50 states in alphabetical order, then DC and the inhabited territories. Names, postal
+/// codes, FIPS codes, capitals and admission dates are in the `states` module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)] // so we can inspect the state in a minute
+pub enum UsState {
+    Alabama,
+    Alaska,
+    Arizona,
+    Arkansas,
+    California,
+    Colorado,
+    Connecticut,
+    Delaware,
+    Florida,
+    Georgia,
+    Hawaii,
+    Idaho,
+    Illinois,
+    Indiana,
+    Iowa,
+    Kansas,
+    Kentucky,
+    Louisiana,
+    Maine,
+    Maryland,
+    Massachusetts,
+    Michigan,
+    Minnesota,
+    Mississippi,
+    Missouri,
+    Montana,
+    Nebraska,
+    Nevada,
+    NewHampshire,
+    NewJersey,
+    NewMexico,
+    NewYork,
+    NorthCarolina,
+    NorthDakota,
+    Ohio,
+    Oklahoma,
+    Oregon,
+    Pennsylvania,
+    RhodeIsland,
+    SouthCarolina,
+    SouthDakota,
+    Tennessee,
+    Texas,
+    Utah,
+    Vermont,
+    Virginia,
+    Washington,
+    WestVirginia,
+    Wisconsin,
+    Wyoming,
+    DistrictOfColumbia,
+    AmericanSamoa,
+    Guam,
+    NorthernMarianaIslands,
+    PuertoRico,
+    UsVirginIslands,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Command)]
+pub enum Coin {
+    Penny,
+    Nickel,
+    Dime,
+    Quarter(UsState),
+}
+
+/// Which coin a `Coin` is, without the state on a quarter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CoinKind {
+    Penny,
+    Nickel,
+    Dime,
+    Quarter,
+}
+
+impl CoinKind {
+    /// Smallest to largest.
+    pub const ALL: [CoinKind; 4] = [
+        CoinKind::Penny,
+        CoinKind::Nickel,
+        CoinKind::Dime,
+        CoinKind::Quarter,
+    ];
+
+    /// Its value in `crate::currency::DENOMINATIONS`.
+    pub fn value_in_cents(self) -> u8 {
+        u8::try_from(self.value_in_minor_units()).expect("every US coin is worth under $2.56")
+    }
+}
+
+impl Coin {
+    pub fn kind(&self) -> CoinKind {
+        match self {
+            Coin::Penny => CoinKind::Penny,
+            Coin::Nickel => CoinKind::Nickel,
+            Coin::Dime => CoinKind::Dime,
+            Coin::Quarter(_) => CoinKind::Quarter,
+        }
+    }
+}
+
+/// The match arm has two parts: a pattern and some code. Unlike `if`, the value being matched can
+/// be any type rather than boolean-only. `Coin::kind` is the match here; the value comes from
+/// the table behind `CoinKind`, so the two can never disagree.
+// 💡 a u8 holds 255 cents at most; crate::money adds amounts up without a limit
+pub fn value_in_cents(coin: Coin) -> u8 {
+    coin.kind().value_in_cents()
+}
+
+// ! Matching with Option<T>
+// Matches are exhaustive: leaving out the `None` arm would not compile.
+// 💡 `i + 1` would overflow on i32::MAX, so that is None too; crate::numeric has the other
+// widths and overflow policies
+pub fn plus_one(x: Option<i32>) -> Option<i32> {
+    match x {
+        None => None,
+        Some(i) => i.checked_add(1),
+    }
+}
+
+pub fn demo() {
+    let four = IpAddrKind::V4;
+    let six = IpAddrKind::V6;
+    println!("the default routes are {} and {}", route(four), route(six));
+
+    let home = IpAddr::V4(127, 0, 0, 1);
+    let loopback = IpAddr::V6(Ipv6Addr::LOCALHOST);
+    println!("home is {} ({:?}, {:?})", home, home.kind(), home.scope());
+    println!(
+        "loopback is {} ({:?}, {:?})",
+        loopback,
+        loopback.kind(),
+        loopback.scope()
+    );
+
+    // 💡 Parsing goes through FromStr, so any str can be turned into an IpAddr with ~parse
+    for text in [
+        "192.168.0.1",
+        "2001:DB8:0:0:1:0:0:1",
+        "::ffff:10.0.0.1",
+        "fe80::1%eth0",
+        "256.0.0.1",
+    ] {
+        match text.parse::<IpAddr>() {
+            Ok(ip) => println!("{:?} parses as {}", text, ip),
+            Err(e) => println!("{:?} is not an address: {}", text, e),
+        }
+    }
+
+    let mut table = RoutingTable::new();
+    table.insert(
+        route(four),
+        NextHop::via(IpAddr::V4(192, 168, 0, 1)).with_dev("eth0"),
+    );
+    table.insert("10.0.0.0/8".parse().unwrap(), NextHop::dev("tun0"));
+    print!("{}", table);
+    if let Some((network, next_hop)) = table.lookup(&IpAddr::V4(10, 1, 2, 3)) {
+        println!("10.1.2.3 goes {} (matched {})", next_hop, network);
+    }
+
+    let m = Message::Write(String::from("hello"));
+    let mut state = DrawState::new();
+    println!("{:?} -> {:?}", m, m.call(&mut state));
+    let program = [
+        Message::Move { x: 3, y: -4 },
+        Message::ChangeColor(255, 0, 128),
+        Message::Quit,
+        Message::Write(String::from("too late")),
+    ];
+    match state.run(&program) {
+        Ok(outcomes) => println!("ran {} messages", outcomes.len()),
+        Err(e) => println!("{}", e),
+    }
+    println!("{:?}", state);
+
+    for coin in [
+        Coin::Penny,
+        Coin::Nickel,
+        Coin::Dime,
+        Coin::Quarter(UsState::Alaska),
+    ] {
+        if let Coin::Quarter(state) = coin {
+            println!("State quarter from {:?}!", state); // The binding for ~state will be UsState::Alaska
+            if let Some(date) = state.admitted() {
+                println!("{} joined the union on {}", state, date);
+            }
+        }
+        // 💡 `#[derive(Command)]` gives Coin a text form, so the coin can be printed as a command
+        println!("{} is worth {} cents", coin, value_in_cents(coin));
+    }
+    // 💡 and back again: crate::change finds the fewest coins for an amount
+    println!("68 cents is {:?}", crate::change::make_change(68));
+
+    let five = Some(5);
+    let six = plus_one(five);
+    let none = plus_one(None);
+    println!(
+        "plus_one({:?}) = {:?}, plus_one(None) = {:?}",
+        five, six, none
+    );
+
+    // ! The _ Placeholder
+    let some_u8_value = 0u8;
+    match some_u8_value {
+        1 => println!("one"),
+        3 => println!("three"),
+        5 => println!("five"),
+        7 => println!("seven"),
+        _ => println!("{} is not one, three, five or seven", some_u8_value),
+    }
+}
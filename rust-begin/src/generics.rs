@@ -0,0 +1,123 @@
+//! Generic types remove duplication: the same function, struct or enum can work over many
+//! concrete types, and trait bounds say what those types must be able to do.
+
+// Without Generic type we need one function per type
+pub fn largest_i32(list: &[i32]) -> i32 {
+    let mut largest = list[0];
+
+    for &item in list.iter() {
+        if item > largest {
+            largest = item;
+        }
+    }
+
+    largest
+}
+
+pub fn largest_char(list: &[char]) -> char {
+    let mut largest = list[0];
+
+    for &item in list.iter() {
+        if item > largest {
+            largest = item;
+        }
+    }
+
+    largest
+}
+
+/// The generic version. Comparing with `>` needs `PartialOrd`, and moving `list[0]` out of the
+/// slice needs `Copy`.
+///
+/// Panics if `list` is empty.
+pub fn largest<T: PartialOrd + Copy>(list: &[T]) -> T {
+    let mut largest = list[0];
+
+    for &item in list.iter() {
+        if item > largest {
+            largest = item;
+        }
+    }
+
+    largest
+}
+
+/// Returning a reference instead means no `Copy` or `Clone` bound and no heap allocations.
+pub fn largest_ref<T: PartialOrd>(list: &[T]) -> Option<&T> {
+    let mut iter = list.iter();
+    let mut largest = iter.next()?;
+
+    for item in iter {
+        if item > largest {
+            largest = item;
+        }
+    }
+
+    Some(largest)
+}
+
+//  Because We only use one ~T generic type, both x and y have to be the same type regardless of what type it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point<T> {
+    // By declaring ~T as a generic type after ~impl, Rust can identify that the type in the angle brackets in ~Point is a generic type
+    pub fn x(&self) -> &T {
+        &self.x
+    }
+}
+
+impl Point<f32> {
+    // only applies to ~Point instances with type ~f32 as a parameter
+    pub fn distance_from_origin(&self) -> f32 {
+        (self.x.powi(2) + self.y.powi(2)).sqrt()
+    }
+}
+
+//  <-- x and y doesnt have to be the same type -->
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MixedPoint<T, U> {
+    pub x: T,
+    pub y: U,
+}
+
+impl<T, U> MixedPoint<T, U> {
+    pub fn mixup<V, W>(self, other: MixedPoint<V, W>) -> MixedPoint<T, W> {
+        MixedPoint {
+            x: self.x,
+            y: other.y,
+        }
+    }
+}
+
+pub fn demo() {
+    let number_list = vec![34, 50, 25, 100, 65];
+    println!("The largest number is {}", largest_i32(&number_list));
+
+    let char_list = vec!['y', 'm', 'a', 'q'];
+    println!("The largest char is {}", largest_char(&char_list));
+
+    let number_list = vec![102, 34, 6000, 89, 54, 2, 43, 8];
+    println!("The largest number is {}", largest(&number_list));
+    println!("The largest char is {}", largest(&char_list));
+
+    let words = vec![String::from("apple"), String::from("pear")];
+    println!("The largest word is {:?}", largest_ref(&words));
+
+    let integer = Point { x: 5, y: 10 };
+    let float = Point { x: 1.0f32, y: 4.0 };
+    println!("integer.x = {}", integer.x());
+    println!(
+        "float is {:?}, {} away from the origin",
+        float,
+        float.distance_from_origin()
+    );
+
+    let p1 = MixedPoint { x: 5, y: 10.4 };
+    let p2 = MixedPoint { x: "Hello", y: 'c' };
+    let p3 = p1.mixup(p2);
+    println!("p3.x = {}, p3.y = {}", p3.x, p3.y);
+}
@@ -0,0 +1,82 @@
+//! Lessons from learning Rust, collected into a library.
+//!
+//! Every module is one topic from the loose notes next to this crate (`enum.rs`, `struct.rs`, ...),
+//! rewritten so that it compiles and the types can be reused. Each lesson has a `demo` function
+//! that prints what the lesson is about; the `rust-begin run <lesson>` binary calls it.
+
+// lets `#[derive(Command)]` name this crate `::rust_begin` from inside it too
+extern crate self as rust_begin;
+
+pub mod change;
+pub mod collections;
+pub mod command;
+pub mod credentials;
+pub mod currency;
+pub mod enums;
+pub mod generics;
+pub mod lifetimes;
+pub mod message;
+pub mod money;
+pub mod net;
+pub mod numeric;
+pub mod quarters;
+pub mod states;
+pub mod strings;
+pub mod structs;
+pub mod traits;
+pub mod users;
+pub mod vending;
+
+mod atomic;
+mod timestamp;
+
+/// A lesson that can be run from the command line.
+pub struct Lesson {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub demo: fn(),
+}
+
+/// All lessons, in the order they are taught.
+pub const LESSONS: &[Lesson] = &[
+    Lesson {
+        name: "structs",
+        about: "Defining structs, methods and associated functions",
+        demo: structs::demo,
+    },
+    Lesson {
+        name: "enums",
+        about: "Enums, Option<T> and the match control flow operator",
+        demo: enums::demo,
+    },
+    Lesson {
+        name: "collections",
+        about: "Vectors and hash maps",
+        demo: collections::demo,
+    },
+    Lesson {
+        name: "strings",
+        about: "Creating, updating, slicing and iterating over strings",
+        demo: strings::demo,
+    },
+    Lesson {
+        name: "generics",
+        about: "Generic functions, structs and methods",
+        demo: generics::demo,
+    },
+    Lesson {
+        name: "traits",
+        about: "Defining shared behavior with traits and trait bounds",
+        demo: traits::demo,
+    },
+    Lesson {
+        name: "lifetimes",
+        about: "Validating references with lifetimes",
+        demo: lifetimes::demo,
+    },
+];
+
+/// Looks a lesson up by its name.
+pub fn find_lesson(name: &str) -> Option<&'static Lesson> {
+    LESSONS.iter().find(|lesson| lesson.name == name)
+}
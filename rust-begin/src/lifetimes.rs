@@ -0,0 +1,94 @@
+//! Every reference in Rust has a lifetime, which is the scope for which that reference is valid.
+//!
+//! Lifetime annotations don't change how long any of the references live. They describe the
+//! relationships of the lifetimes of multiple references to each other, so the borrow checker can
+//! reject any values that don't adhere to these constraints.
+
+use std::fmt::Display;
+
+/// All references in the parameters and the return value must have the same lifetime.
+/// In practice the returned reference lives as long as the shorter of the two inputs.
+pub fn longest<'a>(x: &'a str, y: &'a str) -> &'a str {
+    if x.len() > y.len() {
+        x
+    } else {
+        y
+    }
+}
+
+/// The lifetime of `y` has no relationship with the lifetime of `x` or the return value.
+pub fn first_of<'a>(x: &'a str, _y: &str) -> &'a str {
+    x
+}
+
+/// This annotation means an instance of `ImportantExcerpt` can't outlive the reference it holds
+/// in its `part` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportantExcerpt<'a> {
+    pub part: &'a str,
+}
+
+impl<'a> ImportantExcerpt<'a> {
+    pub fn level(&self) -> i32 {
+        3
+    }
+
+    // 3rd elision rule: the lifetime of &self is assigned to the output
+    pub fn announce_and_return_part(&self, announcement: &str) -> &str {
+        println!("Attention please: {}", announcement);
+        self.part
+    }
+}
+
+/// Compiles without annotations thanks to lifetime elision: there is exactly one input lifetime,
+/// so it is assigned to the output (2nd rule).
+pub fn first_word(s: &str) -> &str {
+    let bytes = s.as_bytes();
+
+    for (i, &item) in bytes.iter().enumerate() {
+        if item == b' ' {
+            return &s[0..i];
+        }
+    }
+
+    s
+}
+
+// ! Generic Type Parameters, Trait Bounds, and Lifetimes Together
+pub fn longest_with_an_announcement<'a, T>(x: &'a str, y: &'a str, ann: T) -> &'a str
+where
+    T: Display,
+{
+    println!("Announcement! {}", ann);
+    if x.len() > y.len() {
+        x
+    } else {
+        y
+    }
+}
+
+pub fn demo() {
+    let string1 = String::from("long string is long");
+    {
+        let string2 = String::from("xyz");
+        let result = longest(string1.as_str(), string2.as_str()); // the result has the lifetime of ~string2, the shorter one
+        println!("The longest string is {}", result);
+    }
+
+    let novel = String::from("Call me Ishmael. Some years ago...");
+    let first_sentence = novel.split('.').next().expect("Could not find a '.'");
+    let i = ImportantExcerpt {
+        part: first_sentence,
+    };
+    println!("The excerpt is {:?} (level {})", i.part, i.level());
+    println!("It returned {:?}", i.announce_and_return_part("read this"));
+
+    println!("The first word of {:?} is {:?}", novel, first_word(&novel));
+
+    // All string literals have the 'static lifetime
+    let s: &'static str = "I have a static lifetime.";
+    println!(
+        "The longest is {}",
+        longest_with_an_announcement(s, first_sentence, "Today is someone's birthday!")
+    );
+}
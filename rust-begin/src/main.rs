@@ -0,0 +1,32 @@
+use std::env;
+use std::process;
+
+use rust_begin::{find_lesson, LESSONS};
+
+fn usage() -> ! {
+    eprintln!("usage: rust-begin run <lesson>");
+    eprintln!("       rust-begin list");
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["list"] => {
+            for lesson in LESSONS {
+                println!("{:<12} {}", lesson.name, lesson.about);
+            }
+        }
+        ["run", name] => match find_lesson(name) {
+            Some(lesson) => (lesson.demo)(),
+            None => {
+                eprintln!("unknown lesson `{}`, try `rust-begin list`", name);
+                process::exit(1);
+            }
+        },
+        _ => usage(),
+    }
+}
@@ -0,0 +1,381 @@
+//! A publish/subscribe bus that fans `Message` values out to subscribers on other threads.
+//!
+//! Every subscriber has its own bounded queue and picks which kinds of message it wants.
+//! When a queue is full the subscriber's `Overflow` policy decides what happens: the publisher
+//! waits for room, the oldest queued message is dropped, or the new one is.
+//!
+//! Publishing `Message::Quit` shuts the bus down: the `Quit` itself is still delivered to the
+//! subscribers that asked for it, later publishes fail, and subscribers receive whatever is
+//! left in their queues before `recv` returns `None`. Dropping the last `Bus` handle does the
+//! same without a `Quit`.
+//!
+//! Messages from one publisher reach each subscriber in the order they were published.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use crate::enums::{Message, MessageKind};
+
+/// What to do when a message arrives for a full queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    /// The publisher waits until the subscriber makes room.
+    Block,
+    /// The oldest queued message is dropped to make room.
+    DropOldest,
+    /// The new message is dropped.
+    DropNewest,
+}
+
+/// Delivery counts for one subscriber.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubscriberStats {
+    /// Messages put in the queue.
+    pub delivered: u64,
+    /// Messages skipped because their kind is not subscribed to.
+    pub filtered: u64,
+    /// Messages lost to a full queue, under either drop policy.
+    pub dropped: u64,
+    /// Times a publisher had to wait for room.
+    pub blocked: u64,
+    /// Messages taken out of the queue by the subscriber.
+    pub received: u64,
+    /// Messages waiting in the queue right now.
+    pub queued: usize,
+}
+
+/// `publish` on a bus that has shut down; the message is handed back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusClosed(pub Message);
+
+impl fmt::Display for BusClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the bus has shut down")
+    }
+}
+
+impl Error for BusClosed {}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug)]
+struct QueueState {
+    messages: VecDeque<Message>,
+    /// No more messages will arrive.
+    closed: bool,
+    /// The `Subscriber` was dropped, nobody will take messages out.
+    detached: bool,
+    stats: SubscriberStats,
+}
+
+#[derive(Debug)]
+struct Queue {
+    id: u64,
+    kinds: Vec<MessageKind>,
+    capacity: usize,
+    overflow: Overflow,
+    state: Mutex<QueueState>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl Queue {
+    /// Offers a message; true if it was queued.
+    fn push(&self, message: &Message) -> bool {
+        let mut state = lock(&self.state);
+        if !self.kinds.contains(&message.kind()) {
+            state.stats.filtered += 1;
+            return false;
+        }
+        if state.closed || state.detached {
+            return false;
+        }
+        if state.messages.len() == self.capacity {
+            match self.overflow {
+                Overflow::Block => {
+                    state.stats.blocked += 1;
+                    state = self
+                        .not_full
+                        .wait_while(state, |s| {
+                            s.messages.len() == self.capacity && !s.closed && !s.detached
+                        })
+                        .unwrap_or_else(|e| e.into_inner());
+                    if state.closed || state.detached {
+                        return false;
+                    }
+                }
+                Overflow::DropOldest => {
+                    state.messages.pop_front();
+                    state.stats.dropped += 1;
+                }
+                Overflow::DropNewest => {
+                    state.stats.dropped += 1;
+                    return false;
+                }
+            }
+        }
+        state.messages.push_back(message.clone());
+        state.stats.delivered += 1;
+        self.not_empty.notify_one();
+        true
+    }
+
+    fn close(&self) {
+        lock(&self.state).closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+/// Whether the bus has shut down, and how many publishes are under way.
+///
+/// Only held for a moment, never while a publish waits on a full queue, so a subscriber can
+/// still ask whether the bus is closed while a `Quit` waits for a blocked publisher.
+#[derive(Debug, Default)]
+struct Gate {
+    closed: bool,
+    publishing: usize,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    queues: Mutex<Vec<Arc<Queue>>>,
+    gate: Mutex<Gate>,
+    /// Signalled when the last publish under way finishes, so that `Quit` can go out behind
+    /// everything published before it.
+    idle: Condvar,
+    next_id: AtomicU64,
+}
+
+impl Inner {
+    /// The subscribers still listening.
+    fn live_queues(&self) -> Vec<Arc<Queue>> {
+        let mut queues = lock(&self.queues);
+        queues.retain(|queue| !lock(&queue.state).detached);
+        queues.clone()
+    }
+
+    fn close_queues(&self) {
+        for queue in lock(&self.queues).iter() {
+            queue.close();
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        lock(&self.gate).closed = true;
+        self.close_queues();
+    }
+}
+
+/// A handle to the bus. Clones share it, so each producer thread can have its own.
+#[derive(Debug, Clone, Default)]
+pub struct Bus {
+    inner: Arc<Inner>,
+}
+
+impl Bus {
+    pub fn new() -> Bus {
+        Bus::default()
+    }
+
+    /// Starts a subscription: every kind of message, a queue of 64 and `Overflow::Block`
+    /// unless changed.
+    pub fn subscriber(&self) -> SubscriberBuilder<'_> {
+        SubscriberBuilder {
+            bus: self,
+            kinds: MessageKind::ALL.to_vec(),
+            capacity: 64,
+            overflow: Overflow::Block,
+        }
+    }
+
+    /// Hands the message to every subscriber, and returns how many queued it.
+    ///
+    /// With `Overflow::Block` subscribers this can wait for them to catch up. Publishing
+    /// `Quit` shuts the bus down once it has been delivered.
+    pub fn publish(&self, message: Message) -> Result<usize, BusClosed> {
+        if message == Message::Quit {
+            return self.quit();
+        }
+        {
+            let mut gate = lock(&self.inner.gate);
+            if gate.closed {
+                return Err(BusClosed(message));
+            }
+            gate.publishing += 1;
+        }
+        let queues = self.inner.live_queues();
+        let delivered = queues.iter().filter(|queue| queue.push(&message)).count();
+        let mut gate = lock(&self.inner.gate);
+        gate.publishing -= 1;
+        if gate.publishing == 0 {
+            self.inner.idle.notify_all();
+        }
+        Ok(delivered)
+    }
+
+    /// Delivers `Quit` once every publish already under way has finished, then shuts down.
+    /// Publishes that start after this are refused straight away.
+    fn quit(&self) -> Result<usize, BusClosed> {
+        {
+            let mut gate = lock(&self.inner.gate);
+            if gate.closed {
+                return Err(BusClosed(Message::Quit));
+            }
+            gate.closed = true;
+            let _idle = self
+                .inner
+                .idle
+                .wait_while(gate, |gate| gate.publishing > 0)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        let queues = self.inner.live_queues();
+        let delivered = queues
+            .iter()
+            .filter(|queue| queue.push(&Message::Quit))
+            .count();
+        self.inner.close_queues();
+        Ok(delivered)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        lock(&self.inner.gate).closed
+    }
+
+    /// Statistics of every current subscriber, by subscriber id.
+    pub fn stats(&self) -> Vec<(u64, SubscriberStats)> {
+        lock(&self.inner.queues)
+            .iter()
+            .map(|queue| (queue.id, stats_of(queue)))
+            .collect()
+    }
+}
+
+fn stats_of(queue: &Queue) -> SubscriberStats {
+    let state = lock(&queue.state);
+    SubscriberStats {
+        queued: state.messages.len(),
+        ..state.stats
+    }
+}
+
+/// Options for a new subscriber, from `Bus::subscriber`.
+#[derive(Debug)]
+pub struct SubscriberBuilder<'a> {
+    bus: &'a Bus,
+    kinds: Vec<MessageKind>,
+    capacity: usize,
+    overflow: Overflow,
+}
+
+impl SubscriberBuilder<'_> {
+    /// Only these kinds of message are delivered.
+    pub fn kinds(mut self, kinds: &[MessageKind]) -> Self {
+        self.kinds = kinds.to_vec();
+        self
+    }
+
+    /// The most messages the queue holds; at least 1.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Registers the subscriber. On a bus that has shut down it starts out closed.
+    pub fn subscribe(self) -> Subscriber {
+        let inner = &self.bus.inner;
+        let queue = Arc::new(Queue {
+            id: inner.next_id.fetch_add(1, Ordering::Relaxed),
+            kinds: self.kinds,
+            capacity: self.capacity,
+            overflow: self.overflow,
+            state: Mutex::new(QueueState {
+                messages: VecDeque::with_capacity(self.capacity.min(1024)),
+                closed: false,
+                detached: false,
+                stats: SubscriberStats::default(),
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        });
+        let gate = lock(&inner.gate);
+        if gate.closed {
+            queue.close();
+        } else {
+            lock(&inner.queues).push(Arc::clone(&queue));
+        }
+        Subscriber { queue }
+    }
+}
+
+/// The receiving end of a subscription. Dropping it unsubscribes.
+#[derive(Debug)]
+pub struct Subscriber {
+    queue: Arc<Queue>,
+}
+
+impl Subscriber {
+    pub fn id(&self) -> u64 {
+        self.queue.id
+    }
+
+    /// Waits for the next message. `None` once the bus has shut down and the queue is empty.
+    pub fn recv(&self) -> Option<Message> {
+        let state = lock(&self.queue.state);
+        let mut state = self
+            .queue
+            .not_empty
+            .wait_while(state, |s| s.messages.is_empty() && !s.closed)
+            .unwrap_or_else(|e| e.into_inner());
+        self.take(&mut state)
+    }
+
+    /// The next message if one is waiting.
+    pub fn try_recv(&self) -> Option<Message> {
+        self.take(&mut lock(&self.queue.state))
+    }
+
+    fn take(&self, state: &mut QueueState) -> Option<Message> {
+        let message = state.messages.pop_front()?;
+        state.stats.received += 1;
+        self.queue.not_full.notify_one();
+        Some(message)
+    }
+
+    /// Whether the bus has shut down. Messages may still be queued.
+    pub fn is_closed(&self) -> bool {
+        lock(&self.queue.state).closed
+    }
+
+    pub fn stats(&self) -> SubscriberStats {
+        stats_of(&self.queue)
+    }
+}
+
+/// Receives until the bus shuts down and the queue is drained.
+impl Iterator for &Subscriber {
+    type Item = Message;
+
+    fn next(&mut self) -> Option<Message> {
+        self.recv()
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        lock(&self.queue.state).detached = true;
+        self.queue.not_full.notify_all();
+    }
+}
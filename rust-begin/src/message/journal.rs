@@ -0,0 +1,480 @@
+//! An append-only journal of the messages applied to a `DrawState`, for replaying them later.
+//!
+//! # File format
+//!
+//! The file starts with the magic bytes `RBJ` and a version byte, currently 1. Records follow
+//! back to back:
+//!
+//! | bytes | field                                              |
+//! |-------|----------------------------------------------------|
+//! | 4     | payload length, little-endian                      |
+//! | 4     | CRC-32 (IEEE) of the payload, little-endian        |
+//! | 8     | sequence number, little-endian, counting from 1    |
+//! | 1     | entry kind: 0 message, 1 undo, 2 redo              |
+//! | rest  | the message in the `wire` body encoding, if any    |
+//!
+//! The payload is everything after the checksum.
+//!
+//! # Recovery
+//!
+//! A crash in the middle of an append leaves a torn record at the end of the file. Opening
+//! the journal keeps every record up to the first one that is cut short or fails its
+//! checksum, and truncates the file there. `Recovery` says how much was dropped.
+//!
+//! # Undo
+//!
+//! Undo and redo are journaled too, so the file is never rewritten. A `Session` keeps a
+//! snapshot of the state every few messages and recomputes the state after an undo from the
+//! nearest snapshot plus the messages after it.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::wire::{self, Frame};
+use super::{CallError, DrawState, Outcome};
+use crate::enums::Message;
+
+const MAGIC: &[u8; 3] = b"RBJ";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 4;
+/// Length and checksum.
+const RECORD_HEADER_LEN: usize = 8;
+/// Records longer than this are taken to be garbage rather than read into memory.
+const MAX_PAYLOAD_LEN: u32 = 16 * 1024 * 1024;
+
+const KIND_MESSAGE: u8 = 0;
+const KIND_UNDO: u8 = 1;
+const KIND_REDO: u8 = 2;
+
+/// One record of the journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    Message(Message),
+    Undo,
+    Redo,
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    Io(io::Error),
+    /// The file is not a journal, or a version this code does not know.
+    BadHeader,
+    /// A record with a valid checksum that still makes no sense; this is not a torn write.
+    Corrupt {
+        seq: u64,
+        reason: String,
+    },
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Io(e) => write!(f, "journal: {}", e),
+            JournalError::BadHeader => write!(f, "not a version {} journal", VERSION),
+            JournalError::Corrupt { seq, reason } => {
+                write!(f, "journal record {} is corrupt: {}", seq, reason)
+            }
+        }
+    }
+}
+
+impl Error for JournalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JournalError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for JournalError {
+    fn from(e: io::Error) -> Self {
+        JournalError::Io(e)
+    }
+}
+
+/// What opening a journal found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Recovery {
+    /// Records kept.
+    pub records: u64,
+    /// Bytes of torn tail cut off the end of the file.
+    pub truncated: u64,
+}
+
+/// CRC-32 with the IEEE polynomial, as used by zip and Ethernet.
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (crc >> 8) ^ TABLE[((crc ^ u32::from(byte)) & 0xff) as usize]
+    })
+}
+
+fn encode_entry(seq: u64, entry: &JournalEntry) -> Vec<u8> {
+    let mut payload = seq.to_le_bytes().to_vec();
+    match entry {
+        JournalEntry::Message(message) => {
+            payload.push(KIND_MESSAGE);
+            wire::encode_into(message, &mut payload);
+        }
+        JournalEntry::Undo => payload.push(KIND_UNDO),
+        JournalEntry::Redo => payload.push(KIND_REDO),
+    }
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32(&payload).to_le_bytes());
+    record.extend_from_slice(&payload);
+    record
+}
+
+fn decode_entry(expected_seq: u64, payload: &[u8]) -> Result<JournalEntry, JournalError> {
+    let corrupt = |reason: String| JournalError::Corrupt {
+        seq: expected_seq,
+        reason,
+    };
+    let [s0, s1, s2, s3, s4, s5, s6, s7, kind, ref body @ ..] = *payload else {
+        return Err(corrupt("record too short".to_string()));
+    };
+    let seq = u64::from_le_bytes([s0, s1, s2, s3, s4, s5, s6, s7]);
+    if seq != expected_seq {
+        return Err(corrupt(format!("out of order, numbered {}", seq)));
+    }
+    match (kind, body) {
+        (KIND_MESSAGE, body) => match wire::decode(body) {
+            Ok(Frame::Message(message)) => Ok(JournalEntry::Message(message)),
+            Ok(Frame::Unknown { tag, .. }) => Err(corrupt(format!("unknown message tag {}", tag))),
+            Err(e) => Err(corrupt(e.to_string())),
+        },
+        (KIND_UNDO, []) => Ok(JournalEntry::Undo),
+        (KIND_REDO, []) => Ok(JournalEntry::Redo),
+        (kind, _) => Err(corrupt(format!("unknown entry kind {}", kind))),
+    }
+}
+
+/// The journal file and the entries read from it.
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+    entries: Vec<JournalEntry>,
+    recovery: Recovery,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, creating it if needed, and recovers from a torn tail.
+    pub fn open(path: impl AsRef<Path>) -> Result<Journal, JournalError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let header: [u8; HEADER_LEN] = [MAGIC[0], MAGIC[1], MAGIC[2], VERSION];
+        if bytes.len() < header.len() && header.starts_with(&bytes) {
+            // empty, or the crash came while the header was being written
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&header)?;
+            file.sync_all()?;
+            return Ok(Journal {
+                file,
+                entries: Vec::new(),
+                recovery: Recovery {
+                    records: 0,
+                    truncated: bytes.len() as u64,
+                },
+            });
+        }
+        if bytes[..header.len().min(bytes.len())] != header {
+            return Err(JournalError::BadHeader);
+        }
+
+        let mut entries = Vec::new();
+        let mut good = HEADER_LEN;
+        while let Some(payload) = read_record(&bytes[good..]) {
+            entries.push(decode_entry(entries.len() as u64 + 1, payload)?);
+            good += RECORD_HEADER_LEN + payload.len();
+        }
+        let truncated = (bytes.len() - good) as u64;
+        if truncated > 0 {
+            file.set_len(good as u64)?;
+            file.sync_all()?;
+        }
+        file.seek(SeekFrom::End(0))?;
+        Ok(Journal {
+            file,
+            recovery: Recovery {
+                records: entries.len() as u64,
+                truncated,
+            },
+            entries,
+        })
+    }
+
+    /// What `open` found and repaired.
+    pub fn recovery(&self) -> Recovery {
+        self.recovery
+    }
+
+    /// Appends an entry and waits for it to reach the disk. Returns its sequence number.
+    pub fn append(&mut self, entry: JournalEntry) -> Result<u64, JournalError> {
+        let seq = self.entries.len() as u64 + 1;
+        let end = self.file.stream_position()?;
+        if let Err(e) = self
+            .file
+            .write_all(&encode_entry(seq, &entry))
+            .and_then(|()| self.file.sync_data())
+        {
+            // do not leave half a record for the next append to land behind
+            self.file.set_len(end).ok();
+            self.file.seek(SeekFrom::Start(end)).ok();
+            return Err(e.into());
+        }
+        self.entries.push(entry);
+        Ok(seq)
+    }
+
+    /// Entry number `seq`, counting from 1.
+    pub fn get(&self, seq: u64) -> Option<&JournalEntry> {
+        self.entries.get(usize::try_from(seq).ok()?.checked_sub(1)?)
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Sequence number of the last entry, 0 when empty.
+    pub fn last_seq(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// The state after entries 1 to `seq`, starting from a fresh `DrawState`.
+    pub fn replay(&self, seq: u64) -> DrawState {
+        let mut history = History::new(usize::MAX);
+        for entry in self.entries.iter().take(seq.min(self.last_seq()) as usize) {
+            history.apply_entry(entry);
+        }
+        history.state
+    }
+}
+
+/// The payload of the record at the front of `bytes`, if it is whole and its checksum holds.
+fn read_record(bytes: &[u8]) -> Option<&[u8]> {
+    let header: [u8; RECORD_HEADER_LEN] = bytes.get(..RECORD_HEADER_LEN)?.try_into().ok()?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    if len > MAX_PAYLOAD_LEN {
+        return None;
+    }
+    let payload = bytes.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len as usize)?;
+    (crc32(payload) == crc).then_some(payload)
+}
+
+/// The messages in effect and those undone, with snapshots to recompute from.
+#[derive(Debug)]
+struct History {
+    state: DrawState,
+    applied: Vec<Message>,
+    undone: Vec<Message>,
+    /// `snapshots[i]` is the state after the first `i * every` applied messages.
+    snapshots: Vec<DrawState>,
+    every: usize,
+}
+
+impl History {
+    fn new(every: usize) -> History {
+        History {
+            state: DrawState::new(),
+            applied: Vec::new(),
+            undone: Vec::new(),
+            snapshots: vec![DrawState::new()],
+            every: every.max(1),
+        }
+    }
+
+    /// Applies a message on top of the current state, dropping what was undone.
+    fn push(&mut self, message: Message, state: DrawState) {
+        self.undone.clear();
+        // snapshots past this point belonged to the undone branch
+        self.snapshots.truncate(self.applied.len() / self.every + 1);
+        self.applied.push(message);
+        self.state = state;
+        if self.applied.len().is_multiple_of(self.every) {
+            self.snapshots.push(self.state.clone());
+        }
+    }
+
+    /// Recomputes the state from the nearest snapshot.
+    fn rebuild(&mut self) {
+        let base = (self.applied.len() / self.every).min(self.snapshots.len() - 1);
+        self.state = self.snapshots[base].clone();
+        for message in &self.applied[base * self.every..] {
+            // these calls succeeded the first time, and calls are deterministic
+            let _ = message.call(&mut self.state);
+        }
+    }
+
+    fn undo(&mut self) -> bool {
+        let Some(message) = self.applied.pop() else {
+            return false;
+        };
+        self.undone.push(message);
+        self.rebuild();
+        true
+    }
+
+    fn redo(&mut self) -> bool {
+        let Some(message) = self.undone.pop() else {
+            return false;
+        };
+        let _ = message.call(&mut self.state);
+        self.applied.push(message);
+        // the snapshot for this depth survives from before the undo unless it was never taken
+        let depth = self.applied.len();
+        if depth.is_multiple_of(self.every) && self.snapshots.len() == depth / self.every {
+            self.snapshots.push(self.state.clone());
+        }
+        true
+    }
+
+    fn apply_entry(&mut self, entry: &JournalEntry) {
+        match entry {
+            JournalEntry::Message(message) => {
+                let mut state = self.state.clone();
+                if message.call(&mut state).is_ok() {
+                    self.push(message.clone(), state);
+                }
+            }
+            JournalEntry::Undo => {
+                self.undo();
+            }
+            JournalEntry::Redo => {
+                self.redo();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Call(CallError),
+    Journal(JournalError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Call(e) => e.fmt(f),
+            SessionError::Journal(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for SessionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SessionError::Call(e) => Some(e),
+            SessionError::Journal(e) => Some(e),
+        }
+    }
+}
+
+impl From<CallError> for SessionError {
+    fn from(e: CallError) -> Self {
+        SessionError::Call(e)
+    }
+}
+
+impl From<JournalError> for SessionError {
+    fn from(e: JournalError) -> Self {
+        SessionError::Journal(e)
+    }
+}
+
+/// A `DrawState` whose every change is journaled, with undo and redo.
+#[derive(Debug)]
+pub struct Session {
+    journal: Journal,
+    history: History,
+}
+
+impl Session {
+    /// Opens the journal and rebuilds the state from it, snapshotting every
+    /// `snapshot_every` messages.
+    pub fn open(path: impl AsRef<Path>, snapshot_every: usize) -> Result<Session, JournalError> {
+        let journal = Journal::open(path)?;
+        let mut history = History::new(snapshot_every);
+        for entry in journal.entries() {
+            history.apply_entry(entry);
+        }
+        Ok(Session { journal, history })
+    }
+
+    pub fn state(&self) -> &DrawState {
+        &self.history.state
+    }
+
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Calls the message and journals it. A message that fails changes nothing and is not
+    /// journaled.
+    pub fn apply(&mut self, message: &Message) -> Result<Outcome, SessionError> {
+        let mut state = self.history.state.clone();
+        let outcome = message.call(&mut state)?;
+        self.journal
+            .append(JournalEntry::Message(message.clone()))?;
+        self.history.push(message.clone(), state);
+        Ok(outcome)
+    }
+
+    /// Takes back the last message still in effect; false if there is none.
+    pub fn undo(&mut self) -> Result<bool, JournalError> {
+        if self.history.applied.is_empty() {
+            return Ok(false);
+        }
+        self.journal.append(JournalEntry::Undo)?;
+        Ok(self.history.undo())
+    }
+
+    /// Applies the last undone message again; false if there is none.
+    pub fn redo(&mut self) -> Result<bool, JournalError> {
+        if self.history.undone.is_empty() {
+            return Ok(false);
+        }
+        self.journal.append(JournalEntry::Redo)?;
+        Ok(self.history.redo())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.applied.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.undone.is_empty()
+    }
+}
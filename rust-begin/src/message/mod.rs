@@ -0,0 +1,22 @@
+//! Running `Message` values from the enums lesson as commands.
+//!
+//! `state` holds the drawing state that `Message::call` works on. `text` reads and prints
+//! messages as commands such as `move 3 -4`, and `wire` encodes them as bytes so they can travel
+//! between processes. `bus` hands messages from publishers to subscribers on other threads, and
+//! `journal` records them in a file to replay, undo and redo. `remote` serves a state to
+//! clients on other processes over loopback TCP.
+
+mod bus;
+mod journal;
+mod remote;
+mod state;
+mod text;
+pub mod wire;
+
+pub use bus::{Bus, BusClosed, Overflow, Subscriber, SubscriberBuilder, SubscriberStats};
+pub use journal::{Journal, JournalEntry, JournalError, Recovery, Session, SessionError};
+pub use remote::{Client, ClientError, Reply, Server, ServerBuilder, ServerHandle};
+pub use state::{CallError, DrawState, Outcome, ProgramError};
+pub use text::{parse_script, ParseError, ParseErrorKind};
+
+pub(crate) use text::{unescape, write_quoted, BadEscape};
@@ -0,0 +1,563 @@
+//! Driving a `DrawState` in another process by sending it messages over loopback TCP.
+//!
+//! The client sends a `wire` message stream. The server sends back a reply stream: the magic
+//! bytes `RBR` and a version byte, then one frame per reply. The first reply is `Ready`, or
+//! `Busy` when the server already has as many connections as it allows. After that every
+//! message frame gets exactly one reply, in order.
+//!
+//! # Reply body
+//!
+//! | tag | reply      | fields                                                      |
+//! |-----|------------|-------------------------------------------------------------|
+//! | 0   | `Ready`    | none                                                        |
+//! | 1   | `Ack`      | the `Outcome`, below                                        |
+//! | 2   | `Rejected` | the `CallError`, below                                      |
+//! | 3   | `Malformed`| the reason, as a varint byte length and UTF-8               |
+//! | 4   | `Busy`     | none                                                        |
+//! | 5   | `TimedOut` | none                                                        |
+//!
+//! An `Outcome` is a varint tag, 0 `Stopped`, 1 `Moved` with `from` and `to`, 2 `Wrote` with the
+//! length as a varint, 3 `ColorChanged` with `from` and `to`. A `CallError` is 0 `Stopped`, 1
+//! `ColorOutOfRange` with the component as a varint and the value, 2 `CursorOverflow` with
+//! `dx` and `dy`. Points and colors are their components in order; all integers other than
+//! tags and lengths are signed varints, as in `wire`.
+//!
+//! The server closes the connection after `Busy`, `TimedOut`, and a `Malformed` reply to input
+//! it could not find the next frame in. A frame whose body does not decode only gets
+//! `Malformed`, and the connection carries on.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::wire::{self, DecodeError, Frame, MessageWriter, WireError};
+use super::{CallError, DrawState, Outcome};
+use crate::enums::Message;
+use crate::structs::Color;
+
+const MAGIC: &[u8; 3] = b"RBR";
+
+const TAG_READY: u64 = 0;
+const TAG_ACK: u64 = 1;
+const TAG_REJECTED: u64 = 2;
+const TAG_MALFORMED: u64 = 3;
+const TAG_BUSY: u64 = 4;
+const TAG_TIMED_OUT: u64 = 5;
+
+/// What the server says back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// The connection was accepted.
+    Ready,
+    /// The message was applied.
+    Ack(Outcome),
+    /// The message was refused and changed nothing.
+    Rejected(CallError),
+    /// The input was not a message.
+    Malformed(String),
+    /// Too many connections; this one is closed.
+    Busy,
+    /// Nothing arrived within the server's read timeout; the connection is closed.
+    TimedOut,
+}
+
+fn put_point(out: &mut Vec<u8>, (x, y): (i32, i32)) {
+    wire::put_signed(out, x);
+    wire::put_signed(out, y);
+}
+
+fn put_color(out: &mut Vec<u8>, Color(r, g, b): Color) {
+    wire::put_signed(out, r);
+    wire::put_signed(out, g);
+    wire::put_signed(out, b);
+}
+
+fn take_point(input: &mut &[u8]) -> Result<(i32, i32), DecodeError> {
+    Ok((wire::take_signed(input)?, wire::take_signed(input)?))
+}
+
+fn take_color(input: &mut &[u8]) -> Result<Color, DecodeError> {
+    Ok(Color(
+        wire::take_signed(input)?,
+        wire::take_signed(input)?,
+        wire::take_signed(input)?,
+    ))
+}
+
+impl Reply {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            Reply::Ready => wire::put_varint(&mut out, TAG_READY),
+            Reply::Ack(outcome) => {
+                wire::put_varint(&mut out, TAG_ACK);
+                match outcome {
+                    Outcome::Stopped => wire::put_varint(&mut out, 0),
+                    Outcome::Moved { from, to } => {
+                        wire::put_varint(&mut out, 1);
+                        put_point(&mut out, *from);
+                        put_point(&mut out, *to);
+                    }
+                    Outcome::Wrote { len } => {
+                        wire::put_varint(&mut out, 2);
+                        wire::put_varint(&mut out, *len as u64);
+                    }
+                    Outcome::ColorChanged { from, to } => {
+                        wire::put_varint(&mut out, 3);
+                        put_color(&mut out, *from);
+                        put_color(&mut out, *to);
+                    }
+                }
+            }
+            Reply::Rejected(error) => {
+                wire::put_varint(&mut out, TAG_REJECTED);
+                match error {
+                    CallError::Stopped => wire::put_varint(&mut out, 0),
+                    CallError::ColorOutOfRange { component, value } => {
+                        wire::put_varint(&mut out, 1);
+                        wire::put_varint(&mut out, *component as u64);
+                        wire::put_signed(&mut out, *value);
+                    }
+                    CallError::CursorOverflow { dx, dy } => {
+                        wire::put_varint(&mut out, 2);
+                        put_point(&mut out, (*dx, *dy));
+                    }
+                }
+            }
+            Reply::Malformed(reason) => {
+                wire::put_varint(&mut out, TAG_MALFORMED);
+                wire::put_varint(&mut out, reason.len() as u64);
+                out.extend_from_slice(reason.as_bytes());
+            }
+            Reply::Busy => wire::put_varint(&mut out, TAG_BUSY),
+            Reply::TimedOut => wire::put_varint(&mut out, TAG_TIMED_OUT),
+        }
+        out
+    }
+
+    fn decode(body: &[u8]) -> Result<Reply, ClientError> {
+        let input = &mut &body[..];
+        let tag = wire::take_varint(input)?;
+        let reply = match tag {
+            TAG_READY => Reply::Ready,
+            TAG_ACK => Reply::Ack(match wire::take_varint(input)? {
+                0 => Outcome::Stopped,
+                1 => Outcome::Moved {
+                    from: take_point(input)?,
+                    to: take_point(input)?,
+                },
+                2 => Outcome::Wrote {
+                    len: usize::try_from(wire::take_varint(input)?)
+                        .map_err(|_| DecodeError::Truncated)?,
+                },
+                3 => Outcome::ColorChanged {
+                    from: take_color(input)?,
+                    to: take_color(input)?,
+                },
+                kind => {
+                    return Err(ClientError::UnknownReply {
+                        tag,
+                        kind: Some(kind),
+                    })
+                }
+            }),
+            TAG_REJECTED => Reply::Rejected(match wire::take_varint(input)? {
+                0 => CallError::Stopped,
+                1 => CallError::ColorOutOfRange {
+                    component: wire::take_varint(input)? as usize,
+                    value: wire::take_signed(input)?,
+                },
+                2 => {
+                    let (dx, dy) = take_point(input)?;
+                    CallError::CursorOverflow { dx, dy }
+                }
+                kind => {
+                    return Err(ClientError::UnknownReply {
+                        tag,
+                        kind: Some(kind),
+                    })
+                }
+            }),
+            TAG_MALFORMED => {
+                let len = wire::take_varint(input)?;
+                let reason = wire::take_bytes(input, len)?;
+                Reply::Malformed(String::from_utf8_lossy(reason).into_owned())
+            }
+            TAG_BUSY => Reply::Busy,
+            TAG_TIMED_OUT => Reply::TimedOut,
+            _ => return Err(ClientError::UnknownReply { tag, kind: None }),
+        };
+        Ok(reply)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<DrawState>,
+    /// Open connections by id, so that shutting down can close them.
+    connections: Mutex<HashMap<u64, TcpStream>>,
+    next_id: AtomicU64,
+    stopping: AtomicBool,
+    max_connections: usize,
+    read_timeout: Option<Duration>,
+}
+
+/// Options for a new server, from `Server::builder`.
+#[derive(Debug, Clone)]
+pub struct ServerBuilder {
+    max_connections: usize,
+    read_timeout: Option<Duration>,
+}
+
+impl ServerBuilder {
+    /// How many clients may be connected at once; at least 1.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max.max(1);
+        self
+    }
+
+    /// How long a connection may sit idle before it is closed. `None` waits forever.
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout.filter(|timeout| !timeout.is_zero());
+        self
+    }
+
+    /// Listens on `addr`, which has to be a loopback address. Port 0 picks a free port.
+    pub fn bind(self, addr: impl ToSocketAddrs) -> io::Result<Server> {
+        let addr = addr
+            .to_socket_addrs()?
+            .find(|addr| addr.ip().is_loopback())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the server only listens on loopback addresses",
+                )
+            })?;
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            shared: Arc::new(Shared {
+                state: Mutex::new(DrawState::new()),
+                connections: Mutex::new(HashMap::new()),
+                next_id: AtomicU64::new(0),
+                stopping: AtomicBool::new(false),
+                max_connections: self.max_connections,
+                read_timeout: self.read_timeout,
+            }),
+        })
+    }
+}
+
+/// Applies messages from every client to one shared `DrawState`.
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    shared: Arc<Shared>,
+}
+
+impl Server {
+    /// 16 connections and a 30 second read timeout unless changed.
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder {
+            max_connections: 16,
+            read_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+
+    /// A server with the default options.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Server> {
+        Server::builder().bind(addr)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// A copy of the state as it is now.
+    pub fn state(&self) -> DrawState {
+        lock(&self.shared.state).clone()
+    }
+
+    /// Accepts connections, each on its own thread, until the server is shut down.
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                // the client gave up before we got to it
+                Err(e) if e.kind() == io::ErrorKind::ConnectionAborted => continue,
+                Err(e) => return Err(e),
+            };
+            let mut connections = lock(&self.shared.connections);
+            // checked under the lock so that `shutdown` sees every connection it has to close
+            if self.shared.stopping.load(Ordering::SeqCst) {
+                break;
+            }
+            if connections.len() >= self.shared.max_connections {
+                drop(connections);
+                refuse(stream);
+                continue;
+            }
+            let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+            let Ok(clone) = stream.try_clone() else {
+                continue;
+            };
+            connections.insert(id, clone);
+            drop(connections);
+            let shared = Arc::clone(&self.shared);
+            thread::spawn(move || {
+                // errors here only mean the client went away
+                let _ = serve(&shared, stream);
+                lock(&shared.connections).remove(&id);
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs the server on a thread of its own.
+    pub fn spawn(self) -> io::Result<ServerHandle> {
+        let addr = self.local_addr()?;
+        let shared = Arc::clone(&self.shared);
+        let thread = thread::spawn(move || self.run());
+        Ok(ServerHandle {
+            addr,
+            shared,
+            thread,
+        })
+    }
+}
+
+/// A server running on its own thread, from `Server::spawn`.
+#[derive(Debug)]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    thread: JoinHandle<io::Result<()>>,
+}
+
+impl ServerHandle {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// A copy of the state as it is now.
+    pub fn state(&self) -> DrawState {
+        lock(&self.shared.state).clone()
+    }
+
+    /// How many clients are connected.
+    pub fn connections(&self) -> usize {
+        lock(&self.shared.connections).len()
+    }
+
+    /// Stops accepting, closes every connection and waits for the accept loop to end.
+    pub fn shutdown(self) -> io::Result<()> {
+        {
+            let connections = lock(&self.shared.connections);
+            self.shared.stopping.store(true, Ordering::SeqCst);
+            for stream in connections.values() {
+                stream.shutdown(Shutdown::Both).ok();
+            }
+        }
+        // wake the accept loop up so that it sees the flag
+        TcpStream::connect(self.addr).ok();
+        self.thread
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("the server thread panicked")))
+    }
+}
+
+fn send(writer: &mut impl Write, reply: &Reply) -> io::Result<()> {
+    wire::write_frame(writer, &reply.encode())?;
+    writer.flush()
+}
+
+/// Tells a client over the connection limit to go away.
+fn refuse(stream: TcpStream) {
+    let mut writer = BufWriter::new(&stream);
+    if wire::write_header(&mut writer, MAGIC).is_ok() {
+        send(&mut writer, &Reply::Busy).ok();
+    }
+}
+
+fn is_timeout(e: &WireError) -> bool {
+    matches!(e, WireError::Io(e)
+        if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut))
+}
+
+/// Replies to one client until it hangs up.
+fn serve(shared: &Shared, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(shared.read_timeout)?;
+    stream.set_nodelay(true)?;
+    let mut writer = BufWriter::new(stream.try_clone()?);
+    let mut reader = BufReader::new(stream);
+    wire::write_header(&mut writer, MAGIC)?;
+    send(&mut writer, &Reply::Ready)?;
+
+    let framing_failed = |writer: &mut BufWriter<TcpStream>, e: WireError| match e {
+        e if is_timeout(&e) => send(writer, &Reply::TimedOut),
+        WireError::Io(e) => Err(e),
+        e => send(writer, &Reply::Malformed(e.to_string())),
+    };
+    if let Err(e) = wire::read_header(&mut reader, wire::MAGIC) {
+        return framing_failed(&mut writer, e);
+    }
+    loop {
+        let body = match wire::read_frame_body(&mut reader) {
+            Ok(Some(body)) => body,
+            Ok(None) => return Ok(()),
+            Err(e) => return framing_failed(&mut writer, e),
+        };
+        let reply = match wire::decode(&body) {
+            Ok(Frame::Message(message)) => match message.call(&mut lock(&shared.state)) {
+                Ok(outcome) => Reply::Ack(outcome),
+                Err(error) => Reply::Rejected(error),
+            },
+            Ok(Frame::Unknown { tag, .. }) => {
+                Reply::Malformed(format!("unknown message tag {}", tag))
+            }
+            Err(e) => Reply::Malformed(e.to_string()),
+        };
+        send(&mut writer, &reply)?;
+    }
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    /// The server's replies could not be read.
+    Wire(WireError),
+    /// A reply tag this client does not know, or a kind of outcome or error under a known tag.
+    UnknownReply {
+        tag: u64,
+        kind: Option<u64>,
+    },
+    /// The server refused the message.
+    Rejected(CallError),
+    /// The server did not understand what it was sent.
+    Malformed(String),
+    /// The server has as many connections as it allows.
+    Busy,
+    /// The server closed the connection after waiting too long for a message.
+    TimedOut,
+    /// The server closed the connection.
+    Closed,
+    /// A reply that makes no sense at this point in the conversation.
+    Unexpected(Reply),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => e.fmt(f),
+            ClientError::Wire(e) => write!(f, "bad reply: {}", e),
+            ClientError::UnknownReply { tag, kind: None } => {
+                write!(f, "unknown reply tag {}", tag)
+            }
+            ClientError::UnknownReply {
+                tag,
+                kind: Some(kind),
+            } => write!(f, "unknown kind {} of reply {}", kind, tag),
+            ClientError::Rejected(e) => write!(f, "rejected: {}", e),
+            ClientError::Malformed(reason) => write!(f, "server could not read it: {}", reason),
+            ClientError::Busy => write!(f, "the server is busy"),
+            ClientError::TimedOut => write!(f, "the server timed the connection out"),
+            ClientError::Closed => write!(f, "the server closed the connection"),
+            ClientError::Unexpected(reply) => write!(f, "unexpected reply {:?}", reply),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::Io(e) => Some(e),
+            ClientError::Wire(e) => Some(e),
+            ClientError::Rejected(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+impl From<WireError> for ClientError {
+    fn from(e: WireError) -> Self {
+        match e {
+            WireError::Io(e) => ClientError::Io(e),
+            e => ClientError::Wire(e),
+        }
+    }
+}
+
+impl From<DecodeError> for ClientError {
+    fn from(e: DecodeError) -> Self {
+        ClientError::Wire(WireError::Decode(e))
+    }
+}
+
+/// A connection to a `Server`.
+#[derive(Debug)]
+pub struct Client {
+    writer: MessageWriter<BufWriter<TcpStream>>,
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    /// Connects and waits for the server to accept. Fails with `Busy` if it does not.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Client, ClientError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        wire::read_header(&mut reader, MAGIC)?;
+        let mut client = Client {
+            writer: MessageWriter::new(BufWriter::new(stream))?,
+            reader,
+        };
+        match client.read_reply()? {
+            Reply::Ready => Ok(client),
+            Reply::Busy => Err(ClientError::Busy),
+            reply => Err(ClientError::Unexpected(reply)),
+        }
+    }
+
+    /// How long to wait for each reply. `None` waits forever.
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.reader
+            .get_ref()
+            .set_read_timeout(timeout.filter(|timeout| !timeout.is_zero()))
+    }
+
+    /// Sends one message and waits for its reply.
+    pub fn send(&mut self, message: &Message) -> Result<Outcome, ClientError> {
+        self.writer.write(message)?;
+        self.writer.flush()?;
+        match self.read_reply()? {
+            Reply::Ack(outcome) => Ok(outcome),
+            Reply::Rejected(error) => Err(ClientError::Rejected(error)),
+            Reply::Malformed(reason) => Err(ClientError::Malformed(reason)),
+            Reply::TimedOut => Err(ClientError::TimedOut),
+            reply => Err(ClientError::Unexpected(reply)),
+        }
+    }
+
+    /// The next reply from the server.
+    pub fn read_reply(&mut self) -> Result<Reply, ClientError> {
+        match wire::read_frame_body(&mut self.reader)? {
+            Some(body) => Reply::decode(&body),
+            None => Err(ClientError::Closed),
+        }
+    }
+}
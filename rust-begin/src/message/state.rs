@@ -0,0 +1,122 @@
+//! The drawing state a `Message` acts on.
+//!
+//! A program is a list of messages run in order against one `DrawState`: `Move` shifts the
+//! cursor, `Write` appends to the text buffer, `ChangeColor` sets the pen color and `Quit`
+//! stops the program. A message that fails leaves the state as it was.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::enums::Message;
+use crate::structs::Color;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawState {
+    pub cursor: (i32, i32),
+    pub color: Color,
+    pub text: String,
+    /// Cleared by `Quit`; once false every message is refused.
+    pub running: bool,
+}
+
+impl DrawState {
+    /// The cursor at the origin, a black pen, no text, and running.
+    pub fn new() -> DrawState {
+        DrawState {
+            cursor: (0, 0),
+            color: Color(0, 0, 0),
+            text: String::new(),
+            running: true,
+        }
+    }
+
+    /// Calls every message in turn. Stops at the first error and reports which message it was.
+    pub fn run<'a>(
+        &mut self,
+        program: impl IntoIterator<Item = &'a Message>,
+    ) -> Result<Vec<Outcome>, ProgramError> {
+        program
+            .into_iter()
+            .enumerate()
+            .map(|(index, message)| {
+                message
+                    .call(self)
+                    .map_err(|error| ProgramError { index, error })
+            })
+            .collect()
+    }
+}
+
+impl Default for DrawState {
+    fn default() -> Self {
+        DrawState::new()
+    }
+}
+
+/// What a successful `Message::call` did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Stopped,
+    Moved {
+        from: (i32, i32),
+        to: (i32, i32),
+    },
+    /// `len` bytes were appended to the text buffer.
+    Wrote {
+        len: usize,
+    },
+    ColorChanged {
+        from: Color,
+        to: Color,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The state has already seen `Quit`.
+    Stopped,
+    /// A color component outside 0 to 255; `component` is 0 for red, 1 for green, 2 for blue.
+    ColorOutOfRange { component: usize, value: i32 },
+    /// The move would take the cursor past the range of an `i32`.
+    CursorOverflow { dx: i32, dy: i32 },
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Stopped => write!(f, "the program has already quit"),
+            CallError::ColorOutOfRange { component, value } => {
+                let name = ["red", "green", "blue"].get(*component).unwrap_or(&"color");
+                write!(f, "{} component {} is outside 0..=255", name, value)
+            }
+            CallError::CursorOverflow { dx, dy } => {
+                write!(
+                    f,
+                    "moving by ({}, {}) takes the cursor out of range",
+                    dx, dy
+                )
+            }
+        }
+    }
+}
+
+impl Error for CallError {}
+
+/// A `CallError` and the position of the message in the program that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramError {
+    pub index: usize,
+    pub error: CallError,
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "message {}: {}", self.index, self.error)
+    }
+}
+
+impl Error for ProgramError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
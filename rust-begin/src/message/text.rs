@@ -0,0 +1,338 @@
+//! A line-based text syntax for `Message`, one command per line:
+//!
+//! ```text
+//! move 3 -4
+//! write "hello"
+//! color 255 0 128
+//! quit
+//! ```
+//!
+//! Words and numbers are separated by spaces or tabs. Text for `write` goes in double quotes,
+//! with `\"`, `\\`, `\n`, `\t`, `\r`, `\0` and `\u{1F980}` escapes. A `#` outside quotes starts a
+//! comment that runs to the end of the line. `Display` prints a `Message` back in this syntax,
+//! so printing and parsing round-trip.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::enums::Message;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A line with no command on it.
+    Empty,
+    UnknownCommand(String),
+    /// The line ended where this argument should be.
+    MissingArgument(&'static str),
+    /// An argument that should have been an integer.
+    InvalidInteger(String),
+    /// An integer that does not fit an `i32`.
+    OutOfRange(String),
+    /// `write` takes its text in double quotes.
+    ExpectedString,
+    UnterminatedString,
+    InvalidEscape(String),
+    /// More arguments than the command takes.
+    TrailingInput,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "expected a command"),
+            ParseErrorKind::UnknownCommand(word) => write!(
+                f,
+                "unknown command `{}`, expected move, write, color or quit",
+                word
+            ),
+            ParseErrorKind::MissingArgument(name) => write!(f, "missing argument `{}`", name),
+            ParseErrorKind::InvalidInteger(word) => write!(f, "`{}` is not an integer", word),
+            ParseErrorKind::OutOfRange(word) => {
+                write!(f, "{} does not fit in a 32-bit integer", word)
+            }
+            ParseErrorKind::ExpectedString => write!(f, "expected text in double quotes"),
+            ParseErrorKind::UnterminatedString => write!(f, "missing closing quote"),
+            ParseErrorKind::InvalidEscape(escape) => write!(f, "invalid escape `{}`", escape),
+            ParseErrorKind::TrailingInput => write!(f, "unexpected extra argument"),
+        }
+    }
+}
+
+/// What went wrong and where: `line` and `column` count from 1, `column` and `width` in
+/// characters, so a caret line can be drawn under the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub width: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(text: &str, line: usize, span: Range<usize>, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line,
+            column: text[..span.start].chars().count() + 1,
+            width: text[span].chars().count().max(1),
+            kind,
+        }
+    }
+
+    /// Spaces and carets marking the error, to print under the line it came from.
+    pub fn caret(&self) -> String {
+        format!("{}{}", " ".repeat(self.column - 1), "^".repeat(self.width))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl Error for ParseError {}
+
+/// What separates words. Line breaks count so that a second line is trailing input.
+const SPACE: &[char] = &[' ', '\t', '\r', '\n'];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Text(String),
+}
+
+/// Splits one line into words and quoted strings, recording where each one was.
+struct Lexer<'a> {
+    text: &'a str,
+    line: usize,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn error(&self, span: Range<usize>, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(self.text, self.line, span, kind)
+    }
+
+    /// The span just past the end of the line, for arguments that are missing.
+    fn end(&self) -> Range<usize> {
+        let end = self.text.trim_end().len();
+        end..end
+    }
+
+    fn next(&mut self) -> Result<Option<(Token<'a>, Range<usize>)>, ParseError> {
+        let rest = &self.text[self.pos..];
+        let start = self.pos + (rest.len() - rest.trim_start_matches(SPACE).len());
+        self.pos = start;
+        match self.text[start..].chars().next() {
+            None | Some('#') => Ok(None),
+            Some('"') => self.string(start).map(Some),
+            Some(_) => {
+                let len = self.text[start..]
+                    .find(|c| SPACE.contains(&c) || c == '"' || c == '#')
+                    .unwrap_or(self.text.len() - start);
+                self.pos = start + len;
+                Ok(Some((
+                    Token::Word(&self.text[start..self.pos]),
+                    start..self.pos,
+                )))
+            }
+        }
+    }
+
+    /// Reads a quoted string whose opening quote is at `start`.
+    fn string(&mut self, start: usize) -> Result<(Token<'a>, Range<usize>), ParseError> {
+        let mut out = String::new();
+        let mut pos = start + 1;
+        loop {
+            let Some(c) = self.text[pos..].chars().next() else {
+                let end = self.text.trim_end().len();
+                return Err(self.error(start..end, ParseErrorKind::UnterminatedString));
+            };
+            match c {
+                '"' => {
+                    self.pos = pos + 1;
+                    return Ok((Token::Text(out), start..self.pos));
+                }
+                '\\' => {
+                    let (unescaped, len) = self.escape(pos)?;
+                    out.push(unescaped);
+                    pos += len;
+                }
+                _ => {
+                    out.push(c);
+                    pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    /// The character an escape starting with the backslash at `at` stands for, and the
+    /// escape's length in bytes.
+    fn escape(&self, at: usize) -> Result<(char, usize), ParseError> {
+        let rest = &self.text[at..];
+        unescape(rest).map_err(|bad| match bad {
+            BadEscape::Unterminated => {
+                let end = self.text.trim_end().len();
+                self.error(at..end, ParseErrorKind::UnterminatedString)
+            }
+            BadEscape::Invalid(len) => self.error(
+                at..at + len,
+                ParseErrorKind::InvalidEscape(rest[..len].to_string()),
+            ),
+        })
+    }
+}
+
+/// Why `unescape` failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BadEscape {
+    /// Nothing follows the backslash.
+    Unterminated,
+    /// The escape is the first this many bytes, which are always whole characters.
+    Invalid(usize),
+}
+
+/// Reads the escape at the start of `rest`, a backslash and what follows it up to the end of
+/// the line: the character it stands for and its length in bytes. Shared with the lexer in
+/// `crate::command`, so that both quote strings the same way.
+pub(crate) fn unescape(rest: &str) -> Result<(char, usize), BadEscape> {
+    let Some(escape) = rest[1..].chars().next() else {
+        return Err(BadEscape::Unterminated);
+    };
+    let unescaped = match escape {
+        '"' => '"',
+        '\\' => '\\',
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        'u' => {
+            // the braces have to close before the string does
+            let close = rest[2..].find(['}', '"']).map_or(rest.len(), |i| i + 2);
+            if !rest[close..].starts_with('}') {
+                return Err(BadEscape::Invalid(close));
+            }
+            let hex = rest[2..close]
+                .strip_prefix('{')
+                .ok_or(BadEscape::Invalid(2))?;
+            let valid =
+                !hex.is_empty() && hex.len() <= 6 && hex.bytes().all(|b| b.is_ascii_hexdigit());
+            return u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| valid)
+                .and_then(char::from_u32)
+                .map(|c| (c, close + 1))
+                .ok_or(BadEscape::Invalid(close + 1));
+        }
+        _ => return Err(BadEscape::Invalid(1 + escape.len_utf8())),
+    };
+    Ok((unescaped, 2))
+}
+
+fn parse_int(lexer: &mut Lexer<'_>, name: &'static str) -> Result<i32, ParseError> {
+    match lexer.next()? {
+        None => Err(lexer.error(lexer.end(), ParseErrorKind::MissingArgument(name))),
+        Some((Token::Word(word), span)) => {
+            let digits = word.strip_prefix(['-', '+']).unwrap_or(word);
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(lexer.error(span, ParseErrorKind::InvalidInteger(word.to_string())));
+            }
+            word.parse()
+                .map_err(|_| lexer.error(span, ParseErrorKind::OutOfRange(word.to_string())))
+        }
+        Some((Token::Text(_), span)) => Err(lexer.error(
+            span.clone(),
+            ParseErrorKind::InvalidInteger(lexer.text[span].to_string()),
+        )),
+    }
+}
+
+/// Parses one line as the `line`-th of a script. `None` for blank and comment-only lines.
+fn parse_line(text: &str, line: usize) -> Result<Option<Message>, ParseError> {
+    let mut lexer = Lexer { text, line, pos: 0 };
+    let (command, span) = match lexer.next()? {
+        None => return Ok(None),
+        Some((Token::Word(word), span)) => (word, span),
+        Some((Token::Text(_), span)) => {
+            let kind = ParseErrorKind::UnknownCommand(text[span.clone()].to_string());
+            return Err(lexer.error(span, kind));
+        }
+    };
+    let message = match command {
+        "quit" => Message::Quit,
+        "move" => Message::Move {
+            x: parse_int(&mut lexer, "x")?,
+            y: parse_int(&mut lexer, "y")?,
+        },
+        "write" => match lexer.next()? {
+            Some((Token::Text(text), _)) => Message::Write(text),
+            Some((Token::Word(_), span)) => {
+                return Err(lexer.error(span, ParseErrorKind::ExpectedString))
+            }
+            None => return Err(lexer.error(lexer.end(), ParseErrorKind::MissingArgument("text"))),
+        },
+        "color" => Message::ChangeColor(
+            parse_int(&mut lexer, "red")?,
+            parse_int(&mut lexer, "green")?,
+            parse_int(&mut lexer, "blue")?,
+        ),
+        _ => return Err(lexer.error(span, ParseErrorKind::UnknownCommand(command.to_string()))),
+    };
+    match lexer.next()? {
+        None => Ok(Some(message)),
+        Some((_, span)) => Err(lexer.error(span, ParseErrorKind::TrailingInput)),
+    }
+}
+
+/// Parses a script of one command per line, skipping blank lines and comments.
+pub fn parse_script(script: &str) -> Result<Vec<Message>, ParseError> {
+    script
+        .lines()
+        .enumerate()
+        .filter_map(|(i, text)| parse_line(text, i + 1).transpose())
+        .collect()
+}
+
+/// Parses a single command. Surrounding spaces and a trailing comment are allowed.
+impl FromStr for Message {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_line(s, 1)?
+            .ok_or_else(|| ParseError::new(s, 1, s.len()..s.len(), ParseErrorKind::Empty))
+    }
+}
+
+/// Writes `text` in double quotes, escaped so that the lexer reads it back unchanged.
+pub(crate) fn write_quoted(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            '\0' => f.write_str("\\0")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Quit => write!(f, "quit"),
+            Message::Move { x, y } => write!(f, "move {} {}", x, y),
+            Message::Write(text) => {
+                f.write_str("write ")?;
+                write_quoted(f, text)
+            }
+            Message::ChangeColor(r, g, b) => write!(f, "color {} {} {}", r, g, b),
+        }
+    }
+}
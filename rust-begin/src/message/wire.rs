@@ -0,0 +1,397 @@
+//! A compact binary encoding of `Message`, for sending messages between processes.
+//!
+//! # Message body
+//!
+//! A body is the variant tag followed by the variant's fields:
+//!
+//! | tag | variant       | fields                                   |
+//! |-----|---------------|------------------------------------------|
+//! | 0   | `Quit`        | none                                     |
+//! | 1   | `Move`        | `x`, `y` as signed varints               |
+//! | 2   | `Write`       | byte length as a varint, then UTF-8      |
+//! | 3   | `ChangeColor` | red, green, blue as signed varints       |
+//!
+//! Varints are unsigned LEB128: seven bits per byte, least significant group first, the high
+//! bit set on every byte but the last. Signed integers are zigzag-mapped first (0, -1, 1, -2,
+//! ... become 0, 1, 2, 3, ...) so small negative numbers stay short. The tag is a varint too.
+//!
+//! # Streams
+//!
+//! A stream starts with the magic bytes `RBM` and a version byte, currently 1. Each message
+//! then goes in a frame: the body length as a varint, then the body.
+//!
+//! # Compatibility
+//!
+//! Frames make the format forward compatible. A reader that meets a tag it does not know gets
+//! `Frame::Unknown` with the raw body and can carry on with the next frame, and bytes after
+//! the fields it knows are ignored, so later versions may add variants and append fields to
+//! existing ones. Readers accept streams of any version from 1 up for the same reason.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::enums::Message;
+
+pub(crate) const MAGIC: &[u8; 3] = b"RBM";
+/// The stream version written by `MessageWriter`.
+pub const VERSION: u8 = 1;
+/// Frames longer than this are refused instead of being read into memory.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+const TAG_QUIT: u64 = 0;
+const TAG_MOVE: u64 = 1;
+const TAG_WRITE: u64 = 2;
+const TAG_CHANGE_COLOR: u64 = 3;
+
+/// A decoded body: a message, or a variant from a newer version of the format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Message(Message),
+    Unknown { tag: u64, body: Vec<u8> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended in the middle of a value.
+    Truncated,
+    /// A varint longer than 64 bits.
+    VarintOverflow,
+    /// A signed integer that does not fit an `i32`.
+    OutOfRange(i64),
+    /// `Write` text that is not UTF-8.
+    InvalidUtf8,
+    /// A frame longer than `MAX_FRAME_LEN`.
+    FrameTooLarge(u64),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "message is truncated"),
+            DecodeError::VarintOverflow => write!(f, "varint does not fit in 64 bits"),
+            DecodeError::OutOfRange(value) => write!(f, "{} does not fit in an i32", value),
+            DecodeError::InvalidUtf8 => write!(f, "text is not valid UTF-8"),
+            DecodeError::FrameTooLarge(len) => write!(
+                f,
+                "frame of {} bytes exceeds the limit of {}",
+                len, MAX_FRAME_LEN
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+#[derive(Debug)]
+pub enum WireError {
+    Io(io::Error),
+    /// The stream does not start with `RBM`.
+    BadMagic([u8; 3]),
+    /// Version 0 is not a version of this format.
+    UnsupportedVersion(u8),
+    Decode(DecodeError),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Io(e) => e.fmt(f),
+            WireError::BadMagic(magic) => write!(f, "not a message stream (starts {:02x?})", magic),
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "unsupported stream version {}", version)
+            }
+            WireError::Decode(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for WireError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WireError::Io(e) => Some(e),
+            WireError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WireError {
+    fn from(e: io::Error) -> Self {
+        WireError::Io(e)
+    }
+}
+
+impl From<DecodeError> for WireError {
+    fn from(e: DecodeError) -> Self {
+        WireError::Decode(e)
+    }
+}
+
+pub(crate) fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub(crate) fn put_signed(out: &mut Vec<u8>, value: i32) {
+    put_varint(out, ((value << 1) ^ (value >> 31)) as u32 as u64);
+}
+
+/// Reads a varint from the front of `input` and advances past it.
+pub(crate) fn take_varint(input: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value = 0u64;
+    for (i, &byte) in input.iter().enumerate() {
+        let bits = u64::from(byte & 0x7f);
+        // the tenth byte may only hold the single top bit
+        if i == 9 && bits > 1 {
+            return Err(DecodeError::VarintOverflow);
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            *input = &input[i + 1..];
+            return Ok(value);
+        }
+        if i == 9 {
+            return Err(DecodeError::VarintOverflow);
+        }
+    }
+    Err(DecodeError::Truncated)
+}
+
+pub(crate) fn take_signed(input: &mut &[u8]) -> Result<i32, DecodeError> {
+    let zigzag = take_varint(input)?;
+    let value = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+    i32::try_from(value).map_err(|_| DecodeError::OutOfRange(value))
+}
+
+pub(crate) fn take_bytes<'a>(input: &mut &'a [u8], len: u64) -> Result<&'a [u8], DecodeError> {
+    let len = usize::try_from(len).map_err(|_| DecodeError::Truncated)?;
+    if input.len() < len {
+        return Err(DecodeError::Truncated);
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+/// Appends the body of `message` to `out`.
+pub fn encode_into(message: &Message, out: &mut Vec<u8>) {
+    match message {
+        Message::Quit => put_varint(out, TAG_QUIT),
+        Message::Move { x, y } => {
+            put_varint(out, TAG_MOVE);
+            put_signed(out, *x);
+            put_signed(out, *y);
+        }
+        Message::Write(text) => {
+            put_varint(out, TAG_WRITE);
+            put_varint(out, text.len() as u64);
+            out.extend_from_slice(text.as_bytes());
+        }
+        Message::ChangeColor(r, g, b) => {
+            put_varint(out, TAG_CHANGE_COLOR);
+            put_signed(out, *r);
+            put_signed(out, *g);
+            put_signed(out, *b);
+        }
+    }
+}
+
+/// The body of `message`.
+pub fn encode(message: &Message) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_into(message, &mut out);
+    out
+}
+
+/// Decodes one body. Bytes after the fields of a known variant are ignored.
+pub fn decode(body: &[u8]) -> Result<Frame, DecodeError> {
+    let mut input = body;
+    let tag = take_varint(&mut input)?;
+    let message = match tag {
+        TAG_QUIT => Message::Quit,
+        TAG_MOVE => Message::Move {
+            x: take_signed(&mut input)?,
+            y: take_signed(&mut input)?,
+        },
+        TAG_WRITE => {
+            let len = take_varint(&mut input)?;
+            let text = take_bytes(&mut input, len)?;
+            let text = std::str::from_utf8(text).map_err(|_| DecodeError::InvalidUtf8)?;
+            Message::Write(text.to_string())
+        }
+        TAG_CHANGE_COLOR => Message::ChangeColor(
+            take_signed(&mut input)?,
+            take_signed(&mut input)?,
+            take_signed(&mut input)?,
+        ),
+        _ => {
+            return Ok(Frame::Unknown {
+                tag,
+                body: input.to_vec(),
+            })
+        }
+    };
+    Ok(Frame::Message(message))
+}
+
+/// Writes messages as frames, after the stream header.
+#[derive(Debug)]
+pub struct MessageWriter<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> MessageWriter<W> {
+    /// Writes the stream header.
+    pub fn new(mut writer: W) -> io::Result<MessageWriter<W>> {
+        write_header(&mut writer, MAGIC)?;
+        Ok(MessageWriter {
+            writer,
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn write(&mut self, message: &Message) -> io::Result<()> {
+        self.buf.clear();
+        encode_into(message, &mut self.buf);
+        write_frame(&mut self.writer, &self.buf)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads frames written by a `MessageWriter`, or a later version of it.
+#[derive(Debug)]
+pub struct MessageReader<R> {
+    reader: R,
+    version: u8,
+    done: bool,
+}
+
+impl<R: Read> MessageReader<R> {
+    /// Reads and checks the stream header.
+    pub fn new(mut reader: R) -> Result<MessageReader<R>, WireError> {
+        let version = read_header(&mut reader, MAGIC)?;
+        Ok(MessageReader {
+            reader,
+            version,
+            done: false,
+        })
+    }
+
+    /// The version the stream was written with.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The next frame, or `None` when the stream ends cleanly between frames.
+    pub fn read_frame(&mut self) -> Result<Option<Frame>, WireError> {
+        match read_frame_body(&mut self.reader)? {
+            Some(body) => Ok(Some(decode(&body)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The next known message, skipping frames of unknown variants.
+    pub fn read_message(&mut self) -> Result<Option<Message>, WireError> {
+        loop {
+            match self.read_frame()? {
+                Some(Frame::Message(message)) => return Ok(Some(message)),
+                Some(Frame::Unknown { .. }) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Writes `magic` and the current version.
+pub(crate) fn write_header<W: Write>(writer: &mut W, magic: &[u8; 3]) -> io::Result<()> {
+    writer.write_all(magic)?;
+    writer.write_all(&[VERSION])
+}
+
+/// Reads a header written by `write_header` and returns its version.
+pub(crate) fn read_header<R: Read>(reader: &mut R, magic: &[u8; 3]) -> Result<u8, WireError> {
+    let mut header = [0; 4];
+    reader.read_exact(&mut header)?;
+    let found = [header[0], header[1], header[2]];
+    if &found != magic {
+        return Err(WireError::BadMagic(found));
+    }
+    if header[3] == 0 {
+        return Err(WireError::UnsupportedVersion(0));
+    }
+    Ok(header[3])
+}
+
+/// Writes `body` as a frame.
+pub(crate) fn write_frame<W: Write>(writer: &mut W, body: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(body.len() + 10);
+    put_varint(&mut frame, body.len() as u64);
+    frame.extend_from_slice(body);
+    writer.write_all(&frame)
+}
+
+/// The body of the next frame, or `None` when the stream ends cleanly between frames.
+pub(crate) fn read_frame_body<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, WireError> {
+    let Some(len) = read_len(reader)? else {
+        return Ok(None);
+    };
+    if len > MAX_FRAME_LEN as u64 {
+        return Err(DecodeError::FrameTooLarge(len).into());
+    }
+    let mut body = vec![0; len as usize];
+    reader.read_exact(&mut body).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => WireError::Decode(DecodeError::Truncated),
+        _ => WireError::Io(e),
+    })?;
+    Ok(Some(body))
+}
+
+/// The frame length, one byte at a time since the varint's size is not known up front.
+fn read_len<R: Read>(reader: &mut R) -> Result<Option<u64>, WireError> {
+    let mut bytes = Vec::with_capacity(10);
+    loop {
+        let mut byte = [0];
+        match reader.read(&mut byte) {
+            Ok(0) if bytes.is_empty() => return Ok(None),
+            Ok(0) => return Err(DecodeError::Truncated.into()),
+            Ok(_) => bytes.push(byte[0]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+        if byte[0] & 0x80 == 0 || bytes.len() == 10 {
+            return Ok(Some(take_varint(&mut &bytes[..])?));
+        }
+    }
+}
+
+/// Yields known messages until the end of the stream, or until the first error.
+impl<R: Read> Iterator for MessageReader<R> {
+    type Item = Result<Message, WireError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let message = self.read_message().transpose();
+        if !matches!(message, Some(Ok(_))) {
+            self.done = true;
+        }
+        message
+    }
+}
@@ -0,0 +1,406 @@
+//! Integers of any size, as a sign and a magnitude in 32-bit limbs.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// A signed integer of any size.
+///
+/// Only what `Money` needs: addition, subtraction, multiplication, division with remainder,
+/// comparison and decimal text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    /// Least significant limb first, with no zero limbs at the end; zero has none.
+    magnitude: Vec<u32>,
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &limb) in long.iter().enumerate() {
+        let sum = u64::from(limb) + u64::from(short.get(i).copied().unwrap_or(0)) + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry != 0 {
+        out.push(carry as u32);
+    }
+    out
+}
+
+/// `a - b`, for `a >= b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &limb) in a.iter().enumerate() {
+        let mut difference = i64::from(limb) - i64::from(b.get(i).copied().unwrap_or(0)) - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        out.push(difference as u32);
+    }
+    trim(&mut out);
+    out
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let product = u64::from(x) * u64::from(y) + u64::from(out[i + j]) + carry;
+            out[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    trim(&mut out);
+    out
+}
+
+/// Quotient and remainder of `a / d` for a one-limb `d`.
+fn div_rem_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = 0u64;
+    for (i, &limb) in a.iter().enumerate().rev() {
+        let current = remainder << 32 | u64::from(limb);
+        quotient[i] = (current / u64::from(d)) as u32;
+        remainder = current % u64::from(d);
+    }
+    trim(&mut quotient);
+    (quotient, remainder as u32)
+}
+
+/// Quotient and remainder of `a / d`, `d` not zero. Bit by bit above one limb, which is
+/// slow for huge numbers but amounts of money are not huge.
+fn div_rem_magnitude(a: &[u32], d: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [d] = d {
+        let (quotient, remainder) = div_rem_small(a, *d);
+        let mut remainder = vec![remainder];
+        trim(&mut remainder);
+        return (quotient, remainder);
+    }
+    if cmp_magnitude(a, d) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        // remainder = remainder * 2 + the next bit of a
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for limb in remainder.iter_mut() {
+            let next = *limb >> 31;
+            *limb = *limb << 1 | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            remainder.push(carry);
+        }
+        if cmp_magnitude(&remainder, d) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, d);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt::default()
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> BigInt {
+        trim(&mut magnitude);
+        BigInt {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    /// `10^exponent`.
+    pub fn pow10(exponent: u32) -> BigInt {
+        (0..exponent).fold(BigInt::from(1), |n, _| n * BigInt::from(10))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.magnitude.first().is_none_or(|limb| limb % 2 == 0)
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_parts(false, self.magnitude.clone())
+    }
+
+    /// Quotient and remainder, rounding toward zero as `/` and `%` do on the primitives: the
+    /// remainder has the sign of `self`. `None` if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &divisor.magnitude);
+        Some((
+            BigInt::from_parts(self.negative != divisor.negative, quotient),
+            BigInt::from_parts(self.negative, remainder),
+        ))
+    }
+
+    /// Quotient and remainder, rounding toward negative infinity: the remainder is never
+    /// negative. `None` if `divisor` is not positive.
+    pub fn div_floor(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_negative() {
+            return None;
+        }
+        let (quotient, remainder) = self.div_rem(divisor)?;
+        if remainder.is_negative() {
+            Some((quotient - BigInt::from(1), remainder + divisor))
+        } else {
+            Some((quotient, remainder))
+        }
+    }
+
+    /// The value, if it fits.
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.magnitude.len() > 4 {
+            return None;
+        }
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u128, |n, &limb| n << 32 | u128::from(limb));
+        if self.negative {
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// The decimal digits of the magnitude, most significant first; `"0"` for zero.
+    pub(crate) fn magnitude_digits(&self) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        // nine digits at a time
+        let mut chunks = Vec::new();
+        let mut rest = self.magnitude.clone();
+        while !rest.is_empty() {
+            let (quotient, remainder) = div_rem_small(&rest, 1_000_000_000);
+            chunks.push(remainder);
+            rest = quotient;
+        }
+        let mut digits = chunks.pop().expect("not zero").to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:09}", chunk));
+        }
+        digits
+    }
+
+    /// From decimal digits alone, no sign; `None` if there are none or any is not a digit.
+    pub(crate) fn from_digits(digits: &str) -> Option<BigInt> {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let mut magnitude: Vec<u32> = Vec::new();
+        for digit in digits.bytes() {
+            let mut carry = u64::from(digit - b'0');
+            for limb in magnitude.iter_mut() {
+                let next = u64::from(*limb) * 10 + carry;
+                *limb = next as u32;
+                carry = next >> 32;
+            }
+            if carry != 0 {
+                magnitude.push(carry as u32);
+            }
+        }
+        Some(BigInt::from_parts(false, magnitude))
+    }
+}
+
+macro_rules! from_unsigned {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for BigInt {
+            fn from(n: $ty) -> BigInt {
+                let n = n as u128;
+                let magnitude = (0..4).map(|i| (n >> (32 * i)) as u32).collect();
+                BigInt::from_parts(false, magnitude)
+            }
+        }
+    )*};
+}
+
+macro_rules! from_signed {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for BigInt {
+            fn from(n: $ty) -> BigInt {
+                let magnitude = BigInt::from((n as i128).unsigned_abs()).magnitude;
+                BigInt::from_parts(n < 0, magnitude)
+            }
+        }
+    )*};
+}
+
+from_unsigned!(u8, u16, u32, u64, u128, usize);
+from_signed!(i8, i16, i32, i64, i128, isize);
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude)
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        -self.clone()
+    }
+}
+
+impl Add<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(
+                self.negative,
+                add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+        // opposite signs: the larger magnitude wins
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(
+                other.negative,
+                sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::from_parts(
+                self.negative,
+                sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+}
+
+impl Sub<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mul_magnitude(&self.magnitude, &other.magnitude),
+        )
+    }
+}
+
+macro_rules! by_value {
+    ($($trait:ident $method:ident),*) => {$(
+        impl $trait<BigInt> for BigInt {
+            type Output = BigInt;
+
+            fn $method(self, other: BigInt) -> BigInt {
+                (&self).$method(&other)
+            }
+        }
+
+        impl $trait<&BigInt> for BigInt {
+            type Output = BigInt;
+
+            fn $method(self, other: &BigInt) -> BigInt {
+                (&self).$method(other)
+            }
+        }
+    )*};
+}
+
+by_value!(Add add, Sub sub, Mul mul);
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(!self.negative, "", &self.magnitude_digits())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBigIntError {
+    pub found: String,
+}
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not an integer: {:?}", self.found)
+    }
+}
+
+impl Error for ParseBigIntError {}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    /// Decimal digits with an optional `-` or `+` in front.
+    fn from_str(s: &str) -> Result<BigInt, ParseBigIntError> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        BigInt::from_digits(digits)
+            .map(|n| BigInt::from_parts(negative, n.magnitude))
+            .ok_or_else(|| ParseBigIntError {
+                found: s.to_string(),
+            })
+    }
+}
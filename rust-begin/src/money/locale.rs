@@ -0,0 +1,256 @@
+//! How amounts are written in different places: `$1,234.56` or `1.234,56 €`.
+
+use std::error::Error;
+use std::fmt;
+
+use super::{BigInt, Money};
+use crate::currency::{Currency, CURRENCIES};
+
+/// The conventions of one place for writing amounts of money.
+///
+/// Output only ever uses plain spaces; parsing also takes the no-break spaces that other
+/// software puts in their place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Locale {
+    /// A language tag, like `en-US`.
+    pub tag: &'static str,
+    pub decimal: char,
+    /// Between groups of three digits.
+    pub group: char,
+    /// Whether the symbol goes before the number.
+    pub symbol_first: bool,
+    /// Whether a space separates the symbol from the number. A symbol made of letters, like
+    /// `CHF`, always gets one.
+    pub symbol_space: bool,
+}
+
+const fn locale(
+    tag: &'static str,
+    decimal: char,
+    group: char,
+    symbol_first: bool,
+    symbol_space: bool,
+) -> Locale {
+    Locale {
+        tag,
+        decimal,
+        group,
+        symbol_first,
+        symbol_space,
+    }
+}
+
+/// Every locale this module knows.
+#[rustfmt::skip]
+pub static LOCALES: &[Locale] = &[
+    locale("en-US", '.', ',', true, false),
+    locale("en-GB", '.', ',', true, false),
+    locale("de-DE", ',', '.', false, true),
+    locale("fr-FR", ',', ' ', false, true),
+    locale("de-CH", '.', '’', true, true),
+    locale("ja-JP", '.', ',', true, false),
+];
+
+impl Locale {
+    /// Looks a locale up by its tag, in any case and with `_` or `-`.
+    pub fn from_tag(tag: &str) -> Option<&'static Locale> {
+        let tag = tag.replace('_', "-");
+        LOCALES
+            .iter()
+            .find(|locale| locale.tag.eq_ignore_ascii_case(&tag))
+    }
+
+    /// Whether `c` is written for `group` by this or other software.
+    fn is_group(&self, c: char) -> bool {
+        match self.group {
+            ' ' => matches!(c, ' ' | '\u{a0}' | '\u{202f}'),
+            '’' => matches!(c, '’' | '\''),
+            group => c == group,
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag)
+    }
+}
+
+/// The magnitude of `units` as a decimal with `exponent` digits after the point, the whole
+/// part grouped by threes, using `decimal` and `group`. `group` is `None` for no grouping.
+pub(super) fn decimal_text(
+    units: &BigInt,
+    exponent: u8,
+    decimal: char,
+    group: Option<char>,
+) -> String {
+    let exponent = usize::from(exponent);
+    let mut digits = units.magnitude_digits();
+    if digits.len() <= exponent {
+        digits.insert_str(0, &"0".repeat(exponent + 1 - digits.len()));
+    }
+    let (whole, fraction) = digits.split_at(digits.len() - exponent);
+    let mut text = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            if let Some(group) = group {
+                text.push(group);
+            }
+        }
+        text.push(digit);
+    }
+    if !fraction.is_empty() {
+        text.push(decimal);
+        text.push_str(fraction);
+    }
+    text
+}
+
+/// Why text is not an amount of money.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoneyError {
+    Empty,
+    /// Neither the symbol nor the code of a known currency was found.
+    NoCurrency(String),
+    /// The number was not digits with an optional decimal separator, or its groups of digits
+    /// were not of three.
+    InvalidNumber(String),
+    /// More digits after the decimal separator than the currency has minor units for.
+    TooManyDecimals {
+        currency: &'static str,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMoneyError::Empty => write!(f, "no amount"),
+            ParseMoneyError::NoCurrency(text) => write!(f, "no known currency in {:?}", text),
+            ParseMoneyError::InvalidNumber(text) => write!(f, "not a number: {:?}", text),
+            ParseMoneyError::TooManyDecimals { currency, found } => {
+                let exponent = Currency::from_code(currency).map_or(0, |c| c.exponent);
+                write!(
+                    f,
+                    "{} has {} decimal places, not {}",
+                    currency, exponent, found
+                )
+            }
+        }
+    }
+}
+
+impl Error for ParseMoneyError {}
+
+fn is_space(c: char) -> bool {
+    c.is_whitespace() || c == '\u{202f}'
+}
+
+/// The currency whose symbol or code `text` starts with (or ends with, if `at_end`), and the
+/// rest of the text. Longer matches win, so `CHF` is not taken for something shorter.
+fn strip_currency(text: &str, at_end: bool) -> Option<(&'static Currency, &str)> {
+    CURRENCIES
+        .iter()
+        .flat_map(|currency| [(currency, currency.symbol), (currency, currency.code)])
+        .filter_map(|(currency, mark)| {
+            let rest = if at_end {
+                text.strip_suffix(mark)
+            } else {
+                text.strip_prefix(mark)
+            }?;
+            Some((currency, mark.len(), rest))
+        })
+        .max_by_key(|(_, len, _)| *len)
+        .map(|(currency, _, rest)| (currency, rest))
+}
+
+/// The minor units in a number written with `decimal` and, if given, groups split by
+/// characters `is_group` accepts.
+pub(super) fn parse_number(
+    number: &str,
+    currency: &'static Currency,
+    decimal: char,
+    is_group: impl Fn(char) -> bool,
+) -> Result<BigInt, ParseMoneyError> {
+    let invalid = || ParseMoneyError::InvalidNumber(number.to_string());
+    let (whole, fraction) = match number.split_once(decimal) {
+        Some(("", _) | (_, "")) => return Err(invalid()),
+        Some((whole, fraction)) => (whole, fraction),
+        None => (number, ""),
+    };
+    if whole.is_empty() {
+        return Err(invalid());
+    }
+    let groups: Vec<&str> = whole.split(&is_group).collect();
+    let grouped = groups.len() > 1
+        && (1..=3).contains(&groups[0].len())
+        && groups[1..].iter().all(|group| group.len() == 3);
+    if groups.len() > 1 && !grouped {
+        return Err(invalid());
+    }
+    let exponent = usize::from(currency.exponent);
+    if fraction.len() > exponent {
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        return Err(ParseMoneyError::TooManyDecimals {
+            currency: currency.code,
+            found: fraction.len(),
+        });
+    }
+    let digits = format!(
+        "{}{}{}",
+        groups.concat(),
+        fraction,
+        "0".repeat(exponent - fraction.len())
+    );
+    BigInt::from_digits(&digits).ok_or_else(invalid)
+}
+
+impl Money {
+    /// The amount as `locale` writes it, with the currency's symbol: `$1,234.56` in `en-US`,
+    /// `1.234,56 €` in `de-DE`. A negative amount starts with `-`.
+    pub fn format(&self, locale: &Locale) -> String {
+        let currency = self.currency();
+        let number = decimal_text(
+            self.minor_units(),
+            currency.exponent,
+            locale.decimal,
+            Some(locale.group),
+        );
+        let symbol = currency.symbol;
+        let spaced = locale.symbol_space || symbol.chars().all(char::is_alphabetic);
+        let space = if spaced { " " } else { "" };
+        let sign = if self.is_negative() { "-" } else { "" };
+        if locale.symbol_first {
+            format!("{}{}{}{}", sign, symbol, space, number)
+        } else {
+            format!("{}{}{}{}", sign, number, space, symbol)
+        }
+    }
+
+    /// Reads an amount written as `locale` writes it. The currency may be given by its symbol
+    /// or its code, before or after the number, and digits need not be grouped. Fewer digits
+    /// after the decimal separator than the currency has are fine, more are an error.
+    pub fn parse(text: &str, locale: &Locale) -> Result<Money, ParseMoneyError> {
+        let text = text.trim_matches(is_space);
+        if text.is_empty() {
+            return Err(ParseMoneyError::Empty);
+        }
+        let (negative, rest) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start_matches(is_space)),
+            None => (false, text),
+        };
+        let (currency, number) = strip_currency(rest, false)
+            .or_else(|| strip_currency(rest, true))
+            .ok_or_else(|| ParseMoneyError::NoCurrency(text.to_string()))?;
+        let number = number.trim_matches(is_space);
+        // a minus after a leading symbol, as in `$-5.00`
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) if !negative => (true, number),
+            _ => (negative, number),
+        };
+        let units = parse_number(number, currency, locale.decimal, |c| locale.is_group(c))?;
+        Ok(Money::new(currency, if negative { -units } else { units }))
+    }
+}
@@ -0,0 +1,305 @@
+//! Amounts of money that do not lose cents.
+//!
+//! `value_in_cents` returns a `u8`, so it tops out at 255 cents, and a count of cents does
+//! not say which cents. A `Money` is a currency from `CURRENCIES` and a whole number of its
+//! minor units, held in a `BigInt` so that it never overflows. Arithmetic is exact: adding
+//! needs both amounts in the same currency, and the operations that can leave a fraction of a
+//! minor unit take a `Rounding` mode. Splitting an amount hands the leftover units out one at
+//! a time, so the parts always add up to the whole.
+//!
+//! ```
+//! use rust_begin::money::{Locale, Money, Rounding};
+//!
+//! let ten = Money::parse("$10.00", Locale::from_tag("en-US").unwrap())?;
+//! let parts: Vec<String> = ten.split(3).unwrap().iter().map(|m| m.to_string()).collect();
+//! assert_eq!(parts, ["USD 3.34", "USD 3.33", "USD 3.33"]);
+//!
+//! let price = Money::parse("1.234,56 €", Locale::from_tag("de-DE").unwrap())?;
+//! let tax = price.scale(19, 100, Rounding::HalfEven);
+//! assert_eq!(tax.format(Locale::from_tag("fr-FR").unwrap()), "234,57 €");
+//! # Ok::<(), rust_begin::money::ParseMoneyError>(())
+//! ```
+
+mod bigint;
+mod locale;
+
+pub use bigint::{BigInt, ParseBigIntError};
+pub use locale::{Locale, ParseMoneyError, LOCALES};
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::currency::{ConvertError, Currency, Denominated, ExchangeRates, Rate};
+
+/// How to round a result that falls between two whole minor units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// To the nearest; halves go to the even neighbour, so they do not all lean one way.
+    /// Banker's rounding.
+    HalfEven,
+    /// To the nearest; halves go away from zero, as taught at school.
+    HalfUp,
+    /// Down, toward negative infinity.
+    Floor,
+}
+
+impl Rounding {
+    pub const ALL: [Rounding; 3] = [Rounding::HalfEven, Rounding::HalfUp, Rounding::Floor];
+
+    /// `numer / denom`, rounded this way. `denom` must be positive.
+    pub fn divide(self, numer: &BigInt, denom: &BigInt) -> BigInt {
+        let (quotient, remainder) = numer
+            .div_floor(denom)
+            .expect("dividing by a positive number");
+        if remainder.is_zero() || self == Rounding::Floor {
+            return quotient;
+        }
+        let up = quotient.clone() + BigInt::from(1);
+        match (&remainder + &remainder).cmp(denom) {
+            Ordering::Less => quotient,
+            Ordering::Greater => up,
+            Ordering::Equal => match self {
+                Rounding::HalfEven if quotient.is_even() => quotient,
+                Rounding::HalfEven => up,
+                // the quotient is below the exact half: up is away from zero unless negative
+                _ if numer.is_negative() => quotient,
+                _ => up,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneyError {
+    /// The amounts are in different currencies.
+    CurrencyMismatch {
+        left: &'static str,
+        right: &'static str,
+    },
+    /// Nothing to split into: no parts, or ratios that are all zero.
+    NoParts,
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::CurrencyMismatch { left, right } => {
+                write!(f, "cannot combine {} with {}", left, right)
+            }
+            MoneyError::NoParts => write!(f, "nothing to split the amount into"),
+        }
+    }
+}
+
+impl Error for MoneyError {}
+
+/// An amount of one currency, in whole minor units.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Money {
+    currency: &'static Currency,
+    units: BigInt,
+}
+
+impl Money {
+    /// `units` minor units of `currency`: cents of a dollar, whole yen.
+    pub fn new(currency: &'static Currency, units: impl Into<BigInt>) -> Money {
+        Money {
+            currency,
+            units: units.into(),
+        }
+    }
+
+    pub fn zero(currency: &'static Currency) -> Money {
+        Money::new(currency, 0)
+    }
+
+    /// What a coin or banknote is worth.
+    pub fn of(item: &impl Denominated) -> Money {
+        Money::new(item.currency(), item.value_in_minor_units())
+    }
+
+    /// The sum of `amounts`, zero of `currency` if there are none.
+    pub fn total(
+        currency: &'static Currency,
+        amounts: impl IntoIterator<Item = Money>,
+    ) -> Result<Money, MoneyError> {
+        amounts
+            .into_iter()
+            .try_fold(Money::zero(currency), |sum, amount| {
+                sum.checked_add(&amount)
+            })
+    }
+
+    pub fn currency(&self) -> &'static Currency {
+        self.currency
+    }
+
+    pub fn minor_units(&self) -> &BigInt {
+        &self.units
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.units.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.units.is_negative()
+    }
+
+    pub fn negate(&self) -> Money {
+        Money::new(self.currency, -&self.units)
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch {
+                left: self.currency.code,
+                right: other.currency.code,
+            })
+        }
+    }
+
+    /// The sum, if both are in the same currency. It cannot overflow.
+    pub fn checked_add(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        Ok(Money::new(self.currency, &self.units + &other.units))
+    }
+
+    /// The difference, if both are in the same currency. It may be negative.
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        Ok(Money::new(self.currency, &self.units - &other.units))
+    }
+
+    pub fn times(&self, n: impl Into<BigInt>) -> Money {
+        Money::new(self.currency, &self.units * &n.into())
+    }
+
+    /// `self * numer / denom`, rounded to a whole minor unit: a percentage, a tax rate.
+    ///
+    /// # Panics
+    ///
+    /// If `denom` is zero.
+    pub fn scale(&self, numer: u64, denom: u64, rounding: Rounding) -> Money {
+        assert!(denom != 0, "scaling by a fraction with a zero denominator");
+        let units = rounding.divide(&(&self.units * &BigInt::from(numer)), &BigInt::from(denom));
+        Money::new(self.currency, units)
+    }
+
+    /// Rounded to a multiple of `step` minor units: 100 for whole dollars, 5 for the Swiss
+    /// five-centime coin.
+    ///
+    /// # Panics
+    ///
+    /// If `step` is zero.
+    pub fn round_to_multiple(&self, step: u64, rounding: Rounding) -> Money {
+        assert!(step != 0, "rounding to a multiple of zero");
+        let step = BigInt::from(step);
+        Money::new(self.currency, rounding.divide(&self.units, &step) * &step)
+    }
+
+    /// In `to` at `rate`, the number of `to` one major unit of this currency buys, rounded to
+    /// a whole minor unit of `to`.
+    pub fn convert(&self, to: &'static Currency, rate: Rate, rounding: Rounding) -> Money {
+        let (numer, denom) = rate.parts();
+        let numer = BigInt::from(numer) * BigInt::pow10(u32::from(to.exponent));
+        let denom = BigInt::from(denom) * BigInt::pow10(u32::from(self.currency.exponent));
+        Money::new(to, rounding.divide(&(&self.units * &numer), &denom))
+    }
+
+    /// In the currency coded `to`, at its rate in `rates`.
+    pub fn exchange(
+        &self,
+        rates: &ExchangeRates,
+        to: &str,
+        rounding: Rounding,
+    ) -> Result<Money, ConvertError> {
+        let rate = rates.rate(self.currency.code, to)?;
+        let to = Currency::from_code(to).expect("rate checked the code");
+        Ok(self.convert(to, rate, rounding))
+    }
+
+    /// Split in proportion to `ratios` without losing a minor unit: each part gets its share
+    /// rounded down, and what is left goes out one unit at a time to the parts that lost the
+    /// most to rounding, earlier parts first on a tie. A negative amount splits the same way
+    /// into negative parts.
+    pub fn allocate(&self, ratios: &[u64]) -> Result<Vec<Money>, MoneyError> {
+        let sum: BigInt = ratios
+            .iter()
+            .fold(BigInt::zero(), |sum, &ratio| sum + BigInt::from(ratio));
+        if sum.is_zero() {
+            return Err(MoneyError::NoParts);
+        }
+        let whole = self.units.abs();
+        let mut shares: Vec<(BigInt, BigInt)> = ratios
+            .iter()
+            .map(|&ratio| {
+                (&whole * &BigInt::from(ratio))
+                    .div_floor(&sum)
+                    .expect("the sum is positive")
+            })
+            .collect();
+        let given = shares
+            .iter()
+            .fold(BigInt::zero(), |given, (share, _)| given + share);
+        let left = (whole - given)
+            .to_i128()
+            .expect("fewer units left than parts") as usize;
+        let mut order: Vec<usize> = (0..shares.len()).collect();
+        // stable, so ties keep their order
+        order.sort_by(|&a, &b| shares[b].1.cmp(&shares[a].1));
+        for &i in &order[..left] {
+            shares[i].0 = &shares[i].0 + &BigInt::from(1);
+        }
+        Ok(shares
+            .into_iter()
+            .map(|(share, _)| {
+                let share = if self.is_negative() { -share } else { share };
+                Money::new(self.currency, share)
+            })
+            .collect())
+    }
+
+    /// Split into `parts` parts as equal as they can be, the larger ones first.
+    pub fn split(&self, parts: usize) -> Result<Vec<Money>, MoneyError> {
+        self.allocate(&vec![1; parts])
+    }
+}
+
+/// Amounts in different currencies are not ordered.
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Money) -> Option<Ordering> {
+        self.same_currency(other).ok()?;
+        Some(self.units.cmp(&other.units))
+    }
+}
+
+/// The code and a plain decimal, the same in every locale: `USD 1234.56`, `JPY -500`.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let number = locale::decimal_text(&self.units, self.currency.exponent, '.', None);
+        write!(f, "{} {}{}", self.currency.code, sign, number)
+    }
+}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    /// The form `Display` writes: a currency code, a space and a plain decimal.
+    fn from_str(s: &str) -> Result<Money, ParseMoneyError> {
+        let (code, number) = s.trim().split_once(' ').ok_or(ParseMoneyError::Empty)?;
+        let currency =
+            Currency::from_code(code).ok_or_else(|| ParseMoneyError::NoCurrency(s.to_string()))?;
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, number),
+        };
+        let units = locale::parse_number(number, currency, '.', |_| false)?;
+        Ok(Money::new(currency, if negative { -units } else { units }))
+    }
+}
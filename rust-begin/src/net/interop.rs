@@ -0,0 +1,154 @@
+//! Conversions to and from `std::net`, so addresses can be handed to socket code.
+//!
+//! Every conversion is lossless: it either gives a value that converts back to exactly the
+//! original, or fails. That is why some directions are `TryFrom`: `std::net::Ipv6Addr` has no
+//! zone, a `std::net::SocketAddrV6` only knows numeric zones (its scope id), and our
+//! `SocketAddr` has nowhere to keep an IPv6 flow label.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net;
+use std::option;
+
+use super::SocketAddr;
+use crate::enums::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The zone cannot be represented on the other side.
+    Zone(String),
+    /// A `std::net::SocketAddrV6` carried a non-zero flow label.
+    FlowInfo(u32),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::Zone(zone) => {
+                write!(f, "zone %{} cannot be represented by std::net", zone)
+            }
+            ConversionError::FlowInfo(flow) => {
+                write!(f, "flow info {} cannot be kept in a SocketAddr", flow)
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+impl From<net::Ipv4Addr> for Ipv4Addr {
+    fn from(ip: net::Ipv4Addr) -> Ipv4Addr {
+        Ipv4Addr::from_octets(ip.octets())
+    }
+}
+
+impl From<Ipv4Addr> for net::Ipv4Addr {
+    fn from(ip: Ipv4Addr) -> net::Ipv4Addr {
+        net::Ipv4Addr::from(ip.octets())
+    }
+}
+
+impl From<net::Ipv6Addr> for Ipv6Addr {
+    fn from(ip: net::Ipv6Addr) -> Ipv6Addr {
+        Ipv6Addr::from_segments(ip.segments())
+    }
+}
+
+impl TryFrom<Ipv6Addr> for net::Ipv6Addr {
+    type Error = ConversionError;
+
+    fn try_from(ip: Ipv6Addr) -> Result<net::Ipv6Addr, ConversionError> {
+        match ip.zone() {
+            Some(zone) => Err(ConversionError::Zone(zone.to_string())),
+            None => Ok(net::Ipv6Addr::from(ip.segments())),
+        }
+    }
+}
+
+impl From<net::IpAddr> for IpAddr {
+    fn from(ip: net::IpAddr) -> IpAddr {
+        match ip {
+            net::IpAddr::V4(ip) => IpAddr::from(Ipv4Addr::from(ip)),
+            net::IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(ip)),
+        }
+    }
+}
+
+impl TryFrom<IpAddr> for net::IpAddr {
+    type Error = ConversionError;
+
+    fn try_from(ip: IpAddr) -> Result<net::IpAddr, ConversionError> {
+        match ip {
+            IpAddr::V4(a, b, c, d) => Ok(net::IpAddr::V4(net::Ipv4Addr::new(a, b, c, d))),
+            IpAddr::V6(ip) => net::Ipv6Addr::try_from(ip).map(net::IpAddr::V6),
+        }
+    }
+}
+
+/// A non-zero scope id becomes a numeric zone.
+impl TryFrom<net::SocketAddr> for SocketAddr {
+    type Error = ConversionError;
+
+    fn try_from(addr: net::SocketAddr) -> Result<SocketAddr, ConversionError> {
+        match addr {
+            net::SocketAddr::V4(addr) => Ok(SocketAddr::new(
+                IpAddr::from(Ipv4Addr::from(*addr.ip())),
+                addr.port(),
+            )),
+            net::SocketAddr::V6(addr) => {
+                if addr.flowinfo() != 0 {
+                    return Err(ConversionError::FlowInfo(addr.flowinfo()));
+                }
+                let ip = Ipv6Addr::from(*addr.ip());
+                let ip = match addr.scope_id() {
+                    0 => ip,
+                    scope_id => ip.with_zone(scope_id.to_string()),
+                };
+                Ok(SocketAddr::new(IpAddr::V6(ip), addr.port()))
+            }
+        }
+    }
+}
+
+/// A numeric zone becomes the scope id; named zones such as `%eth0` are refused, resolving
+/// them needs the operating system.
+impl TryFrom<SocketAddr> for net::SocketAddr {
+    type Error = ConversionError;
+
+    fn try_from(addr: SocketAddr) -> Result<net::SocketAddr, ConversionError> {
+        match addr.ip() {
+            IpAddr::V4(a, b, c, d) => Ok(net::SocketAddr::from((
+                net::Ipv4Addr::new(*a, *b, *c, *d),
+                addr.port(),
+            ))),
+            IpAddr::V6(ip) => {
+                let scope_id = match ip.zone() {
+                    None => 0,
+                    // "0" would come back as no zone at all
+                    Some(zone) => match zone.parse::<u32>() {
+                        Ok(id) if id != 0 && !zone.starts_with('0') => id,
+                        _ => return Err(ConversionError::Zone(zone.to_string())),
+                    },
+                };
+                Ok(net::SocketAddr::V6(net::SocketAddrV6::new(
+                    net::Ipv6Addr::from(ip.segments()),
+                    addr.port(),
+                    0,
+                    scope_id,
+                )))
+            }
+        }
+    }
+}
+
+/// Lets a `SocketAddr` go straight to `TcpStream::connect` and friends.
+impl net::ToSocketAddrs for SocketAddr {
+    type Iter = option::IntoIter<net::SocketAddr>;
+
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        net::SocketAddr::try_from(self.clone())
+            .map(|addr| Some(addr).into_iter())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+}
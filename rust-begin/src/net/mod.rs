@@ -0,0 +1,66 @@
+//! Networking built on the `IpAddr` enum from the enums lesson.
+//!
+//! `text` turns addresses into strings and back, `network` works with CIDR blocks,
+//! `routing` finds the route for an address and `scope` tells what an address is for.
+//! `socket` adds ports, and `interop` converts all of it to and from `std::net`.
+//! `packet` reads and writes the IP headers that carry addresses on the wire, and `pcap` pulls
+//! packets out of capture files. `pool` leases addresses out of a subnet.
+
+mod interop;
+mod network;
+pub mod packet;
+pub mod pcap;
+mod pool;
+mod routing;
+mod scope;
+mod socket;
+mod text;
+mod trie;
+
+pub use interop::ConversionError;
+pub use network::{Hosts, IpNetwork, NetworkError, Subnets};
+pub use pool::{IpPool, Lease, LeaseFileError, PoolError};
+pub use routing::{NextHop, RoutingTable};
+pub use scope::{MulticastScope, Scope, SpecialBlock, SPECIAL_PURPOSE_BLOCKS};
+pub use socket::{SocketAddr, SocketAddrParseError};
+pub use text::{AddrParseError, GroupError, OctetError};
+
+use crate::enums::{IpAddr, IpAddrKind, Ipv6Addr};
+
+impl IpAddrKind {
+    /// Number of bits in an address of this kind.
+    pub const fn bit_len(self) -> u8 {
+        match self {
+            IpAddrKind::V4 => 32,
+            IpAddrKind::V6 => 128,
+        }
+    }
+}
+
+/// The address as an unsigned number; IPv4 addresses use the low 32 bits. The zone is dropped.
+pub(crate) fn addr_bits(ip: &IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(a, b, c, d) => u128::from(u32::from_be_bytes([*a, *b, *c, *d])),
+        IpAddr::V6(ip) => ip.to_bits(),
+    }
+}
+
+/// The inverse of `addr_bits`. Bits above the width of `kind` are ignored.
+pub(crate) fn addr_from_bits(kind: IpAddrKind, bits: u128) -> IpAddr {
+    match kind {
+        IpAddrKind::V4 => {
+            let [a, b, c, d] = (bits as u32).to_be_bytes();
+            IpAddr::V4(a, b, c, d)
+        }
+        IpAddrKind::V6 => IpAddr::V6(Ipv6Addr::from_bits(bits)),
+    }
+}
+
+/// The lowest `len` bits set, for `len` up to 128.
+pub(crate) fn low_mask(len: u8) -> u128 {
+    if len >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << len) - 1
+    }
+}
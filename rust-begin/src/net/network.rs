@@ -0,0 +1,392 @@
+//! CIDR networks: an address plus a prefix length, like `192.168.0.0/16` or `2001:db8::/32`.
+//!
+//! A network always stores its first address; host bits given to `IpNetwork::new` or in text
+//! are cleared, so `192.168.1.7/24` becomes `192.168.1.0/24`.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{addr_bits, addr_from_bits, low_mask, AddrParseError};
+use crate::enums::{IpAddr, IpAddrKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkError {
+    /// The prefix length is outside the range allowed here.
+    PrefixOutOfRange { prefix_len: u8, min: u8, max: u8 },
+    /// The text after `/` is not a prefix length.
+    InvalidPrefix(String),
+    /// The text before `/` is not an address.
+    Addr(AddrParseError),
+    /// A network cannot be split into zero subnets, into more subnets than it has addresses, or
+    /// into more than `split` can count.
+    InvalidSubnetCount(usize),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::PrefixOutOfRange {
+                prefix_len,
+                min,
+                max,
+            } => write!(
+                f,
+                "prefix length {} is not between {} and {}",
+                prefix_len, min, max
+            ),
+            NetworkError::InvalidPrefix(text) => write!(f, "invalid prefix length {:?}", text),
+            NetworkError::Addr(e) => e.fmt(f),
+            NetworkError::InvalidSubnetCount(n) => write!(f, "cannot split into {} subnets", n),
+        }
+    }
+}
+
+impl Error for NetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetworkError::Addr(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<AddrParseError> for NetworkError {
+    fn from(e: AddrParseError) -> Self {
+        NetworkError::Addr(e)
+    }
+}
+
+/// An IPv4 or IPv6 network.
+///
+/// Networks order by kind (IPv4 first), then by first address, then by prefix length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IpNetwork {
+    kind: IpAddrKind,
+    bits: u128,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<IpNetwork, NetworkError> {
+        let kind = addr.kind();
+        if prefix_len > kind.bit_len() {
+            return Err(NetworkError::PrefixOutOfRange {
+                prefix_len,
+                min: 0,
+                max: kind.bit_len(),
+            });
+        }
+        Ok(IpNetwork::from_bits(kind, addr_bits(&addr), prefix_len))
+    }
+
+    /// Builds a network without checking `prefix_len`, which must fit `kind`.
+    pub(crate) fn from_bits(kind: IpAddrKind, bits: u128, prefix_len: u8) -> IpNetwork {
+        let host_mask = low_mask(kind.bit_len() - prefix_len);
+        IpNetwork {
+            kind,
+            bits: bits & !host_mask,
+            prefix_len,
+        }
+    }
+
+    pub fn kind(&self) -> IpAddrKind {
+        self.kind
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Number of bits left for hosts.
+    pub fn host_bits(&self) -> u8 {
+        self.kind.bit_len() - self.prefix_len
+    }
+
+    /// The first address of the network.
+    pub fn network(&self) -> IpAddr {
+        addr_from_bits(self.kind, self.bits)
+    }
+
+    /// The last address of the network; the broadcast address for IPv4.
+    pub fn last(&self) -> IpAddr {
+        addr_from_bits(self.kind, self.last_bits())
+    }
+
+    pub fn netmask(&self) -> IpAddr {
+        addr_from_bits(self.kind, low_mask(self.kind.bit_len()) & !self.host_mask())
+    }
+
+    /// Number of addresses in the network, or `None` for `::/0`, whose size does not fit in
+    /// a `u128`.
+    pub fn size(&self) -> Option<u128> {
+        1u128.checked_shl(u32::from(self.host_bits()))
+    }
+
+    pub(crate) fn bits(&self) -> u128 {
+        self.bits
+    }
+
+    pub(crate) fn last_bits(&self) -> u128 {
+        self.bits | self.host_mask()
+    }
+
+    fn host_mask(&self) -> u128 {
+        low_mask(self.host_bits())
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        ip.kind() == self.kind && addr_bits(ip) & !self.host_mask() == self.bits
+    }
+
+    /// Whether `other` lies completely inside this network.
+    pub fn contains_network(&self, other: &IpNetwork) -> bool {
+        other.kind == self.kind
+            && other.prefix_len >= self.prefix_len
+            && other.bits & !self.host_mask() == self.bits
+    }
+
+    /// Whether the two networks share any address. CIDR blocks either nest or are disjoint, so
+    /// this is the case exactly when one contains the other.
+    pub fn overlaps(&self, other: &IpNetwork) -> bool {
+        self.contains_network(other) || other.contains_network(self)
+    }
+
+    /// Every address in the network, including the network and broadcast addresses.
+    pub fn iter(&self) -> Hosts {
+        Hosts::new(self.kind, self.bits, self.last_bits())
+    }
+
+    /// The addresses that can be given to hosts.
+    ///
+    /// For IPv4 the network and broadcast addresses are left out, except in `/31` and `/32`
+    /// networks (RFC 3021). For IPv6 the Subnet-Router anycast address (the first one) is left
+    /// out, except in `/127` and `/128` networks (RFC 6164).
+    pub fn hosts(&self) -> Hosts {
+        let (first, last) = match self.kind {
+            IpAddrKind::V4 if self.host_bits() >= 2 => (self.bits + 1, self.last_bits() - 1),
+            IpAddrKind::V6 if self.host_bits() >= 2 => (self.bits + 1, self.last_bits()),
+            _ => (self.bits, self.last_bits()),
+        };
+        Hosts::new(self.kind, first, last)
+    }
+
+    /// The network one bit shorter that contains this one, or `None` for a `/0`.
+    pub fn supernet(&self) -> Option<IpNetwork> {
+        let prefix_len = self.prefix_len.checked_sub(1)?;
+        Some(IpNetwork::from_bits(self.kind, self.bits, prefix_len))
+    }
+
+    /// All subnets with the longer `prefix_len`, in address order.
+    pub fn subnets(&self, prefix_len: u8) -> Result<Subnets, NetworkError> {
+        if prefix_len < self.prefix_len || prefix_len > self.kind.bit_len() {
+            return Err(NetworkError::PrefixOutOfRange {
+                prefix_len,
+                min: self.prefix_len,
+                max: self.kind.bit_len(),
+            });
+        }
+        Ok(Subnets {
+            kind: self.kind,
+            next: Some(self.bits),
+            last: self.last_bits(),
+            prefix_len,
+        })
+    }
+
+    /// Splits the network into exactly `n` subnets that together cover it, in address order.
+    ///
+    /// When `n` is a power of two all subnets have the same size. Otherwise they differ by one
+    /// bit of prefix length and the larger ones come first: a `/24` split in three gives a `/25`
+    /// and two `/26`s. More than `usize::MAX / 2 + 1` subnets is an error, like more than the
+    /// network has addresses.
+    pub fn split(&self, n: usize) -> Result<Vec<IpNetwork>, NetworkError> {
+        let available = self.size().unwrap_or(u128::MAX);
+        if n == 0 || n as u128 > available {
+            return Err(NetworkError::InvalidSubnetCount(n));
+        }
+
+        // 2^(k-1) < n <= 2^k
+        let k = (usize::BITS - (n - 1).leading_zeros()) as u8;
+        let larger = 1usize
+            .checked_shl(u32::from(k))
+            .ok_or(NetworkError::InvalidSubnetCount(n))?
+            - n;
+        let mut subnets = Vec::with_capacity(n);
+        if larger > 0 {
+            subnets.extend(self.subnets(self.prefix_len + k - 1)?.take(larger));
+        }
+        let rest = self.subnets(self.prefix_len + k)?.skip(2 * larger);
+        subnets.extend(rest);
+        Ok(subnets)
+    }
+
+    /// The smallest network that contains all of `networks`, or `None` when there are none or
+    /// they are of different kinds.
+    pub fn smallest_supernet(networks: &[IpNetwork]) -> Option<IpNetwork> {
+        let (first, rest) = networks.split_first()?;
+        let mut low = first.bits;
+        let mut high = first.last_bits();
+        for network in rest {
+            if network.kind != first.kind {
+                return None;
+            }
+            low = low.min(network.bits);
+            high = high.max(network.last_bits());
+        }
+
+        // the common leading bits of the lowest and highest address
+        let unused = 128 - u32::from(first.kind.bit_len());
+        let prefix_len = ((low ^ high).leading_zeros() - unused) as u8;
+        Some(IpNetwork::from_bits(first.kind, low, prefix_len))
+    }
+
+    /// The shortest list of networks that covers exactly the same addresses as `networks`.
+    ///
+    /// Networks inside others are dropped and neighbours are merged, so `10.0.0.0/25`,
+    /// `10.0.0.128/25` and `10.0.0.7/32` collapse into `10.0.0.0/24`. The result is sorted.
+    pub fn collapse(networks: impl IntoIterator<Item = IpNetwork>) -> Vec<IpNetwork> {
+        let mut networks: Vec<IpNetwork> = networks.into_iter().collect();
+        networks.sort();
+
+        // merge into inclusive ranges of addresses, then cut the ranges back into blocks
+        let mut ranges: Vec<(IpAddrKind, u128, u128)> = Vec::new();
+        for network in networks {
+            let (first, last) = (network.bits, network.last_bits());
+            match ranges.last_mut() {
+                Some((kind, _, end))
+                    if *kind == network.kind && (*end == u128::MAX || first <= *end + 1) =>
+                {
+                    *end = (*end).max(last);
+                }
+                _ => ranges.push((network.kind, first, last)),
+            }
+        }
+
+        let mut collapsed = Vec::new();
+        for (kind, first, last) in ranges {
+            push_range(&mut collapsed, kind, first, last);
+        }
+        collapsed
+    }
+}
+
+/// Covers the inclusive range `first..=last` with as few blocks as possible.
+pub(crate) fn push_range(out: &mut Vec<IpNetwork>, kind: IpAddrKind, first: u128, last: u128) {
+    let width = kind.bit_len();
+    let mut start = first;
+    loop {
+        // the largest block that starts at `start` and does not go past `last`
+        let mut host_bits = (start.trailing_zeros() as u8).min(width);
+        while start + low_mask(host_bits) > last {
+            host_bits -= 1;
+        }
+        out.push(IpNetwork::from_bits(kind, start, width - host_bits));
+
+        let end = start + low_mask(host_bits);
+        if end >= last {
+            break;
+        }
+        start = end + 1;
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network(), self.prefix_len)
+    }
+}
+
+/// Parses `address/prefix`. Without a prefix the network holds just that address.
+impl FromStr for IpNetwork {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix)) => {
+                let prefix_len = match prefix.parse::<u8>() {
+                    Ok(len) if prefix.bytes().all(|b| b.is_ascii_digit()) => len,
+                    _ => return Err(NetworkError::InvalidPrefix(prefix.to_string())),
+                };
+                (addr.parse::<IpAddr>()?, prefix_len)
+            }
+            None => {
+                let addr = s.parse::<IpAddr>()?;
+                let len = addr.kind().bit_len();
+                (addr, len)
+            }
+        };
+        IpNetwork::new(addr, prefix_len)
+    }
+}
+
+/// Iterator over a range of addresses, see `IpNetwork::iter` and `IpNetwork::hosts`.
+#[derive(Debug, Clone)]
+pub struct Hosts {
+    kind: IpAddrKind,
+    next: Option<u128>,
+    last: u128,
+}
+
+impl Hosts {
+    fn new(kind: IpAddrKind, first: u128, last: u128) -> Hosts {
+        Hosts {
+            kind,
+            next: (first <= last).then_some(first),
+            last,
+        }
+    }
+
+    /// First and last address bits still to come, or `None` when empty.
+    pub(crate) fn bounds(&self) -> Option<(u128, u128)> {
+        self.next.map(|next| (next, self.last))
+    }
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let current = self.next?;
+        self.next = if current < self.last {
+            Some(current + 1)
+        } else {
+            None
+        };
+        Some(addr_from_bits(self.kind, current))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.next {
+            Some(next) => match usize::try_from(self.last - next) {
+                Ok(n) if n < usize::MAX => (n + 1, Some(n + 1)),
+                _ => (usize::MAX, None),
+            },
+            None => (0, Some(0)),
+        }
+    }
+}
+
+/// Iterator over equally sized subnets, see `IpNetwork::subnets`.
+#[derive(Debug, Clone)]
+pub struct Subnets {
+    kind: IpAddrKind,
+    next: Option<u128>,
+    last: u128,
+    prefix_len: u8,
+}
+
+impl Iterator for Subnets {
+    type Item = IpNetwork;
+
+    fn next(&mut self) -> Option<IpNetwork> {
+        let current = self.next?;
+        let subnet = IpNetwork::from_bits(self.kind, current, self.prefix_len);
+        self.next = if subnet.last_bits() < self.last {
+            Some(subnet.last_bits() + 1)
+        } else {
+            None
+        };
+        Some(subnet)
+    }
+}
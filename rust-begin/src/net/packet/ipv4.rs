@@ -0,0 +1,349 @@
+//! The IPv4 header of RFC 791.
+//!
+//! ```text
+//!  0                   1                   2                   3
+//!  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |Version|  IHL  |   DSCP    |ECN|          Total Length         |
+//! |         Identification        |Flags|      Fragment Offset    |
+//! |  Time to Live |    Protocol   |         Header Checksum       |
+//! |                       Source Address                          |
+//! |                    Destination Address                        |
+//! |                    Options                    |    Padding    |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! ```
+
+use super::{internet_checksum, need, PacketError};
+use crate::enums::Ipv4Addr;
+
+const MIN_LEN: usize = 20;
+const MAX_OPTIONS_LEN: usize = 40;
+
+const OPTION_END: u8 = 0;
+const OPTION_NOP: u8 = 1;
+
+/// A parsed IPv4 header borrowing the packet it was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Header<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Ipv4Header<'a> {
+    /// Checks the fixed fields and the option layout. The checksum is not checked here, see
+    /// `verify_checksum`.
+    ///
+    /// A capture may have cut the packet short, so the payload is only as long as the bytes
+    /// that are there, even if the total length says more.
+    pub fn parse(data: &'a [u8]) -> Result<Ipv4Header<'a>, PacketError> {
+        need(data, MIN_LEN)?;
+        let header = Ipv4Header { data };
+        if header.version() != 4 {
+            return Err(PacketError::Version(header.version()));
+        }
+        if header.ihl() < 5 {
+            return Err(PacketError::HeaderLength(header.ihl()));
+        }
+        need(data, header.header_len())?;
+        if usize::from(header.total_len()) < header.header_len() {
+            return Err(PacketError::TotalLength(header.total_len()));
+        }
+        check_options(header.raw_options())?;
+        Ok(header)
+    }
+
+    pub fn version(&self) -> u8 {
+        self.data[0] >> 4
+    }
+
+    /// Header length in 32-bit words.
+    pub fn ihl(&self) -> u8 {
+        self.data[0] & 0x0f
+    }
+
+    /// Header length in bytes, options included.
+    pub fn header_len(&self) -> usize {
+        usize::from(self.ihl()) * 4
+    }
+
+    pub fn dscp(&self) -> u8 {
+        self.data[1] >> 2
+    }
+
+    pub fn ecn(&self) -> u8 {
+        self.data[1] & 0x03
+    }
+
+    /// Length of the whole packet, header and payload.
+    pub fn total_len(&self) -> u16 {
+        u16::from_be_bytes([self.data[2], self.data[3]])
+    }
+
+    pub fn identification(&self) -> u16 {
+        u16::from_be_bytes([self.data[4], self.data[5]])
+    }
+
+    pub fn dont_fragment(&self) -> bool {
+        self.data[6] & 0x40 != 0
+    }
+
+    pub fn more_fragments(&self) -> bool {
+        self.data[6] & 0x20 != 0
+    }
+
+    /// Offset of this fragment in 8-byte units.
+    pub fn fragment_offset(&self) -> u16 {
+        u16::from_be_bytes([self.data[6] & 0x1f, self.data[7]])
+    }
+
+    pub fn ttl(&self) -> u8 {
+        self.data[8]
+    }
+
+    pub fn protocol(&self) -> u8 {
+        self.data[9]
+    }
+
+    pub fn checksum(&self) -> u16 {
+        u16::from_be_bytes([self.data[10], self.data[11]])
+    }
+
+    pub fn source(&self) -> Ipv4Addr {
+        Ipv4Addr::new(self.data[12], self.data[13], self.data[14], self.data[15])
+    }
+
+    pub fn destination(&self) -> Ipv4Addr {
+        Ipv4Addr::new(self.data[16], self.data[17], self.data[18], self.data[19])
+    }
+
+    /// The header bytes, options included.
+    pub fn header_bytes(&self) -> &'a [u8] {
+        &self.data[..self.header_len()]
+    }
+
+    fn raw_options(&self) -> &'a [u8] {
+        &self.data[MIN_LEN..self.header_len()]
+    }
+
+    pub fn options(&self) -> Ipv4Options<'a> {
+        Ipv4Options {
+            data: self.raw_options(),
+        }
+    }
+
+    /// Whether the header checksum matches the header.
+    pub fn verify_checksum(&self) -> bool {
+        internet_checksum(self.header_bytes()) == 0
+    }
+
+    /// The checksum the header should carry.
+    pub fn compute_checksum(&self) -> u16 {
+        let mut header = self.header_bytes().to_vec();
+        header[10] = 0;
+        header[11] = 0;
+        internet_checksum(&header)
+    }
+
+    pub fn payload(&self) -> &'a [u8] {
+        let end = usize::from(self.total_len()).min(self.data.len());
+        &self.data[self.header_len()..end]
+    }
+}
+
+fn check_options(mut options: &[u8]) -> Result<(), PacketError> {
+    let start = options.len();
+    while let [kind, rest @ ..] = options {
+        match *kind {
+            OPTION_END => return Ok(()),
+            OPTION_NOP => options = rest,
+            _ => {
+                let offset = MIN_LEN + start - options.len();
+                match rest.first() {
+                    Some(&len) if len >= 2 && usize::from(len) <= options.len() => {
+                        options = &options[usize::from(len)..];
+                    }
+                    _ => return Err(PacketError::BadOption { offset }),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One IPv4 option. `data` excludes the type and length bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Option<'a> {
+    pub kind: u8,
+    pub data: &'a [u8],
+}
+
+impl Ipv4Option<'_> {
+    /// Whether routers copy the option into every fragment.
+    pub fn copied(&self) -> bool {
+        self.kind & 0x80 != 0
+    }
+}
+
+/// Iterator over the options of an `Ipv4Header`, stopping at End of Options List.
+/// No Operation padding is skipped.
+#[derive(Debug, Clone)]
+pub struct Ipv4Options<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Ipv4Options<'a> {
+    type Item = Ipv4Option<'a>;
+
+    fn next(&mut self) -> Option<Ipv4Option<'a>> {
+        loop {
+            match *self.data {
+                [] | [OPTION_END, ..] => return None,
+                [OPTION_NOP, ref rest @ ..] => self.data = rest,
+                // the layout was checked when the header was parsed
+                [kind, len, ..] => {
+                    let (option, rest) = self.data.split_at(usize::from(len));
+                    self.data = rest;
+                    return Some(Ipv4Option {
+                        kind,
+                        data: &option[2..],
+                    });
+                }
+                [_] => return None,
+            }
+        }
+    }
+}
+
+/// Serializes an IPv4 header in front of a payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv4HeaderBuilder {
+    source: Ipv4Addr,
+    destination: Ipv4Addr,
+    protocol: u8,
+    ttl: u8,
+    dscp: u8,
+    ecn: u8,
+    identification: u16,
+    dont_fragment: bool,
+    more_fragments: bool,
+    fragment_offset: u16,
+    options: Vec<u8>,
+}
+
+impl Ipv4HeaderBuilder {
+    /// A header with a TTL of 64 and everything else zero.
+    pub fn new(source: Ipv4Addr, destination: Ipv4Addr, protocol: u8) -> Ipv4HeaderBuilder {
+        Ipv4HeaderBuilder {
+            source,
+            destination,
+            protocol,
+            ttl: 64,
+            dscp: 0,
+            ecn: 0,
+            identification: 0,
+            dont_fragment: false,
+            more_fragments: false,
+            fragment_offset: 0,
+            options: Vec::new(),
+        }
+    }
+
+    /// Starts from the fields of a parsed header, options included.
+    pub fn from_header(header: &Ipv4Header<'_>) -> Ipv4HeaderBuilder {
+        Ipv4HeaderBuilder {
+            source: header.source(),
+            destination: header.destination(),
+            protocol: header.protocol(),
+            ttl: header.ttl(),
+            dscp: header.dscp(),
+            ecn: header.ecn(),
+            identification: header.identification(),
+            dont_fragment: header.dont_fragment(),
+            more_fragments: header.more_fragments(),
+            fragment_offset: header.fragment_offset(),
+            options: header.raw_options().to_vec(),
+        }
+    }
+
+    pub fn ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The 6-bit DSCP; higher bits are dropped.
+    pub fn dscp(mut self, dscp: u8) -> Self {
+        self.dscp = dscp & 0x3f;
+        self
+    }
+
+    /// The 2-bit ECN; higher bits are dropped.
+    pub fn ecn(mut self, ecn: u8) -> Self {
+        self.ecn = ecn & 0x03;
+        self
+    }
+
+    pub fn identification(mut self, identification: u16) -> Self {
+        self.identification = identification;
+        self
+    }
+
+    pub fn dont_fragment(mut self, dont_fragment: bool) -> Self {
+        self.dont_fragment = dont_fragment;
+        self
+    }
+
+    /// Marks the packet as a fragment at `offset` (in 8-byte units, 13 bits).
+    pub fn fragment(mut self, offset: u16, more_fragments: bool) -> Self {
+        self.fragment_offset = offset & 0x1fff;
+        self.more_fragments = more_fragments;
+        self
+    }
+
+    /// Appends an option; `data` excludes the type and length bytes.
+    pub fn option(mut self, kind: u8, data: &[u8]) -> Self {
+        match kind {
+            OPTION_END | OPTION_NOP => self.options.push(kind),
+            _ => {
+                self.options.push(kind);
+                self.options.push((data.len() + 2) as u8);
+                self.options.extend_from_slice(data);
+            }
+        }
+        self
+    }
+
+    /// The header followed by `payload`, with total length and checksum filled in.
+    pub fn build(&self, payload: &[u8]) -> Result<Vec<u8>, PacketError> {
+        let mut options = self.options.clone();
+        // pad to whole words with End of Options List
+        while !options.len().is_multiple_of(4) {
+            options.push(OPTION_END);
+        }
+        if options.len() > MAX_OPTIONS_LEN {
+            return Err(PacketError::OptionsTooLong(options.len()));
+        }
+        check_options(&options)?;
+
+        let header_len = MIN_LEN + options.len();
+        let total_len = header_len + payload.len();
+        let total_len16 = u16::try_from(total_len).map_err(|_| PacketError::TooLarge(total_len))?;
+
+        let mut packet = Vec::with_capacity(total_len);
+        packet.push(0x40 | (header_len / 4) as u8);
+        packet.push(self.dscp << 2 | self.ecn);
+        packet.extend_from_slice(&total_len16.to_be_bytes());
+        packet.extend_from_slice(&self.identification.to_be_bytes());
+        let flags = u16::from(self.dont_fragment) << 14 | u16::from(self.more_fragments) << 13;
+        packet.extend_from_slice(&(flags | self.fragment_offset).to_be_bytes());
+        packet.push(self.ttl);
+        packet.push(self.protocol);
+        packet.extend_from_slice(&[0, 0]);
+        packet.extend_from_slice(&self.source.octets());
+        packet.extend_from_slice(&self.destination.octets());
+        packet.extend_from_slice(&options);
+
+        let checksum = internet_checksum(&packet);
+        packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+        packet.extend_from_slice(payload);
+        Ok(packet)
+    }
+}
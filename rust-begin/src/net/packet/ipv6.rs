@@ -0,0 +1,345 @@
+//! The IPv6 header of RFC 8200 and its chain of extension headers.
+//!
+//! ```text
+//!  0                   1                   2                   3
+//!  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! |Version| Traffic Class |           Flow Label                  |
+//! |         Payload Length        |  Next Header  |   Hop Limit   |
+//! |                     Source Address (128 bits)                 |
+//! |                  Destination Address (128 bits)               |
+//! +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//! ```
+//!
+//! The next header field names either the upper-layer protocol or an extension header, which
+//! starts with its own next header field, and so on until the upper layer is reached.
+
+use super::{need, protocol, PacketError};
+use crate::enums::Ipv6Addr;
+
+const FIXED_LEN: usize = 40;
+
+/// Whether `kind` is an extension header that can be walked past. ESP is left out: what
+/// follows it is encrypted.
+fn is_extension(kind: u8) -> bool {
+    matches!(
+        kind,
+        protocol::HOP_BY_HOP
+            | protocol::ROUTING
+            | protocol::FRAGMENT
+            | protocol::AUTH
+            | protocol::DESTINATION_OPTIONS
+            | protocol::MOBILITY
+            | protocol::HIP
+            | protocol::SHIM6
+    )
+}
+
+/// Length in bytes of an extension header from its length byte. Each kind counts differently:
+/// the fragment header has a fixed size and AH counts 4-byte words less two.
+fn extension_len(kind: u8, len: u8) -> usize {
+    match kind {
+        protocol::FRAGMENT => 8,
+        protocol::AUTH => (usize::from(len) + 2) * 4,
+        _ => (usize::from(len) + 1) * 8,
+    }
+}
+
+/// The length byte for an extension header of `total` bytes, if that size can be encoded.
+fn extension_len_byte(kind: u8, total: usize) -> Option<u8> {
+    match kind {
+        _ if !is_extension(kind) => None,
+        protocol::FRAGMENT => (total == 8).then_some(0),
+        protocol::AUTH if total.is_multiple_of(4) && total >= 8 => u8::try_from(total / 4 - 2).ok(),
+        protocol::AUTH => None,
+        _ if total.is_multiple_of(8) => u8::try_from(total / 8 - 1).ok(),
+        _ => None,
+    }
+}
+
+/// A parsed IPv6 header borrowing the packet it was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv6Header<'a> {
+    data: &'a [u8],
+    /// Offset of the upper-layer payload, past every extension header.
+    upper_offset: usize,
+    upper_protocol: u8,
+}
+
+impl<'a> Ipv6Header<'a> {
+    /// Checks the fixed header and walks the extension headers to find the upper layer.
+    ///
+    /// As with IPv4, a capture cut short gives a short payload rather than an error, but every
+    /// extension header has to be there in full. The walk stops after a fragment header with
+    /// a non-zero offset: what follows is the middle of the original payload, whose headers
+    /// came in the first fragment.
+    pub fn parse(data: &'a [u8]) -> Result<Ipv6Header<'a>, PacketError> {
+        need(data, FIXED_LEN)?;
+        let version = data[0] >> 4;
+        if version != 6 {
+            return Err(PacketError::Version(version));
+        }
+        let mut header = Ipv6Header {
+            data,
+            upper_offset: FIXED_LEN,
+            upper_protocol: data[6],
+        };
+        let end = header.end();
+        while is_extension(header.upper_protocol) {
+            let kind = header.upper_protocol;
+            let offset = header.upper_offset;
+            need(&data[..end], offset + 2)?;
+            let len = extension_len(kind, data[offset + 1]);
+            need(&data[..end], offset + len)?;
+            header.upper_protocol = data[offset];
+            header.upper_offset = offset + len;
+            let fragment_offset = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) >> 3;
+            if kind == protocol::FRAGMENT && fragment_offset != 0 {
+                break;
+            }
+        }
+        Ok(header)
+    }
+
+    /// End of the packet: the payload length, or less if the bytes stop sooner.
+    fn end(&self) -> usize {
+        (FIXED_LEN + usize::from(self.payload_len())).min(self.data.len())
+    }
+
+    pub fn version(&self) -> u8 {
+        self.data[0] >> 4
+    }
+
+    pub fn traffic_class(&self) -> u8 {
+        self.data[0] << 4 | self.data[1] >> 4
+    }
+
+    /// The 20-bit flow label.
+    pub fn flow_label(&self) -> u32 {
+        u32::from_be_bytes([0, self.data[1] & 0x0f, self.data[2], self.data[3]])
+    }
+
+    /// Length of everything after the fixed header, extension headers included.
+    pub fn payload_len(&self) -> u16 {
+        u16::from_be_bytes([self.data[4], self.data[5]])
+    }
+
+    /// The next header field of the fixed header.
+    pub fn next_header(&self) -> u8 {
+        self.data[6]
+    }
+
+    pub fn hop_limit(&self) -> u8 {
+        self.data[7]
+    }
+
+    pub fn source(&self) -> Ipv6Addr {
+        Ipv6Addr::from_octets(self.data[8..24].try_into().unwrap())
+    }
+
+    pub fn destination(&self) -> Ipv6Addr {
+        Ipv6Addr::from_octets(self.data[24..40].try_into().unwrap())
+    }
+
+    /// The fixed header bytes.
+    pub fn header_bytes(&self) -> &'a [u8] {
+        &self.data[..FIXED_LEN]
+    }
+
+    /// Everything after the fixed header, extension headers included.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.data[FIXED_LEN..self.end()]
+    }
+
+    pub fn extension_headers(&self) -> ExtensionHeaders<'a> {
+        ExtensionHeaders {
+            data: &self.data[FIXED_LEN..self.upper_offset],
+            kind: self.next_header(),
+        }
+    }
+
+    /// The protocol after the last extension header. `protocol::NO_NEXT_HEADER` when the
+    /// chain says nothing follows, `protocol::ESP` when the rest is encrypted. In a fragment
+    /// after the first it is the fragment header's next header, and the payload is fragment
+    /// data rather than that header.
+    pub fn upper_layer_protocol(&self) -> u8 {
+        self.upper_protocol
+    }
+
+    /// The bytes after the last extension header.
+    pub fn upper_layer_payload(&self) -> &'a [u8] {
+        &self.data[self.upper_offset..self.end()]
+    }
+}
+
+/// One extension header. `data` is everything after the next header and length bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtensionHeader<'a> {
+    /// Which extension header this is, from the next header field before it.
+    pub kind: u8,
+    pub next_header: u8,
+    pub data: &'a [u8],
+}
+
+impl ExtensionHeader<'_> {
+    /// Fragment offset in 8-byte units, more-fragments flag and identification, for a
+    /// fragment header.
+    pub fn fragment(&self) -> Option<(u16, bool, u32)> {
+        match (self.kind, self.data) {
+            (protocol::FRAGMENT, [offset_hi, offset_lo, a, b, c, d]) => Some((
+                u16::from_be_bytes([*offset_hi, *offset_lo]) >> 3,
+                offset_lo & 1 != 0,
+                u32::from_be_bytes([*a, *b, *c, *d]),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the extension headers of an `Ipv6Header`, in packet order.
+#[derive(Debug, Clone)]
+pub struct ExtensionHeaders<'a> {
+    data: &'a [u8],
+    kind: u8,
+}
+
+impl<'a> Iterator for ExtensionHeaders<'a> {
+    type Item = ExtensionHeader<'a>;
+
+    fn next(&mut self) -> Option<ExtensionHeader<'a>> {
+        // the chain was checked when the header was parsed
+        let [next_header, len, ..] = *self.data else {
+            return None;
+        };
+        let (header, rest) = self.data.split_at(extension_len(self.kind, len));
+        let extension = ExtensionHeader {
+            kind: self.kind,
+            next_header,
+            data: &header[2..],
+        };
+        self.data = rest;
+        self.kind = next_header;
+        Some(extension)
+    }
+}
+
+/// Serializes an IPv6 header and its extension headers in front of a payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv6HeaderBuilder {
+    source: Ipv6Addr,
+    destination: Ipv6Addr,
+    protocol: u8,
+    traffic_class: u8,
+    flow_label: u32,
+    hop_limit: u8,
+    extensions: Vec<(u8, Vec<u8>)>,
+}
+
+impl Ipv6HeaderBuilder {
+    /// A header with a hop limit of 64 and everything else zero. `protocol` is the upper
+    /// layer; extension headers go in between. Zones are not carried on the wire and are
+    /// dropped.
+    pub fn new(source: Ipv6Addr, destination: Ipv6Addr, protocol: u8) -> Ipv6HeaderBuilder {
+        Ipv6HeaderBuilder {
+            source,
+            destination,
+            protocol,
+            traffic_class: 0,
+            flow_label: 0,
+            hop_limit: 64,
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Starts from the fields of a parsed header, extension headers included.
+    pub fn from_header(header: &Ipv6Header<'_>) -> Ipv6HeaderBuilder {
+        Ipv6HeaderBuilder {
+            source: header.source(),
+            destination: header.destination(),
+            protocol: header.upper_layer_protocol(),
+            traffic_class: header.traffic_class(),
+            flow_label: header.flow_label(),
+            hop_limit: header.hop_limit(),
+            extensions: header
+                .extension_headers()
+                .map(|extension| (extension.kind, extension.data.to_vec()))
+                .collect(),
+        }
+    }
+
+    pub fn traffic_class(mut self, traffic_class: u8) -> Self {
+        self.traffic_class = traffic_class;
+        self
+    }
+
+    /// The 20-bit flow label; higher bits are dropped.
+    pub fn flow_label(mut self, flow_label: u32) -> Self {
+        self.flow_label = flow_label & 0xf_ffff;
+        self
+    }
+
+    pub fn hop_limit(mut self, hop_limit: u8) -> Self {
+        self.hop_limit = hop_limit;
+        self
+    }
+
+    /// Appends an extension header; `data` excludes the next header and length bytes.
+    /// `build` refuses kinds that are not walkable extension headers.
+    ///
+    /// Hop-by-Hop and Destination Options are padded to a whole number of 8-byte units with
+    /// Pad1/PadN options. Other kinds must already have a size their length field can encode.
+    pub fn extension(mut self, kind: u8, data: &[u8]) -> Self {
+        let mut data = data.to_vec();
+        if matches!(kind, protocol::HOP_BY_HOP | protocol::DESTINATION_OPTIONS) {
+            match (8 - (data.len() + 2) % 8) % 8 {
+                0 => {}
+                1 => data.push(0),
+                pad => {
+                    data.extend_from_slice(&[1, pad as u8 - 2]);
+                    data.resize(data.len() + pad - 2, 0);
+                }
+            }
+        }
+        self.extensions.push((kind, data));
+        self
+    }
+
+    /// The headers followed by `payload`, with the payload length and next header fields
+    /// filled in.
+    pub fn build(&self, payload: &[u8]) -> Result<Vec<u8>, PacketError> {
+        let extensions_len: usize = self.extensions.iter().map(|(_, data)| data.len() + 2).sum();
+        let payload_len = extensions_len + payload.len();
+        let payload_len16 = u16::try_from(payload_len)
+            .map_err(|_| PacketError::TooLarge(FIXED_LEN + payload_len))?;
+
+        let first = self
+            .extensions
+            .first()
+            .map_or(self.protocol, |(kind, _)| *kind);
+        let mut packet = Vec::with_capacity(FIXED_LEN + payload_len);
+        let first_word = 6 << 28 | u32::from(self.traffic_class) << 20 | self.flow_label;
+        packet.extend_from_slice(&first_word.to_be_bytes());
+        packet.extend_from_slice(&payload_len16.to_be_bytes());
+        packet.push(first);
+        packet.push(self.hop_limit);
+        packet.extend_from_slice(&self.source.octets());
+        packet.extend_from_slice(&self.destination.octets());
+
+        for (i, (kind, data)) in self.extensions.iter().enumerate() {
+            let next = self
+                .extensions
+                .get(i + 1)
+                .map_or(self.protocol, |(kind, _)| *kind);
+            let len =
+                extension_len_byte(*kind, data.len() + 2).ok_or(PacketError::BadExtension {
+                    kind: *kind,
+                    len: data.len(),
+                })?;
+            packet.push(next);
+            packet.push(len);
+            packet.extend_from_slice(data);
+        }
+        packet.extend_from_slice(payload);
+        Ok(packet)
+    }
+}
@@ -0,0 +1,165 @@
+//! IPv4 and IPv6 packet headers.
+//!
+//! `Ipv4Header` and `Ipv6Header` are views over a byte slice: parsing checks that the header is
+//! well formed and every accessor reads straight from the borrowed bytes, nothing is copied.
+//! The builders go the other way and serialize a header, with its checksum, in front of a
+//! payload.
+
+mod ipv4;
+mod ipv6;
+
+pub use ipv4::{Ipv4Header, Ipv4HeaderBuilder, Ipv4Option, Ipv4Options};
+pub use ipv6::{ExtensionHeader, ExtensionHeaders, Ipv6Header, Ipv6HeaderBuilder};
+
+use std::error::Error;
+use std::fmt;
+
+use crate::enums::IpAddr;
+
+/// IANA protocol numbers used in the IPv4 protocol and IPv6 next header fields.
+pub mod protocol {
+    pub const HOP_BY_HOP: u8 = 0;
+    pub const ICMP: u8 = 1;
+    pub const TCP: u8 = 6;
+    pub const UDP: u8 = 17;
+    pub const ROUTING: u8 = 43;
+    pub const FRAGMENT: u8 = 44;
+    pub const ESP: u8 = 50;
+    pub const AUTH: u8 = 51;
+    pub const ICMPV6: u8 = 58;
+    pub const NO_NEXT_HEADER: u8 = 59;
+    pub const DESTINATION_OPTIONS: u8 = 60;
+    pub const MOBILITY: u8 = 135;
+    pub const HIP: u8 = 139;
+    pub const SHIM6: u8 = 140;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    /// Fewer bytes than the header says it needs.
+    Truncated { needed: usize, available: usize },
+    /// The version field does not match the header being parsed.
+    Version(u8),
+    /// The IPv4 header length field is below the minimum of 5 words.
+    HeaderLength(u8),
+    /// The IPv4 total length is smaller than the header itself.
+    TotalLength(u16),
+    /// An IPv4 option runs past the end of the options, or has a length below 2.
+    BadOption { offset: usize },
+    /// Options longer than the 40 bytes an IPv4 header has room for.
+    OptionsTooLong(usize),
+    /// A packet too large for its length field.
+    TooLarge(usize),
+    /// Not an extension header, or data whose size its length field cannot encode.
+    BadExtension { kind: u8, len: usize },
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketError::Truncated { needed, available } => write!(
+                f,
+                "packet truncated: needed {} bytes, have {}",
+                needed, available
+            ),
+            PacketError::Version(v) => write!(f, "unexpected IP version {}", v),
+            PacketError::HeaderLength(ihl) => write!(f, "IPv4 header length {} is below 5", ihl),
+            PacketError::TotalLength(len) => {
+                write!(f, "IPv4 total length {} is shorter than the header", len)
+            }
+            PacketError::BadOption { offset } => {
+                write!(f, "malformed IPv4 option at offset {}", offset)
+            }
+            PacketError::OptionsTooLong(len) => {
+                write!(f, "{} bytes of IPv4 options, at most 40 fit", len)
+            }
+            PacketError::TooLarge(len) => write!(f, "packet of {} bytes is too large", len),
+            PacketError::BadExtension { kind, len } => write!(
+                f,
+                "extension header {} cannot carry {} bytes of data",
+                kind, len
+            ),
+        }
+    }
+}
+
+impl Error for PacketError {}
+
+/// Checks that `data` holds at least `needed` bytes.
+fn need(data: &[u8], needed: usize) -> Result<(), PacketError> {
+    if data.len() < needed {
+        Err(PacketError::Truncated {
+            needed,
+            available: data.len(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// The Internet checksum of RFC 1071: the ones' complement of the ones' complement sum of the
+/// data taken as 16-bit big-endian words, an odd last byte padded with zero.
+///
+/// Running it over a header that already holds its checksum gives 0 when the checksum is right.
+pub fn internet_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = 0;
+    let mut words = data.chunks_exact(2);
+    for word in &mut words {
+        sum += u32::from(u16::from_be_bytes([word[0], word[1]]));
+    }
+    if let [last] = words.remainder() {
+        sum += u32::from(*last) << 8;
+    }
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// An IPv4 or IPv6 header, chosen by the version nibble.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpHeader<'a> {
+    V4(Ipv4Header<'a>),
+    V6(Ipv6Header<'a>),
+}
+
+impl<'a> IpHeader<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<IpHeader<'a>, PacketError> {
+        need(data, 1)?;
+        match data[0] >> 4 {
+            4 => Ipv4Header::parse(data).map(IpHeader::V4),
+            6 => Ipv6Header::parse(data).map(IpHeader::V6),
+            version => Err(PacketError::Version(version)),
+        }
+    }
+
+    pub fn source(&self) -> IpAddr {
+        match self {
+            IpHeader::V4(header) => IpAddr::from(header.source()),
+            IpHeader::V6(header) => IpAddr::V6(header.source()),
+        }
+    }
+
+    pub fn destination(&self) -> IpAddr {
+        match self {
+            IpHeader::V4(header) => IpAddr::from(header.destination()),
+            IpHeader::V6(header) => IpAddr::V6(header.destination()),
+        }
+    }
+
+    /// The protocol of the payload; for IPv6 the one after all extension headers.
+    pub fn protocol(&self) -> u8 {
+        match self {
+            IpHeader::V4(header) => header.protocol(),
+            IpHeader::V6(header) => header.upper_layer_protocol(),
+        }
+    }
+
+    /// The bytes after the header; for IPv6 after all extension headers.
+    pub fn payload(&self) -> &'a [u8] {
+        match self {
+            IpHeader::V4(header) => header.payload(),
+            IpHeader::V6(header) => header.upper_layer_payload(),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Reading classic libpcap capture files, the `.pcap` format written by tcpdump.
+//!
+//! A file is a 24-byte global header followed by records, each a 16-byte record header and the
+//! captured bytes. The magic number at the start tells the byte order of every field after it
+//! and whether timestamps count microseconds or nanoseconds. The newer pcapng format is not
+//! handled.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::time::Duration;
+
+use super::packet::{IpHeader, PacketError};
+
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+/// Records larger than this are treated as corruption rather than allocated.
+const MAX_RECORD_LEN: u32 = 256 * 1024 * 1024;
+
+/// Link-layer header types from the tcpdump.org registry, the ones `ip_header` understands.
+pub mod link_type {
+    /// BSD loopback: a 4-byte address family in the host's byte order.
+    pub const NULL: u32 = 0;
+    pub const ETHERNET: u32 = 1;
+    /// Bare IPv4 or IPv6 packets.
+    pub const RAW: u32 = 101;
+    /// OpenBSD loopback: like `NULL`, but the family is big-endian.
+    pub const LOOP: u32 = 108;
+    /// Linux "cooked" capture, as taken on the `any` interface.
+    pub const LINUX_SLL: u32 = 113;
+    pub const IPV4: u32 = 228;
+    pub const IPV6: u32 = 229;
+}
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+
+#[derive(Debug)]
+pub enum PcapError {
+    Io(io::Error),
+    /// The file does not start with a pcap magic number.
+    BadMagic(u32),
+    /// A record claims more bytes than any capture would hold.
+    RecordTooLarge(u32),
+    /// The link layer is one `ip_header` does not know.
+    UnsupportedLinkType(u32),
+    /// The link layer carries something other than IP.
+    NotIp(u16),
+    Packet(PacketError),
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcapError::Io(e) => write!(f, "reading capture: {}", e),
+            PcapError::BadMagic(magic) => write!(f, "not a pcap file (magic {:#010x})", magic),
+            PcapError::RecordTooLarge(len) => write!(f, "record of {} bytes is too large", len),
+            PcapError::UnsupportedLinkType(link) => write!(f, "unsupported link type {}", link),
+            PcapError::NotIp(ethertype) => {
+                write!(f, "frame carries ethertype {:#06x}, not IP", ethertype)
+            }
+            PcapError::Packet(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for PcapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PcapError::Io(e) => Some(e),
+            PcapError::Packet(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PcapError {
+    fn from(e: io::Error) -> Self {
+        PcapError::Io(e)
+    }
+}
+
+impl From<PacketError> for PcapError {
+    fn from(e: PacketError) -> Self {
+        PcapError::Packet(e)
+    }
+}
+
+/// One captured packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcapRecord {
+    /// Time since the Unix epoch.
+    pub timestamp: Duration,
+    /// Length of the packet on the wire; `data` is shorter when the snapshot length cut it.
+    pub original_len: u32,
+    pub data: Vec<u8>,
+    link_type: u32,
+}
+
+impl PcapRecord {
+    /// Whether the capture kept less than the whole packet.
+    pub fn is_truncated(&self) -> bool {
+        (self.data.len() as u64) < u64::from(self.original_len)
+    }
+
+    /// The IP header inside the frame, after the link-layer header and any VLAN tags.
+    pub fn ip_header(&self) -> Result<IpHeader<'_>, PcapError> {
+        let data = &self.data[..];
+        let ip = match self.link_type {
+            link_type::RAW | link_type::IPV4 | link_type::IPV6 => data,
+            link_type::NULL | link_type::LOOP => data.get(4..).unwrap_or_default(),
+            link_type::ETHERNET => {
+                let mut offset = 12;
+                loop {
+                    let ethertype = read_u16(data, offset)?;
+                    match ethertype {
+                        ETHERTYPE_VLAN | ETHERTYPE_QINQ => offset += 4,
+                        ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => break &data[offset + 2..],
+                        _ => return Err(PcapError::NotIp(ethertype)),
+                    }
+                }
+            }
+            link_type::LINUX_SLL => match read_u16(data, 14)? {
+                ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => &data[16..],
+                ethertype => return Err(PcapError::NotIp(ethertype)),
+            },
+            link => return Err(PcapError::UnsupportedLinkType(link)),
+        };
+        Ok(IpHeader::parse(ip)?)
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, PcapError> {
+    match data.get(offset..offset + 2) {
+        Some(&[hi, lo]) => Ok(u16::from_be_bytes([hi, lo])),
+        _ => Err(PcapError::Packet(PacketError::Truncated {
+            needed: offset + 2,
+            available: data.len(),
+        })),
+    }
+}
+
+/// Reads records from a capture file.
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use rust_begin::net::pcap::PcapReader;
+///
+/// let file = BufReader::new(File::open("capture.pcap")?);
+/// for record in PcapReader::new(file)? {
+///     let record = record?;
+///     if let Ok(header) = record.ip_header() {
+///         println!("{} -> {}", header.source(), header.destination());
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct PcapReader<R> {
+    reader: R,
+    big_endian: bool,
+    nanos: bool,
+    version: (u16, u16),
+    snaplen: u32,
+    link_type: u32,
+    done: bool,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Reads the global header.
+    pub fn new(mut reader: R) -> Result<PcapReader<R>, PcapError> {
+        let mut header = [0; 24];
+        reader.read_exact(&mut header)?;
+        let magic = u32::from_be_bytes(header[..4].try_into().unwrap());
+        let (big_endian, nanos) = match (magic, magic.swap_bytes()) {
+            (MAGIC_MICROS, _) => (true, false),
+            (MAGIC_NANOS, _) => (true, true),
+            (_, MAGIC_MICROS) => (false, false),
+            (_, MAGIC_NANOS) => (false, true),
+            _ => return Err(PcapError::BadMagic(magic)),
+        };
+        let mut pcap = PcapReader {
+            reader,
+            big_endian,
+            nanos,
+            version: (0, 0),
+            snaplen: 0,
+            link_type: 0,
+            done: false,
+        };
+        pcap.version = (pcap.u16_at(&header, 4), pcap.u16_at(&header, 6));
+        pcap.snaplen = pcap.u32_at(&header, 16);
+        // the top bits of the link type field carry FCS information
+        pcap.link_type = pcap.u32_at(&header, 20) & 0x0fff_ffff;
+        Ok(pcap)
+    }
+
+    fn u16_at(&self, bytes: &[u8], offset: usize) -> u16 {
+        let raw = [bytes[offset], bytes[offset + 1]];
+        if self.big_endian {
+            u16::from_be_bytes(raw)
+        } else {
+            u16::from_le_bytes(raw)
+        }
+    }
+
+    fn u32_at(&self, bytes: &[u8], offset: usize) -> u32 {
+        let raw = bytes[offset..offset + 4].try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(raw)
+        } else {
+            u32::from_le_bytes(raw)
+        }
+    }
+
+    /// Major and minor format version, normally 2.4.
+    pub fn version(&self) -> (u16, u16) {
+        self.version
+    }
+
+    /// The most bytes kept of any packet.
+    pub fn snaplen(&self) -> u32 {
+        self.snaplen
+    }
+
+    /// One of the `link_type` constants, or another registry value.
+    pub fn link_type(&self) -> u32 {
+        self.link_type
+    }
+
+    /// Whether timestamps in the file count nanoseconds rather than microseconds.
+    pub fn nanosecond_timestamps(&self) -> bool {
+        self.nanos
+    }
+
+    /// The next record, or `None` at a clean end of file. A record cut off part way is an
+    /// `UnexpectedEof` error.
+    pub fn read_record(&mut self) -> Result<Option<PcapRecord>, PcapError> {
+        let mut header = [0; 16];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.reader.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let seconds = self.u32_at(&header, 0);
+        let fraction = self.u32_at(&header, 4);
+        let captured_len = self.u32_at(&header, 8);
+        let original_len = self.u32_at(&header, 12);
+        if captured_len > MAX_RECORD_LEN {
+            return Err(PcapError::RecordTooLarge(captured_len));
+        }
+
+        let mut data = vec![0; captured_len as usize];
+        self.reader.read_exact(&mut data)?;
+        let nanos = if self.nanos {
+            u64::from(fraction)
+        } else {
+            u64::from(fraction) * 1000
+        };
+        Ok(Some(PcapRecord {
+            timestamp: Duration::from_secs(u64::from(seconds)) + Duration::from_nanos(nanos),
+            original_len,
+            data,
+            link_type: self.link_type,
+        }))
+    }
+}
+
+/// Yields records until the end of the file, or until the first error.
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<PcapRecord, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
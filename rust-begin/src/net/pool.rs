@@ -0,0 +1,528 @@
+//! Handing out addresses from a subnet, the way a DHCP server does.
+//!
+//! An `IpPool` owns the usable hosts of one `IpNetwork`. Ranges can be reserved so they are
+//! never handed out, and every other address is leased to a client for a limited time. Each
+//! client holds at most one lease: asking again renews it instead of taking a second address.
+//!
+//! The pool locks its table internally, so one pool can be shared between threads behind an
+//! `Arc`. Two threads asking for the same address are serialized, and whichever comes second
+//! gets `PoolError::Conflict` naming the client that won.
+//!
+//! Times are passed in rather than read from the clock, which keeps expiry predictable. The
+//! table can be saved to a text file and loaded again after a restart:
+//!
+//! ```text
+//! network 192.0.2.0/24
+//! reserve 192.0.2.1 192.0.2.9
+//! lease 192.0.2.10 1700000000.000000000 aa:bb:cc:dd:ee:ff
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{addr_bits, addr_from_bits, IpNetwork};
+use crate::atomic::write_atomic;
+use crate::enums::IpAddr;
+use crate::timestamp::{format_timestamp, parse_timestamp};
+
+/// An address held by a client until `expires`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    addr: IpAddr,
+    client: String,
+    expires: SystemTime,
+}
+
+impl Lease {
+    pub fn addr(&self) -> &IpAddr {
+        &self.addr
+    }
+
+    pub fn client(&self) -> &str {
+        &self.client
+    }
+
+    pub fn expires(&self) -> SystemTime {
+        self.expires
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires <= now
+    }
+
+    /// Time left on the lease, zero once it has expired.
+    pub fn remaining(&self, now: SystemTime) -> Duration {
+        self.expires.duration_since(now).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolError {
+    /// The address is not one of the pool's hosts.
+    NotInPool(IpAddr),
+    /// The address falls in a reserved range.
+    Reserved(IpAddr),
+    /// Another client holds an unexpired lease on the address.
+    Conflict { addr: IpAddr, holder: String },
+    /// Every address is reserved or leased.
+    Exhausted,
+    /// The client holds no lease on the address.
+    NotLeased(IpAddr),
+    /// A reserved range whose first address comes after its last.
+    InvalidRange { first: IpAddr, last: IpAddr },
+    /// Client ids must be non-empty and free of control characters.
+    InvalidClient(String),
+    /// A lease time that runs past the end of `SystemTime`.
+    TtlTooLong(Duration),
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::NotInPool(addr) => write!(f, "{} is not in the pool", addr),
+            PoolError::Reserved(addr) => write!(f, "{} is reserved", addr),
+            PoolError::Conflict { addr, holder } => {
+                write!(f, "{} is already leased to {}", addr, holder)
+            }
+            PoolError::Exhausted => write!(f, "no free addresses left in the pool"),
+            PoolError::NotLeased(addr) => write!(f, "no lease on {} for this client", addr),
+            PoolError::InvalidRange { first, last } => {
+                write!(f, "range {} - {} ends before it starts", first, last)
+            }
+            PoolError::InvalidClient(client) => write!(f, "invalid client id {:?}", client),
+            PoolError::TtlTooLong(ttl) => write!(f, "a lease of {:?} is too long", ttl),
+        }
+    }
+}
+
+impl Error for PoolError {}
+
+#[derive(Debug)]
+pub enum LeaseFileError {
+    Io(io::Error),
+    /// A line of the file could not be understood.
+    Parse {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for LeaseFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeaseFileError::Io(e) => write!(f, "lease file: {}", e),
+            LeaseFileError::Parse { line, reason } => {
+                write!(f, "lease file line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl Error for LeaseFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LeaseFileError::Io(e) => Some(e),
+            LeaseFileError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LeaseFileError {
+    fn from(e: io::Error) -> Self {
+        LeaseFileError::Io(e)
+    }
+}
+
+#[derive(Debug)]
+pub struct IpPool {
+    network: IpNetwork,
+    /// Bits of the first and last usable host.
+    first: u128,
+    last: u128,
+    table: Mutex<Table>,
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    /// Inclusive ranges of address bits, in the order they were reserved.
+    reserved: Vec<(u128, u128)>,
+    leases: BTreeMap<u128, Lease>,
+    by_client: HashMap<String, u128>,
+    /// Where the next search for a free address starts, so released addresses are not
+    /// handed out again straight away.
+    next: u128,
+}
+
+impl Table {
+    /// The end of a reserved range holding `bits`.
+    fn reserved_end(&self, bits: u128) -> Option<u128> {
+        self.reserved
+            .iter()
+            .filter(|&&(first, last)| first <= bits && bits <= last)
+            .map(|&(_, last)| last)
+            .max()
+    }
+
+    /// The first address from `from` to `to` that is neither reserved nor under a live lease.
+    fn scan(&self, from: u128, to: u128, now: SystemTime) -> Option<u128> {
+        let mut bits = from;
+        while bits <= to {
+            if let Some(end) = self.reserved_end(bits) {
+                bits = end.checked_add(1)?;
+                continue;
+            }
+            match self.leases.get(&bits) {
+                Some(lease) if !lease.is_expired(now) => bits = bits.checked_add(1)?,
+                _ => return Some(bits),
+            }
+        }
+        None
+    }
+
+    fn remove(&mut self, bits: u128) -> Option<Lease> {
+        let lease = self.leases.remove(&bits)?;
+        self.by_client.remove(&lease.client);
+        Some(lease)
+    }
+
+    fn insert(&mut self, bits: u128, lease: Lease) -> Lease {
+        // a reclaimed, expired lease may still sit on the address
+        self.remove(bits);
+        self.by_client.insert(lease.client.clone(), bits);
+        self.leases.insert(bits, lease.clone());
+        lease
+    }
+}
+
+/// When a lease of `ttl` taken at `now` runs out.
+fn expiry(now: SystemTime, ttl: Duration) -> Result<SystemTime, PoolError> {
+    now.checked_add(ttl).ok_or(PoolError::TtlTooLong(ttl))
+}
+
+fn check_client(client: &str) -> Result<(), PoolError> {
+    if client.is_empty() || client.chars().any(char::is_control) {
+        Err(PoolError::InvalidClient(client.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+impl IpPool {
+    /// A pool of the network's usable hosts, see `IpNetwork::hosts`.
+    pub fn new(network: IpNetwork) -> IpPool {
+        let (first, last) = network
+            .hosts()
+            .bounds()
+            .expect("every network has at least one host");
+        IpPool {
+            network,
+            first,
+            last,
+            table: Mutex::new(Table {
+                next: first,
+                ..Table::default()
+            }),
+        }
+    }
+
+    pub fn network(&self) -> &IpNetwork {
+        &self.network
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Table> {
+        // every update leaves the table consistent, so a panic elsewhere does not spoil it
+        self.table.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn host_bits(&self, addr: &IpAddr) -> Result<u128, PoolError> {
+        let bits = addr_bits(addr);
+        if addr.kind() == self.network.kind() && self.first <= bits && bits <= self.last {
+            Ok(bits)
+        } else {
+            Err(PoolError::NotInPool(addr.clone()))
+        }
+    }
+
+    fn addr(&self, bits: u128) -> IpAddr {
+        addr_from_bits(self.network.kind(), bits)
+    }
+
+    /// Keeps `first` to `last` out of allocation. Fails with `Conflict` if a live lease sits
+    /// in the range.
+    pub fn reserve(&self, first: &IpAddr, last: &IpAddr, now: SystemTime) -> Result<(), PoolError> {
+        let (lo, hi) = (self.host_bits(first)?, self.host_bits(last)?);
+        if lo > hi {
+            return Err(PoolError::InvalidRange {
+                first: first.clone(),
+                last: last.clone(),
+            });
+        }
+        let mut table = self.lock();
+        if let Some(lease) = table
+            .leases
+            .range(lo..=hi)
+            .map(|(_, lease)| lease)
+            .find(|lease| !lease.is_expired(now))
+        {
+            return Err(PoolError::Conflict {
+                addr: lease.addr.clone(),
+                holder: lease.client.clone(),
+            });
+        }
+        table.reserved.push((lo, hi));
+        Ok(())
+    }
+
+    /// The reserved ranges, in the order they were added.
+    pub fn reserved(&self) -> Vec<(IpAddr, IpAddr)> {
+        self.lock()
+            .reserved
+            .iter()
+            .map(|&(first, last)| (self.addr(first), self.addr(last)))
+            .collect()
+    }
+
+    /// Leases the next free address to `client` for `ttl`. A client that already holds a
+    /// lease gets it renewed instead.
+    pub fn allocate(
+        &self,
+        client: &str,
+        ttl: Duration,
+        now: SystemTime,
+    ) -> Result<Lease, PoolError> {
+        check_client(client)?;
+        let expires = expiry(now, ttl)?;
+        let mut table = self.lock();
+        if let Some(&bits) = table.by_client.get(client) {
+            let lease = table
+                .leases
+                .get_mut(&bits)
+                .expect("client index matches leases");
+            lease.expires = expires;
+            return Ok(lease.clone());
+        }
+
+        let start = table.next;
+        let bits = table
+            .scan(start, self.last, now)
+            .or_else(|| table.scan(self.first, start.checked_sub(1)?, now))
+            .ok_or(PoolError::Exhausted)?;
+        table.next = match bits.checked_add(1) {
+            Some(next) if next <= self.last => next,
+            _ => self.first,
+        };
+        Ok(table.insert(
+            bits,
+            Lease {
+                addr: self.addr(bits),
+                client: client.to_string(),
+                expires,
+            },
+        ))
+    }
+
+    /// Leases one particular address to `client`, as when a client asks to keep the address it
+    /// had. Any other lease the client holds is given up.
+    pub fn request(
+        &self,
+        client: &str,
+        addr: &IpAddr,
+        ttl: Duration,
+        now: SystemTime,
+    ) -> Result<Lease, PoolError> {
+        check_client(client)?;
+        let bits = self.host_bits(addr)?;
+        let expires = expiry(now, ttl)?;
+        let mut table = self.lock();
+        if table.reserved_end(bits).is_some() {
+            return Err(PoolError::Reserved(addr.clone()));
+        }
+        if let Some(lease) = table.leases.get(&bits) {
+            if lease.client != client && !lease.is_expired(now) {
+                return Err(PoolError::Conflict {
+                    addr: addr.clone(),
+                    holder: lease.client.clone(),
+                });
+            }
+        }
+        if let Some(&held) = table.by_client.get(client) {
+            table.remove(held);
+        }
+        Ok(table.insert(
+            bits,
+            Lease {
+                addr: self.addr(bits),
+                client: client.to_string(),
+                expires,
+            },
+        ))
+    }
+
+    /// Extends the client's lease on `addr` to `ttl` from `now`. An expired lease can still be
+    /// renewed as long as nobody else has taken the address.
+    pub fn renew(
+        &self,
+        client: &str,
+        addr: &IpAddr,
+        ttl: Duration,
+        now: SystemTime,
+    ) -> Result<Lease, PoolError> {
+        let bits = self.host_bits(addr)?;
+        let expires = expiry(now, ttl)?;
+        let mut table = self.lock();
+        match table.leases.get_mut(&bits) {
+            Some(lease) if lease.client == client => {
+                lease.expires = expires;
+                Ok(lease.clone())
+            }
+            _ => Err(PoolError::NotLeased(addr.clone())),
+        }
+    }
+
+    /// Gives the address back to the pool.
+    pub fn release(&self, client: &str, addr: &IpAddr) -> Result<Lease, PoolError> {
+        let bits = self.host_bits(addr)?;
+        let mut table = self.lock();
+        match table.leases.get(&bits) {
+            Some(lease) if lease.client == client => Ok(table.remove(bits).unwrap()),
+            _ => Err(PoolError::NotLeased(addr.clone())),
+        }
+    }
+
+    /// Drops every lease that has expired by `now` and returns them.
+    pub fn expire(&self, now: SystemTime) -> Vec<Lease> {
+        let mut table = self.lock();
+        let expired: Vec<u128> = table
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.is_expired(now))
+            .map(|(&bits, _)| bits)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|bits| table.remove(bits))
+            .collect()
+    }
+
+    pub fn lease(&self, addr: &IpAddr) -> Option<Lease> {
+        let bits = self.host_bits(addr).ok()?;
+        self.lock().leases.get(&bits).cloned()
+    }
+
+    pub fn lease_for_client(&self, client: &str) -> Option<Lease> {
+        let table = self.lock();
+        let bits = table.by_client.get(client)?;
+        table.leases.get(bits).cloned()
+    }
+
+    /// Every lease in the table, expired ones not yet reclaimed included, in address order.
+    pub fn leases(&self) -> Vec<Lease> {
+        self.lock().leases.values().cloned().collect()
+    }
+
+    /// Writes the table to `path`, so that a crash leaves either the old table or the new one.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LeaseFileError> {
+        let mut out = Vec::new();
+        {
+            let table = self.lock();
+            writeln!(out, "network {}", self.network)?;
+            for &(first, last) in &table.reserved {
+                writeln!(out, "reserve {} {}", self.addr(first), self.addr(last))?;
+            }
+            for lease in table.leases.values() {
+                writeln!(
+                    out,
+                    "lease {} {} {}",
+                    lease.addr,
+                    format_timestamp(lease.expires),
+                    lease.client
+                )?;
+            }
+        }
+        write_atomic(path.as_ref(), &out)?;
+        Ok(())
+    }
+
+    /// Reads a table written by `save`. Expired leases are kept until `expire` or an
+    /// allocation reclaims them.
+    pub fn load(path: impl AsRef<Path>) -> Result<IpPool, LeaseFileError> {
+        let text = fs::read_to_string(path)?;
+        let mut pool: Option<IpPool> = None;
+        for (i, line) in text.lines().enumerate() {
+            let parse_error = |reason: String| LeaseFileError::Parse {
+                line: i + 1,
+                reason,
+            };
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.splitn(4, ' ');
+            match (fields.next(), &pool) {
+                (Some("network"), None) => {
+                    let network = fields.next().unwrap_or_default();
+                    let network: IpNetwork =
+                        network.parse().map_err(|e| parse_error(format!("{}", e)))?;
+                    pool = Some(IpPool::new(network));
+                }
+                (Some("network"), Some(_)) => {
+                    return Err(parse_error("a second network line".to_string()))
+                }
+                (Some(_), None) => {
+                    return Err(parse_error("expected the network line first".to_string()))
+                }
+                (Some("reserve"), Some(pool)) => {
+                    let first = parse_addr(fields.next()).map_err(parse_error)?;
+                    let last = parse_addr(fields.next()).map_err(parse_error)?;
+                    pool.reserve(&first, &last, UNIX_EPOCH)
+                        .map_err(|e| parse_error(e.to_string()))?;
+                }
+                (Some("lease"), Some(pool)) => {
+                    let addr = parse_addr(fields.next()).map_err(parse_error)?;
+                    let expires = parse_time(fields.next()).map_err(parse_error)?;
+                    let client = fields.next().unwrap_or_default();
+                    check_client(client).map_err(|e| parse_error(e.to_string()))?;
+                    let bits = pool
+                        .host_bits(&addr)
+                        .map_err(|e| parse_error(e.to_string()))?;
+                    let mut table = pool.lock();
+                    if table.leases.contains_key(&bits) || table.by_client.contains_key(client) {
+                        return Err(parse_error(format!("duplicate lease for {}", addr)));
+                    }
+                    table.insert(
+                        bits,
+                        Lease {
+                            addr,
+                            client: client.to_string(),
+                            expires,
+                        },
+                    );
+                }
+                (Some(keyword), Some(_)) => {
+                    return Err(parse_error(format!("unknown entry {:?}", keyword)))
+                }
+                (None, _) => unreachable!("splitn yields at least one field"),
+            }
+        }
+        pool.ok_or(LeaseFileError::Parse {
+            line: text.lines().count(),
+            reason: "no network line".to_string(),
+        })
+    }
+}
+
+fn parse_addr(field: Option<&str>) -> Result<IpAddr, String> {
+    let field = field.ok_or("missing address")?;
+    field.parse().map_err(|e| format!("{}", e))
+}
+
+/// `secs.nanos` since the Unix epoch, as written by `save`.
+fn parse_time(field: Option<&str>) -> Result<SystemTime, String> {
+    let field = field.ok_or("missing expiry")?;
+    parse_timestamp(field).ok_or_else(|| format!("bad expiry {:?}", field))
+}
@@ -0,0 +1,193 @@
+//! A routing table: networks mapped to next hops, answering longest-prefix-match lookups.
+
+use std::fmt;
+
+use super::trie::PrefixTrie;
+use super::{addr_bits, IpNetwork};
+use crate::enums::{IpAddr, IpAddrKind};
+
+/// Where packets for a route go: a gateway, an interface, or both.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NextHop {
+    pub via: Option<IpAddr>,
+    pub dev: Option<String>,
+    pub metric: Option<u32>,
+}
+
+impl NextHop {
+    /// A route through a gateway.
+    pub fn via(gateway: IpAddr) -> NextHop {
+        NextHop {
+            via: Some(gateway),
+            ..NextHop::default()
+        }
+    }
+
+    /// A route straight out of an interface.
+    pub fn dev(dev: impl Into<String>) -> NextHop {
+        NextHop {
+            dev: Some(dev.into()),
+            ..NextHop::default()
+        }
+    }
+
+    pub fn with_dev(mut self, dev: impl Into<String>) -> NextHop {
+        self.dev = Some(dev.into());
+        self
+    }
+
+    pub fn with_metric(mut self, metric: u32) -> NextHop {
+        self.metric = Some(metric);
+        self
+    }
+}
+
+/// Prints the part of an `ip route` line after the destination: `via 192.0.2.1 dev eth0`.
+impl fmt::Display for NextHop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        if let Some(via) = &self.via {
+            write!(f, "via {}", via)?;
+            sep = " ";
+        }
+        if let Some(dev) = &self.dev {
+            write!(f, "{}dev {}", sep, dev)?;
+            sep = " ";
+        }
+        if let Some(metric) = self.metric {
+            write!(f, "{}metric {}", sep, metric)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps networks of both kinds to next-hop values of type `T`.
+///
+/// Each kind has its own path-compressed binary trie, so a lookup costs at most one step per
+/// distinct prefix length on the way down, whatever the size of the table.
+#[derive(Debug, Clone)]
+pub struct RoutingTable<T> {
+    v4: PrefixTrie<T>,
+    v6: PrefixTrie<T>,
+}
+
+impl<T> Default for RoutingTable<T> {
+    fn default() -> Self {
+        RoutingTable {
+            v4: PrefixTrie::default(),
+            v6: PrefixTrie::default(),
+        }
+    }
+}
+
+/// The network's first address left-aligned in 128 bits, as the trie wants it.
+fn key(network: &IpNetwork) -> u128 {
+    network.bits() << (128 - u32::from(network.kind().bit_len()))
+}
+
+fn network(kind: IpAddrKind, key: u128, len: u8) -> IpNetwork {
+    IpNetwork::from_bits(kind, key >> (128 - u32::from(kind.bit_len())), len)
+}
+
+impl<T> RoutingTable<T> {
+    pub fn new() -> RoutingTable<T> {
+        RoutingTable::default()
+    }
+
+    fn trie(&self, kind: IpAddrKind) -> &PrefixTrie<T> {
+        match kind {
+            IpAddrKind::V4 => &self.v4,
+            IpAddrKind::V6 => &self.v6,
+        }
+    }
+
+    fn trie_mut(&mut self, kind: IpAddrKind) -> &mut PrefixTrie<T> {
+        match kind {
+            IpAddrKind::V4 => &mut self.v4,
+            IpAddrKind::V6 => &mut self.v6,
+        }
+    }
+
+    /// Number of routes of both kinds.
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a route, returning the next hop it replaced.
+    pub fn insert(&mut self, network: IpNetwork, next_hop: T) -> Option<T> {
+        self.trie_mut(network.kind())
+            .insert(key(&network), network.prefix_len(), next_hop)
+    }
+
+    /// Replaces the next hop of an existing route, returning the old one. Nothing is added
+    /// when there is no route for `network`.
+    pub fn update(&mut self, network: &IpNetwork, next_hop: T) -> Option<T> {
+        self.get_mut(network)
+            .map(|current| std::mem::replace(current, next_hop))
+    }
+
+    pub fn remove(&mut self, network: &IpNetwork) -> Option<T> {
+        self.trie_mut(network.kind())
+            .remove(key(network), network.prefix_len())
+    }
+
+    /// The next hop of exactly this network.
+    pub fn get(&self, network: &IpNetwork) -> Option<&T> {
+        self.trie(network.kind())
+            .get(key(network), network.prefix_len())
+    }
+
+    pub fn get_mut(&mut self, network: &IpNetwork) -> Option<&mut T> {
+        self.trie_mut(network.kind())
+            .get_mut(key(network), network.prefix_len())
+    }
+
+    /// The most specific route that contains `ip`.
+    pub fn lookup(&self, ip: &IpAddr) -> Option<(IpNetwork, &T)> {
+        let kind = ip.kind();
+        let key = addr_bits(ip) << (128 - u32::from(kind.bit_len()));
+        self.trie(kind)
+            .longest_match(key)
+            .map(|(key, len, next_hop)| (network(kind, key, len), next_hop))
+    }
+
+    /// The route a lookup falls back to when nothing more specific matches.
+    pub fn default_route(&self, kind: IpAddrKind) -> Option<&T> {
+        self.trie(kind).get(0, 0)
+    }
+
+    /// All routes, IPv4 first, each kind sorted by address and then prefix length.
+    pub fn iter(&self) -> impl Iterator<Item = (IpNetwork, &T)> {
+        let v4 = self
+            .v4
+            .iter()
+            .map(|(key, len, hop)| (network(IpAddrKind::V4, key, len), hop));
+        let v6 = self
+            .v6
+            .iter()
+            .map(|(key, len, hop)| (network(IpAddrKind::V6, key, len), hop));
+        v4.chain(v6)
+    }
+}
+
+/// Dumps the table like `ip route`: one route per line, `default` for `/0` and host routes
+/// without their prefix length.
+impl<T: fmt::Display> fmt::Display for RoutingTable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (network, next_hop) in self.iter() {
+            if network.prefix_len() == 0 {
+                write!(f, "default")?;
+            } else if network.host_bits() == 0 {
+                write!(f, "{}", network.network())?;
+            } else {
+                write!(f, "{}", network)?;
+            }
+            writeln!(f, " {}", next_hop)?;
+        }
+        Ok(())
+    }
+}
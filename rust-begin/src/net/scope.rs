@@ -0,0 +1,288 @@
+//! What an address is for: loopback, private, multicast, documentation, ...
+//!
+//! The answers come from the IANA IPv4 and IPv6 Special-Purpose Address Registries (RFC 6890),
+//! plus the multicast scopes of RFC 2365 and RFC 4291 and the deprecated IPv4-compatible IPv6
+//! block. An address that falls in several blocks gets the most specific one, so `192.0.0.9`
+//! is the PCP anycast address rather than just "IETF Protocol Assignments".
+
+use std::sync::OnceLock;
+
+use super::{addr_bits, RoutingTable};
+use crate::enums::{IpAddr, IpAddrKind};
+
+/// The kind of special use an address is set aside for, or `Global` when there is none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// `0.0.0.0` or `::`
+    Unspecified,
+    /// `0.0.0.0/8`, "this network"
+    ThisNetwork,
+    Loopback,
+    /// RFC 1918 `10/8`, `172.16/12` and `192.168/16`
+    Private,
+    /// `100.64.0.0/10`, the carrier-grade NAT range of RFC 6598
+    SharedAddressSpace,
+    LinkLocal,
+    /// `fc00::/7`
+    UniqueLocal,
+    Documentation,
+    Benchmarking,
+    /// IETF protocol assignments without a more specific use
+    ProtocolAssignment,
+    /// Reserved for future use or deprecated
+    Reserved,
+    /// `255.255.255.255`
+    Broadcast,
+    Multicast(MulticastScope),
+    /// `::ffff:0:0/96`
+    Ipv4Mapped,
+    /// `::/96`, deprecated by RFC 4291
+    Ipv4Compatible,
+    /// NAT64 prefixes `64:ff9b::/96` and `64:ff9b:1::/48`
+    Translation,
+    /// `100::/64`
+    DiscardOnly,
+    /// `2001::/32`
+    Teredo,
+    /// `2002::/16`
+    SixToFour,
+    /// ORCHID and ORCHIDv2 overlay identifiers
+    Orchid,
+    /// `5f00::/16`, segment routing SIDs
+    SegmentRouting,
+    /// Ordinary unicast, including the globally reachable anycast services of the registries
+    Global,
+}
+
+/// How far a multicast address reaches (RFC 4291 2.7, and RFC 2365 for IPv4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MulticastScope {
+    InterfaceLocal,
+    LinkLocal,
+    RealmLocal,
+    AdminLocal,
+    SiteLocal,
+    OrganizationLocal,
+    Global,
+    /// An IPv6 scope value that is reserved or unassigned.
+    Other(u8),
+}
+
+impl MulticastScope {
+    /// The scope for the 4-bit `scop` field of an IPv6 multicast address.
+    pub fn from_ipv6_field(scop: u8) -> MulticastScope {
+        match scop & 0xf {
+            0x1 => MulticastScope::InterfaceLocal,
+            0x2 => MulticastScope::LinkLocal,
+            0x3 => MulticastScope::RealmLocal,
+            0x4 => MulticastScope::AdminLocal,
+            0x5 => MulticastScope::SiteLocal,
+            0x8 => MulticastScope::OrganizationLocal,
+            0xe => MulticastScope::Global,
+            other => MulticastScope::Other(other),
+        }
+    }
+}
+
+/// One row of the special-purpose registries.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SpecialBlock {
+    /// The block in CIDR notation.
+    pub block: &'static str,
+    pub name: &'static str,
+    /// Where the block is defined.
+    pub rfc: &'static str,
+    pub scope: Scope,
+    pub globally_reachable: bool,
+}
+
+const fn block(
+    block: &'static str,
+    name: &'static str,
+    rfc: &'static str,
+    scope: Scope,
+    globally_reachable: bool,
+) -> SpecialBlock {
+    SpecialBlock {
+        block,
+        name,
+        rfc,
+        scope,
+        globally_reachable,
+    }
+}
+
+/// Every special-purpose block this module knows, IPv4 first.
+#[rustfmt::skip]
+pub static SPECIAL_PURPOSE_BLOCKS: &[SpecialBlock] = &[
+    block("0.0.0.0/8", "This network", "RFC 791", Scope::ThisNetwork, false),
+    block("0.0.0.0/32", "This host on this network", "RFC 1122", Scope::Unspecified, false),
+    block("10.0.0.0/8", "Private-Use", "RFC 1918", Scope::Private, false),
+    block("100.64.0.0/10", "Shared Address Space", "RFC 6598", Scope::SharedAddressSpace, false),
+    block("127.0.0.0/8", "Loopback", "RFC 1122", Scope::Loopback, false),
+    block("169.254.0.0/16", "Link Local", "RFC 3927", Scope::LinkLocal, false),
+    block("172.16.0.0/12", "Private-Use", "RFC 1918", Scope::Private, false),
+    block("192.0.0.0/24", "IETF Protocol Assignments", "RFC 6890", Scope::ProtocolAssignment, false),
+    block("192.0.0.0/29", "IPv4 Service Continuity Prefix", "RFC 7335", Scope::ProtocolAssignment, false),
+    block("192.0.0.8/32", "IPv4 dummy address", "RFC 7600", Scope::ProtocolAssignment, false),
+    block("192.0.0.9/32", "Port Control Protocol Anycast", "RFC 7723", Scope::Global, true),
+    block("192.0.0.10/32", "Traversal Using Relays around NAT Anycast", "RFC 8155", Scope::Global, true),
+    block("192.0.0.170/32", "NAT64/DNS64 Discovery", "RFC 8880", Scope::ProtocolAssignment, false),
+    block("192.0.0.171/32", "NAT64/DNS64 Discovery", "RFC 8880", Scope::ProtocolAssignment, false),
+    block("192.0.2.0/24", "Documentation (TEST-NET-1)", "RFC 5737", Scope::Documentation, false),
+    block("192.31.196.0/24", "AS112-v4", "RFC 7535", Scope::Global, true),
+    block("192.52.193.0/24", "AMT", "RFC 7450", Scope::Global, true),
+    block("192.88.99.0/24", "Deprecated (6to4 Relay Anycast)", "RFC 7526", Scope::Reserved, false),
+    block("192.168.0.0/16", "Private-Use", "RFC 1918", Scope::Private, false),
+    block("192.175.48.0/24", "Direct Delegation AS112 Service", "RFC 7534", Scope::Global, true),
+    block("198.18.0.0/15", "Benchmarking", "RFC 2544", Scope::Benchmarking, false),
+    block("198.51.100.0/24", "Documentation (TEST-NET-2)", "RFC 5737", Scope::Documentation, false),
+    block("203.0.113.0/24", "Documentation (TEST-NET-3)", "RFC 5737", Scope::Documentation, false),
+    block("224.0.0.0/4", "Multicast", "RFC 5771", Scope::Multicast(MulticastScope::Global), true),
+    block("224.0.0.0/24", "Local Network Control Block", "RFC 5771", Scope::Multicast(MulticastScope::LinkLocal), false),
+    block("224.0.1.0/24", "Internetwork Control Block", "RFC 5771", Scope::Multicast(MulticastScope::Global), true),
+    block("232.0.0.0/8", "Source-Specific Multicast Block", "RFC 4607", Scope::Multicast(MulticastScope::Global), true),
+    block("233.0.0.0/8", "GLOP Block", "RFC 3180", Scope::Multicast(MulticastScope::Global), true),
+    block("239.0.0.0/8", "Administratively Scoped Block", "RFC 2365", Scope::Multicast(MulticastScope::AdminLocal), false),
+    block("239.192.0.0/14", "Organization Local Scope", "RFC 2365", Scope::Multicast(MulticastScope::OrganizationLocal), false),
+    block("239.255.0.0/16", "IPv4 Local Scope", "RFC 2365", Scope::Multicast(MulticastScope::SiteLocal), false),
+    block("240.0.0.0/4", "Reserved", "RFC 1112", Scope::Reserved, false),
+    block("255.255.255.255/32", "Limited Broadcast", "RFC 919", Scope::Broadcast, false),
+    block("::/96", "IPv4-compatible Address (deprecated)", "RFC 4291", Scope::Ipv4Compatible, false),
+    block("::/128", "Unspecified Address", "RFC 4291", Scope::Unspecified, false),
+    block("::1/128", "Loopback Address", "RFC 4291", Scope::Loopback, false),
+    block("::ffff:0:0/96", "IPv4-mapped Address", "RFC 4291", Scope::Ipv4Mapped, false),
+    block("64:ff9b::/96", "IPv4-IPv6 Translat.", "RFC 6052", Scope::Translation, true),
+    block("64:ff9b:1::/48", "IPv4-IPv6 Translat.", "RFC 8215", Scope::Translation, false),
+    block("100::/64", "Discard-Only Address Block", "RFC 6666", Scope::DiscardOnly, false),
+    block("2001::/23", "IETF Protocol Assignments", "RFC 2928", Scope::ProtocolAssignment, false),
+    block("2001::/32", "TEREDO", "RFC 4380", Scope::Teredo, false),
+    block("2001:1::1/128", "Port Control Protocol Anycast", "RFC 7723", Scope::Global, true),
+    block("2001:1::2/128", "Traversal Using Relays around NAT Anycast", "RFC 8155", Scope::Global, true),
+    block("2001:1::3/128", "DNS-SD Service Registration Protocol Anycast", "RFC 9665", Scope::Global, true),
+    block("2001:2::/48", "Benchmarking", "RFC 5180", Scope::Benchmarking, false),
+    block("2001:3::/32", "AMT", "RFC 7450", Scope::Global, true),
+    block("2001:4:112::/48", "AS112-v6", "RFC 7535", Scope::Global, true),
+    block("2001:10::/28", "Deprecated (previously ORCHID)", "RFC 4843", Scope::Orchid, false),
+    block("2001:20::/28", "ORCHIDv2", "RFC 7343", Scope::Orchid, true),
+    block("2001:30::/28", "Drone Remote ID Protocol Entity Tags (DETs) Prefix", "RFC 9374", Scope::Global, true),
+    block("2001:db8::/32", "Documentation", "RFC 3849", Scope::Documentation, false),
+    block("2002::/16", "6to4", "RFC 3056", Scope::SixToFour, true),
+    block("2620:4f:8000::/48", "Direct Delegation AS112 Service", "RFC 7534", Scope::Global, true),
+    block("3fff::/20", "Documentation", "RFC 9637", Scope::Documentation, false),
+    block("5f00::/16", "Segment Routing (SRv6) SIDs", "RFC 9602", Scope::SegmentRouting, false),
+    block("fc00::/7", "Unique-Local", "RFC 4193", Scope::UniqueLocal, false),
+    block("fe80::/10", "Link-Local Unicast", "RFC 4291", Scope::LinkLocal, false),
+    block("ff00::/8", "Multicast", "RFC 4291", Scope::Multicast(MulticastScope::Global), true),
+];
+
+/// The registry indexed for longest-prefix matching, built on first use.
+fn registry() -> &'static RoutingTable<&'static SpecialBlock> {
+    static REGISTRY: OnceLock<RoutingTable<&'static SpecialBlock>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut table = RoutingTable::new();
+        for special in SPECIAL_PURPOSE_BLOCKS {
+            let network = special.block.parse().expect("registry blocks are valid");
+            table.insert(network, special);
+        }
+        table
+    })
+}
+
+impl IpAddr {
+    /// The most specific special-purpose block this address belongs to, if any.
+    pub fn special_purpose_block(&self) -> Option<&'static SpecialBlock> {
+        registry().lookup(self).map(|(_, special)| *special)
+    }
+
+    /// What the address is set aside for.
+    pub fn scope(&self) -> Scope {
+        let scope = self
+            .special_purpose_block()
+            .map_or(Scope::Global, |special| special.scope);
+        match (self, scope) {
+            // the scope of an IPv6 multicast address is written in the address itself
+            (IpAddr::V6(ip), Scope::Multicast(_)) => {
+                Scope::Multicast(MulticastScope::from_ipv6_field(ip.segments()[0] as u8))
+            }
+            _ => scope,
+        }
+    }
+
+    /// Whether the registries mark the address as reachable from anywhere on the internet.
+    /// Addresses outside every special-purpose block are.
+    pub fn is_global(&self) -> bool {
+        match self.scope() {
+            Scope::Multicast(scope) => scope == MulticastScope::Global,
+            _ => self
+                .special_purpose_block()
+                .is_none_or(|special| special.globally_reachable),
+        }
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        self.scope() == Scope::Unspecified
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.scope() == Scope::Loopback
+    }
+
+    /// RFC 1918 private IPv4 space.
+    pub fn is_private(&self) -> bool {
+        self.scope() == Scope::Private
+    }
+
+    /// The carrier-grade NAT range `100.64.0.0/10`.
+    pub fn is_shared(&self) -> bool {
+        self.scope() == Scope::SharedAddressSpace
+    }
+
+    /// `169.254.0.0/16` or `fe80::/10`.
+    pub fn is_link_local(&self) -> bool {
+        self.scope() == Scope::LinkLocal
+    }
+
+    pub fn is_unique_local(&self) -> bool {
+        self.scope() == Scope::UniqueLocal
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        matches!(self.scope(), Scope::Multicast(_))
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.scope() == Scope::Broadcast
+    }
+
+    pub fn is_documentation(&self) -> bool {
+        self.scope() == Scope::Documentation
+    }
+
+    pub fn is_benchmarking(&self) -> bool {
+        self.scope() == Scope::Benchmarking
+    }
+
+    pub fn is_reserved(&self) -> bool {
+        self.scope() == Scope::Reserved
+    }
+
+    pub fn is_ipv4_mapped(&self) -> bool {
+        self.scope() == Scope::Ipv4Mapped
+    }
+
+    /// `::a.b.c.d`, except `::` and `::1`.
+    pub fn is_ipv4_compatible(&self) -> bool {
+        self.scope() == Scope::Ipv4Compatible
+    }
+
+    /// Whether the address is a global unicast address: neither special-purpose nor multicast,
+    /// and for IPv6 inside `2000::/3`.
+    pub fn is_unicast_global(&self) -> bool {
+        let in_global_unicast = match self.kind() {
+            IpAddrKind::V4 => true,
+            IpAddrKind::V6 => addr_bits(self) >> 125 == 1,
+        };
+        in_global_unicast && self.scope() == Scope::Global
+    }
+}
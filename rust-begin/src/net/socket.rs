@@ -0,0 +1,126 @@
+//! Socket addresses: an `IpAddr` plus a port.
+//!
+//! Written `192.0.2.1:80` for IPv4 and `[2001:db8::1]:8080` for IPv6, where the brackets keep
+//! the port apart from the groups. A zone goes inside the brackets: `[fe80::1%eth0]:22`.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::text::{parse_ipv4, parse_ipv6};
+use super::AddrParseError;
+use crate::enums::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SocketAddr {
+    ip: IpAddr,
+    port: u16,
+}
+
+impl SocketAddr {
+    pub fn new(ip: IpAddr, port: u16) -> SocketAddr {
+        SocketAddr { ip, port }
+    }
+
+    pub fn ip(&self) -> &IpAddr {
+        &self.ip
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn set_ip(&mut self, ip: IpAddr) {
+        self.ip = ip;
+    }
+
+    pub fn set_port(&mut self, port: u16) {
+        self.port = port;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocketAddrParseError {
+    /// There was no `:port` at the end.
+    MissingPort,
+    /// The port was not a number from 0 to 65535.
+    InvalidPort(String),
+    /// A `[` was not closed by `]` right before the port.
+    UnclosedBracket,
+    /// An IPv6 address was given without brackets.
+    UnbracketedIpv6,
+    Addr(AddrParseError),
+}
+
+impl fmt::Display for SocketAddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SocketAddrParseError::MissingPort => write!(f, "socket address has no port"),
+            SocketAddrParseError::InvalidPort(port) => write!(f, "invalid port {:?}", port),
+            SocketAddrParseError::UnclosedBracket => {
+                write!(f, "expected `]:` after the IPv6 address")
+            }
+            SocketAddrParseError::UnbracketedIpv6 => {
+                write!(f, "IPv6 socket addresses need brackets, as in [::1]:8080")
+            }
+            SocketAddrParseError::Addr(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for SocketAddrParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SocketAddrParseError::Addr(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<AddrParseError> for SocketAddrParseError {
+    fn from(e: AddrParseError) -> Self {
+        SocketAddrParseError::Addr(e)
+    }
+}
+
+fn parse_port(s: &str) -> Result<u16, SocketAddrParseError> {
+    match s.parse::<u16>() {
+        Ok(port) if s.bytes().all(|b| b.is_ascii_digit()) => Ok(port),
+        _ => Err(SocketAddrParseError::InvalidPort(s.to_string())),
+    }
+}
+
+impl FromStr for SocketAddr {
+    type Err = SocketAddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = s.strip_prefix('[') {
+            let (ip, port) = rest.split_once("]:").ok_or(if rest.ends_with(']') {
+                SocketAddrParseError::MissingPort
+            } else {
+                SocketAddrParseError::UnclosedBracket
+            })?;
+            let ip = parse_ipv6(ip)?;
+            return Ok(SocketAddr::new(IpAddr::V6(ip), parse_port(port)?));
+        }
+
+        let (ip, port) = s
+            .rsplit_once(':')
+            .ok_or(SocketAddrParseError::MissingPort)?;
+        if ip.contains(':') {
+            return Err(SocketAddrParseError::UnbracketedIpv6);
+        }
+        let [a, b, c, d] = parse_ipv4(ip)?;
+        Ok(SocketAddr::new(IpAddr::V4(a, b, c, d), parse_port(port)?))
+    }
+}
+
+impl fmt::Display for SocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match &self.ip {
+            IpAddr::V4(..) => format!("{}:{}", self.ip, self.port),
+            IpAddr::V6(ip) => format!("[{}]:{}", ip, self.port),
+        };
+        f.pad(&text)
+    }
+}
@@ -0,0 +1,358 @@
+//! Text representation of IP addresses.
+//!
+//! IPv4 addresses are written as four decimal octets (`192.0.2.1`). Octets with leading zeros
+//! are refused, because some tools read `010` as octal.
+//!
+//! IPv6 addresses follow RFC 4291: eight groups of up to four hex digits, where one run of zero
+//! groups may be replaced by `::` and the last two groups may be written as an IPv4 address
+//! (`::ffff:192.0.2.1`). A zone can follow a `%` (`fe80::1%eth0`). Addresses are printed in the
+//! canonical form of RFC 5952: lowercase, no leading zeros, and the longest run of two or more
+//! zero groups compressed.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::enums::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Why a string could not be parsed as an address.
+///
+/// Octets and groups are numbered from 1, in the order they are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddrParseError {
+    /// The input was empty.
+    Empty,
+    /// An IPv4 address did not have exactly four octets.
+    OctetCount(usize),
+    /// An IPv4 octet was not a valid decimal number.
+    InvalidOctet {
+        octet: usize,
+        text: String,
+        reason: OctetError,
+    },
+    /// An IPv6 address had too many groups, or too few without a `::`.
+    GroupCount(usize),
+    /// An IPv6 group was not a valid hex number.
+    InvalidGroup {
+        group: usize,
+        text: String,
+        reason: GroupError,
+    },
+    /// `::` appeared more than once.
+    MultipleCompressions,
+    /// An IPv4 address appeared somewhere other than the last two groups.
+    MisplacedIpv4,
+    /// The zone after `%` was empty or contained characters that are not allowed.
+    InvalidZone(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OctetError {
+    Empty,
+    NotDecimal,
+    LeadingZero,
+    TooLarge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupError {
+    Empty,
+    NotHex,
+    TooLong,
+}
+
+impl fmt::Display for OctetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OctetError::Empty => "octet is empty",
+            OctetError::NotDecimal => "octet is not a decimal number",
+            OctetError::LeadingZero => "octet has a leading zero",
+            OctetError::TooLarge => "octet is greater than 255",
+        })
+    }
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GroupError::Empty => "group is empty",
+            GroupError::NotHex => "group is not a hex number",
+            GroupError::TooLong => "group has more than four hex digits",
+        })
+    }
+}
+
+impl fmt::Display for AddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddrParseError::Empty => write!(f, "address is empty"),
+            AddrParseError::OctetCount(n) => {
+                write!(f, "IPv4 address needs 4 octets, found {}", n)
+            }
+            AddrParseError::InvalidOctet {
+                octet,
+                text,
+                reason,
+            } => write!(f, "invalid IPv4 octet {} ({:?}): {}", octet, text, reason),
+            AddrParseError::GroupCount(n) => {
+                write!(
+                    f,
+                    "IPv6 address has {} groups, needs 8 or at most 7 with `::`",
+                    n
+                )
+            }
+            AddrParseError::InvalidGroup {
+                group,
+                text,
+                reason,
+            } => write!(f, "invalid IPv6 group {} ({:?}): {}", group, text, reason),
+            AddrParseError::MultipleCompressions => write!(f, "`::` may only appear once"),
+            AddrParseError::MisplacedIpv4 => {
+                write!(f, "an embedded IPv4 address must be the last part")
+            }
+            AddrParseError::InvalidZone(zone) => write!(f, "invalid zone {:?}", zone),
+        }
+    }
+}
+
+impl Error for AddrParseError {}
+
+/// Parses a dotted-quad IPv4 address.
+pub(crate) fn parse_ipv4(s: &str) -> Result<[u8; 4], AddrParseError> {
+    if s.is_empty() {
+        return Err(AddrParseError::Empty);
+    }
+
+    let parts: Vec<&str> = s.split('.').collect();
+    if parts.len() != 4 {
+        return Err(AddrParseError::OctetCount(parts.len()));
+    }
+
+    let mut octets = [0u8; 4];
+    for (i, part) in parts.iter().enumerate() {
+        octets[i] = parse_octet(part).map_err(|reason| AddrParseError::InvalidOctet {
+            octet: i + 1,
+            text: part.to_string(),
+            reason,
+        })?;
+    }
+    Ok(octets)
+}
+
+fn parse_octet(s: &str) -> Result<u8, OctetError> {
+    if s.is_empty() {
+        return Err(OctetError::Empty);
+    }
+    if !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(OctetError::NotDecimal);
+    }
+    if s.len() > 1 && s.starts_with('0') {
+        return Err(OctetError::LeadingZero);
+    }
+    // at most three digits fit, anything longer is too large anyway
+    if s.len() > 3 {
+        return Err(OctetError::TooLarge);
+    }
+    s.parse::<u8>().map_err(|_| OctetError::TooLarge)
+}
+
+fn parse_group(s: &str) -> Result<u16, GroupError> {
+    if s.is_empty() {
+        return Err(GroupError::Empty);
+    }
+    if !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(GroupError::NotHex);
+    }
+    if s.len() > 4 {
+        return Err(GroupError::TooLong);
+    }
+    Ok(u16::from_str_radix(s, 16).expect("at most four hex digits"))
+}
+
+/// Parses the groups on one side of a `::`, numbering them from `first`.
+///
+/// An IPv4 address is only allowed as the last part when `ipv4_allowed` is set; it adds two
+/// groups.
+fn parse_groups(s: &str, first: usize, ipv4_allowed: bool) -> Result<Vec<u16>, AddrParseError> {
+    let mut groups = Vec::new();
+    if s.is_empty() {
+        return Ok(groups);
+    }
+
+    let parts: Vec<&str> = s.split(':').collect();
+    for (i, part) in parts.iter().enumerate() {
+        if part.contains('.') {
+            if !ipv4_allowed || i != parts.len() - 1 {
+                return Err(AddrParseError::MisplacedIpv4);
+            }
+            let [a, b, c, d] = parse_ipv4(part)?;
+            groups.push(u16::from_be_bytes([a, b]));
+            groups.push(u16::from_be_bytes([c, d]));
+        } else {
+            let group = parse_group(part).map_err(|reason| AddrParseError::InvalidGroup {
+                group: first + groups.len(),
+                text: part.to_string(),
+                reason,
+            })?;
+            groups.push(group);
+        }
+    }
+    Ok(groups)
+}
+
+fn valid_zone(zone: &str) -> bool {
+    !zone.is_empty()
+        && zone
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-._~".contains(&b))
+}
+
+/// Parses an RFC 4291 IPv6 address, with an optional `%zone`.
+pub(crate) fn parse_ipv6(s: &str) -> Result<Ipv6Addr, AddrParseError> {
+    if s.is_empty() {
+        return Err(AddrParseError::Empty);
+    }
+
+    let (addr, zone) = match s.split_once('%') {
+        Some((addr, zone)) if valid_zone(zone) => (addr, Some(zone)),
+        Some((_, zone)) => return Err(AddrParseError::InvalidZone(zone.to_string())),
+        None => (s, None),
+    };
+
+    let segments = match addr.split_once("::") {
+        Some((head, tail)) => {
+            if tail.contains("::") {
+                return Err(AddrParseError::MultipleCompressions);
+            }
+            let head = parse_groups(head, 1, false)?;
+            let tail = parse_groups(tail, head.len() + 1, true)?;
+            // `::` stands for at least one zero group
+            if head.len() + tail.len() > 7 {
+                return Err(AddrParseError::GroupCount(head.len() + tail.len()));
+            }
+            let mut segments = [0u16; 8];
+            segments[..head.len()].copy_from_slice(&head);
+            segments[8 - tail.len()..].copy_from_slice(&tail);
+            segments
+        }
+        None => {
+            let groups = parse_groups(addr, 1, true)?;
+            groups
+                .try_into()
+                .map_err(|groups: Vec<u16>| AddrParseError::GroupCount(groups.len()))?
+        }
+    };
+
+    let ip = Ipv6Addr::from_segments(segments);
+    Ok(match zone {
+        Some(zone) => ip.with_zone(zone),
+        None => ip,
+    })
+}
+
+impl FromStr for Ipv4Addr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_ipv4(s).map(Ipv4Addr::from_octets)
+    }
+}
+
+impl FromStr for Ipv6Addr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_ipv6(s)
+    }
+}
+
+impl FromStr for IpAddr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(':') {
+            parse_ipv6(s).map(IpAddr::V6)
+        } else {
+            let [a, b, c, d] = parse_ipv4(s)?;
+            Ok(IpAddr::V4(a, b, c, d))
+        }
+    }
+}
+
+/// Finds the longest run of at least two zero groups, the first one on a tie (RFC 5952 4.2).
+fn longest_zero_run(segments: &[u16]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut start = 0;
+    while start < segments.len() {
+        if segments[start] != 0 {
+            start += 1;
+            continue;
+        }
+        let end = segments[start..]
+            .iter()
+            .position(|&s| s != 0)
+            .map_or(segments.len(), |n| start + n);
+        let len = end - start;
+        if len >= 2 && best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((start, len));
+        }
+        start = end;
+    }
+    best
+}
+
+fn write_groups(out: &mut String, groups: &[u16]) {
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            out.push(':');
+        }
+        out.push_str(&format!("{:x}", group));
+    }
+}
+
+/// Addresses are built as a string first and handed to `Formatter::pad`, so a width like
+/// `{:>39}` lines them up in columns.
+impl fmt::Display for Ipv6Addr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let segments = self.segments();
+        let mut out = String::new();
+
+        // IPv4-mapped addresses keep their IPv4 part readable (RFC 5952 5)
+        if segments[..6] == [0, 0, 0, 0, 0, 0xffff] {
+            let [a, b] = segments[6].to_be_bytes();
+            let [c, d] = segments[7].to_be_bytes();
+            out.push_str(&format!("::ffff:{}.{}.{}.{}", a, b, c, d));
+        } else {
+            match longest_zero_run(&segments) {
+                Some((start, len)) => {
+                    write_groups(&mut out, &segments[..start]);
+                    out.push_str("::");
+                    write_groups(&mut out, &segments[start + len..]);
+                }
+                None => write_groups(&mut out, &segments),
+            }
+        }
+
+        if let Some(zone) = self.zone() {
+            out.push('%');
+            out.push_str(zone);
+        }
+        f.pad(&out)
+    }
+}
+
+impl fmt::Display for Ipv4Addr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d] = self.octets();
+        f.pad(&format!("{}.{}.{}.{}", a, b, c, d))
+    }
+}
+
+impl fmt::Display for IpAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpAddr::V4(a, b, c, d) => Ipv4Addr::new(*a, *b, *c, *d).fmt(f),
+            IpAddr::V6(ip) => ip.fmt(f),
+        }
+    }
+}
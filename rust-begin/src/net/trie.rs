@@ -0,0 +1,216 @@
+//! A path-compressed binary trie of prefixes, the index behind `RoutingTable`.
+//!
+//! Keys are left-aligned in a `u128`: bit 0 of a prefix is the most significant bit, so IPv4
+//! prefixes are shifted up by 96 bits before they get here. Every node is either a prefix that
+//! holds a value or a branch where two stored prefixes part ways, so a trie of `n` prefixes has
+//! fewer than `2n` nodes and a lookup visits at most one node per stored prefix length.
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    key: u128,
+    len: u8,
+    value: Option<T>,
+    children: [Option<Box<Node<T>>>; 2],
+}
+
+impl<T> Node<T> {
+    fn new(key: u128, len: u8, value: Option<T>) -> Node<T> {
+        Node {
+            key,
+            len,
+            value,
+            children: [None, None],
+        }
+    }
+}
+
+/// The highest `len` bits set.
+fn mask(len: u8) -> u128 {
+    match len {
+        0 => 0,
+        len => u128::MAX << (128 - u32::from(len)),
+    }
+}
+
+/// Bit `i` of `key`, counting from the most significant bit.
+fn bit(key: u128, i: u8) -> usize {
+    ((key >> (127 - u32::from(i))) & 1) as usize
+}
+
+/// How many leading bits `a` and `b` share, at most `max`.
+fn common_len(a: u128, b: u128, max: u8) -> u8 {
+    ((a ^ b).leading_zeros() as u8).min(max)
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PrefixTrie<T> {
+    root: Option<Box<Node<T>>>,
+    len: usize,
+}
+
+impl<T> Default for PrefixTrie<T> {
+    fn default() -> Self {
+        PrefixTrie { root: None, len: 0 }
+    }
+}
+
+impl<T> PrefixTrie<T> {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Stores `value` under the prefix, returning the value it replaced.
+    pub(crate) fn insert(&mut self, key: u128, len: u8, value: T) -> Option<T> {
+        let key = key & mask(len);
+        let mut slot = &mut self.root;
+        loop {
+            let Some(node) = slot else {
+                *slot = Some(Box::new(Node::new(key, len, Some(value))));
+                self.len += 1;
+                return None;
+            };
+
+            let common = common_len(node.key, key, node.len.min(len));
+            if common == node.len && common == len {
+                let old = node.value.replace(value);
+                if old.is_none() {
+                    self.len += 1;
+                }
+                return old;
+            }
+            if common == node.len {
+                // the node is a prefix of the key, keep going down
+                slot = &mut slot.as_mut().unwrap().children[bit(key, common)];
+                continue;
+            }
+
+            // the key leaves the node's path at `common`: put a new node there
+            let old = slot.take().unwrap();
+            let mut branch = if common == len {
+                Node::new(key, len, Some(value))
+            } else {
+                let mut branch = Node::new(key & mask(common), common, None);
+                branch.children[bit(key, common)] =
+                    Some(Box::new(Node::new(key, len, Some(value))));
+                branch
+            };
+            let side = bit(old.key, common);
+            branch.children[side] = Some(old);
+            *slot = Some(Box::new(branch));
+            self.len += 1;
+            return None;
+        }
+    }
+
+    fn find(&self, key: u128, len: u8) -> Option<&Node<T>> {
+        let key = key & mask(len);
+        let mut node = self.root.as_deref();
+        while let Some(n) = node {
+            if n.len > len || (n.key ^ key) & mask(n.len) != 0 {
+                return None;
+            }
+            if n.len == len {
+                return Some(n);
+            }
+            node = n.children[bit(key, n.len)].as_deref();
+        }
+        None
+    }
+
+    pub(crate) fn get(&self, key: u128, len: u8) -> Option<&T> {
+        self.find(key, len)?.value.as_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self, key: u128, len: u8) -> Option<&mut T> {
+        let key = key & mask(len);
+        let mut node = self.root.as_deref_mut();
+        while let Some(n) = node {
+            if n.len > len || (n.key ^ key) & mask(n.len) != 0 {
+                return None;
+            }
+            if n.len == len {
+                return n.value.as_mut();
+            }
+            node = n.children[bit(key, n.len)].as_deref_mut();
+        }
+        None
+    }
+
+    pub(crate) fn remove(&mut self, key: u128, len: u8) -> Option<T> {
+        let removed = remove(&mut self.root, key & mask(len), len);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// The longest stored prefix of `key`, with its value.
+    pub(crate) fn longest_match(&self, key: u128) -> Option<(u128, u8, &T)> {
+        let mut best = None;
+        let mut node = self.root.as_deref();
+        while let Some(n) = node {
+            if (n.key ^ key) & mask(n.len) != 0 {
+                break;
+            }
+            if let Some(value) = &n.value {
+                best = Some((n.key, n.len, value));
+            }
+            if n.len == 128 {
+                break;
+            }
+            node = n.children[bit(key, n.len)].as_deref();
+        }
+        best
+    }
+
+    /// All stored prefixes in order: by address, then shorter prefixes first.
+    pub(crate) fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            stack: self.root.as_deref().into_iter().collect(),
+        }
+    }
+}
+
+fn remove<T>(slot: &mut Option<Box<Node<T>>>, key: u128, len: u8) -> Option<T> {
+    let node = slot.as_mut()?;
+    if node.len > len || (node.key ^ key) & mask(node.len) != 0 {
+        return None;
+    }
+
+    let removed = if node.len == len {
+        node.value.take()
+    } else {
+        remove(&mut node.children[bit(key, node.len)], key, len)
+    };
+
+    // a node without a value is only worth keeping while it branches
+    if removed.is_some() && node.value.is_none() {
+        match node.children {
+            [Some(_), Some(_)] => {}
+            [None, None] => *slot = None,
+            [ref mut child @ Some(_), None] | [None, ref mut child @ Some(_)] => {
+                *slot = child.take();
+            }
+        }
+    }
+    removed
+}
+
+pub(crate) struct Iter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (u128, u8, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            self.stack.extend(node.children[1].as_deref());
+            self.stack.extend(node.children[0].as_deref());
+            if let Some(value) = &node.value {
+                return Some((node.key, node.len, value));
+            }
+        }
+        None
+    }
+}
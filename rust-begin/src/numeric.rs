@@ -0,0 +1,281 @@
+//! `plus_one` for every integer width, with a say in what happens on overflow.
+//!
+//! The lesson's `plus_one` works on `Option<i32>` only, and `i32::MAX + 1` panics in a debug
+//! build and wraps to `i32::MIN` in a release one. Here each operation names an overflow
+//! policy as a type parameter:
+//!
+//! - `Checked` returns `None` when the result does not fit
+//! - `Saturating` stops at the type's `MIN` or `MAX`
+//! - `Wrapping` wraps around, as two's complement does
+//! - `Panicking` panics, in every build
+//!
+//! The operand can be a bare integer, an `Option` of one or a `Result` of one, and the result
+//! has the same shape. A `Checked` overflow inside a `Result` becomes an `Err` through
+//! `From<OverflowError>`.
+//!
+//! ```
+//! use rust_begin::numeric::{plus_n, plus_one, times_n, Checked, OverflowError, Saturating, Wrapping};
+//!
+//! assert_eq!(plus_one::<Checked, _>(Some(i32::MAX)), None);
+//! assert_eq!(plus_one::<Checked, _>(41u8), Some(42));
+//! assert_eq!(plus_n::<Saturating, _>(Some(250u8), 10), Some(255));
+//! assert_eq!(times_n::<Wrapping, _>(-128i8, -1), -128);
+//! assert_eq!(
+//!     plus_n::<Checked, _>(Ok::<u16, OverflowError>(u16::MAX), 1),
+//!     Err(OverflowError::Add)
+//! );
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+/// The primitive integers.
+pub trait Integer: Copy + Ord + fmt::Debug + fmt::Display {
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn overflowing_add(self, rhs: Self) -> (Self, bool);
+    fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+    fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+}
+
+macro_rules! integer {
+    ($($ty:ty),*) => {$(
+        impl Integer for $ty {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MIN: Self = <$ty>::MIN;
+            const MAX: Self = <$ty>::MAX;
+
+            fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+                <$ty>::overflowing_add(self, rhs)
+            }
+
+            fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+                <$ty>::overflowing_sub(self, rhs)
+            }
+
+            fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+                <$ty>::overflowing_mul(self, rhs)
+            }
+        }
+    )*};
+}
+
+integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Which operation overflowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowError {
+    Add,
+    Sub,
+    Mul,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            OverflowError::Add => "add",
+            OverflowError::Sub => "subtract",
+            OverflowError::Mul => "multiply",
+        };
+        write!(f, "attempt to {} with overflow", op)
+    }
+}
+
+impl Error for OverflowError {}
+
+/// What to do with a result that does not fit.
+pub trait Policy {
+    /// What an operation returns: `Option<T>` for `Checked`, `T` for the others.
+    type Output<T>;
+
+    /// Makes the output from the wrapped result. `overflowed` says whether it wrapped, and
+    /// `bound` is the end of the range it went past.
+    fn finish<T: Integer>(
+        op: OverflowError,
+        wrapped: T,
+        overflowed: bool,
+        bound: T,
+    ) -> Self::Output<T>;
+
+    /// The output as `Ok`, or the overflow as `Err` if the policy let one through.
+    fn into_result<T>(output: Self::Output<T>, op: OverflowError) -> Result<T, OverflowError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checked;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Saturating;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wrapping;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Panicking;
+
+impl Policy for Checked {
+    type Output<T> = Option<T>;
+
+    fn finish<T: Integer>(_: OverflowError, wrapped: T, overflowed: bool, _: T) -> Option<T> {
+        (!overflowed).then_some(wrapped)
+    }
+
+    fn into_result<T>(output: Option<T>, op: OverflowError) -> Result<T, OverflowError> {
+        output.ok_or(op)
+    }
+}
+
+impl Policy for Saturating {
+    type Output<T> = T;
+
+    fn finish<T: Integer>(_: OverflowError, wrapped: T, overflowed: bool, bound: T) -> T {
+        if overflowed {
+            bound
+        } else {
+            wrapped
+        }
+    }
+
+    fn into_result<T>(output: T, _: OverflowError) -> Result<T, OverflowError> {
+        Ok(output)
+    }
+}
+
+impl Policy for Wrapping {
+    type Output<T> = T;
+
+    fn finish<T: Integer>(_: OverflowError, wrapped: T, _: bool, _: T) -> T {
+        wrapped
+    }
+
+    fn into_result<T>(output: T, _: OverflowError) -> Result<T, OverflowError> {
+        Ok(output)
+    }
+}
+
+impl Policy for Panicking {
+    type Output<T> = T;
+
+    #[track_caller]
+    fn finish<T: Integer>(op: OverflowError, wrapped: T, overflowed: bool, _: T) -> T {
+        if overflowed {
+            panic!("{}", op);
+        }
+        wrapped
+    }
+
+    fn into_result<T>(output: T, _: OverflowError) -> Result<T, OverflowError> {
+        Ok(output)
+    }
+}
+
+/// Something the operations apply through: an integer, or one in an `Option` or a `Result`.
+pub trait Operand {
+    type Int: Integer;
+    /// The result of an operation under policy `P`.
+    type Output<P: Policy>;
+
+    /// Applies `op`, an operation that can overflow with `error`, to the integer inside.
+    fn apply<P: Policy>(
+        self,
+        error: OverflowError,
+        op: impl FnOnce(Self::Int) -> P::Output<Self::Int>,
+    ) -> Self::Output<P>;
+}
+
+macro_rules! operand {
+    ($($ty:ty),*) => {$(
+        impl Operand for $ty {
+            type Int = $ty;
+            type Output<P: Policy> = P::Output<$ty>;
+
+            fn apply<P: Policy>(
+                self,
+                _: OverflowError,
+                op: impl FnOnce($ty) -> P::Output<$ty>,
+            ) -> P::Output<$ty> {
+                op(self)
+            }
+        }
+    )*};
+}
+
+operand!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// `None` stays `None`; a `Checked` overflow is `None` too.
+impl<T: Integer> Operand for Option<T> {
+    type Int = T;
+    type Output<P: Policy> = Option<T>;
+
+    fn apply<P: Policy>(
+        self,
+        error: OverflowError,
+        op: impl FnOnce(T) -> P::Output<T>,
+    ) -> Option<T> {
+        self.and_then(|x| P::into_result(op(x), error).ok())
+    }
+}
+
+/// An `Err` stays as it is; a `Checked` overflow becomes `Err(E::from(OverflowError))`.
+impl<T: Integer, E: From<OverflowError>> Operand for Result<T, E> {
+    type Int = T;
+    type Output<P: Policy> = Result<T, E>;
+
+    fn apply<P: Policy>(
+        self,
+        error: OverflowError,
+        op: impl FnOnce(T) -> P::Output<T>,
+    ) -> Result<T, E> {
+        self.and_then(|x| P::into_result(op(x), error).map_err(E::from))
+    }
+}
+
+/// `a + b`; on overflow the bound is `MIN` if `b` is negative and `MAX` otherwise.
+pub fn add<P: Policy, T: Integer>(a: T, b: T) -> P::Output<T> {
+    let (wrapped, overflowed) = a.overflowing_add(b);
+    let bound = if b < T::ZERO { T::MIN } else { T::MAX };
+    P::finish(OverflowError::Add, wrapped, overflowed, bound)
+}
+
+/// `a - b`; on overflow the bound is `MAX` if `b` is negative and `MIN` otherwise.
+pub fn sub<P: Policy, T: Integer>(a: T, b: T) -> P::Output<T> {
+    let (wrapped, overflowed) = a.overflowing_sub(b);
+    let bound = if b < T::ZERO { T::MAX } else { T::MIN };
+    P::finish(OverflowError::Sub, wrapped, overflowed, bound)
+}
+
+/// `a * b`; on overflow the bound is `MIN` if exactly one of them is negative and `MAX`
+/// otherwise.
+pub fn mul<P: Policy, T: Integer>(a: T, b: T) -> P::Output<T> {
+    let (wrapped, overflowed) = a.overflowing_mul(b);
+    let bound = if (a < T::ZERO) != (b < T::ZERO) {
+        T::MIN
+    } else {
+        T::MAX
+    };
+    P::finish(OverflowError::Mul, wrapped, overflowed, bound)
+}
+
+pub fn plus_n<P: Policy, X: Operand>(x: X, n: X::Int) -> X::Output<P> {
+    x.apply::<P>(OverflowError::Add, |x| add::<P, _>(x, n))
+}
+
+pub fn plus_one<P: Policy, X: Operand>(x: X) -> X::Output<P> {
+    plus_n::<P, X>(x, X::Int::ONE)
+}
+
+pub fn minus_n<P: Policy, X: Operand>(x: X, n: X::Int) -> X::Output<P> {
+    x.apply::<P>(OverflowError::Sub, |x| sub::<P, _>(x, n))
+}
+
+pub fn minus_one<P: Policy, X: Operand>(x: X) -> X::Output<P> {
+    minus_n::<P, X>(x, X::Int::ONE)
+}
+
+pub fn times_n<P: Policy, X: Operand>(x: X, n: X::Int) -> X::Output<P> {
+    x.apply::<P>(OverflowError::Mul, |x| mul::<P, _>(x, n))
+}
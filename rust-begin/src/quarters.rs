@@ -0,0 +1,432 @@
+//! A collection of state quarters, the `Coin::Quarter(state)` arm made into a hobby.
+//!
+//! From 1999 to 2008 the US Mint issued a quarter for each of the 50 states, five a year in
+//! the order the states joined the union, and in 2009 one each for DC and the five
+//! territories. Every quarter was struck at Philadelphia (`P`) and Denver (`D`) for
+//! circulation and at San Francisco (`S`) as proofs, so a quarter is a state, a year and a
+//! mint mark, and the year has to be the state's.
+//!
+//! Collections are saved as CSV with a header line:
+//!
+//! ```text
+//! state,year,mint,count
+//! Delaware,1999,P,2
+//! New York,2001,D,1
+//! ```
+//!
+//! On import the state may also be a postal code, fields may be quoted, `count` may be left
+//! out for one coin, and rows for the same quarter add up.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::atomic::write_atomic;
+use crate::enums::{value_in_cents, Coin, UsState};
+use crate::states::StateKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MintMark {
+    Philadelphia,
+    Denver,
+    /// Proofs only.
+    SanFrancisco,
+}
+
+impl MintMark {
+    pub const ALL: [MintMark; 3] = [
+        MintMark::Philadelphia,
+        MintMark::Denver,
+        MintMark::SanFrancisco,
+    ];
+
+    /// The letter on the coin.
+    pub fn letter(self) -> char {
+        match self {
+            MintMark::Philadelphia => 'P',
+            MintMark::Denver => 'D',
+            MintMark::SanFrancisco => 'S',
+        }
+    }
+}
+
+impl fmt::Display for MintMark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+impl FromStr for MintMark {
+    type Err = QuarterError;
+
+    /// The letter, in either case.
+    fn from_str(s: &str) -> Result<MintMark, QuarterError> {
+        MintMark::ALL
+            .into_iter()
+            .find(|mint| s.eq_ignore_ascii_case(&mint.letter().to_string()))
+            .ok_or_else(|| QuarterError::UnknownMint(s.to_string()))
+    }
+}
+
+/// The year the quarter for `state` came out.
+pub fn issue_year(state: UsState) -> u16 {
+    if state.kind() != StateKind::State {
+        return 2009;
+    }
+    // five a year in order of admission; North and South Dakota share a day and go in
+    // declaration order, as the Mint had it
+    let key = |s: UsState| (s.admitted(), s);
+    let earlier = UsState::states()
+        .filter(|&other| key(other) < key(state))
+        .count();
+    1999 + (earlier / 5) as u16
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuarterError {
+    /// The quarter for `state` came out in `expected`, not `year`.
+    WrongYear {
+        state: UsState,
+        year: u16,
+        expected: u16,
+    },
+    UnknownMint(String),
+}
+
+impl fmt::Display for QuarterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuarterError::WrongYear {
+                state,
+                year,
+                expected,
+            } => write!(
+                f,
+                "there is no {} {} quarter, it came out in {}",
+                year, state, expected
+            ),
+            QuarterError::UnknownMint(mint) => {
+                write!(f, "unknown mint mark {:?}, expected P, D or S", mint)
+            }
+        }
+    }
+}
+
+impl Error for QuarterError {}
+
+/// One kind of state quarter. The fields are private so the year always matches the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StateQuarter {
+    state: UsState,
+    year: u16,
+    mint: MintMark,
+}
+
+impl StateQuarter {
+    pub fn new(state: UsState, year: u16, mint: MintMark) -> Result<StateQuarter, QuarterError> {
+        let expected = issue_year(state);
+        if year != expected {
+            return Err(QuarterError::WrongYear {
+                state,
+                year,
+                expected,
+            });
+        }
+        Ok(StateQuarter { state, year, mint })
+    }
+
+    /// The quarter for `state` from its own year.
+    pub fn of(state: UsState, mint: MintMark) -> StateQuarter {
+        StateQuarter {
+            state,
+            year: issue_year(state),
+            mint,
+        }
+    }
+
+    pub fn state(&self) -> UsState {
+        self.state
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn mint(&self) -> MintMark {
+        self.mint
+    }
+
+    pub fn coin(&self) -> Coin {
+        Coin::Quarter(self.state)
+    }
+}
+
+impl fmt::Display for StateQuarter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}-{}", self.state, self.year, self.mint)
+    }
+}
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// A line of the file could not be understood.
+    Parse {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(e) => write!(f, "collection file: {}", e),
+            CsvError::Parse { line, reason } => {
+                write!(f, "collection file line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io(e) => Some(e),
+            CsvError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(e: io::Error) -> Self {
+        CsvError::Io(e)
+    }
+}
+
+const HEADER: &str = "state,year,mint,count";
+
+/// Splits a CSV line into fields: commas separate them, and a field in double quotes may hold
+/// commas and `""` for a quote.
+fn split_csv(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("a quoted field is not closed".to_string()),
+                }
+            }
+            if !matches!(chars.peek(), None | Some(',')) {
+                return Err("text after a quoted field".to_string());
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+        }
+        fields.push(field.trim().to_string());
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+/// Quarters and how many of each.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuarterCollection {
+    /// No zero counts.
+    coins: BTreeMap<StateQuarter, u32>,
+}
+
+impl QuarterCollection {
+    pub fn new() -> QuarterCollection {
+        QuarterCollection::default()
+    }
+
+    /// Adds one and returns how many of it there are now.
+    pub fn add(&mut self, quarter: StateQuarter) -> u32 {
+        self.add_many(quarter, 1)
+    }
+
+    pub fn add_many(&mut self, quarter: StateQuarter, count: u32) -> u32 {
+        if count == 0 {
+            return self.count(&quarter);
+        }
+        let total = self.coins.entry(quarter).or_insert(0);
+        *total = total.saturating_add(count);
+        *total
+    }
+
+    /// Takes one out; false if there was none.
+    pub fn remove(&mut self, quarter: &StateQuarter) -> bool {
+        match self.coins.get_mut(quarter) {
+            Some(1) => {
+                self.coins.remove(quarter);
+                true
+            }
+            Some(count) => {
+                *count -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn count(&self, quarter: &StateQuarter) -> u32 {
+        self.coins.get(quarter).copied().unwrap_or(0)
+    }
+
+    /// Quarters of `state` from any mint.
+    pub fn count_state(&self, state: UsState) -> u64 {
+        self.iter()
+            .filter(|(quarter, _)| quarter.state == state)
+            .map(|(_, count)| u64::from(count))
+            .sum()
+    }
+
+    /// How many coins there are. Counted in `u64`, since each quarter's count can reach
+    /// `u32::MAX`.
+    pub fn len(&self) -> u64 {
+        self.coins.values().map(|&count| u64::from(count)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coins.is_empty()
+    }
+
+    /// Each kind of quarter with how many there are, by state in declaration order, then
+    /// mint.
+    pub fn iter(&self) -> impl Iterator<Item = (StateQuarter, u32)> + '_ {
+        self.coins.iter().map(|(&quarter, &count)| (quarter, count))
+    }
+
+    /// The 50 states with no quarter in the collection.
+    pub fn missing_states(&self) -> Vec<UsState> {
+        UsState::states()
+            .filter(|&state| self.count_state(state) == 0)
+            .collect()
+    }
+
+    /// The states, DC and territories with no quarter in the collection.
+    pub fn missing(&self) -> Vec<UsState> {
+        UsState::ALL
+            .into_iter()
+            .filter(|&state| self.count_state(state) == 0)
+            .collect()
+    }
+
+    /// Quarters held more than once, with how many spares there are beyond the first.
+    pub fn duplicates(&self) -> impl Iterator<Item = (StateQuarter, u32)> + '_ {
+        self.iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|(quarter, count)| (quarter, count - 1))
+    }
+
+    /// Every spare beyond the first of each quarter.
+    pub fn duplicate_count(&self) -> u64 {
+        self.duplicates().map(|(_, spares)| u64::from(spares)).sum()
+    }
+
+    /// What the coins would spend for, in cents.
+    pub fn face_value_cents(&self) -> u64 {
+        self.iter()
+            .map(|(quarter, count)| u64::from(value_in_cents(quarter.coin())) * u64::from(count))
+            .sum()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", HEADER);
+        for (quarter, count) in self.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                quarter.state, quarter.year, quarter.mint, count
+            ));
+        }
+        csv
+    }
+
+    /// Reads what `to_csv` writes, and the looser forms in the module docs. The header is
+    /// optional.
+    pub fn from_csv(text: &str) -> Result<QuarterCollection, CsvError> {
+        let mut collection = QuarterCollection::new();
+        for (i, line) in text.lines().enumerate() {
+            let parse_error = |reason: String| CsvError::Parse {
+                line: i + 1,
+                reason,
+            };
+            if line.trim().is_empty() || (i == 0 && line.trim() == HEADER) {
+                continue;
+            }
+            let fields = split_csv(line).map_err(parse_error)?;
+            let (state, year, mint, count) = match &fields[..] {
+                [state, year, mint] => (state, year, mint, "1"),
+                [state, year, mint, count] => (state, year, mint, count.as_str()),
+                _ => {
+                    return Err(parse_error(format!(
+                        "expected state,year,mint,count, found {} fields",
+                        fields.len()
+                    )))
+                }
+            };
+            let state: UsState = state
+                .parse()
+                .map_err(|e: crate::states::ParseStateError| parse_error(e.to_string()))?;
+            let year: u16 = year
+                .parse()
+                .map_err(|_| parse_error(format!("not a year: {:?}", year)))?;
+            let mint: MintMark = mint
+                .parse()
+                .map_err(|e: QuarterError| parse_error(e.to_string()))?;
+            let count: u32 = count
+                .parse()
+                .map_err(|_| parse_error(format!("not a count: {:?}", count)))?;
+            let quarter =
+                StateQuarter::new(state, year, mint).map_err(|e| parse_error(e.to_string()))?;
+            collection.add_many(quarter, count);
+        }
+        Ok(collection)
+    }
+
+    /// Writes `to_csv` to `path`, replacing the file whole.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CsvError> {
+        write_atomic(path.as_ref(), self.to_csv().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<QuarterCollection, CsvError> {
+        QuarterCollection::from_csv(&fs::read_to_string(path)?)
+    }
+}
+
+impl Extend<StateQuarter> for QuarterCollection {
+    fn extend<I: IntoIterator<Item = StateQuarter>>(&mut self, quarters: I) {
+        for quarter in quarters {
+            self.add(quarter);
+        }
+    }
+}
+
+impl FromIterator<StateQuarter> for QuarterCollection {
+    fn from_iter<I: IntoIterator<Item = StateQuarter>>(quarters: I) -> Self {
+        let mut collection = QuarterCollection::new();
+        collection.extend(quarters);
+        collection
+    }
+}
@@ -0,0 +1,327 @@
+//! Names, codes and dates for every `UsState`.
+//!
+//! `STATES` has one row per variant, in declaration order: the 50 states, the District of
+//! Columbia and the five inhabited territories. A state parses from its name (`New York`), its
+//! postal code (`NY`) or its word in the command syntax (`new_york`), in any case, and prints
+//! as its name.
+//!
+//! `UsState` implements `Command` by hand rather than deriving it, so that `Coin::Quarter`
+//! keeps its text form (`quarter new_york`) while `Display` and `FromStr` use the names.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::command::{Command, DecodeError, ParseError, ParseErrorKind, Tokens};
+use crate::enums::UsState;
+use crate::message::wire;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StateKind {
+    State,
+    /// The District of Columbia.
+    District,
+    Territory,
+}
+
+/// A calendar date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateInfo {
+    pub state: UsState,
+    pub name: &'static str,
+    /// The state's variant name in the command syntax.
+    pub word: &'static str,
+    /// The two-letter USPS abbreviation.
+    pub postal: &'static str,
+    /// The two-digit FIPS 5-2 code.
+    pub fips: u8,
+    pub capital: &'static str,
+    /// When it joined the union: ratified the Constitution, for the first thirteen. `None`
+    /// for DC and the territories.
+    pub admitted: Option<Date>,
+    pub kind: StateKind,
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn state(
+    state: UsState,
+    word: &'static str,
+    name: &'static str,
+    postal: &'static str,
+    fips: u8,
+    capital: &'static str,
+    (year, month, day): (u16, u8, u8),
+) -> StateInfo {
+    StateInfo {
+        state,
+        name,
+        word,
+        postal,
+        fips,
+        capital,
+        admitted: Some(Date { year, month, day }),
+        kind: StateKind::State,
+    }
+}
+
+const fn other(
+    state: UsState,
+    kind: StateKind,
+    word: &'static str,
+    name: &'static str,
+    postal: &'static str,
+    fips: u8,
+    capital: &'static str,
+) -> StateInfo {
+    StateInfo {
+        state,
+        name,
+        word,
+        postal,
+        fips,
+        capital,
+        admitted: None,
+        kind,
+    }
+}
+
+use StateKind::{District, Territory};
+use UsState::*;
+
+/// One row per `UsState`, in declaration order.
+#[rustfmt::skip]
+pub const STATES: [StateInfo; 56] = [
+    state(Alabama, "alabama", "Alabama", "AL", 1, "Montgomery", (1819, 12, 14)),
+    state(Alaska, "alaska", "Alaska", "AK", 2, "Juneau", (1959, 1, 3)),
+    state(Arizona, "arizona", "Arizona", "AZ", 4, "Phoenix", (1912, 2, 14)),
+    state(Arkansas, "arkansas", "Arkansas", "AR", 5, "Little Rock", (1836, 6, 15)),
+    state(California, "california", "California", "CA", 6, "Sacramento", (1850, 9, 9)),
+    state(Colorado, "colorado", "Colorado", "CO", 8, "Denver", (1876, 8, 1)),
+    state(Connecticut, "connecticut", "Connecticut", "CT", 9, "Hartford", (1788, 1, 9)),
+    state(Delaware, "delaware", "Delaware", "DE", 10, "Dover", (1787, 12, 7)),
+    state(Florida, "florida", "Florida", "FL", 12, "Tallahassee", (1845, 3, 3)),
+    state(Georgia, "georgia", "Georgia", "GA", 13, "Atlanta", (1788, 1, 2)),
+    state(Hawaii, "hawaii", "Hawaii", "HI", 15, "Honolulu", (1959, 8, 21)),
+    state(Idaho, "idaho", "Idaho", "ID", 16, "Boise", (1890, 7, 3)),
+    state(Illinois, "illinois", "Illinois", "IL", 17, "Springfield", (1818, 12, 3)),
+    state(Indiana, "indiana", "Indiana", "IN", 18, "Indianapolis", (1816, 12, 11)),
+    state(Iowa, "iowa", "Iowa", "IA", 19, "Des Moines", (1846, 12, 28)),
+    state(Kansas, "kansas", "Kansas", "KS", 20, "Topeka", (1861, 1, 29)),
+    state(Kentucky, "kentucky", "Kentucky", "KY", 21, "Frankfort", (1792, 6, 1)),
+    state(Louisiana, "louisiana", "Louisiana", "LA", 22, "Baton Rouge", (1812, 4, 30)),
+    state(Maine, "maine", "Maine", "ME", 23, "Augusta", (1820, 3, 15)),
+    state(Maryland, "maryland", "Maryland", "MD", 24, "Annapolis", (1788, 4, 28)),
+    state(Massachusetts, "massachusetts", "Massachusetts", "MA", 25, "Boston", (1788, 2, 6)),
+    state(Michigan, "michigan", "Michigan", "MI", 26, "Lansing", (1837, 1, 26)),
+    state(Minnesota, "minnesota", "Minnesota", "MN", 27, "Saint Paul", (1858, 5, 11)),
+    state(Mississippi, "mississippi", "Mississippi", "MS", 28, "Jackson", (1817, 12, 10)),
+    state(Missouri, "missouri", "Missouri", "MO", 29, "Jefferson City", (1821, 8, 10)),
+    state(Montana, "montana", "Montana", "MT", 30, "Helena", (1889, 11, 8)),
+    state(Nebraska, "nebraska", "Nebraska", "NE", 31, "Lincoln", (1867, 3, 1)),
+    state(Nevada, "nevada", "Nevada", "NV", 32, "Carson City", (1864, 10, 31)),
+    state(NewHampshire, "new_hampshire", "New Hampshire", "NH", 33, "Concord", (1788, 6, 21)),
+    state(NewJersey, "new_jersey", "New Jersey", "NJ", 34, "Trenton", (1787, 12, 18)),
+    state(NewMexico, "new_mexico", "New Mexico", "NM", 35, "Santa Fe", (1912, 1, 6)),
+    state(NewYork, "new_york", "New York", "NY", 36, "Albany", (1788, 7, 26)),
+    state(NorthCarolina, "north_carolina", "North Carolina", "NC", 37, "Raleigh", (1789, 11, 21)),
+    state(NorthDakota, "north_dakota", "North Dakota", "ND", 38, "Bismarck", (1889, 11, 2)),
+    state(Ohio, "ohio", "Ohio", "OH", 39, "Columbus", (1803, 3, 1)),
+    state(Oklahoma, "oklahoma", "Oklahoma", "OK", 40, "Oklahoma City", (1907, 11, 16)),
+    state(Oregon, "oregon", "Oregon", "OR", 41, "Salem", (1859, 2, 14)),
+    state(Pennsylvania, "pennsylvania", "Pennsylvania", "PA", 42, "Harrisburg", (1787, 12, 12)),
+    state(RhodeIsland, "rhode_island", "Rhode Island", "RI", 44, "Providence", (1790, 5, 29)),
+    state(SouthCarolina, "south_carolina", "South Carolina", "SC", 45, "Columbia", (1788, 5, 23)),
+    state(SouthDakota, "south_dakota", "South Dakota", "SD", 46, "Pierre", (1889, 11, 2)),
+    state(Tennessee, "tennessee", "Tennessee", "TN", 47, "Nashville", (1796, 6, 1)),
+    state(Texas, "texas", "Texas", "TX", 48, "Austin", (1845, 12, 29)),
+    state(Utah, "utah", "Utah", "UT", 49, "Salt Lake City", (1896, 1, 4)),
+    state(Vermont, "vermont", "Vermont", "VT", 50, "Montpelier", (1791, 3, 4)),
+    state(Virginia, "virginia", "Virginia", "VA", 51, "Richmond", (1788, 6, 25)),
+    state(Washington, "washington", "Washington", "WA", 53, "Olympia", (1889, 11, 11)),
+    state(WestVirginia, "west_virginia", "West Virginia", "WV", 54, "Charleston", (1863, 6, 20)),
+    state(Wisconsin, "wisconsin", "Wisconsin", "WI", 55, "Madison", (1848, 5, 29)),
+    state(Wyoming, "wyoming", "Wyoming", "WY", 56, "Cheyenne", (1890, 7, 10)),
+    other(DistrictOfColumbia, District, "district_of_columbia", "District of Columbia", "DC", 11, "Washington"),
+    other(AmericanSamoa, Territory, "american_samoa", "American Samoa", "AS", 60, "Pago Pago"),
+    other(Guam, Territory, "guam", "Guam", "GU", 66, "Hagåtña"),
+    other(NorthernMarianaIslands, Territory, "northern_mariana_islands", "Northern Mariana Islands", "MP", 69, "Saipan"),
+    other(PuertoRico, Territory, "puerto_rico", "Puerto Rico", "PR", 72, "San Juan"),
+    other(UsVirginIslands, Territory, "us_virgin_islands", "U.S. Virgin Islands", "VI", 78, "Charlotte Amalie"),
+];
+
+// the rows really are in declaration order, so `info` can index by discriminant
+const _: () = {
+    let mut i = 0;
+    while i < STATES.len() {
+        assert!(STATES[i].state as usize == i);
+        i += 1;
+    }
+};
+
+const WORDS: [&str; STATES.len()] = {
+    let mut words = [""; STATES.len()];
+    let mut i = 0;
+    while i < STATES.len() {
+        words[i] = STATES[i].word;
+        i += 1;
+    }
+    words
+};
+
+impl UsState {
+    /// Every variant, in declaration order.
+    pub const ALL: [UsState; STATES.len()] = {
+        let mut all = [UsState::Alabama; STATES.len()];
+        let mut i = 0;
+        while i < STATES.len() {
+            all[i] = STATES[i].state;
+            i += 1;
+        }
+        all
+    };
+
+    pub fn info(self) -> &'static StateInfo {
+        &STATES[self as usize]
+    }
+
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    /// The postal code, like `NY`.
+    pub fn abbreviation(self) -> &'static str {
+        self.info().postal
+    }
+
+    pub fn fips(self) -> u8 {
+        self.info().fips
+    }
+
+    pub fn capital(self) -> &'static str {
+        self.info().capital
+    }
+
+    /// `None` for DC and the territories.
+    pub fn admitted(self) -> Option<Date> {
+        self.info().admitted
+    }
+
+    pub fn kind(self) -> StateKind {
+        self.info().kind
+    }
+
+    /// The 50 states, without DC and the territories.
+    pub fn states() -> impl Iterator<Item = UsState> {
+        UsState::ALL
+            .into_iter()
+            .filter(|state| state.kind() == StateKind::State)
+    }
+
+    pub fn from_fips(fips: u8) -> Option<UsState> {
+        STATES
+            .iter()
+            .find(|info| info.fips == fips)
+            .map(|info| info.state)
+    }
+
+    /// Looks a state up by its postal code, in any case.
+    pub fn from_abbreviation(postal: &str) -> Option<UsState> {
+        STATES
+            .iter()
+            .find(|info| info.postal.eq_ignore_ascii_case(postal))
+            .map(|info| info.state)
+    }
+}
+
+impl fmt::Display for UsState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStateError {
+    pub found: String,
+}
+
+impl fmt::Display for ParseStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no state or territory is called {:?}", self.found)
+    }
+}
+
+impl Error for ParseStateError {}
+
+impl FromStr for UsState {
+    type Err = ParseStateError;
+
+    /// Takes a name, a postal code or a command word, in any case. Spaces inside a name may
+    /// be any run of whitespace.
+    fn from_str(s: &str) -> Result<UsState, ParseStateError> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let text = words.join(" ");
+        STATES
+            .iter()
+            .find(|info| {
+                info.name.eq_ignore_ascii_case(&text)
+                    || info.postal.eq_ignore_ascii_case(&text)
+                    || info.word.eq_ignore_ascii_case(&text)
+            })
+            .map(|info| info.state)
+            .ok_or_else(|| ParseStateError {
+                found: s.to_string(),
+            })
+    }
+}
+
+impl Command for UsState {
+    const VARIANTS: &'static [&'static str] = &WORDS;
+
+    fn variant_index(&self) -> usize {
+        *self as usize
+    }
+
+    /// One word: the command word or the postal code, in any case.
+    fn parse_tokens(tokens: &mut Tokens<'_>) -> Result<Self, ParseError> {
+        if tokens.at_end() {
+            return Err(tokens.error_at(usize::MAX, ParseErrorKind::Empty));
+        }
+        let (word, start) = tokens.word("state")?;
+        word.parse().map_err(|_| {
+            let kind = ParseErrorKind::UnknownVariant {
+                found: word.to_string(),
+                expected: Self::VARIANTS,
+            };
+            tokens.error_at(start, kind)
+        })
+    }
+
+    fn write_tokens(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.variant_name())
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        wire::put_varint(out, *self as u64);
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let index = wire::take_varint(input)?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| UsState::ALL.get(index).copied())
+            .ok_or(DecodeError::UnknownVariant(index))
+    }
+}
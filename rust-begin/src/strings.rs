@@ -0,0 +1,60 @@
+//! Rust has only one string type in the core language, the string slice `str`, usually seen in its
+//! borrowed form `&str`. The standard library adds the growable, owned `String`.
+//! Both `String` and string slices are UTF-8 encoded.
+
+/// Joins the parts with `-`. Using `format!` is easier to read than `+` and doesn't take ownership
+/// of any of its parameters.
+pub fn join_with_dashes(parts: &[&str]) -> String {
+    let mut s = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            s.push('-'); // push takes a single character
+        }
+        s.push_str(part); // push_str takes a string slice because we don't want to take ownership
+    }
+    s
+}
+
+/// Slices `s` by byte range without panicking when the range does not fall on character
+/// boundaries (`&s[0..1]` of "Здравствуйте" would panic).
+pub fn byte_slice(s: &str, start: usize, end: usize) -> Option<&str> {
+    s.get(start..end)
+}
+
+pub fn demo() {
+    let data = "initial contents";
+    let s = data.to_string(); // available on any type that implements the ~Display trait
+    println!("{}", s);
+
+    let mut s1 = String::from("foo");
+    let s2 = "bar";
+    s1.push_str(s2);
+    println!("s1 is {}, s2 is {}", s1, s2); // ~s2 can still be used because we didn't take ownership
+
+    // ! Concatenation with the + Operator or the format! Macro
+    let s1 = String::from("Hello, ");
+    let s2 = String::from("world!");
+    let s3 = s1 + &s2; // note s1 has been moved here and can no longer be used
+    println!("{}", s3);
+
+    let s1 = String::from("tic");
+    let s2 = String::from("tac");
+    let s3 = String::from("toe");
+    let s = format!("{}-{}-{}", s1, s2, s3);
+    println!("{}", s);
+    println!("{}", join_with_dashes(&[&s1, &s2, &s3]));
+
+    // ! Slicing Strings: Rust doesn't allow indexing, not all characters are encoded within 1 byte
+    let hello = "Здравствуйте";
+    println!("&hello[0..4] is {:?}", byte_slice(hello, 0, 4)); // Some("Зд")
+    println!("&hello[0..1] is {:?}", byte_slice(hello, 0, 1)); // None
+
+    // ! Methods for Iterating Over Strings
+    for c in "नमस्ते".chars() {
+        println!("{}", c);
+    }
+
+    for b in "नमस्ते".bytes() {
+        println!("{}", b);
+    }
+}
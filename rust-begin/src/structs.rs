@@ -0,0 +1,116 @@
+//! Structs are similar to tuples, the pieces of the struct can be different types.
+//! Unlike tuples, every piece is named: a ( key : value ) pair.
+
+// Defining and Instantiating Structs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub username: String, // username, email, sign_in_count, active are called fields
+    pub email: String,
+    pub sign_in_count: u64,
+    pub active: bool,
+}
+
+/// Return a new instance from a function, using the field init shorthand because the variables
+/// and the fields have the same name.
+// 💡 any two strings make a User here; crate::users::UserRegistry checks them first
+pub fn build_user(email: String, username: String) -> User {
+    User {
+        email,
+        username,
+        active: true,
+        sign_in_count: 1,
+    }
+}
+
+// Using Tuple Structs without 👉Named Fields to Create Different Types
+// 💡 A function that takes a parameter of type 👉Color cannot take a 👉Point as an argument even though they are both made up of 3 i32 values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(pub i32, pub i32, pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point(pub i32, pub i32, pub i32);
+
+// Unit-Like Structs Without Any Fields
+// Useful when you need to implement a trait on some type but don't have any data to store in the type itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlwaysEqual;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)] // This annotation enables printing out debugging information
+pub struct Rectangle {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rectangle {
+    /// An associated function: it doesn't take `self`, so it is called with `Rectangle::square(3)`.
+    pub fn square(size: u32) -> Rectangle {
+        Rectangle {
+            width: size,
+            height: size,
+        }
+    }
+
+    pub fn area(&self) -> u32 {
+        // &self -> &Rectangle
+        self.width * self.height
+    }
+
+    pub fn can_hold(&self, other: &Rectangle) -> bool {
+        self.width > other.width && self.height > other.height
+    }
+}
+
+/// The free-function version of `Rectangle::area`, from before we learned about methods.
+pub fn area(rectangle: &Rectangle) -> u32 {
+    rectangle.width * rectangle.height
+}
+
+pub fn demo() {
+    // Note that the entire instance must be mutable; Rust doesn't allow us to mark only certain fields as mutable
+    let mut user1 = build_user(
+        String::from("someone@example.com"),
+        String::from("someusername123"),
+    );
+    user1.email = String::from("anotheremail@example.com");
+    println!("user1 is {:?}", user1);
+
+    // Creating Instances from other Instances with Struct Update Syntax
+    let user2 = User {
+        email: String::from("another@example.com"),
+        username: String::from("anotherusername567"),
+        ..user1
+    };
+    println!("user2 is {:?}", user2);
+
+    let black = Color(0, 0, 0);
+    let origin = Point(0, 0, 0);
+    println!("black is {:?}, origin is {:?}", black, origin);
+
+    let rect1 = Rectangle {
+        width: 30,
+        height: 50,
+    };
+    println!("rect1 is {:#?}", rect1);
+    println!(
+        "The area of the rectangle is {} square pixels.",
+        area(&rect1)
+    );
+    println!(
+        "The area of the rectangle is {} square pixels.",
+        rect1.area()
+    );
+
+    let rect2 = Rectangle {
+        width: 10,
+        height: 40,
+    };
+    let rect3 = Rectangle {
+        width: 60,
+        height: 45,
+    };
+    println!("Can rect1 hold rect2? {}", rect1.can_hold(&rect2));
+    println!("Can rect1 hold rect3? {}", rect1.can_hold(&rect3));
+
+    let sq = Rectangle::square(3);
+    println!("sq is {:?} with area {}", sq, sq.area());
+}
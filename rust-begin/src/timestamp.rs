@@ -0,0 +1,22 @@
+//! Times in the text files the crate saves, written `secs.nanos` since the Unix epoch with
+//! exactly nine digits of nanoseconds, as in `1700000000.000000000`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Times before the epoch are written as the epoch.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{}.{:09}", since.as_secs(), since.subsec_nanos())
+}
+
+/// The time `format_timestamp` wrote, or `None` for anything else, including a time too far
+/// in the future for `SystemTime` to hold.
+pub(crate) fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let (secs, nanos) = text.split_once('.')?;
+    if nanos.len() != 9 || !(secs.bytes().chain(nanos.bytes())).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let secs: u64 = secs.parse().ok()?;
+    let nanos: u32 = nanos.parse().ok()?;
+    UNIX_EPOCH.checked_add(Duration::new(secs, nanos))
+}
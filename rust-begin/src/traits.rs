@@ -0,0 +1,135 @@
+//! We use traits to define shared behavior in an abstract way.
+//! We can use trait bounds to specify that a generic can be any type that has certain behavior.
+
+use std::fmt::Display;
+
+/// Default implementations can call other methods in the same trait, even if those other methods
+/// don't have a default implementation. Implementors only have to provide `summarize_author`.
+pub trait Summary {
+    fn summarize_author(&self) -> String;
+
+    fn summarize(&self) -> String {
+        format!("(Read more from {}...)", self.summarize_author())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewsArticle {
+    pub headline: String,
+    pub location: String,
+    pub author: String,
+    pub content: String,
+}
+
+impl Summary for NewsArticle {
+    fn summarize_author(&self) -> String {
+        self.author.clone()
+    }
+
+    fn summarize(&self) -> String {
+        format!("{}, by {} ({})", self.headline, self.author, self.location) // overrides the default
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tweet {
+    pub username: String,
+    pub content: String,
+    pub reply: bool,
+    pub retweet: bool,
+}
+
+impl Summary for Tweet {
+    fn summarize_author(&self) -> String {
+        format!("@{}", self.username)
+    }
+}
+
+// ! Trait as Parameter
+/// Accepts any type that implements `Summary`; calling it with a `String` or an `i32` won't compile.
+pub fn notify(item: &impl Summary) -> String {
+    format!("Breaking news! {}", item.summarize())
+}
+
+/// The trait bound version of `notify`, which forces both items to have the same type.
+pub fn notify_both<T: Summary>(item1: &T, item2: &T) -> String {
+    format!(
+        "Breaking news! {} / {}",
+        item1.summarize(),
+        item2.summarize()
+    )
+}
+
+/// Multiple trait bounds with a `where` clause.
+pub fn notify_and_show<T>(item: &T) -> String
+where
+    T: Summary + Display,
+{
+    format!("Breaking news! {} [{}]", item.summarize(), item)
+}
+
+/// Returns some type that implements `Summary` without naming the concrete type.
+/// 💡 `impl Trait` can only be used when returning a single type.
+pub fn returns_summarizable() -> impl Summary {
+    Tweet {
+        username: String::from("horse_ebooks"),
+        content: String::from("of course, as you probably already know, people"),
+        reply: false,
+        retweet: false,
+    }
+}
+
+// ! Using Trait Bounds to Conditionally Implement Methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pair<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Pair<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+// Pair<T> always implements `new`, but only implements `cmp_display` if its inner type T
+// implements the PartialOrd trait that enables comparison and the Display trait that enables printing.
+impl<T: Display + PartialOrd> Pair<T> {
+    pub fn cmp_display(&self) -> String {
+        if self.x >= self.y {
+            format!("The largest member is x = {}", self.x)
+        } else {
+            format!("The largest member is y = {}", self.y)
+        }
+    }
+}
+
+pub fn demo() {
+    let tweet = Tweet {
+        username: String::from("horse_ebooks"),
+        content: String::from("of course, as you probably already know, people"),
+        reply: false,
+        retweet: false,
+    };
+    println!("1 new tweet: {}", tweet.summarize()); // It just looks like a method call
+
+    let article = NewsArticle {
+        headline: String::from("Penguins win the Stanley Cup Championship!"),
+        location: String::from("Pittsburgh, PA, USA"),
+        author: String::from("Iceburgh"),
+        content: String::from(
+            "The Pittsburgh Penguins once again are the best hockey team in the NHL.",
+        ),
+    };
+    println!("New article available! {}", article.summarize());
+
+    println!("{}", notify(&article));
+    println!("{}", notify(&returns_summarizable()));
+
+    let pair = Pair::new(3, 7);
+    println!("{}", pair.cmp_display());
+
+    // The standard library implements ToString on any type that implements Display (a blanket implementation)
+    let s = 3.to_string();
+    println!("3.to_string() is {:?}", s);
+}
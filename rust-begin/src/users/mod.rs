@@ -0,0 +1,689 @@
+//! A registry of `User`s that checks what `build_user` accepts without question.
+//!
+//! Usernames are normalized before they are stored or looked up: surrounding whitespace goes
+//! and letters are lowercased, so `Ferris` and ` ferris ` are the same user. Email addresses
+//! are checked against the parts of RFC 5322 that real addresses use and compared without
+//! regard to case. Both must be unique, inactive users included, and registering reports
+//! every field that is wrong at once rather than only the first.
+//!
+//! A user with a password set can sign in, which counts in `sign_in_count`; see
+//! `crate::credentials` for how passwords are kept and accounts locked.
+//!
+//! `UserRegistry::save` and `load` keep the users in a file between runs; see `read_store`
+//! for the format and how older files are migrated and broken ones repaired.
+//!
+//! ```
+//! use rust_begin::users::{EmailError, FieldError, RegistryError, UserRegistry, UsernameError};
+//!
+//! let mut users = UserRegistry::new();
+//! users.register("Ferris", "ferris@example.com")?;
+//! assert_eq!(users.get("FERRIS").unwrap().username, "ferris");
+//!
+//! let Err(RegistryError::Invalid(errors)) = users.register("ferris", "crab@") else {
+//!     panic!("both fields are wrong");
+//! };
+//! assert_eq!(
+//!     errors.errors(),
+//!     [
+//!         FieldError::Username(UsernameError::Taken),
+//!         FieldError::Email(EmailError::EmptyDomain),
+//!     ]
+//! );
+//! # Ok::<(), RegistryError>(())
+//! ```
+
+mod store;
+
+pub use store::{
+    inspect_store, read_store, repair_store, StoreError, StoreFix, StoreProblem, StoreReport,
+    STORE_VERSION,
+};
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::time::SystemTime;
+
+use crate::credentials::{Credential, Lockout, Params, PasswordHash, SignInError};
+use crate::enums::IpAddr;
+use crate::structs::{build_user, User};
+
+pub const USERNAME_MIN: usize = 3;
+pub const USERNAME_MAX: usize = 32;
+/// The longest address that fits the `MAIL` and `RCPT` commands of SMTP (RFC 5321 4.5.3.1).
+pub const EMAIL_MAX: usize = 254;
+pub const LOCAL_PART_MAX: usize = 64;
+pub const LABEL_MAX: usize = 63;
+pub const PASSWORD_MIN: usize = 8;
+/// Long enough for any passphrase, short enough that hashing stays cheap.
+pub const PASSWORD_MAX: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsernameError {
+    Empty,
+    TooShort {
+        min: usize,
+    },
+    TooLong {
+        max: usize,
+    },
+    /// Only ASCII letters, digits, `.`, `_` and `-` are allowed.
+    InvalidChar(char),
+    MustStartWithLetter,
+    /// A `.`, `_` or `-` at the end or next to another one.
+    MisplacedSeparator,
+    Taken,
+}
+
+impl fmt::Display for UsernameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsernameError::Empty => write!(f, "is empty"),
+            UsernameError::TooShort { min } => write!(f, "is shorter than {} characters", min),
+            UsernameError::TooLong { max } => write!(f, "is longer than {} characters", max),
+            UsernameError::InvalidChar(c) => write!(f, "contains {:?}", c),
+            UsernameError::MustStartWithLetter => write!(f, "does not start with a letter"),
+            UsernameError::MisplacedSeparator => {
+                write!(f, "has a separator at the end or two in a row")
+            }
+            UsernameError::Taken => write!(f, "is taken"),
+        }
+    }
+}
+
+impl Error for UsernameError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailError {
+    Empty,
+    TooLong {
+        max: usize,
+    },
+    MissingAt,
+    EmptyLocalPart,
+    LocalPartTooLong {
+        max: usize,
+    },
+    /// A character that is not allowed where it is, like a space or a second `@`. Quoted
+    /// local parts (`"john doe"@example.com`) are not supported.
+    InvalidChar(char),
+    /// A `.` at the start or end of the local part, or two in a row.
+    MisplacedDot,
+    EmptyDomain,
+    /// A label of the domain that is empty, too long, starts or ends with `-`, or is an
+    /// all-digit top-level domain.
+    InvalidLabel(String),
+    /// A domain of one label, like `localhost`.
+    NoTopLevelDomain,
+    /// Not an IPv4 address, or `IPv6:` and an IPv6 address, between the brackets.
+    InvalidAddressLiteral(String),
+    Taken,
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmailError::Empty => write!(f, "is empty"),
+            EmailError::TooLong { max } => write!(f, "is longer than {} characters", max),
+            EmailError::MissingAt => write!(f, "has no @"),
+            EmailError::EmptyLocalPart => write!(f, "has nothing before the @"),
+            EmailError::LocalPartTooLong { max } => {
+                write!(f, "has more than {} characters before the @", max)
+            }
+            EmailError::InvalidChar(c) => write!(f, "contains {:?}", c),
+            EmailError::MisplacedDot => {
+                write!(
+                    f,
+                    "has a dot at the start or end of its name, or two in a row"
+                )
+            }
+            EmailError::EmptyDomain => write!(f, "has nothing after the @"),
+            EmailError::InvalidLabel(label) => write!(f, "has an invalid domain part {:?}", label),
+            EmailError::NoTopLevelDomain => write!(f, "has a domain without a dot"),
+            EmailError::InvalidAddressLiteral(literal) => {
+                write!(f, "has an invalid address {:?}", literal)
+            }
+            EmailError::Taken => write!(f, "is taken"),
+        }
+    }
+}
+
+impl Error for EmailError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordError {
+    TooShort { min: usize },
+    TooLong { max: usize },
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::TooShort { min } => write!(f, "is shorter than {} characters", min),
+            PasswordError::TooLong { max } => write!(f, "is longer than {} characters", max),
+        }
+    }
+}
+
+impl Error for PasswordError {}
+
+/// What is wrong with one field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldError {
+    Username(UsernameError),
+    Email(EmailError),
+    Password(PasswordError),
+}
+
+impl FieldError {
+    /// The name of the field, as in `User`.
+    pub fn field(&self) -> &'static str {
+        match self {
+            FieldError::Username(_) => "username",
+            FieldError::Email(_) => "email",
+            FieldError::Password(_) => "password",
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::Username(e) => write!(f, "username {}", e),
+            FieldError::Email(e) => write!(f, "email {}", e),
+            FieldError::Password(e) => write!(f, "password {}", e),
+        }
+    }
+}
+
+impl Error for FieldError {}
+
+/// Every field that failed, in the order of the fields of `User`. Never empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    pub fn username(&self) -> Option<&UsernameError> {
+        self.errors.iter().find_map(|e| match e {
+            FieldError::Username(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn email(&self) -> Option<&EmailError> {
+        self.errors.iter().find_map(|e| match e {
+            FieldError::Email(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn password(&self) -> Option<&PasswordError> {
+        self.errors.iter().find_map(|e| match e {
+            FieldError::Password(e) => Some(e),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationErrors {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    Invalid(ValidationErrors),
+    /// No user has this username.
+    UnknownUser(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Invalid(errors) => write!(f, "invalid user: {}", errors),
+            RegistryError::UnknownUser(username) => write!(f, "no user called {:?}", username),
+        }
+    }
+}
+
+impl Error for RegistryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RegistryError::Invalid(errors) => Some(errors),
+            RegistryError::UnknownUser(_) => None,
+        }
+    }
+}
+
+impl From<ValidationErrors> for RegistryError {
+    fn from(errors: ValidationErrors) -> Self {
+        RegistryError::Invalid(errors)
+    }
+}
+
+/// The username as it is stored: trimmed and in lowercase. Lookups go through this too.
+fn username_key(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+fn email_key(email: &str) -> String {
+    email.trim().to_ascii_lowercase()
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '.' | '_' | '-')
+}
+
+/// The username as it is stored, or why it cannot be one. It must be 3 to 32 ASCII letters,
+/// digits, `.`, `_` and `-`, start with a letter and not end with or double up a separator.
+pub fn normalize_username(username: &str) -> Result<String, UsernameError> {
+    let username = username_key(username);
+    let length = username.chars().count();
+    if length == 0 {
+        return Err(UsernameError::Empty);
+    }
+    if let Some(c) = username
+        .chars()
+        .find(|&c| !c.is_ascii_alphanumeric() && !is_separator(c))
+    {
+        return Err(UsernameError::InvalidChar(c));
+    }
+    if length < USERNAME_MIN {
+        return Err(UsernameError::TooShort { min: USERNAME_MIN });
+    }
+    if length > USERNAME_MAX {
+        return Err(UsernameError::TooLong { max: USERNAME_MAX });
+    }
+    if !username.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(UsernameError::MustStartWithLetter);
+    }
+    let doubled = username
+        .as_bytes()
+        .windows(2)
+        .any(|pair| is_separator(pair[0] as char) && is_separator(pair[1] as char));
+    if doubled || username.ends_with(is_separator) {
+        return Err(UsernameError::MisplacedSeparator);
+    }
+    Ok(username)
+}
+
+/// The characters RFC 5322 allows in a dot-atom, besides the dots.
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c)
+}
+
+fn check_local_part(local: &str) -> Result<(), EmailError> {
+    if local.is_empty() {
+        return Err(EmailError::EmptyLocalPart);
+    }
+    if local.len() > LOCAL_PART_MAX {
+        return Err(EmailError::LocalPartTooLong {
+            max: LOCAL_PART_MAX,
+        });
+    }
+    if let Some(c) = local.chars().find(|&c| c != '.' && !is_atext(c)) {
+        return Err(EmailError::InvalidChar(c));
+    }
+    if local.split('.').any(str::is_empty) {
+        return Err(EmailError::MisplacedDot);
+    }
+    Ok(())
+}
+
+fn check_domain(domain: &str) -> Result<(), EmailError> {
+    if domain.is_empty() {
+        return Err(EmailError::EmptyDomain);
+    }
+    if let Some(literal) = domain.strip_prefix('[').and_then(|d| d.strip_suffix(']')) {
+        let invalid = || EmailError::InvalidAddressLiteral(literal.to_string());
+        // no zones: they only mean something on the host that wrote them
+        if literal.contains('%') {
+            return Err(invalid());
+        }
+        // the tag is case-insensitive like the rest of the domain
+        let tag = literal
+            .get(..5)
+            .filter(|tag| tag.eq_ignore_ascii_case("IPv6:"));
+        return match tag.map(|tag| &literal[tag.len()..]) {
+            Some(v6) => match v6.parse() {
+                Ok(IpAddr::V6(_)) => Ok(()),
+                _ => Err(invalid()),
+            },
+            None => match literal.parse() {
+                Ok(IpAddr::V4(..)) => Ok(()),
+                _ => Err(invalid()),
+            },
+        };
+    }
+    if let Some(c) = domain
+        .chars()
+        .find(|&c| !c.is_ascii_alphanumeric() && c != '-' && c != '.')
+    {
+        return Err(EmailError::InvalidChar(c));
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    let bad_label = labels.iter().find(|label| {
+        label.is_empty()
+            || label.len() > LABEL_MAX
+            || label.starts_with('-')
+            || label.ends_with('-')
+    });
+    if let Some(label) = bad_label {
+        return Err(EmailError::InvalidLabel(label.to_string()));
+    }
+    if labels.len() < 2 {
+        return Err(EmailError::NoTopLevelDomain);
+    }
+    let top = labels[labels.len() - 1];
+    if top.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(EmailError::InvalidLabel(top.to_string()));
+    }
+    Ok(())
+}
+
+/// The address as it is stored, with surrounding whitespace gone and the domain in lowercase,
+/// or why it is not one.
+///
+/// The local part before the `@` is a dot-atom: RFC 5322's letters, digits and
+/// ``!#$%&'*+/=?^_`{|}~-`` in runs split by single dots. The domain is a host name with at
+/// least one dot, or an address literal like `[192.0.2.1]` or `[IPv6:2001:db8::1]`.
+/// Comments, folding whitespace and quoted local parts are left out: real addresses do not
+/// use them and most mail software turns them away.
+pub fn validate_email(email: &str) -> Result<String, EmailError> {
+    let email = email.trim();
+    if email.is_empty() {
+        return Err(EmailError::Empty);
+    }
+    if email.len() > EMAIL_MAX {
+        return Err(EmailError::TooLong { max: EMAIL_MAX });
+    }
+    let (local, domain) = email.rsplit_once('@').ok_or(EmailError::MissingAt)?;
+    check_local_part(local)?;
+    check_domain(domain)?;
+    Ok(format!("{}@{}", local, domain.to_ascii_lowercase()))
+}
+
+/// Characters, not bytes, so that a passphrase in any script gets the same allowance.
+fn check_password(password: &str) -> Result<(), PasswordError> {
+    let length = password.chars().count();
+    if length < PASSWORD_MIN {
+        return Err(PasswordError::TooShort { min: PASSWORD_MIN });
+    }
+    if length > PASSWORD_MAX {
+        return Err(PasswordError::TooLong { max: PASSWORD_MAX });
+    }
+    Ok(())
+}
+
+/// Users by username, each with a username and an email no other user has, compared without
+/// regard to case, and the passwords of those who have set one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserRegistry {
+    users: BTreeMap<String, User>,
+    /// Lowercased email to username.
+    emails: HashMap<String, String>,
+    credentials: HashMap<String, Credential>,
+    params: Params,
+    lockout: Lockout,
+}
+
+impl UserRegistry {
+    pub fn new() -> UserRegistry {
+        UserRegistry::default()
+    }
+
+    /// The cost to hash new passwords at. Passwords already set keep theirs.
+    pub fn with_params(mut self, params: Params) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_lockout(mut self, lockout: Lockout) -> Self {
+        self.lockout = lockout;
+        self
+    }
+
+    pub fn params(&self) -> Params {
+        self.params
+    }
+
+    pub fn lockout(&self) -> &Lockout {
+        &self.lockout
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// The user called `username`, in any case.
+    pub fn get(&self, username: &str) -> Option<&User> {
+        self.users.get(&username_key(username))
+    }
+
+    /// The user with `email`, in any case.
+    pub fn find_by_email(&self, email: &str) -> Option<&User> {
+        let username = self.emails.get(&email_key(email))?;
+        self.users.get(username)
+    }
+
+    /// Every user, active or not, by username.
+    pub fn iter(&self) -> impl Iterator<Item = &User> {
+        self.users.values()
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &User> {
+        self.iter().filter(|user| user.active)
+    }
+
+    /// The username, checked and free for `owner` (or anyone, if `None`) to take.
+    fn check_username(&self, username: &str, owner: Option<&str>) -> Result<String, FieldError> {
+        let username = normalize_username(username).map_err(FieldError::Username)?;
+        if self.users.contains_key(&username) && owner != Some(username.as_str()) {
+            return Err(FieldError::Username(UsernameError::Taken));
+        }
+        Ok(username)
+    }
+
+    fn check_email(&self, email: &str, owner: Option<&str>) -> Result<String, FieldError> {
+        let email = validate_email(email).map_err(FieldError::Email)?;
+        match self.emails.get(&email_key(&email)) {
+            Some(holder) if owner != Some(holder.as_str()) => {
+                Err(FieldError::Email(EmailError::Taken))
+            }
+            _ => Ok(email),
+        }
+    }
+
+    /// Both fields of a new user, checked and free, or every reason they are not.
+    fn check_new(&self, username: &str, email: &str) -> Result<(String, String), ValidationErrors> {
+        match (
+            self.check_username(username, None),
+            self.check_email(email, None),
+        ) {
+            (Ok(username), Ok(email)) => Ok((username, email)),
+            (username, email) => {
+                let errors = [username.err(), email.err()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                Err(ValidationErrors { errors })
+            }
+        }
+    }
+
+    /// Checks both fields and adds an active user made by `build_user`. Every field that is
+    /// invalid or taken is reported.
+    pub fn register(&mut self, username: &str, email: &str) -> Result<&User, RegistryError> {
+        let (username, email) = self.check_new(username, email)?;
+        self.emails.insert(email_key(&email), username.clone());
+        let user = build_user(email, username.clone());
+        Ok(self.users.entry(username).or_insert(user))
+    }
+
+    /// Adds a user read back from a store, with the same checks as `register` but keeping
+    /// their count, `active` and password.
+    fn insert(
+        &mut self,
+        user: User,
+        credential: Option<Credential>,
+    ) -> Result<(), ValidationErrors> {
+        let (username, email) = self.check_new(&user.username, &user.email)?;
+        self.emails.insert(email_key(&email), username.clone());
+        if let Some(credential) = credential {
+            self.credentials.insert(username.clone(), credential);
+        }
+        let user = User {
+            username: username.clone(),
+            email,
+            ..user
+        };
+        self.users.insert(username, user);
+        Ok(())
+    }
+
+    fn user_mut(&mut self, username: &str) -> Result<&mut User, RegistryError> {
+        self.users
+            .get_mut(&username_key(username))
+            .ok_or_else(|| RegistryError::UnknownUser(username.to_string()))
+    }
+
+    /// Gives the user a new username, which must be valid and not someone else's.
+    pub fn rename(&mut self, username: &str, new_username: &str) -> Result<&User, RegistryError> {
+        let old = username_key(username);
+        if !self.users.contains_key(&old) {
+            return Err(RegistryError::UnknownUser(username.to_string()));
+        }
+        let new = self
+            .check_username(new_username, Some(&old))
+            .map_err(|e| ValidationErrors { errors: vec![e] })?;
+        let mut user = self.users.remove(&old).expect("checked above");
+        user.username = new.clone();
+        self.emails.insert(email_key(&user.email), new.clone());
+        if let Some(credential) = self.credentials.remove(&old) {
+            self.credentials.insert(new.clone(), credential);
+        }
+        Ok(self.users.entry(new).or_insert(user))
+    }
+
+    /// Gives the user a new email, which must be valid and not someone else's.
+    pub fn change_email(&mut self, username: &str, email: &str) -> Result<&User, RegistryError> {
+        let key = username_key(username);
+        if !self.users.contains_key(&key) {
+            return Err(RegistryError::UnknownUser(username.to_string()));
+        }
+        let email = self
+            .check_email(email, Some(&key))
+            .map_err(|e| ValidationErrors { errors: vec![e] })?;
+        let user = self.users.get_mut(&key).expect("checked above");
+        self.emails.remove(&email_key(&user.email));
+        self.emails.insert(email_key(&email), key);
+        user.email = email;
+        Ok(user)
+    }
+
+    /// Clears `active`. The username and email stay taken. Deactivating an inactive user
+    /// does nothing.
+    pub fn deactivate(&mut self, username: &str) -> Result<(), RegistryError> {
+        self.user_mut(username)?.active = false;
+        Ok(())
+    }
+
+    /// Sets `active` again. Reactivating an active user does nothing.
+    pub fn reactivate(&mut self, username: &str) -> Result<(), RegistryError> {
+        self.user_mut(username)?.active = true;
+        Ok(())
+    }
+
+    /// Takes the user out for good, freeing the username and email. The password goes too.
+    pub fn remove(&mut self, username: &str) -> Option<User> {
+        let key = username_key(username);
+        let user = self.users.remove(&key)?;
+        self.emails.remove(&email_key(&user.email));
+        self.credentials.remove(&key);
+        Some(user)
+    }
+
+    /// The user's password and record of signing in, if a password is set.
+    pub fn credential(&self, username: &str) -> Option<&Credential> {
+        self.credentials.get(&username_key(username))
+    }
+
+    /// Sets or replaces the user's password, hashed at `params()`. A new password lifts any
+    /// lock.
+    pub fn set_password(&mut self, username: &str, password: &str) -> Result<(), RegistryError> {
+        let key = username_key(username);
+        if !self.users.contains_key(&key) {
+            return Err(RegistryError::UnknownUser(username.to_string()));
+        }
+        check_password(password).map_err(|e| ValidationErrors {
+            errors: vec![FieldError::Password(e)],
+        })?;
+        let hash = PasswordHash::new(password, self.params);
+        match self.credentials.get_mut(&key) {
+            Some(credential) => credential.set_hash(hash),
+            None => {
+                self.credentials.insert(key, Credential::new(hash));
+            }
+        }
+        Ok(())
+    }
+
+    /// Lifts a lock on the user's account and forgets their failed attempts.
+    pub fn unlock(&mut self, username: &str) -> Result<(), RegistryError> {
+        let key = username_key(username);
+        if !self.users.contains_key(&key) {
+            return Err(RegistryError::UnknownUser(username.to_string()));
+        }
+        if let Some(credential) = self.credentials.get_mut(&key) {
+            credential.unlock();
+        }
+        Ok(())
+    }
+
+    /// Checks the user's password at `now`. On success `sign_in_count` goes up by one (it
+    /// starts at 1, as `build_user` counts registering as the first time) and the time is
+    /// recorded. A wrong password counts as a failed attempt, and enough of them in a row lock
+    /// the account as `lockout()` says. A deactivated user cannot sign in even with the right
+    /// password, though that is not a failed attempt either.
+    pub fn sign_in(
+        &mut self,
+        username: &str,
+        password: &str,
+        now: SystemTime,
+    ) -> Result<&User, SignInError> {
+        let key = username_key(username);
+        let (Some(user), Some(credential)) =
+            (self.users.get_mut(&key), self.credentials.get_mut(&key))
+        else {
+            // as slow as a wrong password, so that the time taken does not tell either
+            PasswordHash::dummy(self.params).verify(password);
+            return Err(SignInError::InvalidCredentials);
+        };
+        credential.check(password, now, &self.lockout)?;
+        if !user.active {
+            return Err(SignInError::Inactive);
+        }
+        credential.record_sign_in(now);
+        user.sign_in_count = user.sign_in_count.saturating_add(1);
+        Ok(user)
+    }
+}
@@ -0,0 +1,424 @@
+//! Saving a `UserRegistry` to a file and reading it back.
+//!
+//! The file is text: a line giving the version of the format, then one line per user, each
+//! field written `name=value`:
+//!
+//! ```text
+//! version 2
+//! user username=corro email=corro@example.com sign_in_count=1 active=false
+//! user username=ferris email=ferris@example.com sign_in_count=3 active=true password=$scrypt$ln=15,r=8,p=1$...$... failed_attempts=0 last_sign_in=1700000000.000000000
+//! ```
+//!
+//! Users with a password also have `failed_attempts` and whichever of `locked_until`,
+//! `last_sign_in` and `last_failed_sign_in` are set, as seconds and nanoseconds since the Unix
+//! epoch. Blank lines and lines starting with `#` are skipped. The registry's `Params` and
+//! `Lockout` are settings rather than data and are not saved.
+//!
+//! A file in an older version is migrated line by line as it is read; the next save writes
+//! the current version. Version 1 is the first `User`, whose count was spelled
+//! `sign_in_acount`. Version 2 spells it `sign_in_count` and adds passwords.
+//!
+//! `load` refuses a file with anything wrong in it. `inspect_store` reads what it can and
+//! says what it had to leave out, and `repair_store` then writes that back.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::UserRegistry;
+use crate::atomic::{leftover_temp_files, write_atomic};
+use crate::credentials::{Credential, PasswordHash};
+use crate::structs::User;
+use crate::timestamp::{format_timestamp, parse_timestamp};
+
+/// The version `save` writes.
+pub const STORE_VERSION: u32 = 2;
+
+/// `MIGRATIONS[i]` brings a line of version `i + 1` up to version `i + 2`.
+const MIGRATIONS: [fn(&mut Record); STORE_VERSION as usize - 1] = [fix_sign_in_count];
+
+fn fix_sign_in_count(record: &mut Record) {
+    record.rename("sign_in_acount", "sign_in_count");
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    /// The file was written by a newer version of this crate, or its version line is wrong.
+    UnsupportedVersion(u32),
+    /// A line of the file could not be understood, or holds a user the registry would not
+    /// take.
+    Parse {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "user store: {}", e),
+            StoreError::UnsupportedVersion(version) => write!(
+                f,
+                "user store version {} is not supported (1 to {})",
+                version, STORE_VERSION
+            ),
+            StoreError::Parse { line, reason } => {
+                write!(f, "user store line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Io(e) => Some(e),
+            StoreError::UnsupportedVersion(_) | StoreError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+/// What `read_store` leaves out because of a problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreFix {
+    /// The whole line, and the user on it if there is one.
+    SkipLine,
+    /// The password and the record of signing in with it; the user must be given a new one.
+    DropPassword,
+    /// One field nothing reads, named here.
+    DropField(String),
+}
+
+impl fmt::Display for StoreFix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreFix::SkipLine => write!(f, "line skipped"),
+            StoreFix::DropPassword => write!(f, "password dropped"),
+            StoreFix::DropField(name) => write!(f, "field {} dropped", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreProblem {
+    pub line: usize,
+    pub reason: String,
+    pub fix: StoreFix,
+}
+
+impl fmt::Display for StoreProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({})", self.line, self.reason, self.fix)
+    }
+}
+
+/// What reading a store found.
+#[derive(Debug, Clone)]
+pub struct StoreReport {
+    /// The version the file is in.
+    pub version: u32,
+    /// Every user that could be read, in the current version.
+    pub registry: UserRegistry,
+    /// What is wrong with the file, in its order.
+    pub problems: Vec<StoreProblem>,
+    /// Temporary files left next to the store by saves that did not finish. Only
+    /// `inspect_store` looks for them.
+    pub temp_files: Vec<PathBuf>,
+}
+
+impl StoreReport {
+    pub fn needs_migration(&self) -> bool {
+        self.version < STORE_VERSION
+    }
+
+    /// Whether `repair_store` would leave the file as it is.
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty() && !self.needs_migration() && self.temp_files.is_empty()
+    }
+}
+
+/// The fields of one `user` line, in the order they were written.
+#[derive(Debug)]
+struct Record {
+    line: usize,
+    fields: Vec<(String, String)>,
+}
+
+impl Record {
+    fn rename(&mut self, from: &str, to: &str) {
+        for (name, _) in &mut self.fields {
+            if name == from {
+                *name = to.to_string();
+            }
+        }
+    }
+
+    fn take(&mut self, name: &str) -> Option<String> {
+        let i = self.fields.iter().position(|(n, _)| n == name)?;
+        Some(self.fields.remove(i).1)
+    }
+
+    fn required(&mut self, name: &str) -> Result<String, String> {
+        self.take(name).ok_or_else(|| format!("no {}", name))
+    }
+}
+
+fn parse_record(line: usize, text: &str) -> Result<Record, String> {
+    let mut words = text.split_whitespace();
+    match words.next() {
+        Some("user") => {}
+        Some(keyword) => return Err(format!("unknown entry {:?}", keyword)),
+        None => unreachable!("blank lines are skipped"),
+    }
+    let mut record = Record {
+        line,
+        fields: Vec::new(),
+    };
+    for word in words {
+        let (name, value) = word
+            .split_once('=')
+            .ok_or_else(|| format!("field {:?} has no value", word))?;
+        if record.fields.iter().any(|(n, _)| n == name) {
+            return Err(format!("field {} given twice", name));
+        }
+        record.fields.push((name.to_string(), value.to_string()));
+    }
+    Ok(record)
+}
+
+fn parse_version(text: &str) -> Option<u32> {
+    let version = text.strip_prefix("version ")?.trim();
+    if !version.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    version.parse().ok()
+}
+
+/// `secs.nanos` since the Unix epoch.
+fn parse_time(name: &str, value: &str) -> Result<SystemTime, String> {
+    parse_timestamp(value).ok_or_else(|| format!("{} is not a time: {:?}", name, value))
+}
+
+fn user_fields(record: &mut Record) -> Result<User, String> {
+    let username = record.required("username")?;
+    let email = record.required("email")?;
+    let count = record.required("sign_in_count")?;
+    let sign_in_count = count
+        .parse()
+        .map_err(|_| format!("sign_in_count is not a number: {:?}", count))?;
+    let active = match record.required("active")?.as_str() {
+        "true" => true,
+        "false" => false,
+        other => return Err(format!("active is not true or false: {:?}", other)),
+    };
+    Ok(User {
+        username,
+        email,
+        sign_in_count,
+        active,
+    })
+}
+
+/// The credential on the line, if it has a password. Every field of one is taken even when
+/// an earlier one is wrong, so that none is left over as unknown.
+fn credential_fields(record: &mut Record) -> Result<Option<Credential>, String> {
+    let password = record.take("password");
+    let failed_attempts = record.take("failed_attempts");
+    let times = ["locked_until", "last_sign_in", "last_failed_sign_in"].map(|name| {
+        record
+            .take(name)
+            .map(|value| parse_time(name, &value))
+            .transpose()
+    });
+    let Some(password) = password else {
+        if failed_attempts.is_some() || times.iter().any(|time| !matches!(time, Ok(None))) {
+            return Err("a record of signing in but no password".to_string());
+        }
+        return Ok(None);
+    };
+    let hash: PasswordHash = password.parse().map_err(|e| format!("{}", e))?;
+    let failed_attempts = match failed_attempts {
+        Some(count) => count
+            .parse()
+            .map_err(|_| format!("failed_attempts is not a number: {:?}", count))?,
+        None => 0,
+    };
+    let [locked_until, last_sign_in, last_failed_sign_in] = times;
+    Ok(Some(Credential::restore(
+        hash,
+        failed_attempts,
+        locked_until?,
+        last_sign_in?,
+        last_failed_sign_in?,
+    )))
+}
+
+/// Adds the user on the line to `registry`, or says why not.
+fn add_record(registry: &mut UserRegistry, mut record: Record, problems: &mut Vec<StoreProblem>) {
+    let line = record.line;
+    let problem = |reason: String, fix: StoreFix| StoreProblem { line, reason, fix };
+    let user = match user_fields(&mut record) {
+        Ok(user) => user,
+        Err(reason) => return problems.push(problem(reason, StoreFix::SkipLine)),
+    };
+    let credential = credential_fields(&mut record).unwrap_or_else(|reason| {
+        problems.push(problem(reason, StoreFix::DropPassword));
+        None
+    });
+    for (name, _) in record.fields {
+        let reason = format!("unknown field {:?}", name);
+        problems.push(problem(reason, StoreFix::DropField(name)));
+    }
+    if let Err(errors) = registry.insert(user, credential) {
+        problems.push(problem(errors.to_string(), StoreFix::SkipLine));
+    }
+}
+
+/// Reads a store from `text`, keeping every user it can and migrating them to the current
+/// version. Only a missing or unsupported version is an error; everything else wrong is
+/// reported in `problems` and left out.
+pub fn read_store(text: &str) -> Result<StoreReport, StoreError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+    let version = match lines.next() {
+        Some((line, text)) => parse_version(text).ok_or_else(|| StoreError::Parse {
+            line,
+            reason: format!("expected the version line first, found {:?}", text),
+        })?,
+        None => {
+            return Err(StoreError::Parse {
+                line: text.lines().count(),
+                reason: "no version line".to_string(),
+            })
+        }
+    };
+    if !(1..=STORE_VERSION).contains(&version) {
+        return Err(StoreError::UnsupportedVersion(version));
+    }
+
+    let mut registry = UserRegistry::new();
+    let mut problems = Vec::new();
+    for (line, text) in lines {
+        let mut record = match parse_record(line, text) {
+            Ok(record) => record,
+            Err(reason) => {
+                problems.push(StoreProblem {
+                    line,
+                    reason,
+                    fix: StoreFix::SkipLine,
+                });
+                continue;
+            }
+        };
+        for migrate in &MIGRATIONS[version as usize - 1..] {
+            migrate(&mut record);
+        }
+        add_record(&mut registry, record, &mut problems);
+    }
+    Ok(StoreReport {
+        version,
+        registry,
+        problems,
+        temp_files: Vec::new(),
+    })
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = PathBuf::from(path);
+    path.as_mut_os_string().push(suffix);
+    path
+}
+
+/// Reads the store at `path` as `read_store` does, and notes any temporary files left next to
+/// it.
+pub fn inspect_store(path: impl AsRef<Path>) -> Result<StoreReport, StoreError> {
+    let path = path.as_ref();
+    let mut report = read_store(&fs::read_to_string(path)?)?;
+    report.temp_files = leftover_temp_files(path)?;
+    Ok(report)
+}
+
+/// Reads the store at `path` as `inspect_store` does and, unless it is clean, removes the
+/// temporary files and writes back what could be read, in the current version. The file as it
+/// was is copied to `path` with `.bak` added first.
+pub fn repair_store(path: impl AsRef<Path>) -> Result<StoreReport, StoreError> {
+    let path = path.as_ref();
+    let report = inspect_store(path)?;
+    for temp in &report.temp_files {
+        fs::remove_file(temp)?;
+    }
+    if !report.problems.is_empty() || report.needs_migration() {
+        fs::copy(path, with_suffix(path, ".bak"))?;
+        report.registry.save(path)?;
+    }
+    Ok(report)
+}
+
+impl UserRegistry {
+    /// Every user in the current version of the store format, by username.
+    pub fn to_store(&self) -> String {
+        let mut text = format!("version {}\n", STORE_VERSION);
+        for (key, user) in &self.users {
+            text.push_str(&format!(
+                "user username={} email={} sign_in_count={} active={}",
+                user.username, user.email, user.sign_in_count, user.active
+            ));
+            if let Some(credential) = self.credentials.get(key) {
+                text.push_str(&format!(
+                    " password={} failed_attempts={}",
+                    credential.hash(),
+                    credential.failed_attempts()
+                ));
+                let times = [
+                    ("locked_until", credential.lock_expiry()),
+                    ("last_sign_in", credential.last_sign_in()),
+                    ("last_failed_sign_in", credential.last_failed_sign_in()),
+                ];
+                for (name, time) in times {
+                    if let Some(time) = time {
+                        text.push_str(&format!(" {}={}", name, format_timestamp(time)));
+                    }
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Reads what `to_store` writes, or an older version of it. Anything `read_store` would
+    /// have to leave out is an error.
+    pub fn from_store(text: &str) -> Result<UserRegistry, StoreError> {
+        let report = read_store(text)?;
+        match report.problems.into_iter().next() {
+            Some(problem) => Err(StoreError::Parse {
+                line: problem.line,
+                reason: problem.reason,
+            }),
+            None => Ok(report.registry),
+        }
+    }
+
+    /// Writes `to_store` to `path`. A save that does not finish leaves the old file as it was,
+    /// and possibly a temporary file that `inspect_store` reports.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), StoreError> {
+        write_atomic(path.as_ref(), self.to_store().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<UserRegistry, StoreError> {
+        UserRegistry::from_store(&fs::read_to_string(path)?)
+    }
+}
@@ -0,0 +1,518 @@
+//! A vending machine that takes `Coin`s.
+//!
+//! The machine is `Idle` until a coin goes in, then `Paying` while it holds the customer's
+//! credit. The coins inserted stay apart from the till until a sale, so a refund gives back
+//! exactly those coins. Selecting a slot with enough credit sells the product and pays the
+//! change out of the till, which by then includes the customer's coins; if no coins in it add
+//! up to the change the sale is refused and nothing moves. `Service` mode is for the operator
+//! to restock slots, load coins for change and collect the cash, and is only open while no
+//! customer has credit.
+//!
+//! Every event goes through `VendingMachine::handle`, like `Message::call` on a `DrawState`:
+//! it either does what was asked or fails and leaves the machine as it was. Sales, refused
+//! sales, refunds and service work are written to a log.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::change::CoinInventory;
+use crate::enums::{Coin, CoinKind};
+
+/// A row of one product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot {
+    /// What the customer types, like `A1`; matched without regard to case.
+    pub code: String,
+    pub product: String,
+    /// In cents.
+    pub price: u32,
+    /// How many are left.
+    pub count: u32,
+}
+
+/// Where the machine is, from `VendingMachine::state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Idle,
+    /// Holding `credit` cents of the customer's coins.
+    Paying {
+        credit: u32,
+    },
+    Service,
+}
+
+/// Something done to the machine, by a customer or by the operator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Insert(Coin),
+    Select(String),
+    Refund,
+    OpenService,
+    CloseService,
+    Restock { slot: String, count: u32 },
+    Load { coin: Coin, count: u32 },
+    Collect,
+}
+
+/// What a successful `VendingMachine::handle` did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The coin was taken; the credit is now `credit` cents.
+    Credited {
+        credit: u32,
+    },
+    Sold(Sale),
+    /// The coins inserted, given back; empty if there were none.
+    Refunded(Vec<Coin>),
+    ServiceOpened,
+    ServiceClosed,
+    Restocked {
+        slot: String,
+        count: u32,
+    },
+    Loaded {
+        coin: Coin,
+        count: u32,
+    },
+    /// Every coin from the till, counted by kind.
+    Collected(CoinInventory),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sale {
+    pub slot: String,
+    pub product: String,
+    pub price: u32,
+    /// The coins the customer put in.
+    pub paid: Vec<Coin>,
+    /// The coins given back, largest first.
+    pub change: Vec<Coin>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VendError {
+    /// The machine does not take this kind of coin; it drops back out.
+    CoinRejected(Coin),
+    UnknownSlot(String),
+    SoldOut(String),
+    /// Restocking would take the slot past `u32::MAX` items.
+    SlotFull {
+        slot: String,
+        count: u32,
+    },
+    /// Loading or a sale would take the till past `u32::MAX` of one coin.
+    TillFull {
+        coin: Coin,
+        count: u32,
+    },
+    InsufficientCredit {
+        price: u32,
+        credit: u32,
+    },
+    /// The till cannot pay `change` cents exactly.
+    CannotMakeChange {
+        change: u32,
+    },
+    /// A customer event while the machine is being serviced.
+    InService,
+    /// An operator event outside of service mode.
+    NotInService,
+    /// Service mode cannot open while a customer has credit.
+    CreditPending {
+        credit: u32,
+    },
+}
+
+impl fmt::Display for VendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VendError::CoinRejected(coin) => write!(f, "{} is not accepted", coin),
+            VendError::UnknownSlot(slot) => write!(f, "there is no slot {}", slot),
+            VendError::SoldOut(slot) => write!(f, "slot {} is sold out", slot),
+            VendError::SlotFull { slot, count } => {
+                write!(f, "slot {} cannot take {} more", slot, count)
+            }
+            VendError::TillFull { coin, count } => {
+                write!(f, "the till cannot take {} more {}", count, coin)
+            }
+            VendError::InsufficientCredit { price, credit } => write!(
+                f,
+                "the price is {} but the credit is only {}",
+                dollars(*price),
+                dollars(*credit)
+            ),
+            VendError::CannotMakeChange { change } => {
+                write!(
+                    f,
+                    "cannot give {} in change, exact change only",
+                    dollars(*change)
+                )
+            }
+            VendError::InService => write!(f, "the machine is being serviced"),
+            VendError::NotInService => write!(f, "the machine is not in service mode"),
+            VendError::CreditPending { credit } => {
+                write!(f, "a customer has {} of credit", dollars(*credit))
+            }
+        }
+    }
+}
+
+impl Error for VendError {}
+
+/// One line of the log. `seq` counts up from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub seq: u64,
+    pub kind: TransactionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionKind {
+    Sale(Sale),
+    /// A selection that failed, and why.
+    Refused {
+        slot: String,
+        error: VendError,
+    },
+    Refund(Vec<Coin>),
+    Restock {
+        slot: String,
+        count: u32,
+    },
+    Load {
+        coin: Coin,
+        count: u32,
+    },
+    Collect(CoinInventory),
+}
+
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>4}  ", self.seq)?;
+        match &self.kind {
+            TransactionKind::Sale(sale) => write!(
+                f,
+                "sold {} from {} for {}, paid {}, change {}",
+                sale.product,
+                sale.slot,
+                dollars(sale.price),
+                coins(&sale.paid),
+                coins(&sale.change)
+            ),
+            TransactionKind::Refused { slot, error } => {
+                write!(f, "refused {}: {}", slot, error)
+            }
+            TransactionKind::Refund(refund) => write!(f, "refunded {}", coins(refund)),
+            TransactionKind::Restock { slot, count } => {
+                write!(f, "restocked {} with {}", slot, count)
+            }
+            TransactionKind::Load { coin, count } => write!(f, "loaded {} x {}", count, coin),
+            TransactionKind::Collect(collected) => write!(f, "collected {}", till(collected)),
+        }
+    }
+}
+
+/// Cents as dollars, like `$1.25`.
+pub fn dollars(cents: impl Into<u64>) -> String {
+    let cents = cents.into();
+    format!("${}.{:02}", cents / 100, cents % 100)
+}
+
+/// Coins and what they add up to, like `[quarter alaska, dime] ($0.35)`.
+fn coins(coins: &[Coin]) -> String {
+    let names: Vec<String> = coins.iter().map(Coin::to_string).collect();
+    let total: u32 = coins
+        .iter()
+        .map(|coin| u32::from(coin.kind().value_in_cents()))
+        .sum();
+    format!("[{}] ({})", names.join(", "), dollars(total))
+}
+
+/// Coins counted by kind and what they add up to, like `[2 x quarter alaska, dime] ($0.60)`.
+fn till(till: &CoinInventory) -> String {
+    let names: Vec<String> = till
+        .iter()
+        .map(|(coin, count)| match count {
+            1 => coin.to_string(),
+            _ => format!("{} x {}", count, coin),
+        })
+        .collect();
+    format!("[{}] ({})", names.join(", "), dollars(till.total_cents()))
+}
+
+/// Sets up a `VendingMachine`, from `VendingMachine::builder`.
+#[derive(Debug, Clone, Default)]
+pub struct VendingMachineBuilder {
+    slots: Vec<Slot>,
+    till: CoinInventory,
+    rejected: Vec<CoinKind>,
+}
+
+impl VendingMachineBuilder {
+    /// A slot of `count` of `product` at `price` cents. A second slot with the same code
+    /// replaces the first.
+    pub fn slot(mut self, code: &str, product: &str, price: u32, count: u32) -> Self {
+        self.slots
+            .retain(|slot| !slot.code.eq_ignore_ascii_case(code));
+        self.slots.push(Slot {
+            code: code.to_string(),
+            product: product.to_string(),
+            price,
+            count,
+        });
+        self
+    }
+
+    /// Puts `count` of `coin` in the till to start with.
+    pub fn coins(mut self, coin: Coin, count: u32) -> Self {
+        self.till.add_many(coin, count);
+        self
+    }
+
+    /// Refuses coins of this kind, as many machines refuse pennies.
+    pub fn reject(mut self, kind: CoinKind) -> Self {
+        if !self.rejected.contains(&kind) {
+            self.rejected.push(kind);
+        }
+        self
+    }
+
+    pub fn build(self) -> VendingMachine {
+        VendingMachine {
+            slots: self.slots,
+            till: self.till,
+            rejected: self.rejected,
+            inserted: Vec::new(),
+            service: false,
+            log: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VendingMachine {
+    slots: Vec<Slot>,
+    /// Coins for change, and the takings.
+    till: CoinInventory,
+    rejected: Vec<CoinKind>,
+    /// The customer's coins, until they are spent or refunded.
+    inserted: Vec<Coin>,
+    service: bool,
+    log: Vec<Transaction>,
+}
+
+impl VendingMachine {
+    pub fn builder() -> VendingMachineBuilder {
+        VendingMachineBuilder::default()
+    }
+
+    pub fn state(&self) -> State {
+        if self.service {
+            State::Service
+        } else if self.inserted.is_empty() {
+            State::Idle
+        } else {
+            State::Paying {
+                credit: self.credit(),
+            }
+        }
+    }
+
+    /// The customer's credit in cents.
+    pub fn credit(&self) -> u32 {
+        self.inserted
+            .iter()
+            .map(|coin| u32::from(coin.kind().value_in_cents()))
+            .sum()
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    pub fn slot(&self, code: &str) -> Option<&Slot> {
+        self.slots
+            .iter()
+            .find(|slot| slot.code.eq_ignore_ascii_case(code))
+    }
+
+    /// The coins in the till, not counting the customer's.
+    pub fn till(&self) -> &CoinInventory {
+        &self.till
+    }
+
+    pub fn accepts(&self, coin: Coin) -> bool {
+        !self.rejected.contains(&coin.kind())
+    }
+
+    pub fn log(&self) -> &[Transaction] {
+        &self.log
+    }
+
+    /// Does what `event` asks; on error the machine is left as it was, apart from a refused
+    /// selection going into the log.
+    pub fn handle(&mut self, event: Event) -> Result<Outcome, VendError> {
+        match event {
+            Event::Insert(coin) => self.insert(coin).map(|credit| Outcome::Credited { credit }),
+            Event::Select(slot) => self.select(&slot).map(Outcome::Sold),
+            Event::Refund => self.refund().map(Outcome::Refunded),
+            Event::OpenService => self.open_service().map(|()| Outcome::ServiceOpened),
+            Event::CloseService => self.close_service().map(|()| Outcome::ServiceClosed),
+            Event::Restock { slot, count } => self
+                .restock(&slot, count)
+                .map(|()| Outcome::Restocked { slot, count }),
+            Event::Load { coin, count } => self
+                .load(coin, count)
+                .map(|()| Outcome::Loaded { coin, count }),
+            Event::Collect => self.collect().map(Outcome::Collected),
+        }
+    }
+
+    /// Takes a coin and returns the credit.
+    pub fn insert(&mut self, coin: Coin) -> Result<u32, VendError> {
+        if self.service {
+            return Err(VendError::InService);
+        }
+        if !self.accepts(coin) {
+            return Err(VendError::CoinRejected(coin));
+        }
+        self.inserted.push(coin);
+        Ok(self.credit())
+    }
+
+    /// Sells the product in `slot` for the credit and gives the change.
+    pub fn select(&mut self, slot: &str) -> Result<Sale, VendError> {
+        if self.service {
+            return Err(VendError::InService);
+        }
+        let sale = self.try_sell(slot);
+        if let Err(error) = &sale {
+            self.record(TransactionKind::Refused {
+                slot: slot.to_string(),
+                error: error.clone(),
+            });
+        }
+        sale
+    }
+
+    fn try_sell(&mut self, code: &str) -> Result<Sale, VendError> {
+        let credit = self.credit();
+        let at = self
+            .slots
+            .iter()
+            .position(|slot| slot.code.eq_ignore_ascii_case(code))
+            .ok_or_else(|| VendError::UnknownSlot(code.to_string()))?;
+        let slot = &self.slots[at];
+        if slot.count == 0 {
+            return Err(VendError::SoldOut(slot.code.clone()));
+        }
+        if credit < slot.price {
+            return Err(VendError::InsufficientCredit {
+                price: slot.price,
+                credit,
+            });
+        }
+        let due = credit - slot.price;
+        let mut till = self.till.clone();
+        for &coin in &self.inserted {
+            till.checked_add_many(coin, 1)
+                .ok_or(VendError::TillFull { coin, count: 1 })?;
+        }
+        let change = till
+            .make_change(due)
+            .map_err(|_| VendError::CannotMakeChange { change: due })?;
+
+        self.till = till;
+        let slot = &mut self.slots[at];
+        slot.count -= 1;
+        let sale = Sale {
+            slot: slot.code.clone(),
+            product: slot.product.clone(),
+            price: slot.price,
+            paid: std::mem::take(&mut self.inserted),
+            change,
+        };
+        self.record(TransactionKind::Sale(sale.clone()));
+        Ok(sale)
+    }
+
+    /// Gives back the coins inserted, exactly as they went in.
+    pub fn refund(&mut self) -> Result<Vec<Coin>, VendError> {
+        if self.service {
+            return Err(VendError::InService);
+        }
+        let coins = std::mem::take(&mut self.inserted);
+        if !coins.is_empty() {
+            self.record(TransactionKind::Refund(coins.clone()));
+        }
+        Ok(coins)
+    }
+
+    /// Opening it when it is already open does nothing, and so does closing it when closed.
+    pub fn open_service(&mut self) -> Result<(), VendError> {
+        if self.service {
+            return Ok(());
+        }
+        if !self.inserted.is_empty() {
+            return Err(VendError::CreditPending {
+                credit: self.credit(),
+            });
+        }
+        self.service = true;
+        Ok(())
+    }
+
+    pub fn close_service(&mut self) -> Result<(), VendError> {
+        self.service = false;
+        Ok(())
+    }
+
+    /// Adds `count` to a slot. A slot that would overflow is left as it was.
+    pub fn restock(&mut self, code: &str, count: u32) -> Result<(), VendError> {
+        if !self.service {
+            return Err(VendError::NotInService);
+        }
+        let slot = self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.code.eq_ignore_ascii_case(code))
+            .ok_or_else(|| VendError::UnknownSlot(code.to_string()))?;
+        slot.count = slot
+            .count
+            .checked_add(count)
+            .ok_or_else(|| VendError::SlotFull {
+                slot: slot.code.clone(),
+                count,
+            })?;
+        let slot = slot.code.clone();
+        self.record(TransactionKind::Restock { slot, count });
+        Ok(())
+    }
+
+    /// Puts coins in the till for change. A till that would overflow is left as it was.
+    pub fn load(&mut self, coin: Coin, count: u32) -> Result<(), VendError> {
+        if !self.service {
+            return Err(VendError::NotInService);
+        }
+        self.till
+            .checked_add_many(coin, count)
+            .ok_or(VendError::TillFull { coin, count })?;
+        self.record(TransactionKind::Load { coin, count });
+        Ok(())
+    }
+
+    /// Empties the till and returns what was in it.
+    pub fn collect(&mut self) -> Result<CoinInventory, VendError> {
+        if !self.service {
+            return Err(VendError::NotInService);
+        }
+        let collected = std::mem::take(&mut self.till);
+        self.record(TransactionKind::Collect(collected.clone()));
+        Ok(collected)
+    }
+
+    fn record(&mut self, kind: TransactionKind) {
+        let seq = self.log.len() as u64 + 1;
+        self.log.push(Transaction { seq, kind });
+    }
+}
@@ -0,0 +1,163 @@
+//! Parsing and printing IPv4 and IPv6 addresses, and the errors for text that is neither.
+
+mod common;
+
+use std::net;
+
+use rust_begin::enums::{IpAddr, Ipv4Addr, Ipv6Addr};
+use rust_begin::net::{AddrParseError, GroupError, OctetError};
+
+use common::Rng;
+
+impl Rng {
+    /// Mostly zero groups, so that runs of them of every length and place turn up.
+    fn segments(&mut self) -> [u16; 8] {
+        let mut segments = [0; 8];
+        for segment in &mut segments {
+            *segment = match self.below(3) {
+                0 => self.next() as u16,
+                1 => self.below(16) as u16,
+                _ => 0,
+            };
+        }
+        segments
+    }
+}
+
+fn v6(text: &str) -> Ipv6Addr {
+    text.parse().unwrap()
+}
+
+fn v6_error(text: &str) -> AddrParseError {
+    text.parse::<Ipv6Addr>().unwrap_err()
+}
+
+#[test]
+fn ipv4_round_trips() {
+    for text in ["0.0.0.0", "192.0.2.1", "255.255.255.255", "10.0.0.10"] {
+        let ip: Ipv4Addr = text.parse().unwrap();
+        assert_eq!(ip.to_string(), text);
+        assert_eq!(text.parse::<IpAddr>().unwrap().to_string(), text);
+    }
+    assert_eq!(
+        "192.0.2.1".parse::<Ipv4Addr>(),
+        Ok(Ipv4Addr::new(192, 0, 2, 1))
+    );
+    assert_eq!("1.2.3.4".parse::<IpAddr>(), Ok(IpAddr::V4(1, 2, 3, 4)));
+}
+
+#[test]
+fn ipv6_canonical_forms() {
+    for (text, canonical) in [
+        ("::", "::"),
+        ("0:0:0:0:0:0:0:0", "::"),
+        ("::1", "::1"),
+        ("1::", "1::"),
+        ("2001:DB8:0:0:0:0:0:1", "2001:db8::1"),
+        (
+            "2001:0db8:0000:0000:0000:ff00:0042:8329",
+            "2001:db8::ff00:42:8329",
+        ),
+        // a single zero group is not compressed
+        ("2001:db8:0:1:1:1:1:1", "2001:db8:0:1:1:1:1:1"),
+        ("2001:db8::1:1:1:1:1", "2001:db8:0:1:1:1:1:1"),
+        // the longest run wins, and the first of two equal runs
+        ("1:0:0:1:0:0:0:1", "1:0:0:1::1"),
+        ("1:0:0:1:1:0:0:1", "1::1:1:0:0:1"),
+        ("0:0:1:1:1:1:0:0", "::1:1:1:1:0:0"),
+        ("1:0:0:0:1:0:0:0", "1::1:0:0:0"),
+        // embedded IPv4: only a mapped address prints it back that way
+        ("::ffff:192.0.2.1", "::ffff:192.0.2.1"),
+        ("::FFFF:c000:0201", "::ffff:192.0.2.1"),
+        ("64:ff9b::192.0.2.1", "64:ff9b::c000:201"),
+        ("::192.0.2.1", "::c000:201"),
+        ("1:2:3:4:5:6:1.2.3.4", "1:2:3:4:5:6:102:304"),
+        ("fe80::1%eth0", "fe80::1%eth0"),
+        ("fe80:0::1%1", "fe80::1%1"),
+    ] {
+        let ip = v6(text);
+        assert_eq!(ip.to_string(), canonical, "{}", text);
+        assert_eq!(v6(canonical), ip, "{}", canonical);
+        assert_eq!(
+            text.parse::<IpAddr>().unwrap().to_string(),
+            canonical,
+            "{}",
+            text
+        );
+    }
+    assert_eq!(v6("::"), Ipv6Addr::from_bits(0));
+    assert_eq!(v6("fe80::1%eth0").zone(), Some("eth0"));
+    assert_eq!(v6("fe80::1%eth0").without_zone(), v6("fe80::1"));
+}
+
+#[test]
+fn ipv6_prints_like_std() {
+    let mut rng = Rng(0x1b6a);
+    for _ in 0..5000 {
+        let segments = rng.segments();
+        let ip = Ipv6Addr::from_segments(segments);
+        let [a, b, c, d, e, f, g, h] = segments;
+        let std = net::Ipv6Addr::new(a, b, c, d, e, f, g, h);
+        let text = ip.to_string();
+        assert_eq!(text, std.to_string(), "{:?}", segments);
+        assert_eq!(v6(&text), ip);
+        assert_eq!(v6(&std.to_string()), ip);
+    }
+}
+
+#[test]
+fn width_and_padding() {
+    assert_eq!(format!("{:>12}", v6("::1")), "         ::1");
+    assert_eq!(format!("{:-<10}|", v6("fe80::1")), "fe80::1---|");
+    assert_eq!(format!("{:^9}", v6("::")), "   ::    ");
+    assert_eq!(format!("{:>14}", v6("fe80::1%lo")), "    fe80::1%lo");
+    assert_eq!(format!("{:3}", v6("2001:db8::1")), "2001:db8::1");
+    assert_eq!(format!("{:.4}", v6("2001:db8::1")), "2001");
+    assert_eq!(format!("{:>10}", Ipv4Addr::new(10, 0, 0, 1)), "  10.0.0.1");
+    assert_eq!(format!("{:<9}|", IpAddr::V4(10, 0, 0, 1)), "10.0.0.1 |");
+    assert_eq!(format!("{:>5}", IpAddr::V6(v6("::1"))), "  ::1");
+}
+
+#[test]
+fn bad_addresses() {
+    use AddrParseError::*;
+    assert_eq!("".parse::<Ipv4Addr>(), Err(Empty));
+    assert_eq!("1.2.3".parse::<Ipv4Addr>(), Err(OctetCount(3)));
+    assert_eq!(
+        "1.2.03.4".parse::<Ipv4Addr>(),
+        Err(InvalidOctet {
+            octet: 3,
+            text: "03".to_string(),
+            reason: OctetError::LeadingZero
+        })
+    );
+    assert_eq!(
+        "1.2.3.256".parse::<Ipv4Addr>().unwrap_err().to_string(),
+        "invalid IPv4 octet 4 (\"256\"): octet is greater than 255"
+    );
+
+    assert_eq!(v6_error(""), Empty);
+    assert_eq!(v6_error("1:2:3:4:5:6:7"), GroupCount(7));
+    assert_eq!(v6_error("1:2:3:4:5:6:7:8:9"), GroupCount(9));
+    assert_eq!(v6_error("1:2:3:4::5:6:7:8"), GroupCount(8));
+    assert_eq!(v6_error("1::2::3"), MultipleCompressions);
+    assert_eq!(v6_error("::1.2.3.4:1"), MisplacedIpv4);
+    assert_eq!(
+        v6_error("1:12345::"),
+        InvalidGroup {
+            group: 2,
+            text: "12345".to_string(),
+            reason: GroupError::TooLong
+        }
+    );
+    assert!(matches!(
+        v6_error("1:g::"),
+        InvalidGroup {
+            reason: GroupError::NotHex,
+            ..
+        }
+    ));
+    assert_eq!(v6_error("fe80::1%"), InvalidZone(String::new()));
+    assert_eq!(v6_error("fe80::1%a b"), InvalidZone("a b".to_string()));
+    assert_eq!(v6_error("1::2::3").to_string(), "`::` may only appear once");
+}
@@ -0,0 +1,272 @@
+//! The message bus: filters, overflow policies, shutting down with `Quit`, and publishers on
+//! many threads.
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+use rust_begin::enums::{Message, MessageKind};
+use rust_begin::message::{Bus, BusClosed, Overflow, SubscriberStats};
+
+fn moved(x: i32) -> Message {
+    Message::Move { x, y: 0 }
+}
+
+#[test]
+fn subscribers_only_get_the_kinds_they_asked_for() {
+    let bus = Bus::new();
+    let everything = bus.subscriber().subscribe();
+    let writes = bus
+        .subscriber()
+        .kinds(&[MessageKind::Write, MessageKind::Quit])
+        .subscribe();
+    assert_ne!(everything.id(), writes.id());
+
+    assert_eq!(bus.publish(moved(1)), Ok(1));
+    assert_eq!(bus.publish(Message::Write("hi".to_string())), Ok(2));
+    assert_eq!(bus.publish(Message::ChangeColor(1, 2, 3)), Ok(1));
+
+    assert_eq!(writes.try_recv(), Some(Message::Write("hi".to_string())));
+    assert_eq!(writes.try_recv(), None);
+    assert_eq!(
+        writes.stats(),
+        SubscriberStats {
+            delivered: 1,
+            filtered: 2,
+            received: 1,
+            ..SubscriberStats::default()
+        }
+    );
+    let got: Vec<Message> = std::iter::from_fn(|| everything.try_recv()).collect();
+    assert_eq!(
+        got,
+        [
+            moved(1),
+            Message::Write("hi".to_string()),
+            Message::ChangeColor(1, 2, 3)
+        ]
+    );
+}
+
+#[test]
+fn drop_oldest_keeps_the_latest() {
+    let bus = Bus::new();
+    let sub = bus
+        .subscriber()
+        .capacity(2)
+        .overflow(Overflow::DropOldest)
+        .subscribe();
+    for x in 0..5 {
+        assert_eq!(bus.publish(moved(x)), Ok(1));
+    }
+    assert_eq!(sub.stats().dropped, 3);
+    assert_eq!(sub.stats().queued, 2);
+    assert_eq!(sub.try_recv(), Some(moved(3)));
+    assert_eq!(sub.try_recv(), Some(moved(4)));
+    assert_eq!(sub.try_recv(), None);
+}
+
+#[test]
+fn drop_newest_keeps_the_earliest() {
+    let bus = Bus::new();
+    let sub = bus
+        .subscriber()
+        .capacity(2)
+        .overflow(Overflow::DropNewest)
+        .subscribe();
+    let delivered: Vec<usize> = (0..5).map(|x| bus.publish(moved(x)).unwrap()).collect();
+    assert_eq!(delivered, [1, 1, 0, 0, 0]);
+    assert_eq!(sub.stats().dropped, 3);
+    assert_eq!(sub.try_recv(), Some(moved(0)));
+    assert_eq!(sub.try_recv(), Some(moved(1)));
+    assert_eq!(sub.try_recv(), None);
+}
+
+#[test]
+fn block_waits_for_the_subscriber() {
+    let bus = Bus::new();
+    let sub = bus
+        .subscriber()
+        .capacity(1)
+        .overflow(Overflow::Block)
+        .subscribe();
+    let publisher = {
+        let bus = bus.clone();
+        thread::spawn(move || {
+            for x in 0..50 {
+                bus.publish(moved(x)).unwrap();
+            }
+            bus.publish(Message::Quit).unwrap();
+        })
+    };
+    let got: Vec<Message> = (&sub).collect();
+    publisher.join().unwrap();
+
+    let mut expected: Vec<Message> = (0..50).map(moved).collect();
+    expected.push(Message::Quit);
+    // nothing is lost, however far behind the subscriber falls
+    assert_eq!(got, expected);
+    let stats = sub.stats();
+    assert_eq!(
+        (stats.delivered, stats.received, stats.dropped),
+        (51, 51, 0)
+    );
+    assert_eq!(stats.queued, 0);
+}
+
+#[test]
+fn quit_shuts_the_bus_down() {
+    let bus = Bus::new();
+    let sub = bus.subscriber().subscribe();
+    let no_quit = bus.subscriber().kinds(&[MessageKind::Move]).subscribe();
+    bus.publish(moved(1)).unwrap();
+    assert_eq!(bus.publish(Message::Quit), Ok(1));
+    assert!(bus.is_closed());
+    assert!(sub.is_closed());
+    assert_eq!(bus.publish(moved(2)), Err(BusClosed(moved(2))));
+    assert_eq!(bus.publish(Message::Quit), Err(BusClosed(Message::Quit)));
+
+    // what was queued is still received, then the subscription ends
+    assert_eq!(sub.recv(), Some(moved(1)));
+    assert_eq!(sub.recv(), Some(Message::Quit));
+    assert_eq!(sub.recv(), None);
+    assert_eq!(no_quit.recv(), Some(moved(1)));
+    assert_eq!(no_quit.recv(), None);
+
+    let late = bus.subscriber().subscribe();
+    assert!(late.is_closed());
+    assert_eq!(late.recv(), None);
+}
+
+#[test]
+fn dropping_the_bus_or_a_subscriber() {
+    let bus = Bus::new();
+    let kept = bus.subscriber().subscribe();
+    let gone = bus.subscriber().subscribe();
+    let gone_id = gone.id();
+    drop(gone);
+    assert_eq!(bus.publish(moved(1)), Ok(1));
+    let ids: Vec<u64> = bus.stats().iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, [kept.id()]);
+    assert!(!ids.contains(&gone_id));
+
+    drop(bus);
+    assert!(kept.is_closed());
+    assert_eq!(kept.recv(), Some(moved(1)));
+    assert_eq!(kept.recv(), None);
+}
+
+#[test]
+fn many_publishers_keep_their_own_order() {
+    const THREADS: i32 = 6;
+    const EACH: i32 = 200;
+    let bus = Bus::new();
+    let subs: Vec<_> = (0..3)
+        .map(|_| bus.subscriber().capacity(8).subscribe())
+        .collect();
+    let start = Arc::new(Barrier::new(THREADS as usize));
+    let publishers: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let bus = bus.clone();
+            let start = Arc::clone(&start);
+            thread::spawn(move || {
+                start.wait();
+                for i in 0..EACH {
+                    bus.publish(Message::Move { x: t, y: i }).unwrap();
+                }
+            })
+        })
+        .collect();
+    let receivers: Vec<_> = subs
+        .into_iter()
+        .map(|sub| thread::spawn(move || (&sub).collect::<Vec<Message>>()))
+        .collect();
+    for publisher in publishers {
+        publisher.join().unwrap();
+    }
+    bus.publish(Message::Quit).unwrap();
+
+    for receiver in receivers {
+        let got = receiver.join().unwrap();
+        assert_eq!(got.len(), (THREADS * EACH) as usize + 1);
+        assert_eq!(got.last(), Some(&Message::Quit));
+        let mut next = vec![0; THREADS as usize];
+        for message in &got[..got.len() - 1] {
+            let Message::Move { x, y } = *message else {
+                panic!("unexpected {:?}", message);
+            };
+            assert_eq!(y, next[x as usize], "from publisher {}", x);
+            next[x as usize] += 1;
+        }
+    }
+}
+
+#[test]
+fn nothing_lands_behind_a_quit() {
+    for _ in 0..50 {
+        let bus = Bus::new();
+        let sub = bus.subscriber().capacity(16).subscribe();
+        let receiver = thread::spawn(move || (&sub).collect::<Vec<Message>>());
+        let start = Arc::new(Barrier::new(5));
+        let publishers: Vec<_> = (0..4)
+            .map(|t| {
+                let bus = bus.clone();
+                let start = Arc::clone(&start);
+                thread::spawn(move || {
+                    start.wait();
+                    let mut sent = 0;
+                    while bus.publish(moved(t)).is_ok() {
+                        sent += 1;
+                    }
+                    sent
+                })
+            })
+            .collect();
+        start.wait();
+        bus.publish(Message::Quit).unwrap();
+        let sent: u64 = publishers.into_iter().map(|p| p.join().unwrap()).sum();
+
+        let got = receiver.join().unwrap();
+        assert_eq!(got.last(), Some(&Message::Quit));
+        // every publish that succeeded was delivered ahead of the `Quit`
+        assert_eq!(got.len() as u64, sent + 1);
+    }
+}
+
+#[test]
+fn a_quit_behind_a_blocked_publisher_does_not_lock_the_bus() {
+    let bus = Bus::new();
+    let sub = bus
+        .subscriber()
+        .capacity(1)
+        .overflow(Overflow::Block)
+        .subscribe();
+    bus.publish(moved(0)).unwrap();
+    // one publisher waits for room, and a `Quit` waits for that publisher
+    let publisher = {
+        let bus = bus.clone();
+        thread::spawn(move || bus.publish(moved(1)))
+    };
+    while sub.stats().blocked == 0 {
+        thread::sleep(Duration::from_millis(1));
+    }
+    let quitter = {
+        let bus = bus.clone();
+        thread::spawn(move || bus.publish(Message::Quit))
+    };
+    while !bus.is_closed() {
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // meanwhile the rest of the bus still answers
+    assert_eq!(bus.publish(moved(2)), Err(BusClosed(moved(2))));
+    assert!(bus.subscriber().subscribe().is_closed());
+    assert!(!sub.is_closed());
+
+    assert_eq!(sub.recv(), Some(moved(0)));
+    assert_eq!(publisher.join().unwrap(), Ok(1));
+    assert_eq!(sub.recv(), Some(moved(1)));
+    assert_eq!(sub.recv(), Some(Message::Quit));
+    assert_eq!(quitter.join().unwrap(), Ok(1));
+    assert_eq!(sub.recv(), None);
+}
@@ -0,0 +1,268 @@
+//! Making change with canonical and non-canonical coin sets, checked against brute force.
+
+mod common;
+
+use rust_begin::change::{ChangeError, CoinInventory, Denominations, MAX_AMOUNT};
+use rust_begin::enums::{Coin, CoinKind, UsState};
+
+use common::Rng;
+
+/// Every way to pay `amount` with coins `values[i..]`, each no more than its limit.
+fn brute_force(values: &[u32], limits: &[u32], amount: u32) -> Vec<Vec<u32>> {
+    let Some((&value, rest)) = values.split_first() else {
+        return if amount == 0 { vec![vec![]] } else { vec![] };
+    };
+    let mut ways = Vec::new();
+    for count in 0..=(amount / value).min(limits[0]) {
+        for mut way in brute_force(rest, &limits[1..], amount - count * value) {
+            way.insert(0, count);
+            ways.push(way);
+        }
+    }
+    ways
+}
+
+fn fewest(ways: &[Vec<u32>]) -> Option<u32> {
+    ways.iter().map(|way| way.iter().sum()).min()
+}
+
+#[test]
+fn greedy_is_not_always_best() {
+    let coins = Denominations::new(&[1, 3, 4]).unwrap();
+    assert_eq!(coins.values(), [4, 3, 1]);
+    assert!(!coins.is_canonical());
+    assert_eq!(coins.greedy(6), Some(vec![1, 0, 2]));
+    assert_eq!(coins.optimal(6), Ok(vec![0, 2, 0]));
+    assert_eq!(coins.total(&[0, 2, 0]), 6);
+
+    // without a coin of 1 some amounts cannot be paid at all
+    let coins = Denominations::new(&[5, 3]).unwrap();
+    assert!(!coins.is_canonical());
+    assert_eq!(coins.greedy(9), None);
+    assert_eq!(coins.optimal(9), Ok(vec![0, 3]));
+    assert_eq!(
+        coins.optimal(7),
+        Err(ChangeError::CannotMakeChange { amount: 7 })
+    );
+
+    assert_eq!(Denominations::new(&[]), Err(ChangeError::NoDenominations));
+    assert_eq!(
+        Denominations::new(&[5, 0]),
+        Err(ChangeError::ZeroDenomination)
+    );
+}
+
+#[test]
+fn canonical_sets() {
+    let us = Denominations::us();
+    assert_eq!(us.values(), [25, 10, 5, 1]);
+    assert!(us.is_canonical());
+    assert_eq!(us.optimal(68), Ok(vec![2, 1, 1, 3]));
+    // a canonical set pays any amount, however large, without a table
+    assert_eq!(us.optimal(u32::MAX).unwrap()[0], u32::MAX / 25);
+
+    for (values, canonical) in [
+        (&[1, 2, 5, 10, 20, 50, 100, 200][..], true),
+        (&[1, 5, 10, 25, 50, 100], true),
+        (&[1, 3, 4], false),
+        (&[1, 10, 25], false),
+        (&[1, 2, 4, 8], true),
+        (&[1, 5, 12], false),
+        (&[2, 5], false),
+    ] {
+        let coins = Denominations::new(values).unwrap();
+        assert_eq!(coins.is_canonical(), canonical, "{:?}", values);
+    }
+}
+
+#[test]
+fn canonical_agrees_with_checking_every_amount() {
+    let mut rng = Rng(0x5eed_c01e);
+    for _ in 0..300 {
+        let mut values = vec![1];
+        for _ in 0..rng.below(4) + 1 {
+            values.push(rng.below(30) as u32 + 2);
+        }
+        let coins = Denominations::new(&values).unwrap();
+        let largest = coins.values()[0];
+        // a counterexample, if there is one, is below the sum of the two largest coins
+        let greedy_is_best = (0..largest * 3).all(|amount| {
+            let greedy: u32 = coins.greedy(amount).unwrap().iter().sum();
+            let best: u32 = coins.optimal(amount).unwrap().iter().sum();
+            greedy == best
+        });
+        assert_eq!(coins.is_canonical(), greedy_is_best, "{:?}", coins.values());
+    }
+}
+
+#[test]
+fn optimal_agrees_with_brute_force() {
+    let mut rng = Rng(0xc0ffee);
+    for _ in 0..200 {
+        let values: Vec<u32> = (0..rng.below(4) + 1)
+            .map(|_| rng.below(12) as u32 + 1)
+            .collect();
+        let coins = Denominations::new(&values).unwrap();
+        let unlimited = vec![u32::MAX; coins.values().len()];
+        let limits: Vec<u32> = coins.values().iter().map(|_| rng.below(6) as u32).collect();
+        for amount in 0..40 {
+            let ways = brute_force(coins.values(), &unlimited, amount);
+            let optimal = coins.optimal(amount).ok();
+            assert_eq!(
+                optimal.as_ref().map(|counts| counts.iter().sum()),
+                fewest(&ways),
+                "{:?} for {}",
+                coins.values(),
+                amount
+            );
+            if let Some(counts) = optimal {
+                assert_eq!(coins.total(&counts), u64::from(amount));
+            }
+            assert_eq!(coins.count_ways(amount), Ok(ways.len() as u128));
+
+            let ways = brute_force(coins.values(), &limits, amount);
+            let limited = coins.optimal_with_limits(amount, &limits).ok();
+            assert_eq!(
+                limited.as_ref().map(|counts| counts.iter().sum()),
+                fewest(&ways),
+                "{:?} limited to {:?} for {}",
+                coins.values(),
+                limits,
+                amount
+            );
+            if let Some(counts) = limited {
+                assert_eq!(coins.total(&counts), u64::from(amount));
+                assert!(counts
+                    .iter()
+                    .zip(&limits)
+                    .all(|(count, limit)| count <= limit));
+            }
+        }
+    }
+}
+
+#[test]
+fn limits_are_respected() {
+    let us = Denominations::us();
+    assert_eq!(
+        us.optimal_with_limits(30, &[1, 3, 0, 0]),
+        Ok(vec![0, 3, 0, 0])
+    );
+    assert_eq!(
+        us.optimal_with_limits(30, &[1, 3, 0, 5]),
+        Ok(vec![0, 3, 0, 0])
+    );
+    assert_eq!(
+        us.optimal_with_limits(30, &[1, 2, 1, 0]),
+        Ok(vec![1, 0, 1, 0])
+    );
+    assert_eq!(
+        us.optimal_with_limits(30, &[1, 2, 0, 4]),
+        Err(ChangeError::CannotMakeChange { amount: 30 })
+    );
+    assert_eq!(
+        us.optimal_with_limits(0, &[0, 0, 0, 0]),
+        Ok(vec![0, 0, 0, 0])
+    );
+}
+
+#[test]
+fn combinations_are_every_way_once() {
+    let us = Denominations::us();
+    for amount in 0..=100 {
+        let combinations: Vec<Vec<u32>> = us.combinations(amount).collect();
+        assert_eq!(
+            combinations.len() as u128,
+            us.count_ways(amount).unwrap(),
+            "{}",
+            amount
+        );
+        assert!(combinations
+            .iter()
+            .all(|counts| us.total(counts) == u64::from(amount)));
+        // more of the larger coins first, so each comes once and in order
+        assert!(combinations.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+    assert_eq!(us.count_ways(100), Ok(242));
+
+    let coins = Denominations::new(&[1, 3, 4]).unwrap();
+    let limited: Vec<Vec<u32>> = coins.combinations_with_limits(8, &[1, 2, 3]).collect();
+    assert_eq!(limited, [vec![1, 1, 1], vec![0, 2, 2]]);
+    assert_eq!(
+        limited,
+        brute_force(coins.values(), &[1, 2, 3], 8)
+            .into_iter()
+            .rev()
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn amounts_too_large_for_a_table_are_refused() {
+    let coins = Denominations::new(&[1, 3, 4]).unwrap();
+    let too_much = MAX_AMOUNT + 1;
+    let refused = Err(ChangeError::AmountTooLarge { amount: too_much });
+    assert_eq!(coins.optimal(too_much), refused);
+    assert_eq!(coins.optimal_with_limits(too_much, &[1, 1, 1]), refused);
+    assert_eq!(
+        coins.count_ways(u32::MAX),
+        Err(ChangeError::AmountTooLarge { amount: u32::MAX })
+    );
+    assert_eq!(coins.optimal(MAX_AMOUNT).unwrap()[0], MAX_AMOUNT / 4);
+    assert_eq!(
+        coins.optimal_with_limits(MAX_AMOUNT, &[u32::MAX, 2, 0]),
+        Ok(vec![MAX_AMOUNT / 4, 0, 0])
+    );
+    // without ones, the last two cents take two threes
+    assert_eq!(
+        coins.optimal_with_limits(MAX_AMOUNT - 2, &[u32::MAX, 2, 0]),
+        Ok(vec![MAX_AMOUNT / 4 - 2, 2, 0])
+    );
+}
+
+#[test]
+fn inventory_counts_do_not_overflow() {
+    let mut till = CoinInventory::new();
+    till.add_many(Coin::Quarter(UsState::Ohio), u32::MAX);
+    till.add_many(Coin::Quarter(UsState::Iowa), u32::MAX);
+    till.add(Coin::Dime);
+    assert_eq!(till.len(), 2 * u64::from(u32::MAX) + 1);
+    assert_eq!(till.count_kind(CoinKind::Quarter), u32::MAX);
+    assert_eq!(till.total_cents(), 50 * u64::from(u32::MAX) + 10);
+
+    // adding stops at the most one count holds, unless asked to refuse instead
+    till.add(Coin::Quarter(UsState::Ohio));
+    assert_eq!(till.count(Coin::Quarter(UsState::Ohio)), u32::MAX);
+    assert_eq!(till.checked_add_many(Coin::Quarter(UsState::Ohio), 1), None);
+    assert_eq!(till.checked_add_many(Coin::Dime, 2), Some(3));
+    assert_eq!(till.checked_add_many(Coin::Nickel, 0), Some(0));
+    assert_eq!(till.count(Coin::Nickel), 0);
+    assert_eq!(till.iter().count(), 3);
+}
+
+#[test]
+fn inventories_pay_out_of_what_they_hold() {
+    let mut till: CoinInventory = [
+        Coin::Quarter(UsState::Ohio),
+        Coin::Dime,
+        Coin::Dime,
+        Coin::Dime,
+        Coin::Penny,
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(till.total_cents(), 56);
+    assert_eq!(till.make_change(30), Ok(vec![Coin::Dime; 3]));
+    assert_eq!(till.count_kind(CoinKind::Dime), 0);
+    assert_eq!(
+        till.make_change(5),
+        Err(ChangeError::CannotMakeChange { amount: 5 })
+    );
+    // nothing was taken by the failed attempt
+    assert_eq!(till.len(), 2);
+    assert_eq!(
+        till.make_change(26),
+        Ok(vec![Coin::Quarter(UsState::Ohio), Coin::Penny])
+    );
+    assert!(till.is_empty());
+}
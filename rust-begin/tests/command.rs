@@ -0,0 +1,316 @@
+//! `#[derive(Command)]` on the lesson enums and on enums of every supported shape.
+
+use rust_begin::collections::SpreadsheetCell;
+use rust_begin::command::{Command, DecodeError, ParseError, ParseErrorKind};
+use rust_begin::enums::{Coin, IpAddrKind, Message, UsState};
+use rust_begin::message::wire;
+
+/// `Message` spelled out again, to compare the derived forms with the hand-written ones.
+#[derive(Debug, Clone, PartialEq, Command)]
+enum Mirror {
+    Quit,
+    Move {
+        x: i32,
+        y: i32,
+    },
+    Write(String),
+    #[command(rename = "color")]
+    ChangeColor(
+        #[command(rename = "red")] i32,
+        #[command(rename = "green")] i32,
+        #[command(rename = "blue")] i32,
+    ),
+}
+
+/// Names that are not plain words in PascalCase.
+#[derive(Debug, Clone, PartialEq, Command)]
+enum Awkward {
+    HTTPGet,
+    GetURL,
+    #[allow(clippy::upper_case_acronyms)]
+    ABC,
+    V2Beta,
+    r#Type,
+    r#Match(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Command)]
+#[command(rename_all = "kebab-case")]
+enum AwkwardKebab {
+    HTTPGet,
+    XMLHttpRequest,
+}
+
+fn mirror(message: &Message) -> Mirror {
+    match message.clone() {
+        Message::Quit => Mirror::Quit,
+        Message::Move { x, y } => Mirror::Move { x, y },
+        Message::Write(text) => Mirror::Write(text),
+        Message::ChangeColor(r, g, b) => Mirror::ChangeColor(r, g, b),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Command)]
+#[command(rename_all = "kebab-case")]
+enum Everything {
+    NoFields,
+    Small(i8, u8, i16, u16),
+    Wide {
+        signed: i64,
+        unsigned: u64,
+        size: usize,
+        offset: isize,
+    },
+    Floats(f32, f64),
+    Flags {
+        on: bool,
+        letter: char,
+    },
+    Nested(Coin, IpAddrKind),
+    EmptyTuple(),
+    EmptyStruct {},
+}
+
+fn samples() -> Vec<Everything> {
+    vec![
+        Everything::NoFields,
+        Everything::Small(-128, 255, i16::MIN, u16::MAX),
+        Everything::Wide {
+            signed: i64::MIN,
+            unsigned: u64::MAX,
+            size: 12345,
+            offset: -1,
+        },
+        Everything::Floats(1.5, -0.0),
+        Everything::Floats(f32::INFINITY, 1e300),
+        Everything::Flags {
+            on: true,
+            letter: '"',
+        },
+        Everything::Flags {
+            on: false,
+            letter: '🦀',
+        },
+        Everything::Nested(Coin::Quarter(UsState::Alabama), IpAddrKind::V6),
+        Everything::Nested(Coin::Dime, IpAddrKind::V4),
+        Everything::EmptyTuple(),
+        Everything::EmptyStruct {},
+    ]
+}
+
+#[test]
+fn every_shape_round_trips() {
+    for value in samples() {
+        let text = value.to_string();
+        assert_eq!(text.parse::<Everything>(), Ok(value.clone()), "{}", text);
+        assert_eq!(Everything::from_bytes(&value.to_bytes()), Ok(value.clone()));
+        assert_eq!(
+            value.variant_name(),
+            Everything::VARIANTS[value.variant_index()]
+        );
+    }
+}
+
+#[test]
+fn names_follow_the_attributes() {
+    assert_eq!(
+        Everything::VARIANTS,
+        [
+            "no-fields",
+            "small",
+            "wide",
+            "floats",
+            "flags",
+            "nested",
+            "empty-tuple",
+            "empty-struct"
+        ]
+    );
+    assert_eq!(Mirror::VARIANTS, ["quit", "move", "write", "color"]);
+    assert_eq!(IpAddrKind::VARIANTS, ["v4", "v6"]);
+    assert_eq!(Coin::VARIANTS, ["penny", "nickel", "dime", "quarter"]);
+    assert_eq!(SpreadsheetCell::VARIANTS, ["int", "float", "text"]);
+
+    // runs of capitals are one word, and raw identifiers lose their `r#`
+    assert_eq!(
+        Awkward::VARIANTS,
+        ["http_get", "get_url", "abc", "v2_beta", "type", "match"]
+    );
+    assert_eq!(AwkwardKebab::VARIANTS, ["http-get", "xml-http-request"]);
+    assert_eq!(Awkward::r#Match(7).to_string(), "match 7");
+    assert_eq!("type".parse::<Awkward>().unwrap(), Awkward::r#Type);
+}
+
+#[test]
+fn lesson_enums_have_a_text_form() {
+    assert_eq!(Coin::Quarter(UsState::Alaska).to_string(), "quarter alaska");
+    assert_eq!(
+        "  quarter   alabama  # from 1819".parse::<Coin>(),
+        Ok(Coin::Quarter(UsState::Alabama))
+    );
+    assert_eq!("v6".parse::<IpAddrKind>(), Ok(IpAddrKind::V6));
+    assert_eq!(
+        SpreadsheetCell::Float(10.0).to_string(),
+        "float 10.0",
+        "whole floats keep their point"
+    );
+    assert_eq!(
+        r#"text "tab\tand \u{e9}""#.parse::<SpreadsheetCell>(),
+        Ok(SpreadsheetCell::Text("tab\tand é".to_string()))
+    );
+}
+
+#[test]
+fn derived_forms_match_the_hand_written_message_forms() {
+    let messages = [
+        Message::Quit,
+        Message::Move { x: 3, y: -4 },
+        Message::Move {
+            x: i32::MIN,
+            y: i32::MAX,
+        },
+        Message::Write(String::new()),
+        Message::Write("say \"hi\"\n\u{7}🦀".to_string()),
+        Message::ChangeColor(255, 0, -1),
+    ];
+    for message in &messages {
+        let mirrored = mirror(message);
+        assert_eq!(mirrored.to_bytes(), wire::encode(message));
+        assert_eq!(mirrored.to_string(), message.to_string());
+        assert_eq!(message.to_string().parse::<Mirror>(), Ok(mirrored));
+    }
+}
+
+fn error(text: &str) -> ParseError {
+    text.parse::<Mirror>().unwrap_err()
+}
+
+#[test]
+fn parse_errors_say_what_and_where() {
+    assert_eq!(error("   ").kind, ParseErrorKind::Empty);
+    assert_eq!(
+        error("jump 1"),
+        ParseError {
+            column: 1,
+            kind: ParseErrorKind::UnknownVariant {
+                found: "jump".to_string(),
+                expected: Mirror::VARIANTS,
+            }
+        }
+    );
+    assert_eq!(
+        error("move 1"),
+        ParseError {
+            column: 7,
+            kind: ParseErrorKind::MissingArgument("y")
+        }
+    );
+    assert_eq!(
+        error("color 1 x 3"),
+        ParseError {
+            column: 9,
+            kind: ParseErrorKind::InvalidArgument {
+                name: "green",
+                found: "x".to_string()
+            }
+        }
+    );
+    assert_eq!(
+        error("move 1 99999999999").kind,
+        ParseErrorKind::InvalidArgument {
+            name: "y",
+            found: "99999999999".to_string()
+        }
+    );
+    assert_eq!(error("write hi").kind, ParseErrorKind::ExpectedString("0"));
+    assert_eq!(error("write \"hi").kind, ParseErrorKind::UnterminatedString);
+    assert_eq!(
+        error(r#"write "\q""#),
+        ParseError {
+            column: 8,
+            kind: ParseErrorKind::InvalidEscape("\\q".to_string())
+        }
+    );
+    assert_eq!(
+        error(r#"write "\u{110000}""#).kind,
+        ParseErrorKind::InvalidEscape("\\u{110000}".to_string())
+    );
+    // a sign is not a hex digit, though `u32::from_str_radix` would take it
+    assert_eq!(
+        error(r#"write "\u{+41}""#),
+        ParseError {
+            column: 8,
+            kind: ParseErrorKind::InvalidEscape("\\u{+41}".to_string())
+        }
+    );
+    assert_eq!(
+        error(r#"write "\u{41" x}""#).kind,
+        ParseErrorKind::InvalidEscape("\\u{41".to_string())
+    );
+    assert_eq!(
+        r#"write "\u{41}\u{1F980}""#.parse::<Mirror>(),
+        Ok(Mirror::Write("A🦀".to_string()))
+    );
+    assert_eq!(
+        error("quit now"),
+        ParseError {
+            column: 6,
+            kind: ParseErrorKind::TrailingInput
+        }
+    );
+    assert_eq!(
+        "quarter".parse::<Coin>().unwrap_err().kind,
+        ParseErrorKind::MissingArgument("0")
+    );
+    assert_eq!(
+        "flags true \"ab\"".parse::<Everything>().unwrap_err().kind,
+        ParseErrorKind::InvalidArgument {
+            name: "letter",
+            found: "\"ab\"".to_string()
+        }
+    );
+}
+
+#[test]
+fn bad_bytes_are_reported() {
+    assert_eq!(Mirror::from_bytes(&[]), Err(DecodeError::Truncated));
+    assert_eq!(
+        Mirror::from_bytes(&[4]),
+        Err(DecodeError::UnknownVariant(4))
+    );
+    assert_eq!(
+        Mirror::from_bytes(&[0, 0]),
+        Err(DecodeError::TrailingBytes(1))
+    );
+    assert_eq!(
+        Mirror::from_bytes(&[2, 1, 0xff]),
+        Err(DecodeError::InvalidUtf8)
+    );
+    assert_eq!(
+        Mirror::from_bytes(&[0x80; 11]),
+        Err(DecodeError::VarintOverflow)
+    );
+    assert_eq!(
+        Everything::from_bytes(&[1, 0x80, 0x02, 0, 0, 0]),
+        Err(DecodeError::OutOfRange(128))
+    );
+    assert_eq!(
+        Everything::from_bytes(&[4, 2, 0x41]),
+        Err(DecodeError::InvalidBool(2))
+    );
+    assert_eq!(
+        Everything::from_bytes(&[4, 1, 0x80, 0xb0, 0x03]),
+        Err(DecodeError::InvalidChar(0xd800))
+    );
+    assert_eq!(
+        Everything::from_bytes(&[5, 3, 56]),
+        Err(DecodeError::UnknownVariant(56))
+    );
+    // every prefix of a valid encoding is an error
+    for value in samples() {
+        let bytes = value.to_bytes();
+        for len in 0..bytes.len() {
+            assert!(Everything::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Helpers shared by the integration tests.
+
+// every test crate compiles this, and not all of them use all of it
+#![allow(dead_code)]
+
+/// xorshift64*, seeded the same every run so a failure repeats, without a dependency.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
@@ -0,0 +1,429 @@
+//! The hashing against the published test vectors of the RFCs that define it, and signing in
+//! through `UserRegistry`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rust_begin::credentials::{
+    hmac_sha256, pbkdf2_hmac_sha256, scrypt, sha256, Lockout, Params, PasswordHash, Sha256,
+    SignInError,
+};
+use rust_begin::users::{FieldError, PasswordError, RegistryError, UserRegistry};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex split over lines and spaces, as the RFCs print it.
+fn unspaced(text: &str) -> String {
+    text.split_whitespace().collect()
+}
+
+#[test]
+fn sha256_vectors() {
+    // FIPS 180-4 examples and the NIST short and long message tests
+    #[rustfmt::skip]
+    let cases: [(&[u8], &str); 4] = [
+        (b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        (b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+        (
+            b"The quick brown fox jumps over the lazy dog",
+            "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592",
+        ),
+    ];
+    for (message, digest) in cases {
+        assert_eq!(hex(&sha256(message)), digest);
+    }
+
+    // a million "a"s, fed in uneven pieces to cross block boundaries
+    let mut hash = Sha256::new();
+    let piece = [b'a'; 997];
+    let mut left = 1_000_000;
+    while left > 0 {
+        let take = left.min(piece.len());
+        hash.update(&piece[..take]);
+        left -= take;
+    }
+    assert_eq!(
+        hex(&hash.finish()),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+#[test]
+fn hmac_sha256_vectors() {
+    // RFC 4231 test cases 1, 2, 3 and 6 (the last with a key longer than a block)
+    #[rustfmt::skip]
+    let cases: [(Vec<u8>, Vec<u8>, &str); 4] = [
+        (
+            vec![0x0b; 20],
+            b"Hi There".to_vec(),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+        ),
+        (
+            b"Jefe".to_vec(),
+            b"what do ya want for nothing?".to_vec(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        ),
+        (
+            vec![0xaa; 20],
+            vec![0xdd; 50],
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+        ),
+        (
+            vec![0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+        ),
+    ];
+    for (key, message, mac) in cases {
+        assert_eq!(hex(&hmac_sha256(&key, &message)), mac);
+    }
+}
+
+#[test]
+fn pbkdf2_vectors() {
+    // RFC 7914 section 11
+    let mut out = [0u8; 64];
+    pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut out);
+    assert_eq!(
+        hex(&out),
+        unspaced(
+            "55 ac 04 6e 56 e3 08 9f ec 16 91 c2 25 44 b6 05
+             f9 41 85 21 6d de 04 65 e6 8b 9d 57 c2 0d ac bc
+             49 ca 9c cc f1 79 b6 45 99 16 64 b3 9d 77 ef 31
+             7c 71 b8 45 b1 e3 0b d5 09 11 20 41 d3 a1 97 83"
+        )
+    );
+    pbkdf2_hmac_sha256(b"Password", b"NaCl", 80000, &mut out);
+    assert_eq!(
+        hex(&out),
+        unspaced(
+            "4d dc d8 f6 0b 98 be 21 83 0c ee 5e f2 27 01 f9
+             64 1a 44 18 d0 4c 04 14 ae ff 08 87 6b 34 ab 56
+             a1 d4 25 a1 22 58 33 54 9a db 84 1b 51 c9 b3 17
+             6a 27 2b de bb a1 d0 78 47 8f 62 b3 97 f3 3c 8d"
+        )
+    );
+}
+
+#[test]
+fn scrypt_vectors() {
+    // RFC 7914 section 12, all but the last, which takes a gigabyte
+    #[rustfmt::skip]
+    let cases: [(&str, &str, u8, u32, u32, &str); 3] = [
+        ("", "", 4, 1, 1,
+            "77 d6 57 62 38 65 7b 20 3b 19 ca 42 c1 8a 04 97
+             f1 6b 48 44 e3 07 4a e8 df df fa 3f ed e2 14 42
+             fc d0 06 9d ed 09 48 f8 32 6a 75 3a 0f c8 1f 17
+             e8 d3 e0 fb 2e 0d 36 28 cf 35 e2 0c 38 d1 89 06"),
+        ("password", "NaCl", 10, 8, 16,
+            "fd ba be 1c 9d 34 72 00 78 56 e7 19 0d 01 e9 fe
+             7c 6a d7 cb c8 23 78 30 e7 73 76 63 4b 37 31 62
+             2e af 30 d9 2e 22 a3 88 6f f1 09 27 9d 98 30 da
+             c7 27 af b9 4a 83 ee 6d 83 60 cb df a2 cc 06 40"),
+        ("pleaseletmein", "SodiumChloride", 14, 8, 1,
+            "70 23 bd cb 3a fd 73 48 46 1c 06 cd 81 fd 38 eb
+             fd a8 fb ba 90 4f 8e 3e a9 b5 43 f6 54 5d a1 f2
+             d5 43 29 55 61 3f 0f cf 62 d4 97 05 24 2a 9a f9
+             e6 1e 85 dc 0d 65 1e 40 df cf 01 7b 45 57 58 87"),
+    ];
+    for (password, salt, log_n, r, p, key) in cases {
+        let mut out = [0u8; 64];
+        let params = Params::new(log_n, r, p).unwrap();
+        scrypt(password.as_bytes(), salt.as_bytes(), params, &mut out);
+        assert_eq!(hex(&out), unspaced(key), "{:?}", password);
+    }
+}
+
+#[test]
+fn params_follow_the_rfc_limits() {
+    assert!(Params::new(0, 8, 1).is_none());
+    assert!(Params::new(4, 0, 1).is_none());
+    assert!(Params::new(4, 1, 0).is_none());
+    assert!(Params::new(16, 1, 1).is_none()); // N must be below 2^(16 r)
+    assert!(Params::new(15, 1, 1).is_some());
+    assert!(Params::new(4, 1 << 15, 1 << 15).is_none()); // r p below 2^30
+    assert_eq!(Params::DEFAULT.memory(), 32 << 20);
+}
+
+#[test]
+fn password_hashes_round_trip() {
+    let params = Params::new(4, 1, 1).unwrap();
+    let hash = PasswordHash::with_salt("hunter22", b"0123456789abcdef", params);
+    assert_eq!(
+        hash.to_string(),
+        "$scrypt$ln=4,r=1,p=1$MDEyMzQ1Njc4OWFiY2RlZg$\
+         +sy13Bl5E/O6Nwgy523eXwxrh9SLg6D5JSA7JiFfqHw"
+    );
+    let parsed: PasswordHash = hash.to_string().parse().unwrap();
+    assert_eq!(parsed, hash);
+    assert!(parsed.verify("hunter22"));
+    assert!(!parsed.verify("hunter2"));
+
+    // a new salt each time, so the same password hashes differently
+    let a = PasswordHash::new("hunter22", params);
+    let b = PasswordHash::new("hunter22", params);
+    assert_ne!(a.salt(), b.salt());
+    assert_ne!(a, b);
+    assert!(a.verify("hunter22") && b.verify("hunter22"));
+
+    for bad in [
+        "",
+        "$scrypt$ln=4,r=1,p=1$MDEy",
+        "$argon2id$ln=4,r=1,p=1$MDEy$MDEy",
+        "$scrypt$ln=4,r=1$MDEy$MDEy",
+        "$scrypt$ln=4,p=1,r=1$MDEy$MDEy",
+        "$scrypt$ln=0,r=1,p=1$MDEy$MDEy",
+        "$scrypt$ln=4,r=1,p=1$MDE!$MDEy",
+        "$scrypt$ln=4,r=1,p=1$MDF$MDEy", // stray low bits
+        "$scrypt$ln=4,r=1,p=1$MDEy$",
+        // valid scrypt, but far too costly to be a hash anyone stored
+        "$scrypt$ln=40,r=8,p=1$MDEy$MDEy",
+        "$scrypt$ln=21,r=8,p=1$MDEy$MDEy",
+        "$scrypt$ln=4,r=1,p=17$MDEy$MDEy",
+        "$scrypt$ln=1,r=4194304,p=16$MDEy$MDEy", // 1 GiB table, 8 GiB of blocks
+    ] {
+        assert!(bad.parse::<PasswordHash>().is_err(), "{:?}", bad);
+    }
+    // the most a stored hash may cost
+    let dearest: PasswordHash = "$scrypt$ln=20,r=8,p=16$MDEy$MDEy".parse().unwrap();
+    assert_eq!(dearest.params().memory(), Params::MAX_MEMORY);
+}
+
+#[test]
+fn lockout_doubles_up_to_the_cap() {
+    let lockout = Lockout {
+        threshold: 3,
+        base: Duration::from_secs(10),
+        max: Duration::from_secs(60),
+    };
+    let delays: Vec<Option<u64>> = (0..8)
+        .map(|failed| lockout.delay(failed).map(|d| d.as_secs()))
+        .collect();
+    #[rustfmt::skip]
+    assert_eq!(delays, [None, None, None, Some(10), Some(20), Some(40), Some(60), Some(60)]);
+    assert_eq!(lockout.delay(u32::MAX), Some(Duration::from_secs(60)));
+    let never = Lockout {
+        threshold: 0,
+        ..lockout
+    };
+    assert_eq!(never.delay(100), None);
+}
+
+fn registry() -> UserRegistry {
+    let mut users = UserRegistry::new()
+        .with_params(Params::new(4, 1, 1).unwrap())
+        .with_lockout(Lockout {
+            threshold: 3,
+            base: Duration::from_secs(60),
+            max: Duration::from_secs(600),
+        });
+    users.register("ferris", "ferris@example.com").unwrap();
+    users.set_password("ferris", "crustacean").unwrap();
+    users
+}
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[test]
+fn signing_in_counts() {
+    let mut users = registry();
+    assert_eq!(users.get("ferris").unwrap().sign_in_count, 1);
+    let user = users.sign_in("Ferris", "crustacean", at(1000)).unwrap();
+    assert_eq!(user.sign_in_count, 2);
+    users.sign_in("ferris", "crustacean", at(2000)).unwrap();
+    let credential = users.credential("ferris").unwrap();
+    assert_eq!(credential.last_sign_in(), Some(at(2000)));
+    assert_eq!(credential.last_failed_sign_in(), None);
+    assert_eq!(users.get("ferris").unwrap().sign_in_count, 3);
+
+    // unknown users, users without a password and wrong passwords look the same
+    users.register("corro", "corro@example.com").unwrap();
+    for (username, password) in [
+        ("nobody", "crustacean"),
+        ("corro", "crustacean"),
+        ("ferris", "Crustacean"),
+    ] {
+        assert_eq!(
+            users.sign_in(username, password, at(3000)).unwrap_err(),
+            SignInError::InvalidCredentials
+        );
+    }
+    assert_eq!(users.get("ferris").unwrap().sign_in_count, 3);
+    assert_eq!(
+        users.credential("ferris").unwrap().last_failed_sign_in(),
+        Some(at(3000))
+    );
+    // a success clears the failed attempt
+    users.sign_in("ferris", "crustacean", at(3001)).unwrap();
+    assert_eq!(users.credential("ferris").unwrap().failed_attempts(), 0);
+}
+
+#[test]
+fn too_many_failures_lock_the_account_with_back_off() {
+    let mut users = registry();
+    let wrong =
+        |users: &mut UserRegistry, secs| users.sign_in("ferris", "wrong", at(secs)).map(|_| ());
+    assert_eq!(wrong(&mut users, 0), Err(SignInError::InvalidCredentials));
+    assert_eq!(wrong(&mut users, 1), Err(SignInError::InvalidCredentials));
+    // the third locks for a minute
+    assert_eq!(
+        wrong(&mut users, 2),
+        Err(SignInError::Locked { until: at(62) })
+    );
+    // while locked even the right password is not checked
+    assert_eq!(
+        users.sign_in("ferris", "crustacean", at(30)).unwrap_err(),
+        SignInError::Locked { until: at(62) }
+    );
+    assert_eq!(users.credential("ferris").unwrap().failed_attempts(), 3);
+
+    // each failure after a lock runs out doubles it
+    assert_eq!(
+        wrong(&mut users, 62),
+        Err(SignInError::Locked { until: at(182) })
+    );
+    assert_eq!(
+        wrong(&mut users, 182),
+        Err(SignInError::Locked { until: at(422) })
+    );
+    assert_eq!(
+        wrong(&mut users, 422),
+        Err(SignInError::Locked { until: at(902) })
+    );
+    // up to the cap of ten minutes
+    assert_eq!(
+        wrong(&mut users, 902),
+        Err(SignInError::Locked { until: at(1502) })
+    );
+
+    let user = users.sign_in("ferris", "crustacean", at(1502)).unwrap();
+    assert_eq!(user.sign_in_count, 2);
+    let credential = users.credential("ferris").unwrap();
+    assert_eq!(credential.failed_attempts(), 0);
+    assert_eq!(credential.locked_until(at(1502)), None);
+    assert_eq!(credential.last_failed_sign_in(), Some(at(902)));
+}
+
+#[test]
+fn a_lock_too_long_for_the_clock_ends_in_the_far_future() {
+    let mut users = registry().with_lockout(Lockout {
+        threshold: 1,
+        base: Duration::MAX,
+        max: Duration::MAX,
+    });
+    // the first day of the year 10000
+    let far = at(253_402_300_800);
+    assert_eq!(
+        users.sign_in("ferris", "wrong", at(0)).unwrap_err(),
+        SignInError::Locked { until: far }
+    );
+    assert_eq!(
+        users.sign_in("ferris", "crustacean", at(1)).unwrap_err(),
+        SignInError::Locked { until: far }
+    );
+    assert_eq!(
+        users.credential("ferris").unwrap().locked_until(at(1)),
+        Some(far)
+    );
+    // and the store can write it and read it back
+    let loaded = UserRegistry::from_store(&users.to_store()).unwrap();
+    assert_eq!(
+        loaded.credential("ferris").unwrap().locked_until(at(1)),
+        Some(far)
+    );
+}
+
+#[test]
+fn unlocking_and_new_passwords_lift_the_lock() {
+    let mut users = registry();
+    for secs in 0..3 {
+        users.sign_in("ferris", "wrong", at(secs)).unwrap_err();
+    }
+    users.unlock("ferris").unwrap();
+    users.sign_in("ferris", "crustacean", at(10)).unwrap();
+
+    for secs in 20..23 {
+        users.sign_in("ferris", "wrong", at(secs)).unwrap_err();
+    }
+    users.set_password("ferris", "new password").unwrap();
+    assert_eq!(
+        users.sign_in("ferris", "crustacean", at(30)).unwrap_err(),
+        SignInError::InvalidCredentials
+    );
+    users.sign_in("ferris", "new password", at(31)).unwrap();
+    assert_eq!(
+        users.credential("ferris").unwrap().last_sign_in(),
+        Some(at(31))
+    );
+}
+
+#[test]
+fn deactivated_users_cannot_sign_in() {
+    let mut users = registry();
+    users.deactivate("ferris").unwrap();
+    assert_eq!(
+        users.sign_in("ferris", "crustacean", at(0)).unwrap_err(),
+        SignInError::Inactive
+    );
+    // a wrong password still counts against them
+    assert_eq!(
+        users.sign_in("ferris", "wrong", at(1)).unwrap_err(),
+        SignInError::InvalidCredentials
+    );
+    assert_eq!(users.credential("ferris").unwrap().failed_attempts(), 1);
+    assert_eq!(users.credential("ferris").unwrap().last_sign_in(), None);
+    assert_eq!(users.get("ferris").unwrap().sign_in_count, 1);
+
+    users.reactivate("ferris").unwrap();
+    assert_eq!(
+        users
+            .sign_in("ferris", "crustacean", at(2))
+            .unwrap()
+            .sign_in_count,
+        2
+    );
+}
+
+#[test]
+fn passwords_are_checked_and_follow_the_user() {
+    let mut users = registry();
+    let Err(RegistryError::Invalid(errors)) = users.set_password("ferris", "short") else {
+        panic!("seven characters are too few");
+    };
+    assert_eq!(
+        errors.errors(),
+        [FieldError::Password(PasswordError::TooShort { min: 8 })]
+    );
+    let Err(RegistryError::Invalid(errors)) = users.set_password("ferris", &"x".repeat(1025))
+    else {
+        panic!("1025 characters are too many");
+    };
+    assert_eq!(
+        errors.password(),
+        Some(&PasswordError::TooLong { max: 1024 })
+    );
+    // the old password still works
+    users.sign_in("ferris", "crustacean", at(0)).unwrap();
+    assert_eq!(
+        users.set_password("nobody", "long enough"),
+        Err(RegistryError::UnknownUser("nobody".to_string()))
+    );
+
+    users.rename("ferris", "crab").unwrap();
+    assert!(users.credential("ferris").is_none());
+    users.sign_in("crab", "crustacean", at(1)).unwrap();
+    users.remove("crab");
+    users.register("crab", "crab@example.com").unwrap();
+    assert!(users.credential("crab").is_none());
+    assert_eq!(
+        users.sign_in("crab", "crustacean", at(2)).unwrap_err(),
+        SignInError::InvalidCredentials
+    );
+}
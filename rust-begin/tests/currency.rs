@@ -0,0 +1,159 @@
+//! Currencies, their coins and banknotes, and converting amounts with exchange rates.
+
+use rust_begin::currency::{
+    ConvertError, Currency, Denominated, ExchangeRates, Form, Rate, RatesFileError, CURRENCIES,
+    DENOMINATIONS,
+};
+use rust_begin::enums::{value_in_cents, Coin, CoinKind, UsState};
+
+fn currency(code: &str) -> &'static Currency {
+    Currency::from_code(code).unwrap()
+}
+
+fn parse_error(text: &str) -> (usize, String) {
+    match ExchangeRates::parse(text) {
+        Err(RatesFileError::Parse { line, reason }) => (line, reason),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn us_coins_agree_with_the_table() {
+    let coins = [
+        Coin::Penny,
+        Coin::Nickel,
+        Coin::Dime,
+        Coin::Quarter(UsState::Alaska),
+    ];
+    assert_eq!(coins.map(value_in_cents), [1, 5, 10, 25]);
+    for (coin, kind) in coins.into_iter().zip(CoinKind::ALL) {
+        let value = u32::from(value_in_cents(coin));
+        assert_eq!(u32::from(kind.value_in_cents()), value, "{:?}", kind);
+        assert_eq!(kind.denomination().value, value, "{:?}", kind);
+        assert_eq!(coin.value_in_minor_units(), value, "{:?}", coin);
+        assert_eq!(coin.currency().code, "USD");
+    }
+    let usd: Vec<u32> = currency("USD").coins().map(|d| d.value).collect();
+    assert_eq!(usd, [1, 5, 10, 25, 50, 100]);
+}
+
+#[test]
+fn currencies_and_their_denominations() {
+    assert_eq!(currency("usd").code, "USD");
+    assert_eq!(currency("JPY").minor_per_major(), 1);
+    assert_eq!(currency("EUR").minor_per_major(), 100);
+    assert!(Currency::from_code("XXX").is_none());
+    assert_eq!(currency("GBP").to_string(), "GBP");
+
+    for currency in CURRENCIES {
+        let values: Vec<u32> = currency.denominations().map(|d| d.value).collect();
+        assert!(!values.is_empty(), "{}", currency);
+        assert!(
+            values.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{}",
+            currency
+        );
+        assert_eq!(
+            currency.coins().count() + currency.banknotes().count(),
+            values.len()
+        );
+    }
+    assert!(DENOMINATIONS
+        .iter()
+        .all(|d| Currency::from_code(d.currency).is_some()));
+
+    let franc = currency("CHF").denomination("HALF FRANC").unwrap();
+    assert_eq!((franc.value, franc.form), (50, Form::Coin));
+    assert_eq!(franc.currency().code, "CHF");
+    assert!(currency("CHF").denomination("1 centime").is_none());
+    // a dollar coin and a dollar note are one value for change
+    assert_eq!(
+        currency("USD").change_denominations().values()[..3],
+        [10_000, 5_000, 2_000]
+    );
+    assert_eq!(currency("USD").change_denominations().values().len(), 12);
+}
+
+#[test]
+fn rates_are_exact_fractions() {
+    let rate: Rate = "0.9200".parse().unwrap();
+    assert_eq!(rate.parts(), (23, 25));
+    assert_eq!(rate.inverse().parts(), (25, 23));
+    assert_eq!("150".parse::<Rate>().unwrap().parts(), (150, 1));
+    assert_eq!(rate.then("150".parse().unwrap()).unwrap().parts(), (138, 1));
+    assert_eq!(Rate::new(4, 6).unwrap().parts(), (2, 3));
+    assert_eq!(Rate::new(0, 6), None);
+    for text in ["", ".5", "-1", "1e3", "0.0", "1.2.3"] {
+        assert!(text.parse::<Rate>().is_err(), "{:?}", text);
+    }
+    let huge = Rate::new(u128::MAX, 1).unwrap();
+    assert_eq!(huge.then(huge), None);
+}
+
+#[test]
+fn converting() {
+    let rates = ExchangeRates::parse(
+        "# comments and blank lines are skipped\n\nUSD EUR 0.92\n  usd jpy 150  \nGBP USD 1.25\n",
+    )
+    .unwrap();
+    assert_eq!(rates.convert(1000, "USD", "EUR"), Ok(920));
+    // the other way round is the inverse
+    assert_eq!(rates.convert(920, "EUR", "USD"), Ok(1000));
+    // through the dollar, with the yen's lack of minor units
+    assert_eq!(rates.convert(920, "EUR", "JPY"), Ok(1500));
+    assert_eq!(rates.convert(100, "GBP", "JPY"), Ok(188));
+    assert_eq!(rates.convert(1, "JPY", "USD"), Ok(1));
+    assert_eq!(rates.convert(123, "usd", "USD"), Ok(123));
+    // halves round up: a cent is 1.5 yen
+    assert_eq!(rates.convert(1, "USD", "JPY"), Ok(2));
+    assert_eq!(rates.convert(50, "JPY", "GBP"), Ok(27));
+
+    assert_eq!(
+        rates.convert(1, "USD", "CHF"),
+        Err(ConvertError::NoRate {
+            from: "USD",
+            to: "CHF"
+        })
+    );
+    assert_eq!(
+        rates.convert(1, "USD", "XXX"),
+        Err(ConvertError::UnknownCurrency("XXX".to_string()))
+    );
+    assert_eq!(
+        rates.convert(u64::MAX, "USD", "JPY"),
+        Err(ConvertError::Overflow)
+    );
+}
+
+#[test]
+fn bad_rates_files() {
+    assert_eq!(
+        parse_error("USD EUR 0.92\nUSD EUR\n"),
+        (2, "expected FROM TO RATE, found \"USD EUR\"".to_string())
+    );
+    assert_eq!(
+        parse_error("USD XXX 1\n"),
+        (1, "unknown currency XXX".to_string())
+    );
+    assert_eq!(
+        parse_error("USD usd 1\n"),
+        (1, "a rate from USD to itself".to_string())
+    );
+    assert_eq!(
+        parse_error("USD EUR 0\n"),
+        (1, "a rate of zero: 0".to_string())
+    );
+    assert_eq!(
+        parse_error("USD EUR 0.92\nUSD EUR 0.93\n"),
+        (2, "a second rate from USD to EUR".to_string())
+    );
+    // a pair given both ways round could disagree with itself
+    assert_eq!(
+        parse_error("USD EUR 0.92\n\nEUR USD 1.2\n"),
+        (
+            3,
+            "a rate from EUR to USD as well as one from USD to EUR".to_string()
+        )
+    );
+    assert!(ExchangeRates::load("/nonexistent/rates").is_err());
+}
@@ -0,0 +1,281 @@
+//! The message journal: records that survive reopening, recovery from torn and damaged tails,
+//! replay, and undo and redo across snapshots.
+
+use std::fs;
+use std::path::PathBuf;
+
+use rust_begin::enums::Message;
+use rust_begin::message::{DrawState, Journal, JournalEntry, JournalError, Recovery, Session};
+
+/// A fresh directory for one test, removed when it is dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let dir = std::env::temp_dir().join(format!(
+            "rust-begin-journal-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn file(&self) -> PathBuf {
+        self.0.join("journal")
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+fn messages() -> Vec<Message> {
+    vec![
+        Message::Move { x: 3, y: -4 },
+        Message::Write("hello".to_string()),
+        Message::ChangeColor(255, 128, 0),
+        Message::Move { x: -1, y: 10 },
+        Message::Write(", world".to_string()),
+        Message::ChangeColor(0, 0, 255),
+        Message::Move { x: 100, y: 100 },
+    ]
+}
+
+/// The state after the first `n` messages, the slow way.
+fn after(n: usize) -> DrawState {
+    let mut state = DrawState::new();
+    state.run(&messages()[..n]).unwrap();
+    state
+}
+
+/// A journal holding `messages()` and the length of the file after each record.
+fn written(path: &PathBuf) -> Vec<u64> {
+    let mut journal = Journal::open(path).unwrap();
+    let mut ends = vec![fs::metadata(path).unwrap().len()];
+    for message in messages() {
+        journal.append(JournalEntry::Message(message)).unwrap();
+        ends.push(fs::metadata(path).unwrap().len());
+    }
+    ends
+}
+
+#[test]
+fn records_survive_reopening() {
+    let dir = TempDir::new("reopen");
+    let mut journal = Journal::open(dir.file()).unwrap();
+    assert_eq!(journal.recovery(), Recovery::default());
+    assert_eq!(journal.last_seq(), 0);
+    assert_eq!(
+        journal
+            .append(JournalEntry::Message(Message::Move { x: 1, y: 2 }))
+            .unwrap(),
+        1
+    );
+    assert_eq!(journal.append(JournalEntry::Undo).unwrap(), 2);
+    assert_eq!(journal.append(JournalEntry::Redo).unwrap(), 3);
+    drop(journal);
+
+    let journal = Journal::open(dir.file()).unwrap();
+    assert_eq!(
+        journal.recovery(),
+        Recovery {
+            records: 3,
+            truncated: 0
+        }
+    );
+    assert_eq!(
+        journal.entries(),
+        [
+            JournalEntry::Message(Message::Move { x: 1, y: 2 }),
+            JournalEntry::Undo,
+            JournalEntry::Redo,
+        ]
+    );
+    assert_eq!(journal.get(2), Some(&JournalEntry::Undo));
+    assert_eq!(journal.get(0), None);
+    assert_eq!(journal.get(4), None);
+}
+
+#[test]
+fn replaying_to_any_point() {
+    let dir = TempDir::new("replay");
+    written(&dir.file());
+    let mut journal = Journal::open(dir.file()).unwrap();
+    for n in 0..=messages().len() {
+        assert_eq!(journal.replay(n as u64), after(n), "after {}", n);
+    }
+    // past the end is the same as the end
+    assert_eq!(journal.replay(u64::MAX), after(messages().len()));
+
+    journal.append(JournalEntry::Undo).unwrap();
+    journal.append(JournalEntry::Undo).unwrap();
+    journal.append(JournalEntry::Redo).unwrap();
+    assert_eq!(journal.replay(8), after(6));
+    assert_eq!(journal.replay(9), after(5));
+    assert_eq!(journal.replay(10), after(6));
+}
+
+#[test]
+fn a_torn_tail_is_cut_off() {
+    let dir = TempDir::new("torn");
+    let ends = written(&dir.file());
+    let whole = fs::read(dir.file()).unwrap();
+    let last = messages().len();
+    // every place a crash could stop the last append
+    for cut in ends[last - 1] + 1..ends[last] {
+        fs::write(dir.file(), &whole[..cut as usize]).unwrap();
+        let journal = Journal::open(dir.file()).unwrap();
+        assert_eq!(
+            journal.recovery(),
+            Recovery {
+                records: last as u64 - 1,
+                truncated: cut - ends[last - 1]
+            }
+        );
+        assert_eq!(fs::metadata(dir.file()).unwrap().len(), ends[last - 1]);
+        assert_eq!(journal.replay(u64::MAX), after(last - 1));
+    }
+
+    // appending after a recovery lands where the torn record was
+    let mut journal = Journal::open(dir.file()).unwrap();
+    let message = messages()[last - 1].clone();
+    assert_eq!(
+        journal.append(JournalEntry::Message(message)).unwrap(),
+        last as u64
+    );
+    drop(journal);
+    assert_eq!(fs::read(dir.file()).unwrap(), whole);
+
+    // a crash while the header was being written
+    fs::write(dir.file(), b"RB").unwrap();
+    let journal = Journal::open(dir.file()).unwrap();
+    assert_eq!(
+        journal.recovery(),
+        Recovery {
+            records: 0,
+            truncated: 2
+        }
+    );
+    assert_eq!(fs::read(dir.file()).unwrap(), b"RBJ\x01");
+}
+
+#[test]
+fn a_damaged_record_ends_the_journal() {
+    let dir = TempDir::new("flip");
+    let ends = written(&dir.file());
+    let whole = fs::read(dir.file()).unwrap();
+    // flip each byte of the third record in turn: its length, checksum or payload
+    for at in ends[2]..ends[3] {
+        let mut bytes = whole.clone();
+        bytes[at as usize] ^= 0x20;
+        fs::write(dir.file(), &bytes).unwrap();
+        let journal = Journal::open(dir.file()).unwrap();
+        assert_eq!(journal.recovery().records, 2, "byte {} flipped", at);
+        assert_eq!(
+            journal.recovery().truncated,
+            whole.len() as u64 - ends[2],
+            "byte {} flipped",
+            at
+        );
+        assert_eq!(journal.replay(u64::MAX), after(2));
+        assert_eq!(fs::metadata(dir.file()).unwrap().len(), ends[2]);
+    }
+
+    let mut bytes = whole.clone();
+    bytes[0] = b'X';
+    fs::write(dir.file(), &bytes).unwrap();
+    assert!(matches!(
+        Journal::open(dir.file()),
+        Err(JournalError::BadHeader)
+    ));
+    // a bad header is not repaired away
+    assert_eq!(fs::read(dir.file()).unwrap(), bytes);
+}
+
+#[test]
+fn undo_and_redo_across_snapshots() {
+    for every in 1..=4 {
+        let dir = TempDir::new(&format!("undo-{}", every));
+        let mut session = Session::open(dir.file(), every).unwrap();
+        assert!(!session.can_undo());
+        assert!(!session.undo().unwrap());
+        for message in messages() {
+            session.apply(&message).unwrap();
+        }
+        let total = messages().len();
+        assert_eq!(session.state(), &after(total));
+
+        // back past several snapshots, one step at a time
+        for n in (2..total).rev() {
+            assert!(session.undo().unwrap());
+            assert_eq!(
+                session.state(),
+                &after(n),
+                "every {}, undone to {}",
+                every,
+                n
+            );
+        }
+        for n in 3..=5 {
+            assert!(session.redo().unwrap());
+            assert_eq!(
+                session.state(),
+                &after(n),
+                "every {}, redone to {}",
+                every,
+                n
+            );
+        }
+        assert!(session.can_redo());
+
+        // a new message drops what is still undone
+        let detour = Message::Write("!".to_string());
+        session.apply(&detour).unwrap();
+        assert!(!session.can_redo());
+        assert!(!session.redo().unwrap());
+        let mut expected = after(5);
+        detour.call(&mut expected).unwrap();
+        assert_eq!(session.state(), &expected);
+        assert!(session.undo().unwrap());
+        assert_eq!(session.state(), &after(5));
+        assert!(session.redo().unwrap());
+        assert_eq!(session.state(), &expected);
+
+        // the journal rebuilds the same state and history
+        let last_seq = session.journal().last_seq();
+        drop(session);
+        let mut session = Session::open(dir.file(), every).unwrap();
+        assert_eq!(session.state(), &expected);
+        assert_eq!(session.journal().last_seq(), last_seq);
+        assert_eq!(session.journal().replay(last_seq), expected);
+        for n in (0..=5).rev() {
+            assert!(session.undo().unwrap());
+            assert_eq!(
+                session.state(),
+                &after(n),
+                "every {}, reopened, at {}",
+                every,
+                n
+            );
+        }
+        assert!(!session.undo().unwrap());
+    }
+}
+
+#[test]
+fn failed_messages_are_not_journaled() {
+    let dir = TempDir::new("failed");
+    let mut session = Session::open(dir.file(), 2).unwrap();
+    session.apply(&Message::Move { x: 1, y: 1 }).unwrap();
+    assert!(session.apply(&Message::ChangeColor(300, 0, 0)).is_err());
+    session.apply(&Message::Quit).unwrap();
+    assert!(session.apply(&Message::Move { x: 1, y: 1 }).is_err());
+    assert_eq!(session.journal().last_seq(), 2);
+    assert!(!session.state().running);
+    // undoing `Quit` starts the program again
+    session.undo().unwrap();
+    assert!(session.state().running);
+}
@@ -0,0 +1,61 @@
+//! The lesson list, and the `rust-begin` binary that lists and runs it.
+
+use std::collections::HashSet;
+use std::process::{Command, Output};
+
+use rust_begin::{find_lesson, LESSONS};
+
+fn rust_begin(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rust-begin"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn every_lesson_is_found_by_its_name() {
+    assert!(!LESSONS.is_empty());
+    for lesson in LESSONS {
+        let found = find_lesson(lesson.name).unwrap();
+        assert_eq!((found.name, found.about), (lesson.name, lesson.about));
+        assert!(!lesson.about.is_empty(), "{}", lesson.name);
+    }
+    let names: HashSet<&str> = LESSONS.iter().map(|lesson| lesson.name).collect();
+    assert_eq!(names.len(), LESSONS.len());
+
+    assert!(find_lesson("nope").is_none());
+    assert!(find_lesson("").is_none());
+    // names are matched exactly
+    assert!(find_lesson("Structs").is_none());
+}
+
+#[test]
+fn the_binary_lists_and_runs_lessons() {
+    let list = rust_begin(&["list"]);
+    assert!(list.status.success());
+    let listed: Vec<String> = String::from_utf8(list.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| line.split_whitespace().next().unwrap().to_string())
+        .collect();
+    let names: Vec<&str> = LESSONS.iter().map(|lesson| lesson.name).collect();
+    assert_eq!(listed, names);
+
+    for lesson in LESSONS {
+        assert!(
+            rust_begin(&["run", lesson.name]).status.success(),
+            "{}",
+            lesson.name
+        );
+    }
+
+    let unknown = rust_begin(&["run", "nope"]);
+    assert_eq!(unknown.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(unknown.stderr).unwrap(),
+        "unknown lesson `nope`, try `rust-begin list`\n"
+    );
+    for args in [&[][..], &["run"], &["list", "structs"], &["help"]] {
+        assert_eq!(rust_begin(args).status.code(), Some(2), "{:?}", args);
+    }
+}
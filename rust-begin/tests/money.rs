@@ -0,0 +1,322 @@
+mod common;
+
+use rust_begin::currency::{Currency, ExchangeRates, Rate, CURRENCIES};
+use rust_begin::enums::{Coin, UsState};
+use rust_begin::money::{BigInt, Locale, Money, MoneyError, ParseMoneyError, Rounding, LOCALES};
+
+use common::Rng;
+
+impl Rng {
+    /// Signed, and small now and then.
+    fn int(&mut self) -> i64 {
+        let n = self.next() as i64;
+        match self.next() % 3 {
+            0 => n % 1000,
+            1 => n >> (self.next() % 64),
+            _ => n,
+        }
+    }
+}
+
+fn usd(cents: i64) -> Money {
+    Money::new(Currency::from_code("USD").unwrap(), cents)
+}
+
+fn big(text: &str) -> BigInt {
+    text.parse().unwrap()
+}
+
+#[test]
+fn big_integers_agree_with_i128() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..2000 {
+        let (a, b) = (rng.int() as i128, rng.int() as i128);
+        let (x, y) = (BigInt::from(a), BigInt::from(b));
+        assert_eq!((&x + &y).to_i128(), Some(a + b), "{} + {}", a, b);
+        assert_eq!((&x - &y).to_i128(), Some(a - b), "{} - {}", a, b);
+        assert_eq!((&x * &y).to_i128(), Some(a * b), "{} * {}", a, b);
+        assert_eq!(x.cmp(&y), a.cmp(&b));
+        assert_eq!(x.to_string(), a.to_string());
+        assert_eq!(big(&a.to_string()), x);
+        if b != 0 {
+            let (q, r) = x.div_rem(&y).unwrap();
+            assert_eq!(
+                (q.to_i128(), r.to_i128()),
+                (Some(a / b), Some(a % b)),
+                "{} / {}",
+                a,
+                b
+            );
+        }
+        if b > 0 {
+            let (q, r) = x.div_floor(&y).unwrap();
+            assert_eq!(q.to_i128(), Some(a.div_euclid(b)));
+            assert_eq!(r.to_i128(), Some(a.rem_euclid(b)));
+        }
+    }
+}
+
+#[test]
+fn big_integers_go_past_128_bits() {
+    let huge = big("340282366920938463463374607431768211456"); // 2^128
+    assert_eq!(huge.to_i128(), None);
+    assert_eq!(
+        (&huge * &huge).to_string(),
+        "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+    );
+    let n = big("-123456789012345678901234567890123456789012345678901234567890");
+    let d = big("98765432109876543210987");
+    let (q, r) = n.div_rem(&d).unwrap();
+    assert_eq!(q * &d + &r, n);
+    assert!(r.is_negative() && r.abs() < d);
+    assert_eq!(
+        BigInt::pow10(40).to_string(),
+        format!("1{}", "0".repeat(40))
+    );
+    assert_eq!(BigInt::from(i128::MIN).to_i128(), Some(i128::MIN));
+    assert!("12a".parse::<BigInt>().is_err());
+    assert!("-".parse::<BigInt>().is_err());
+}
+
+#[test]
+fn rounding_modes() {
+    use Rounding::*;
+    #[rustfmt::skip]
+    let cases = [
+        // numer, denom, half even, half up, floor
+        (25, 10, 2, 3, 2),
+        (35, 10, 4, 4, 3),
+        (-25, 10, -2, -3, -3),
+        (-35, 10, -4, -4, -4),
+        (26, 10, 3, 3, 2),
+        (24, 10, 2, 2, 2),
+        (-24, 10, -2, -2, -3),
+        (-26, 10, -3, -3, -3),
+        (30, 10, 3, 3, 3),
+        (1, 3, 0, 0, 0),
+        (-1, 3, 0, 0, -1),
+    ];
+    for (numer, denom, even, up, floor) in cases {
+        let (n, d) = (BigInt::from(numer), BigInt::from(denom));
+        for (mode, expected) in [(HalfEven, even), (HalfUp, up), (Floor, floor)] {
+            assert_eq!(
+                mode.divide(&n, &d),
+                BigInt::from(expected),
+                "{}/{} {:?}",
+                numer,
+                denom,
+                mode
+            );
+        }
+    }
+}
+
+#[test]
+fn adding_checks_the_currency() {
+    let euro = Money::new(Currency::from_code("EUR").unwrap(), 100);
+    assert_eq!(usd(150).checked_add(&usd(275)), Ok(usd(425)));
+    assert_eq!(usd(150).checked_sub(&usd(275)), Ok(usd(-125)));
+    assert_eq!(
+        usd(150).checked_add(&euro),
+        Err(MoneyError::CurrencyMismatch {
+            left: "USD",
+            right: "EUR"
+        })
+    );
+    assert_eq!(usd(1).partial_cmp(&euro), None);
+    assert!(usd(1) < usd(2));
+
+    // far past the 255 cents of a u8
+    let coins = (0..1000).map(|_| Money::of(&Coin::Quarter(UsState::Ohio)));
+    let total = Money::total(Currency::from_code("USD").unwrap(), coins).unwrap();
+    assert_eq!(total, usd(25_000));
+    let huge = usd(i64::MAX).times(i64::MAX);
+    assert_eq!(
+        huge.checked_add(&huge).unwrap().to_string(),
+        "USD 1701411834604692316947938155684650024.98"
+    );
+}
+
+#[test]
+fn allocating_never_loses_a_unit() {
+    assert_eq!(usd(1000).split(3).unwrap(), [usd(334), usd(333), usd(333)]);
+    assert_eq!(
+        usd(-1000).split(3).unwrap(),
+        [usd(-334), usd(-333), usd(-333)]
+    );
+    // 70/30 of 5 cents is 3.5 and 1.5: a tie, so the first part gets the odd cent
+    assert_eq!(usd(5).allocate(&[70, 30]).unwrap(), [usd(4), usd(1)]);
+    // 1/6 and 5/6 of a dollar: the larger remainder wins
+    assert_eq!(usd(100).allocate(&[1, 5]).unwrap(), [usd(17), usd(83)]);
+    assert_eq!(usd(100).allocate(&[0, 1]).unwrap(), [usd(0), usd(100)]);
+    assert_eq!(usd(100).split(0), Err(MoneyError::NoParts));
+    assert_eq!(usd(100).allocate(&[0, 0]), Err(MoneyError::NoParts));
+
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..500 {
+        let amount = usd(rng.int());
+        let ratios: Vec<u64> = (0..1 + rng.next() % 7).map(|_| rng.next() % 50).collect();
+        if ratios.iter().all(|&r| r == 0) {
+            continue;
+        }
+        let parts = amount.allocate(&ratios).unwrap();
+        let currency = amount.currency();
+        assert_eq!(Money::total(currency, parts.clone()).unwrap(), amount);
+        // each part is within one unit of its exact share
+        let sum: u64 = ratios.iter().sum();
+        for (part, &ratio) in parts.iter().zip(&ratios) {
+            let exact = amount.minor_units().clone() * BigInt::from(ratio);
+            let low = Rounding::Floor.divide(&exact, &BigInt::from(sum));
+            let units = part.minor_units();
+            assert!(
+                *units == low || *units == low.clone() + BigInt::from(1),
+                "{} of {:?} of {}",
+                part,
+                ratios,
+                amount
+            );
+        }
+    }
+}
+
+#[test]
+fn scaling_rounding_and_converting() {
+    assert_eq!(usd(1050).scale(1, 4, Rounding::HalfEven), usd(262)); // 262.5
+    assert_eq!(usd(1050).scale(1, 4, Rounding::HalfUp), usd(263));
+    assert_eq!(usd(1050).scale(1, 4, Rounding::Floor), usd(262));
+    assert_eq!(usd(-1050).scale(1, 4, Rounding::Floor), usd(-263));
+    assert_eq!(
+        usd(250).round_to_multiple(100, Rounding::HalfEven),
+        usd(200)
+    );
+    assert_eq!(
+        usd(350).round_to_multiple(100, Rounding::HalfEven),
+        usd(400)
+    );
+    let chf = Currency::from_code("CHF").unwrap();
+    assert_eq!(
+        Money::new(chf, 1233).round_to_multiple(5, Rounding::HalfUp),
+        Money::new(chf, 1235)
+    );
+
+    let jpy = Currency::from_code("JPY").unwrap();
+    let rate: Rate = "149.5".parse().unwrap();
+    assert_eq!(
+        usd(1001).convert(jpy, rate, Rounding::HalfEven),
+        Money::new(jpy, 1496)
+    ); // 1496.495
+    assert_eq!(
+        Money::new(jpy, 299).convert(usd(0).currency(), rate.inverse(), Rounding::Floor),
+        usd(200)
+    );
+    let rates = ExchangeRates::parse("USD EUR 0.92\n").unwrap();
+    let eur = Currency::from_code("EUR").unwrap();
+    assert_eq!(
+        usd(1000).exchange(&rates, "EUR", Rounding::HalfUp),
+        Ok(Money::new(eur, 920))
+    );
+    assert!(usd(1000).exchange(&rates, "JPY", Rounding::HalfUp).is_err());
+}
+
+#[test]
+fn formatting_in_locales() {
+    let eur = Currency::from_code("EUR").unwrap();
+    let jpy = Currency::from_code("JPY").unwrap();
+    let chf = Currency::from_code("CHF").unwrap();
+    #[rustfmt::skip]
+    let cases = [
+        (usd(123456), "en-US", "$1,234.56"),
+        (usd(-5), "en-US", "-$0.05"),
+        (usd(100_000_000), "en-US", "$1,000,000.00"),
+        (Money::new(eur, 123456), "de-DE", "1.234,56 €"),
+        (Money::new(eur, 123456), "fr-FR", "1 234,56 €"),
+        (Money::new(eur, 123456), "en-US", "€1,234.56"),
+        (Money::new(chf, 123456), "de-CH", "CHF 1’234.56"),
+        (Money::new(chf, 123456), "en-US", "CHF 1,234.56"),
+        (Money::new(jpy, 1234567), "ja-JP", "¥1,234,567"),
+        (Money::new(jpy, -12), "de-DE", "-12 ¥"),
+        (usd(0), "en-GB", "$0.00"),
+    ];
+    for (money, tag, text) in cases {
+        let locale = Locale::from_tag(tag).unwrap();
+        assert_eq!(money.format(locale), text);
+        assert_eq!(Money::parse(text, locale), Ok(money.clone()), "{}", text);
+    }
+    assert_eq!(usd(123456).to_string(), "USD 1234.56");
+    assert_eq!(Money::new(jpy, -500).to_string(), "JPY -500");
+}
+
+#[test]
+fn every_amount_reads_back_in_every_locale() {
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+    for locale in LOCALES {
+        for currency in CURRENCIES {
+            for _ in 0..20 {
+                let money = Money::new(currency, rng.int());
+                let text = money.format(locale);
+                assert_eq!(Money::parse(&text, locale), Ok(money.clone()), "{}", text);
+                assert_eq!(money.to_string().parse(), Ok(money));
+            }
+        }
+    }
+}
+
+#[test]
+fn parsing_is_lenient_about_spaces_and_strict_about_numbers() {
+    let us = Locale::from_tag("en_us").unwrap();
+    let de = Locale::from_tag("de-DE").unwrap();
+    let fr = Locale::from_tag("fr-FR").unwrap();
+    let eur = Currency::from_code("EUR").unwrap();
+    assert_eq!(Money::parse("  $5.5 ", us), Ok(usd(550)));
+    assert_eq!(Money::parse("$-5", us), Ok(usd(-500)));
+    assert_eq!(Money::parse("USD 1234.56", us), Ok(usd(123456)));
+    assert_eq!(Money::parse("1234,56 EUR", de), Ok(Money::new(eur, 123456)));
+    assert_eq!(
+        Money::parse("1\u{202f}234,56\u{a0}€", fr),
+        Ok(Money::new(eur, 123456))
+    );
+
+    assert_eq!(Money::parse("", us), Err(ParseMoneyError::Empty));
+    assert_eq!(
+        Money::parse("1,234.56", us),
+        Err(ParseMoneyError::NoCurrency("1,234.56".to_string()))
+    );
+    assert_eq!(
+        Money::parse("$1.234", us),
+        Err(ParseMoneyError::TooManyDecimals {
+            currency: "USD",
+            found: 3
+        })
+    );
+    for bad in [
+        "$12,34.00",
+        "$1,2345",
+        "$1.",
+        "$.50",
+        "$1.2.3",
+        "$1e5",
+        "$ ,123",
+        "$",
+        "€",
+        "$-",
+    ] {
+        assert!(
+            matches!(
+                Money::parse(bad, us),
+                Err(ParseMoneyError::InvalidNumber(_))
+            ),
+            "{}",
+            bad
+        );
+    }
+    // the German decimal comma is a grouping comma in English
+    assert_eq!(Money::parse("$1,234", us), Ok(usd(123400)));
+    assert_eq!(
+        ParseMoneyError::TooManyDecimals {
+            currency: "JPY",
+            found: 2
+        }
+        .to_string(),
+        "JPY has 0 decimal places, not 2"
+    );
+}
@@ -0,0 +1,283 @@
+//! `IpNetwork`: parsing CIDR text, what a network holds, and splitting and merging networks.
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use rust_begin::enums::{IpAddr, IpAddrKind};
+use rust_begin::net::{IpNetwork, NetworkError};
+
+use common::Rng;
+
+fn net(text: &str) -> IpNetwork {
+    text.parse().unwrap()
+}
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+fn nets(texts: &[&str]) -> Vec<IpNetwork> {
+    texts.iter().map(|text| net(text)).collect()
+}
+
+/// The last octet of an address in `10.0.0.0/24`.
+fn octet(ip: IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(10, 0, 0, d) => d,
+        other => panic!("{} is not in 10.0.0.0/24", other),
+    }
+}
+
+#[test]
+fn parsing_and_printing() {
+    assert_eq!(net("192.168.1.7/24").to_string(), "192.168.1.0/24");
+    assert_eq!(net("2001:db8::1/32").to_string(), "2001:db8::/32");
+    assert_eq!(net("10.0.0.1").to_string(), "10.0.0.1/32");
+    assert_eq!(net("::1").prefix_len(), 128);
+    assert_eq!(
+        IpNetwork::new(ip("192.168.1.7"), 24),
+        Ok(net("192.168.1.0/24"))
+    );
+    assert_eq!(net("0.0.0.0/0").kind(), IpAddrKind::V4);
+    assert_eq!(net("::/0").kind(), IpAddrKind::V6);
+
+    assert_eq!(
+        IpNetwork::new(ip("10.0.0.0"), 33),
+        Err(NetworkError::PrefixOutOfRange {
+            prefix_len: 33,
+            min: 0,
+            max: 32
+        })
+    );
+    assert_eq!(
+        "::/129".parse::<IpNetwork>().unwrap_err().to_string(),
+        "prefix length 129 is not between 0 and 128"
+    );
+    for prefix in ["", "+8", "-1", "x", "256", " 8"] {
+        assert_eq!(
+            format!("10.0.0.0/{}", prefix).parse::<IpNetwork>(),
+            Err(NetworkError::InvalidPrefix(prefix.to_string()))
+        );
+    }
+    assert!(matches!(
+        "10.0.0/8".parse::<IpNetwork>(),
+        Err(NetworkError::Addr(_))
+    ));
+}
+
+#[test]
+fn what_a_network_holds() {
+    let lan = net("192.168.1.0/24");
+    assert_eq!(lan.network(), ip("192.168.1.0"));
+    assert_eq!(lan.last(), ip("192.168.1.255"));
+    assert_eq!(lan.netmask(), ip("255.255.255.0"));
+    assert_eq!(lan.host_bits(), 8);
+    assert_eq!(lan.size(), Some(256));
+    assert_eq!(net("0.0.0.0/0").netmask(), ip("0.0.0.0"));
+    assert_eq!(net("2001:db8::/32").netmask(), ip("ffff:ffff::"));
+    assert_eq!(net("::/1").size(), Some(1 << 127));
+    assert_eq!(net("::/0").size(), None);
+    assert_eq!(
+        net("::/0").last(),
+        ip("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")
+    );
+
+    assert!(lan.contains(&ip("192.168.1.0")));
+    assert!(lan.contains(&ip("192.168.1.255")));
+    assert!(!lan.contains(&ip("192.168.2.0")));
+    assert!(!lan.contains(&ip("::ffff:192.168.1.1")));
+    assert!(lan.contains_network(&net("192.168.1.128/25")));
+    assert!(lan.contains_network(&lan));
+    assert!(!lan.contains_network(&net("192.168.0.0/16")));
+    assert!(lan.overlaps(&net("192.168.0.0/16")));
+    assert!(!lan.overlaps(&net("192.168.2.0/24")));
+    assert!(!net("0.0.0.0/0").overlaps(&net("::/0")));
+
+    assert_eq!(
+        net("10.0.0.0/30").iter().map(octet).collect::<Vec<_>>(),
+        [0, 1, 2, 3]
+    );
+    assert_eq!(net("10.0.0.0/30").iter().size_hint(), (4, Some(4)));
+    assert_eq!(net("::/0").iter().size_hint(), (usize::MAX, None));
+}
+
+#[test]
+fn usable_hosts() {
+    let hosts = |text: &str| net(text).hosts().collect::<Vec<_>>();
+    assert_eq!(
+        hosts("10.0.0.0/29")
+            .into_iter()
+            .map(octet)
+            .collect::<Vec<_>>(),
+        [1, 2, 3, 4, 5, 6]
+    );
+    assert_eq!(net("10.0.0.0/24").hosts().count(), 254);
+    // point-to-point links use both addresses (RFC 3021)
+    assert_eq!(hosts("10.0.0.0/31"), [ip("10.0.0.0"), ip("10.0.0.1")]);
+    assert_eq!(hosts("10.0.0.9/32"), [ip("10.0.0.9")]);
+    // IPv6 has no broadcast address, only the Subnet-Router anycast address at the start
+    assert_eq!(
+        hosts("2001:db8::/126"),
+        [ip("2001:db8::1"), ip("2001:db8::2"), ip("2001:db8::3")]
+    );
+    assert_eq!(
+        hosts("2001:db8::/127"),
+        [ip("2001:db8::"), ip("2001:db8::1")]
+    );
+    assert_eq!(hosts("2001:db8::1/128"), [ip("2001:db8::1")]);
+}
+
+#[test]
+fn supernets_and_subnets() {
+    assert_eq!(net("10.0.1.0/24").supernet(), Some(net("10.0.0.0/23")));
+    assert_eq!(net("0.0.0.0/0").supernet(), None);
+    assert_eq!(
+        net("10.0.0.0/24").subnets(26).unwrap().collect::<Vec<_>>(),
+        nets(&[
+            "10.0.0.0/26",
+            "10.0.0.64/26",
+            "10.0.0.128/26",
+            "10.0.0.192/26"
+        ])
+    );
+    assert_eq!(
+        net("10.0.0.0/24").subnets(24).unwrap().collect::<Vec<_>>(),
+        [net("10.0.0.0/24")]
+    );
+    assert_eq!(
+        net("::/0").subnets(128).unwrap().nth(5),
+        Some(net("::5/128"))
+    );
+    assert_eq!(
+        net("255.255.255.0/24").subnets(32).unwrap().last(),
+        Some(net("255.255.255.255/32"))
+    );
+    assert_eq!(
+        net("10.0.0.0/24").subnets(23).unwrap_err(),
+        NetworkError::PrefixOutOfRange {
+            prefix_len: 23,
+            min: 24,
+            max: 32
+        }
+    );
+    assert!(net("10.0.0.0/24").subnets(33).is_err());
+}
+
+#[test]
+fn splitting() {
+    let lan = net("10.0.0.0/24");
+    assert_eq!(lan.split(1), Ok(vec![lan]));
+    assert_eq!(
+        lan.split(3),
+        Ok(nets(&["10.0.0.0/25", "10.0.0.128/26", "10.0.0.192/26"]))
+    );
+    assert_eq!(
+        lan.split(4),
+        Ok(nets(&[
+            "10.0.0.0/26",
+            "10.0.0.64/26",
+            "10.0.0.128/26",
+            "10.0.0.192/26"
+        ]))
+    );
+    assert_eq!(lan.split(256).unwrap().len(), 256);
+    assert_eq!(lan.split(0), Err(NetworkError::InvalidSubnetCount(0)));
+    assert_eq!(lan.split(257), Err(NetworkError::InvalidSubnetCount(257)));
+    // more subnets than a `usize` power of two can count, even though `::/0` has the room
+    assert_eq!(
+        net("::/0").split(usize::MAX),
+        Err(NetworkError::InvalidSubnetCount(usize::MAX))
+    );
+    assert_eq!(
+        net("::/0").split(usize::MAX / 2 + 2),
+        Err(NetworkError::InvalidSubnetCount(usize::MAX / 2 + 2))
+    );
+
+    // every split covers the network exactly, the larger subnets first
+    for n in 1..=256 {
+        let subnets = lan.split(n).unwrap();
+        assert_eq!(subnets.len(), n);
+        assert_eq!(IpNetwork::collapse(subnets.iter().copied()), [lan], "{}", n);
+        let covered: Vec<u8> = subnets.iter().flat_map(|s| s.iter()).map(octet).collect();
+        assert!(covered.iter().copied().eq(0..=255), "{}", n);
+        assert!(subnets
+            .windows(2)
+            .all(|pair| pair[0].prefix_len() <= pair[1].prefix_len()));
+        let lens: BTreeSet<u8> = subnets.iter().map(|s| s.prefix_len()).collect();
+        assert!(lens.len() <= 2, "{}", n);
+    }
+}
+
+#[test]
+fn smallest_supernets() {
+    assert_eq!(
+        IpNetwork::smallest_supernet(&nets(&["10.0.0.0/24", "10.0.3.0/24"])),
+        Some(net("10.0.0.0/22"))
+    );
+    assert_eq!(
+        IpNetwork::smallest_supernet(&nets(&["10.0.0.1", "10.0.0.1"])),
+        Some(net("10.0.0.1/32"))
+    );
+    assert_eq!(
+        IpNetwork::smallest_supernet(&nets(&["0.0.0.0/32", "255.0.0.0/8"])),
+        Some(net("0.0.0.0/0"))
+    );
+    assert_eq!(
+        IpNetwork::smallest_supernet(&nets(&["2001:db8::/48", "2001:db8:ff::/48"])),
+        Some(net("2001:db8::/40"))
+    );
+    assert_eq!(IpNetwork::smallest_supernet(&[]), None);
+    assert_eq!(
+        IpNetwork::smallest_supernet(&nets(&["10.0.0.0/8", "::/8"])),
+        None
+    );
+}
+
+#[test]
+fn collapsing() {
+    assert_eq!(
+        IpNetwork::collapse(nets(&["10.0.0.128/25", "10.0.0.7/32", "10.0.0.0/25"])),
+        [net("10.0.0.0/24")]
+    );
+    assert_eq!(
+        IpNetwork::collapse(nets(&[
+            "10.0.0.1",
+            "10.0.0.2",
+            "::/0",
+            "10.0.0.3",
+            "255.255.255.255"
+        ])),
+        nets(&["10.0.0.1/32", "10.0.0.2/31", "255.255.255.255/32", "::/0"])
+    );
+    assert_eq!(
+        IpNetwork::collapse(nets(&["0.0.0.0/1", "128.0.0.0/1"])),
+        [net("0.0.0.0/0")]
+    );
+    assert_eq!(IpNetwork::collapse([]), []);
+
+    // against the set of addresses they cover, inside one /24
+    let mut rng = Rng(0xc1d7);
+    for _ in 0..500 {
+        let networks: Vec<IpNetwork> = (0..rng.below(8))
+            .map(|_| {
+                let last = rng.below(256) as u8;
+                let prefix_len = 24 + rng.below(9) as u8;
+                IpNetwork::new(IpAddr::V4(10, 0, 0, last), prefix_len).unwrap()
+            })
+            .collect();
+        let collapsed = IpNetwork::collapse(networks.iter().copied());
+        let want: BTreeSet<u8> = networks.iter().flat_map(|n| n.iter()).map(octet).collect();
+        let got: Vec<u8> = collapsed.iter().flat_map(|n| n.iter()).map(octet).collect();
+        assert!(
+            got.iter().copied().eq(want.iter().copied()),
+            "{:?}",
+            networks
+        );
+        // nothing left that could merge with its neighbour or hold another
+        for pair in collapsed.windows(2) {
+            assert!(!pair[0].overlaps(&pair[1]));
+            assert_ne!(pair[0].supernet(), pair[1].supernet());
+        }
+    }
+}
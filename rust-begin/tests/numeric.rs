@@ -0,0 +1,272 @@
+//! Every operation under every policy, for every integer width, against exact arithmetic in
+//! a 320-bit reference integer, on edge values and random ones.
+
+mod common;
+
+use std::panic;
+use std::sync::Once;
+
+use rust_begin::numeric::{
+    add, minus_n, minus_one, mul, plus_n, plus_one, sub, times_n, Checked, OverflowError,
+    Panicking, Saturating, Wrapping,
+};
+
+use common::Rng;
+
+/// A 320-bit two's complement integer, little-endian limbs. Wide enough for any sum,
+/// difference or product of two 128-bit integers, signed or not, with the sign to spare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Wide([u64; LIMBS]);
+
+const LIMBS: usize = 5;
+
+impl Wide {
+    fn from_i128(v: i128) -> Wide {
+        let fill = if v < 0 { u64::MAX } else { 0 };
+        Wide([v as u64, (v >> 64) as u64, fill, fill, fill])
+    }
+
+    fn from_u128(v: u128) -> Wide {
+        Wide([v as u64, (v >> 64) as u64, 0, 0, 0])
+    }
+
+    fn add(self, other: Wide) -> Wide {
+        let mut out = [0; LIMBS];
+        let mut carry = 0u128;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let sum = u128::from(self.0[i]) + u128::from(other.0[i]) + carry;
+            *limb = sum as u64;
+            carry = sum >> 64;
+        }
+        Wide(out)
+    }
+
+    fn neg(self) -> Wide {
+        Wide(self.0.map(|limb| !limb)).add(Wide::from_u128(1))
+    }
+
+    fn sub(self, other: Wide) -> Wide {
+        self.add(other.neg())
+    }
+
+    fn mul(self, other: Wide) -> Wide {
+        let mut out = [0u64; LIMBS];
+        for i in 0..LIMBS {
+            let mut carry = 0u128;
+            for j in 0..LIMBS - i {
+                let product =
+                    u128::from(self.0[i]) * u128::from(other.0[j]) + u128::from(out[i + j]) + carry;
+                out[i + j] = product as u64;
+                carry = product >> 64;
+            }
+        }
+        Wide(out)
+    }
+
+    /// Signed order: flip the sign bit and compare as unsigned, high limb first.
+    fn key(self) -> [u64; LIMBS] {
+        let mut key = self.0;
+        key.reverse();
+        key[0] ^= 1 << 63;
+        key
+    }
+
+    fn low_u128(self) -> u128 {
+        u128::from(self.0[0]) | u128::from(self.0[1]) << 64
+    }
+}
+
+impl Rng {
+    /// Random bits, or few bits now and then so small values and near misses come up too.
+    fn bits(&mut self) -> u128 {
+        let bits = u128::from(self.next()) << 64 | u128::from(self.next());
+        match self.next() % 4 {
+            0 => bits & 0xff,
+            1 => bits >> (self.next() % 128),
+            _ => bits,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum AppError {
+    Overflow(OverflowError),
+    Other,
+}
+
+impl From<OverflowError> for AppError {
+    fn from(e: OverflowError) -> Self {
+        AppError::Overflow(e)
+    }
+}
+
+/// Keeps the expected overflow panics of `Panicking` off stderr; other panics still print.
+fn quiet_overflow_panics() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let message = info.payload().downcast_ref::<String>();
+            if !message.is_some_and(|m| m.ends_with("with overflow")) {
+                default(info);
+            }
+        }));
+    });
+}
+
+macro_rules! properties {
+    ($($name:ident: $ty:ty, $wide:path;)*) => {$(
+        #[test]
+        fn $name() {
+            quiet_overflow_panics();
+            let wide = |v: $ty| $wide(v as _);
+            let (min, max) = (wide(<$ty>::MIN), wide(<$ty>::MAX));
+
+            let mut samples: Vec<$ty> = vec![
+                <$ty>::MIN,
+                <$ty>::MIN + 1,
+                <$ty>::MAX,
+                <$ty>::MAX - 1,
+                <$ty>::MAX / 2,
+                0,
+                1,
+                2,
+            ];
+            if <$ty>::MIN != 0 {
+                samples.extend([(0 as $ty).wrapping_sub(1), <$ty>::MIN / 2]);
+            }
+            let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+            samples.extend((0..48).map(|_| rng.bits() as $ty));
+
+            let ops: [(OverflowError, fn(Wide, Wide) -> Wide); 3] = [
+                (OverflowError::Add, Wide::add),
+                (OverflowError::Sub, Wide::sub),
+                (OverflowError::Mul, Wide::mul),
+            ];
+            for &a in &samples {
+                for &b in &samples {
+                    for (op, exact) in ops {
+                        let exact = exact(wide(a), wide(b));
+                        let fits = min.key() <= exact.key() && exact.key() <= max.key();
+                        let wrapped = exact.low_u128() as $ty;
+                        let saturated = if exact.key() < min.key() {
+                            <$ty>::MIN
+                        } else if exact.key() > max.key() {
+                            <$ty>::MAX
+                        } else {
+                            wrapped
+                        };
+                        let checked = fits.then_some(wrapped);
+                        let case = format!("{} {:?} {}", a, op, b);
+
+                        let (got_checked, got_saturating, got_wrapping, got_panicking) = match op {
+                            OverflowError::Add => (
+                                add::<Checked, $ty>(a, b),
+                                add::<Saturating, $ty>(a, b),
+                                add::<Wrapping, $ty>(a, b),
+                                panic::catch_unwind(|| add::<Panicking, $ty>(a, b)),
+                            ),
+                            OverflowError::Sub => (
+                                sub::<Checked, $ty>(a, b),
+                                sub::<Saturating, $ty>(a, b),
+                                sub::<Wrapping, $ty>(a, b),
+                                panic::catch_unwind(|| sub::<Panicking, $ty>(a, b)),
+                            ),
+                            OverflowError::Mul => (
+                                mul::<Checked, $ty>(a, b),
+                                mul::<Saturating, $ty>(a, b),
+                                mul::<Wrapping, $ty>(a, b),
+                                panic::catch_unwind(|| mul::<Panicking, $ty>(a, b)),
+                            ),
+                        };
+                        assert_eq!(got_checked, checked, "checked {}", case);
+                        assert_eq!(got_saturating, saturated, "saturating {}", case);
+                        assert_eq!(got_wrapping, wrapped, "wrapping {}", case);
+                        assert_eq!(got_panicking.ok(), checked, "panicking {}", case);
+                    }
+
+                    // the same through Option and Result
+                    let sum = add::<Checked, $ty>(a, b);
+                    let difference = sub::<Checked, $ty>(a, b);
+                    let product = mul::<Checked, $ty>(a, b);
+                    assert_eq!(plus_n::<Checked, _>(a, b), sum);
+                    assert_eq!(plus_n::<Checked, _>(Some(a), b), sum);
+                    assert_eq!(minus_n::<Checked, _>(Some(a), b), difference);
+                    assert_eq!(times_n::<Checked, _>(Some(a), b), product);
+                    assert_eq!(plus_n::<Checked, Option<$ty>>(None, b), None);
+                    assert_eq!(
+                        plus_n::<Saturating, _>(Some(a), b),
+                        Some(add::<Saturating, $ty>(a, b))
+                    );
+                    assert_eq!(
+                        plus_n::<Checked, _>(Ok::<$ty, AppError>(a), b),
+                        sum.ok_or(AppError::Overflow(OverflowError::Add))
+                    );
+                    assert_eq!(
+                        times_n::<Checked, _>(Ok::<$ty, AppError>(a), b),
+                        product.ok_or(AppError::Overflow(OverflowError::Mul))
+                    );
+                    assert_eq!(
+                        minus_n::<Wrapping, _>(Ok::<$ty, AppError>(a), b),
+                        Ok(sub::<Wrapping, $ty>(a, b))
+                    );
+                    assert_eq!(
+                        plus_n::<Checked, _>(Err::<$ty, AppError>(AppError::Other), b),
+                        Err(AppError::Other)
+                    );
+                }
+
+                assert_eq!(plus_one::<Checked, _>(a), add::<Checked, $ty>(a, 1));
+                assert_eq!(minus_one::<Saturating, _>(Some(a)), Some(sub::<Saturating, $ty>(a, 1)));
+            }
+        }
+    )*};
+}
+
+properties! {
+    i8_matches_exact_arithmetic: i8, Wide::from_i128;
+    i16_matches_exact_arithmetic: i16, Wide::from_i128;
+    i32_matches_exact_arithmetic: i32, Wide::from_i128;
+    i64_matches_exact_arithmetic: i64, Wide::from_i128;
+    i128_matches_exact_arithmetic: i128, Wide::from_i128;
+    isize_matches_exact_arithmetic: isize, Wide::from_i128;
+    u8_matches_exact_arithmetic: u8, Wide::from_u128;
+    u16_matches_exact_arithmetic: u16, Wide::from_u128;
+    u32_matches_exact_arithmetic: u32, Wide::from_u128;
+    u64_matches_exact_arithmetic: u64, Wide::from_u128;
+    u128_matches_exact_arithmetic: u128, Wide::from_u128;
+    usize_matches_exact_arithmetic: usize, Wide::from_u128;
+}
+
+#[test]
+fn the_reference_is_right_at_the_edges() {
+    let max = Wide::from_u128(u128::MAX);
+    // (2^128 - 1)^2 = 2^256 - 2^129 + 1, which only fits signed with a fifth limb
+    let square = max.mul(max);
+    assert_eq!(square.0, [1, 0, u64::MAX - 1, u64::MAX, 0]);
+    assert!(square.key() > max.key());
+    assert_eq!(
+        Wide::from_i128(i128::MIN).mul(Wide::from_i128(-1)),
+        Wide::from_u128(1 << 127)
+    );
+    assert_eq!(
+        Wide::from_i128(-5).add(Wide::from_i128(3)),
+        Wide::from_i128(-2)
+    );
+    assert!(Wide::from_i128(-1).key() < Wide::from_i128(0).key());
+}
+
+#[test]
+fn panics_name_the_operation() {
+    let caught = panic::catch_unwind(|| plus_one::<Panicking, _>(u8::MAX)).unwrap_err();
+    assert_eq!(
+        caught.downcast_ref::<String>().map(String::as_str),
+        Some("attempt to add with overflow")
+    );
+}
+
+#[test]
+fn the_lesson_plus_one_no_longer_overflows() {
+    assert_eq!(rust_begin::enums::plus_one(Some(i32::MAX)), None);
+    assert_eq!(rust_begin::enums::plus_one(Some(5)), Some(6));
+}
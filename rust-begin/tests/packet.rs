@@ -0,0 +1,411 @@
+//! IPv4 and IPv6 headers, the Internet checksum, and reading them out of pcap captures.
+
+use std::io;
+use std::time::Duration;
+
+use rust_begin::enums::{IpAddr, Ipv4Addr, Ipv6Addr};
+use rust_begin::net::packet::{
+    internet_checksum, protocol, IpHeader, Ipv4Header, Ipv4HeaderBuilder, Ipv6Header,
+    Ipv6HeaderBuilder, PacketError,
+};
+use rust_begin::net::pcap::{link_type, PcapError, PcapReader};
+
+/// A header from a real capture, UDP from 192.168.0.1 to 192.168.0.199, checksum 0xb861.
+const CAPTURED: [u8; 20] = [
+    0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00, 0x01,
+    0xc0, 0xa8, 0x00, 0xc7,
+];
+
+fn v6(s: &str) -> Ipv6Addr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn the_checksum_of_rfc_1071() {
+    // the worked example in section 3: the sum is 0xddf2
+    let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+    assert_eq!(internet_checksum(&data), !0xddf2);
+    // an odd byte is padded on the right
+    assert_eq!(internet_checksum(&data[..7]), !0xddf2 + 0xf7);
+    assert_eq!(internet_checksum(&[]), 0xffff);
+    // the carries wrap around
+    assert_eq!(internet_checksum(&[0xff, 0xff, 0x00, 0x01]), !0x0001);
+
+    let mut header = CAPTURED;
+    assert_eq!(internet_checksum(&header), 0);
+    header[10..12].copy_from_slice(&[0, 0]);
+    assert_eq!(internet_checksum(&header), 0xb861);
+}
+
+#[test]
+fn parsing_ipv4() {
+    let mut packet = CAPTURED.to_vec();
+    packet.extend_from_slice(&[0xab; 0x73 - 20]);
+    let header = Ipv4Header::parse(&packet).unwrap();
+    assert_eq!(
+        (header.version(), header.ihl(), header.header_len()),
+        (4, 5, 20)
+    );
+    assert_eq!(header.total_len(), 0x73);
+    assert!(header.dont_fragment());
+    assert!(!header.more_fragments());
+    assert_eq!((header.ttl(), header.protocol()), (64, protocol::UDP));
+    assert_eq!(header.source(), Ipv4Addr::new(192, 168, 0, 1));
+    assert_eq!(header.destination(), Ipv4Addr::new(192, 168, 0, 199));
+    assert_eq!(header.checksum(), 0xb861);
+    assert!(header.verify_checksum());
+    assert_eq!(header.compute_checksum(), 0xb861);
+    assert_eq!(header.payload().len(), 0x73 - 20);
+    assert_eq!(header.options().count(), 0);
+
+    // a capture cut short has a short payload; a header cut short is an error
+    assert_eq!(
+        Ipv4Header::parse(&packet[..30]).unwrap().payload().len(),
+        10
+    );
+    assert_eq!(
+        Ipv4Header::parse(&packet[..19]),
+        Err(PacketError::Truncated {
+            needed: 20,
+            available: 19
+        })
+    );
+    let mut bad = packet.clone();
+    bad[0] = 0x44;
+    assert_eq!(Ipv4Header::parse(&bad), Err(PacketError::HeaderLength(4)));
+    bad[0] = 0x65;
+    assert_eq!(Ipv4Header::parse(&bad), Err(PacketError::Version(6)));
+    let mut bad = packet.clone();
+    bad[3] = 19;
+    assert_eq!(Ipv4Header::parse(&bad), Err(PacketError::TotalLength(19)));
+    let mut bad = packet.clone();
+    bad[11] ^= 1;
+    assert!(!Ipv4Header::parse(&bad).unwrap().verify_checksum());
+
+    // an option whose length runs past the header
+    let mut options = CAPTURED.to_vec();
+    options[0] = 0x46;
+    options.extend_from_slice(&[0x07, 0x08, 0, 0]);
+    assert_eq!(
+        Ipv4Header::parse(&options),
+        Err(PacketError::BadOption { offset: 20 })
+    );
+}
+
+#[test]
+fn ipv4_builder_round_trip() {
+    let builder = Ipv4HeaderBuilder::new(
+        Ipv4Addr::new(10, 0, 0, 1),
+        Ipv4Addr::new(10, 0, 0, 2),
+        protocol::TCP,
+    )
+    .ttl(3)
+    .dscp(46)
+    .ecn(1)
+    .identification(0x1234)
+    .fragment(100, true)
+    .option(1, &[])
+    .option(0x94, &[0, 0]);
+    let packet = builder.build(b"payload").unwrap();
+    let header = Ipv4Header::parse(&packet).unwrap();
+    assert!(header.verify_checksum());
+    assert_eq!(header.header_len(), 28);
+    assert_eq!((header.dscp(), header.ecn(), header.ttl()), (46, 1, 3));
+    assert_eq!(header.identification(), 0x1234);
+    assert_eq!(
+        (header.fragment_offset(), header.more_fragments()),
+        (100, true)
+    );
+    assert!(!header.dont_fragment());
+    let options: Vec<_> = header.options().map(|o| (o.kind, o.data)).collect();
+    assert_eq!(options, [(0x94, &[0, 0][..])]);
+    assert!(header.options().next().unwrap().copied());
+    assert_eq!(header.payload(), b"payload");
+    // and back again, byte for byte
+    assert_eq!(
+        Ipv4HeaderBuilder::from_header(&header).build(header.payload()),
+        Ok(packet)
+    );
+
+    let too_many = (0..10).fold(builder.clone(), |b, _| b.option(7, &[0, 0]));
+    assert_eq!(too_many.build(b""), Err(PacketError::OptionsTooLong(48)));
+    assert_eq!(
+        builder.build(&vec![0; 65535]),
+        Err(PacketError::TooLarge(65535 + 28))
+    );
+}
+
+#[test]
+fn ipv6_extension_headers() {
+    let packet = Ipv6HeaderBuilder::new(v6("2001:db8::1"), v6("2001:db8::2"), protocol::UDP)
+        .traffic_class(0xb8)
+        .flow_label(0x12345)
+        .hop_limit(9)
+        .extension(protocol::HOP_BY_HOP, &[5, 2, 0, 0])
+        .extension(protocol::FRAGMENT, &[0, 1, 0xde, 0xad, 0xbe, 0xef])
+        .extension(protocol::DESTINATION_OPTIONS, &[])
+        .build(b"datagram")
+        .unwrap();
+    let header = Ipv6Header::parse(&packet).unwrap();
+    assert_eq!(header.version(), 6);
+    assert_eq!(header.traffic_class(), 0xb8);
+    assert_eq!(header.flow_label(), 0x12345);
+    assert_eq!(header.hop_limit(), 9);
+    assert_eq!(header.payload_len(), 8 + 8 + 8 + 8);
+    assert_eq!(header.next_header(), protocol::HOP_BY_HOP);
+    assert_eq!(header.source(), v6("2001:db8::1"));
+    assert_eq!(header.destination(), v6("2001:db8::2"));
+    let kinds: Vec<u8> = header.extension_headers().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            protocol::HOP_BY_HOP,
+            protocol::FRAGMENT,
+            protocol::DESTINATION_OPTIONS
+        ]
+    );
+    let fragment = header.extension_headers().nth(1).unwrap();
+    assert_eq!(fragment.fragment(), Some((0, true, 0xdead_beef)));
+    assert_eq!(header.upper_layer_protocol(), protocol::UDP);
+    assert_eq!(header.upper_layer_payload(), b"datagram");
+    assert_eq!(
+        Ipv6HeaderBuilder::from_header(&header).build(b"datagram"),
+        Ok(packet.clone())
+    );
+
+    // the last extension header cut off
+    assert!(matches!(
+        Ipv6Header::parse(&packet[..60]),
+        Err(PacketError::Truncated { .. })
+    ));
+    assert_eq!(
+        Ipv6HeaderBuilder::new(v6("::1"), v6("::1"), protocol::TCP)
+            .extension(protocol::ROUTING, &[0; 5])
+            .build(b""),
+        Err(PacketError::BadExtension {
+            kind: protocol::ROUTING,
+            len: 5
+        })
+    );
+    assert_eq!(
+        Ipv6HeaderBuilder::new(v6("::1"), v6("::1"), protocol::TCP)
+            .extension(protocol::TCP, &[0; 6])
+            .build(b""),
+        Err(PacketError::BadExtension {
+            kind: protocol::TCP,
+            len: 6
+        })
+    );
+}
+
+#[test]
+fn a_later_fragment_ends_the_walk() {
+    // fragment data that happens to look like a destination options header claiming 1608 bytes
+    let data = [protocol::TCP, 200, 1, 2, 3, 4, 5, 6];
+    let first = Ipv6HeaderBuilder::new(v6("::1"), v6("::2"), protocol::DESTINATION_OPTIONS)
+        .extension(protocol::FRAGMENT, &[0, 1, 0, 0, 0, 7])
+        .build(&data)
+        .unwrap();
+    // in the first fragment the header is real, and it does not fit
+    assert!(matches!(
+        Ipv6Header::parse(&first),
+        Err(PacketError::Truncated { .. })
+    ));
+
+    // at offset 1 (8 bytes) the same bytes are the middle of the original payload
+    let later = Ipv6HeaderBuilder::new(v6("::1"), v6("::2"), protocol::DESTINATION_OPTIONS)
+        .extension(protocol::FRAGMENT, &[0, 0x08, 0, 0, 0, 7])
+        .build(&data)
+        .unwrap();
+    let header = Ipv6Header::parse(&later).unwrap();
+    assert_eq!(header.upper_layer_protocol(), protocol::DESTINATION_OPTIONS);
+    assert_eq!(header.upper_layer_payload(), data);
+    let fragments: Vec<_> = header
+        .extension_headers()
+        .map(|e| e.fragment().unwrap())
+        .collect();
+    assert_eq!(fragments, [(1, false, 7)]);
+}
+
+#[test]
+fn either_version() {
+    let v4 = Ipv4HeaderBuilder::new(
+        Ipv4Addr::new(10, 0, 0, 1),
+        Ipv4Addr::new(10, 0, 0, 2),
+        protocol::ICMP,
+    )
+    .build(b"ping")
+    .unwrap();
+    let header = IpHeader::parse(&v4).unwrap();
+    assert_eq!(header.source(), IpAddr::V4(10, 0, 0, 1));
+    assert_eq!(header.destination(), IpAddr::V4(10, 0, 0, 2));
+    assert_eq!(
+        (header.protocol(), header.payload()),
+        (protocol::ICMP, &b"ping"[..])
+    );
+
+    let v6 = Ipv6HeaderBuilder::new(v6("fe80::1"), v6("ff02::1"), protocol::ICMPV6)
+        .build(b"ping")
+        .unwrap();
+    let header = IpHeader::parse(&v6).unwrap();
+    assert_eq!(header.destination().to_string(), "ff02::1");
+    assert_eq!(header.protocol(), protocol::ICMPV6);
+
+    assert_eq!(IpHeader::parse(&[0x50]), Err(PacketError::Version(5)));
+    assert!(matches!(
+        IpHeader::parse(&[]),
+        Err(PacketError::Truncated { .. })
+    ));
+}
+
+/// A capture file: the global header, then each record as (seconds, fraction, length on the
+/// wire, bytes kept).
+fn capture(
+    big_endian: bool,
+    nanos: bool,
+    link: u32,
+    records: &[(u32, u32, u32, &[u8])],
+) -> Vec<u8> {
+    let u16_bytes = |n: u16| {
+        if big_endian {
+            n.to_be_bytes()
+        } else {
+            n.to_le_bytes()
+        }
+    };
+    let u32_bytes = |n: u32| {
+        if big_endian {
+            n.to_be_bytes()
+        } else {
+            n.to_le_bytes()
+        }
+    };
+    let mut file = Vec::new();
+    file.extend_from_slice(&u32_bytes(if nanos { 0xa1b2_3c4d } else { 0xa1b2_c3d4 }));
+    file.extend_from_slice(&u16_bytes(2));
+    file.extend_from_slice(&u16_bytes(4));
+    file.extend_from_slice(&[0; 8]);
+    file.extend_from_slice(&u32_bytes(65535));
+    file.extend_from_slice(&u32_bytes(link));
+    for &(seconds, fraction, original_len, data) in records {
+        file.extend_from_slice(&u32_bytes(seconds));
+        file.extend_from_slice(&u32_bytes(fraction));
+        file.extend_from_slice(&u32_bytes(data.len() as u32));
+        file.extend_from_slice(&u32_bytes(original_len));
+        file.extend_from_slice(data);
+    }
+    file
+}
+
+fn ipv4_packet() -> Vec<u8> {
+    Ipv4HeaderBuilder::new(
+        Ipv4Addr::new(192, 0, 2, 1),
+        Ipv4Addr::new(192, 0, 2, 2),
+        protocol::UDP,
+    )
+    .build(&[0; 12])
+    .unwrap()
+}
+
+#[test]
+fn reading_captures() {
+    let packet = ipv4_packet();
+    // Ethernet with a VLAN tag, then an ARP frame
+    let mut tagged = vec![0xff; 12];
+    tagged.extend_from_slice(&[0x81, 0x00, 0x00, 0x05, 0x08, 0x00]);
+    tagged.extend_from_slice(&packet);
+    let mut arp = vec![0xff; 12];
+    arp.extend_from_slice(&[0x08, 0x06, 0, 1]);
+    let file = capture(
+        false,
+        false,
+        link_type::ETHERNET,
+        &[
+            (1_700_000_000, 250_000, tagged.len() as u32, &tagged),
+            (1_700_000_001, 0, 1514, &arp),
+        ],
+    );
+    let mut reader = PcapReader::new(&file[..]).unwrap();
+    assert_eq!(reader.version(), (2, 4));
+    assert_eq!(reader.snaplen(), 65535);
+    assert_eq!(reader.link_type(), link_type::ETHERNET);
+    assert!(!reader.nanosecond_timestamps());
+
+    let record = reader.read_record().unwrap().unwrap();
+    assert_eq!(record.timestamp, Duration::new(1_700_000_000, 250_000_000));
+    assert!(!record.is_truncated());
+    let header = record.ip_header().unwrap();
+    assert_eq!(header.source(), IpAddr::V4(192, 0, 2, 1));
+    assert_eq!(header.payload(), [0; 12]);
+
+    let record = reader.read_record().unwrap().unwrap();
+    assert!(record.is_truncated());
+    assert!(matches!(record.ip_header(), Err(PcapError::NotIp(0x0806))));
+    assert!(reader.read_record().unwrap().is_none());
+
+    // big-endian with nanoseconds, bare IP
+    let file = capture(true, true, link_type::RAW, &[(5, 7, 32, &packet)]);
+    let records: Vec<_> = PcapReader::new(&file[..])
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].timestamp, Duration::new(5, 7));
+    assert_eq!(
+        records[0].ip_header().unwrap().destination(),
+        IpAddr::V4(192, 0, 2, 2)
+    );
+
+    // Linux cooked capture and BSD loopback
+    let mut cooked = vec![0; 14];
+    cooked.extend_from_slice(&[0x08, 0x00]);
+    cooked.extend_from_slice(&packet);
+    let file = capture(false, false, link_type::LINUX_SLL, &[(0, 0, 48, &cooked)]);
+    let record = PcapReader::new(&file[..]).unwrap().next().unwrap().unwrap();
+    assert!(record.ip_header().is_ok());
+    let mut looped = 2u32.to_le_bytes().to_vec();
+    looped.extend_from_slice(&packet);
+    let file = capture(false, false, link_type::NULL, &[(0, 0, 36, &looped)]);
+    let record = PcapReader::new(&file[..]).unwrap().next().unwrap().unwrap();
+    assert!(record.ip_header().is_ok());
+    let file = capture(false, false, 147, &[(0, 0, 32, &packet)]);
+    let record = PcapReader::new(&file[..]).unwrap().next().unwrap().unwrap();
+    assert!(matches!(
+        record.ip_header(),
+        Err(PcapError::UnsupportedLinkType(147))
+    ));
+}
+
+#[test]
+fn broken_captures() {
+    let packet = ipv4_packet();
+    assert!(matches!(
+        PcapReader::new(&[0u8; 24][..]),
+        Err(PcapError::BadMagic(0))
+    ));
+    assert!(matches!(
+        PcapReader::new(&[0xd4, 0xc3, 0xb2][..]),
+        Err(PcapError::Io(_))
+    ));
+
+    // a record cut off part way, then iteration stops
+    let file = capture(false, false, link_type::RAW, &[(0, 0, 32, &packet)]);
+    let mut reader = PcapReader::new(&file[..file.len() - 1]).unwrap();
+    match reader.next() {
+        Some(Err(PcapError::Io(e))) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+        other => panic!("expected an early end, got {:?}", other),
+    }
+    assert!(reader.next().is_none());
+    let mut reader = PcapReader::new(&file[..30]).unwrap();
+    assert!(matches!(reader.read_record(), Err(PcapError::Io(_))));
+
+    let mut huge = capture(false, false, link_type::RAW, &[]);
+    huge.extend_from_slice(&[0; 8]);
+    huge.extend_from_slice(&u32::MAX.to_le_bytes());
+    huge.extend_from_slice(&u32::MAX.to_le_bytes());
+    let mut reader = PcapReader::new(&huge[..]).unwrap();
+    assert!(matches!(
+        reader.read_record(),
+        Err(PcapError::RecordTooLarge(u32::MAX))
+    ));
+}
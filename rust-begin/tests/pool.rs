@@ -0,0 +1,262 @@
+//! Leasing addresses out of a subnet, from one thread and from many.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rust_begin::enums::IpAddr;
+use rust_begin::net::{IpPool, LeaseFileError, PoolError};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+fn pool(network: &str) -> IpPool {
+    IpPool::new(network.parse().unwrap())
+}
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs)
+}
+
+#[test]
+fn allocating_skips_reserved_and_leased_addresses() {
+    let pool = pool("192.0.2.0/29");
+    pool.reserve(&ip("192.0.2.1"), &ip("192.0.2.2"), at(0))
+        .unwrap();
+    let a = pool.allocate("a", HOUR, at(0)).unwrap();
+    assert_eq!(a.addr(), &ip("192.0.2.3"));
+    assert_eq!(a.expires(), at(3600));
+    let b = pool.allocate("b", HOUR, at(0)).unwrap();
+    assert_eq!(b.addr(), &ip("192.0.2.4"));
+    // asking again renews rather than taking a second address
+    let again = pool.allocate("a", HOUR, at(10)).unwrap();
+    assert_eq!((again.addr(), again.expires()), (a.addr(), at(3610)));
+
+    for client in ["c", "d"] {
+        pool.allocate(client, HOUR, at(0)).unwrap();
+    }
+    assert_eq!(pool.allocate("e", HOUR, at(0)), Err(PoolError::Exhausted));
+    assert_eq!(
+        pool.request("e", &ip("192.0.2.3"), HOUR, at(0)),
+        Err(PoolError::Conflict {
+            addr: ip("192.0.2.3"),
+            holder: "a".to_string()
+        })
+    );
+    assert_eq!(
+        pool.request("e", &ip("192.0.2.2"), HOUR, at(0)),
+        Err(PoolError::Reserved(ip("192.0.2.2")))
+    );
+    assert_eq!(
+        pool.request("e", &ip("192.0.2.7"), HOUR, at(0)),
+        Err(PoolError::NotInPool(ip("192.0.2.7")))
+    );
+    assert_eq!(
+        pool.allocate("", HOUR, at(0)),
+        Err(PoolError::InvalidClient(String::new()))
+    );
+}
+
+#[test]
+fn renewing_and_releasing() {
+    let pool = pool("192.0.2.0/24");
+    let lease = pool.allocate("a", HOUR, at(0)).unwrap();
+    let addr = lease.addr().clone();
+    assert_eq!(lease.remaining(at(600)), Duration::from_secs(3000));
+
+    let renewed = pool.renew("a", &addr, HOUR, at(1800)).unwrap();
+    assert_eq!(renewed.expires(), at(5400));
+    // an expired lease can be renewed while nobody else has the address
+    let late = pool.renew("a", &addr, HOUR, at(6000)).unwrap();
+    assert_eq!(late.expires(), at(9600));
+    assert_eq!(
+        pool.renew("b", &addr, HOUR, at(0)),
+        Err(PoolError::NotLeased(addr.clone()))
+    );
+
+    assert_eq!(
+        pool.release("b", &addr),
+        Err(PoolError::NotLeased(addr.clone()))
+    );
+    assert_eq!(pool.release("a", &addr).unwrap().client(), "a");
+    assert!(pool.lease(&addr).is_none());
+    assert!(pool.lease_for_client("a").is_none());
+    // a released address is not the next one handed out
+    assert_ne!(pool.allocate("b", HOUR, at(0)).unwrap().addr(), &addr);
+}
+
+#[test]
+fn expired_leases_are_reclaimed() {
+    let pool = pool("192.0.2.0/30");
+    let a = pool.allocate("a", HOUR, at(0)).unwrap();
+    pool.allocate("b", 2 * HOUR, at(0)).unwrap();
+    assert_eq!(pool.allocate("c", HOUR, at(0)), Err(PoolError::Exhausted));
+    assert!(!a.is_expired(at(3599)));
+    assert!(a.is_expired(at(3600)));
+
+    // an allocation takes over an expired lease
+    let c = pool.allocate("c", 2 * HOUR, at(3600)).unwrap();
+    assert_eq!(c.addr(), a.addr());
+    assert!(pool.lease_for_client("a").is_none());
+
+    let expired = pool.expire(at(7200));
+    let clients: Vec<&str> = expired.iter().map(|lease| lease.client()).collect();
+    assert_eq!(clients, ["b"]);
+    assert_eq!(pool.leases().len(), 1);
+    assert!(pool.expire(at(7200)).is_empty());
+}
+
+#[test]
+fn a_lease_too_long_for_the_clock_is_refused() {
+    let pool = pool("192.0.2.0/24");
+    assert_eq!(
+        pool.allocate("a", Duration::MAX, at(0)),
+        Err(PoolError::TtlTooLong(Duration::MAX))
+    );
+    let lease = pool.allocate("a", HOUR, at(0)).unwrap();
+    let addr = lease.addr().clone();
+    assert_eq!(
+        pool.allocate("a", Duration::MAX, at(0)),
+        Err(PoolError::TtlTooLong(Duration::MAX))
+    );
+    assert_eq!(
+        pool.renew("a", &addr, Duration::MAX, at(0)),
+        Err(PoolError::TtlTooLong(Duration::MAX))
+    );
+    assert_eq!(
+        pool.request("b", &ip("192.0.2.200"), Duration::MAX, at(0)),
+        Err(PoolError::TtlTooLong(Duration::MAX))
+    );
+    // nothing changed on the way
+    assert_eq!(pool.leases(), [lease]);
+}
+
+#[test]
+fn threads_never_share_an_address() {
+    const THREADS: usize = 8;
+    const EACH: usize = 30;
+    let pool = Arc::new(pool("10.0.0.0/24"));
+    let start = Arc::new(Barrier::new(THREADS));
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let pool = Arc::clone(&pool);
+            let start = Arc::clone(&start);
+            thread::spawn(move || {
+                start.wait();
+                (0..EACH)
+                    .map(|i| {
+                        let client = format!("client-{}-{}", t, i);
+                        pool.allocate(&client, HOUR, at(0)).unwrap()
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let leases: Vec<_> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    let addrs: HashSet<String> = leases.iter().map(|l| l.addr().to_string()).collect();
+    assert_eq!(addrs.len(), THREADS * EACH);
+    assert_eq!(pool.leases().len(), THREADS * EACH);
+    for lease in &leases {
+        assert_eq!(pool.lease_for_client(lease.client()).as_ref(), Some(lease));
+    }
+}
+
+#[test]
+fn racing_requests_for_one_address_have_one_winner() {
+    const THREADS: usize = 16;
+    let pool = Arc::new(pool("10.0.0.0/24"));
+    let start = Arc::new(Barrier::new(THREADS));
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let pool = Arc::clone(&pool);
+            let start = Arc::clone(&start);
+            thread::spawn(move || {
+                start.wait();
+                pool.request(&format!("client-{}", t), &ip("10.0.0.42"), HOUR, at(0))
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    let winners: Vec<_> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    assert_eq!(winners.len(), 1);
+    let holder = winners[0].client().to_string();
+    for result in &results {
+        match result {
+            Ok(_) => {}
+            Err(e) => assert_eq!(
+                e,
+                &PoolError::Conflict {
+                    addr: ip("10.0.0.42"),
+                    holder: holder.clone()
+                }
+            ),
+        }
+    }
+}
+
+#[test]
+fn leases_survive_saving_and_loading() {
+    let dir = std::env::temp_dir().join(format!("rust-begin-pool-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("leases");
+
+    let pool = pool("2001:db8::/120");
+    pool.reserve(&ip("2001:db8::1"), &ip("2001:db8::f"), at(0))
+        .unwrap();
+    pool.allocate("aa:bb:cc:dd:ee:ff", HOUR, at(0)).unwrap();
+    pool.request(
+        "client two",
+        &ip("2001:db8::80"),
+        HOUR,
+        UNIX_EPOCH + Duration::new(5, 7),
+    )
+    .unwrap();
+    pool.save(&path).unwrap();
+
+    let loaded = IpPool::load(&path).unwrap();
+    assert_eq!(loaded.network(), pool.network());
+    assert_eq!(loaded.reserved(), pool.reserved());
+    assert_eq!(loaded.leases(), pool.leases());
+    // and allocation carries on around what was loaded
+    let next = loaded.allocate("third", HOUR, at(0)).unwrap();
+    assert!(!pool
+        .leases()
+        .iter()
+        .any(|lease| lease.addr() == next.addr()));
+
+    std::fs::write(
+        &path,
+        "network 192.0.2.0/24\nlease 192.0.2.300 1.000000000 a\n",
+    )
+    .unwrap();
+    assert!(matches!(
+        IpPool::load(&path),
+        Err(LeaseFileError::Parse { line: 2, .. })
+    ));
+    std::fs::write(&path, "lease 192.0.2.3 1.000000000 a\n").unwrap();
+    assert!(matches!(
+        IpPool::load(&path),
+        Err(LeaseFileError::Parse { line: 1, .. })
+    ));
+    // an expiry past what the clock can hold is an error, not a panic
+    std::fs::write(
+        &path,
+        "network 192.0.2.0/24\nlease 192.0.2.5 18446744073709551615.000000000 c\n",
+    )
+    .unwrap();
+    match IpPool::load(&path) {
+        Err(LeaseFileError::Parse { line, reason }) => {
+            assert_eq!(line, 2);
+            assert_eq!(reason, "bad expiry \"18446744073709551615.000000000\"");
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,221 @@
+//! State quarters: issue years, collections, and reading and writing them as CSV.
+
+use rust_begin::enums::{Coin, UsState};
+use rust_begin::quarters::{
+    issue_year, CsvError, MintMark, QuarterCollection, QuarterError, StateQuarter,
+};
+
+fn issued_in(year: u16) -> Vec<UsState> {
+    UsState::ALL
+        .into_iter()
+        .filter(|&state| issue_year(state) == year)
+        .collect()
+}
+
+fn parse_reason(text: &str) -> (usize, String) {
+    match QuarterCollection::from_csv(text) {
+        Err(CsvError::Parse { line, reason }) => (line, reason),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn five_a_year_in_order_of_admission() {
+    use UsState::*;
+    let mut first = issued_in(1999);
+    first.sort_by_key(|state| state.admitted());
+    assert_eq!(
+        first,
+        [Delaware, Pennsylvania, NewJersey, Georgia, Connecticut]
+    );
+    assert_eq!(
+        issued_in(2001),
+        [Kentucky, NewYork, NorthCarolina, RhodeIsland, Vermont]
+    );
+    // the Dakotas joined on the same day, North first, and close 2006 ahead of Montana
+    assert_eq!(
+        issued_in(2006),
+        [Colorado, Nebraska, Nevada, NorthDakota, SouthDakota]
+    );
+    assert_eq!(issue_year(Montana), 2007);
+    assert_eq!(
+        issued_in(2008),
+        [Alaska, Arizona, Hawaii, NewMexico, Oklahoma]
+    );
+    assert_eq!(
+        issued_in(2009),
+        [
+            DistrictOfColumbia,
+            AmericanSamoa,
+            Guam,
+            NorthernMarianaIslands,
+            PuertoRico,
+            UsVirginIslands
+        ]
+    );
+    for year in 1999..=2008 {
+        assert_eq!(issued_in(year).len(), 5, "{}", year);
+    }
+}
+
+#[test]
+fn the_year_has_to_be_the_states() {
+    assert_eq!(
+        StateQuarter::new(UsState::Ohio, 2001, MintMark::Denver),
+        Err(QuarterError::WrongYear {
+            state: UsState::Ohio,
+            year: 2001,
+            expected: 2002
+        })
+    );
+    let ohio = StateQuarter::new(UsState::Ohio, 2002, MintMark::Denver).unwrap();
+    assert_eq!(ohio, StateQuarter::of(UsState::Ohio, MintMark::Denver));
+    assert_eq!(ohio.coin(), Coin::Quarter(UsState::Ohio));
+    assert_eq!(ohio.to_string(), "Ohio 2002-D");
+    assert_eq!(
+        QuarterError::WrongYear {
+            state: UsState::Ohio,
+            year: 2001,
+            expected: 2002
+        }
+        .to_string(),
+        "there is no 2001 Ohio quarter, it came out in 2002"
+    );
+
+    assert_eq!("s".parse(), Ok(MintMark::SanFrancisco));
+    assert_eq!(
+        "X".parse::<MintMark>(),
+        Err(QuarterError::UnknownMint("X".to_string()))
+    );
+    assert_eq!(
+        parse_reason("Ohio,2001,P\n"),
+        (
+            1,
+            "there is no 2001 Ohio quarter, it came out in 2002".to_string()
+        )
+    );
+}
+
+#[test]
+fn csv_round_trip() {
+    let mut collection = QuarterCollection::new();
+    collection.add_many(StateQuarter::of(UsState::NewYork, MintMark::Denver), 3);
+    collection.add(StateQuarter::of(UsState::Delaware, MintMark::Philadelphia));
+    collection.add(StateQuarter::of(UsState::Delaware, MintMark::Philadelphia));
+    collection.add(StateQuarter::of(UsState::Guam, MintMark::SanFrancisco));
+    let csv = collection.to_csv();
+    assert_eq!(
+        csv,
+        "state,year,mint,count\n\
+         Delaware,1999,P,2\n\
+         New York,2001,D,3\n\
+         Guam,2009,S,1\n"
+    );
+    assert_eq!(QuarterCollection::from_csv(&csv).unwrap(), collection);
+    assert_eq!(
+        QuarterCollection::from_csv(&QuarterCollection::new().to_csv()).unwrap(),
+        QuarterCollection::new()
+    );
+}
+
+#[test]
+fn quoted_fields() {
+    let collection = QuarterCollection::from_csv("\"New York\", 2001 ,\"D\",\"2\"\n").unwrap();
+    assert_eq!(
+        collection.count(&StateQuarter::of(UsState::NewYork, MintMark::Denver)),
+        2
+    );
+
+    // a comma inside quotes does not split the field
+    assert_eq!(
+        parse_reason("\"Ohio,2002\",P\n"),
+        (
+            1,
+            "expected state,year,mint,count, found 2 fields".to_string()
+        )
+    );
+    // and "" is a quote
+    let (line, reason) = parse_reason("state,year,mint,count\n\"Ohio \"\"Buckeye\"\"\",2002,P\n");
+    assert_eq!(line, 2);
+    assert!(reason.contains(r#""Ohio \"Buckeye\"""#), "{}", reason);
+
+    assert_eq!(
+        parse_reason("\"Ohio,2002,P\n"),
+        (1, "a quoted field is not closed".to_string())
+    );
+    assert_eq!(
+        parse_reason("\"Ohio\"x,2002,P\n"),
+        (1, "text after a quoted field".to_string())
+    );
+}
+
+#[test]
+fn imports_are_forgiving() {
+    let text = "\
+ny,2001,d
+NY,2001,D,2
+
+De,1999,p,0
+Delaware,1999,P,1
+";
+    let collection = QuarterCollection::from_csv(text).unwrap();
+    // rows for the same quarter add up, and a missing count is one coin
+    assert_eq!(
+        collection.count(&StateQuarter::of(UsState::NewYork, MintMark::Denver)),
+        3
+    );
+    assert_eq!(
+        collection.count(&StateQuarter::of(UsState::Delaware, MintMark::Philadelphia)),
+        1
+    );
+    assert_eq!(collection.len(), 4);
+
+    assert_eq!(parse_reason("Ohio,2002,P,-1\n").0, 1);
+    assert_eq!(parse_reason("Ohio,two,P\n").0, 1);
+    assert_eq!(parse_reason("Ohio,2002,P\nAtlantis,2002,P\n").0, 2);
+}
+
+#[test]
+fn what_is_missing_and_what_is_spare() {
+    let mut collection: QuarterCollection = UsState::states()
+        .filter(|&state| state != UsState::Texas && state != UsState::Iowa)
+        .map(|state| StateQuarter::of(state, MintMark::Philadelphia))
+        .collect();
+    assert_eq!(collection.missing_states(), [UsState::Iowa, UsState::Texas]);
+    assert_eq!(collection.missing().len(), 2 + 6);
+    assert_eq!(collection.duplicate_count(), 0);
+
+    let ohio = StateQuarter::of(UsState::Ohio, MintMark::Philadelphia);
+    let ohio_denver = StateQuarter::of(UsState::Ohio, MintMark::Denver);
+    collection.add_many(ohio, 2);
+    collection.add(ohio_denver);
+    assert_eq!(collection.duplicates().collect::<Vec<_>>(), [(ohio, 2)]);
+    assert_eq!(collection.count_state(UsState::Ohio), 4);
+    assert_eq!(collection.len(), 48 + 3);
+    assert_eq!(collection.face_value_cents(), 51 * 25);
+
+    assert!(collection.remove(&ohio_denver));
+    assert!(!collection.remove(&ohio_denver));
+    assert_eq!(collection.count(&ohio_denver), 0);
+    assert!(QuarterCollection::new().is_empty());
+    assert_eq!(QuarterCollection::new().face_value_cents(), 0);
+}
+
+#[test]
+fn counts_past_u32_do_not_overflow() {
+    let collection = QuarterCollection::from_csv(
+        "state,year,mint,count\nDelaware,1999,P,4294967295\nDelaware,1999,D,1\n",
+    )
+    .unwrap();
+    let most = u64::from(u32::MAX);
+    assert_eq!(collection.count_state(UsState::Delaware), most + 1);
+    assert_eq!(collection.len(), most + 1);
+    assert_eq!(collection.duplicate_count(), most - 1);
+    assert!(!collection.missing_states().contains(&UsState::Delaware));
+    assert_eq!(collection.face_value_cents(), (most + 1) * 25);
+
+    // and one quarter's own count stops at the most it can hold
+    let mut collection = collection;
+    let delaware = StateQuarter::of(UsState::Delaware, MintMark::Philadelphia);
+    assert_eq!(collection.add(delaware), u32::MAX);
+}
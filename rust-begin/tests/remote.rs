@@ -0,0 +1,203 @@
+//! The message server and client talking over 127.0.0.1.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use rust_begin::enums::Message;
+use rust_begin::message::{
+    CallError, Client, ClientError, DrawState, Outcome, Reply, Server, ServerHandle,
+};
+use rust_begin::structs::Color;
+
+fn server(max_connections: usize, read_timeout: Option<Duration>) -> ServerHandle {
+    Server::builder()
+        .max_connections(max_connections)
+        .read_timeout(read_timeout)
+        .bind("127.0.0.1:0")
+        .unwrap()
+        .spawn()
+        .unwrap()
+}
+
+/// Waits for the server to notice connections coming and going.
+fn wait_for_connections(server: &ServerHandle, count: usize) {
+    for _ in 0..500 {
+        if server.connections() == count {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("{} connections, expected {}", server.connections(), count);
+}
+
+#[test]
+fn messages_are_applied_and_acknowledged() {
+    let server = server(4, None);
+    let mut client = Client::connect(server.local_addr()).unwrap();
+
+    assert_eq!(
+        client.send(&Message::Move { x: 3, y: -4 }).unwrap(),
+        Outcome::Moved {
+            from: (0, 0),
+            to: (3, -4)
+        }
+    );
+    assert_eq!(
+        client.send(&Message::Write("héllo".to_string())).unwrap(),
+        Outcome::Wrote { len: 6 }
+    );
+    assert_eq!(
+        client.send(&Message::ChangeColor(255, 0, 128)).unwrap(),
+        Outcome::ColorChanged {
+            from: Color(0, 0, 0),
+            to: Color(255, 0, 128)
+        }
+    );
+
+    let state = server.state();
+    assert_eq!(state.cursor, (3, -4));
+    assert_eq!(state.text, "héllo");
+    assert_eq!(state.color, Color(255, 0, 128));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn errors_are_reported_and_change_nothing() {
+    let server = server(4, None);
+    let mut client = Client::connect(server.local_addr()).unwrap();
+
+    match client.send(&Message::ChangeColor(0, 256, 0)) {
+        Err(ClientError::Rejected(CallError::ColorOutOfRange {
+            component: 1,
+            value: 256,
+        })) => {}
+        other => panic!("{:?}", other),
+    }
+    client.send(&Message::Move { x: i32::MAX, y: 0 }).unwrap();
+    match client.send(&Message::Move { x: 1, y: 0 }) {
+        Err(ClientError::Rejected(CallError::CursorOverflow { dx: 1, dy: 0 })) => {}
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(client.send(&Message::Quit).unwrap(), Outcome::Stopped);
+    match client.send(&Message::Write("late".to_string())) {
+        Err(ClientError::Rejected(CallError::Stopped)) => {}
+        other => panic!("{:?}", other),
+    }
+
+    let state = server.state();
+    assert_eq!(state.cursor, (i32::MAX, 0));
+    assert_eq!(state.color, DrawState::new().color);
+    assert!(state.text.is_empty());
+    assert!(!state.running);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn clients_share_one_state() {
+    let server = server(8, None);
+    let addr = server.local_addr();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr).unwrap();
+                for _ in 0..100 {
+                    client.send(&Message::Move { x: 1, y: 2 }).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(server.state().cursor, (400, 800));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn connections_over_the_limit_are_turned_away() {
+    let server = server(2, None);
+    let first = Client::connect(server.local_addr()).unwrap();
+    let mut second = Client::connect(server.local_addr()).unwrap();
+    wait_for_connections(&server, 2);
+
+    assert!(matches!(
+        Client::connect(server.local_addr()),
+        Err(ClientError::Busy)
+    ));
+    second.send(&Message::Move { x: 1, y: 1 }).unwrap();
+
+    drop(first);
+    wait_for_connections(&server, 1);
+    let mut third = Client::connect(server.local_addr()).unwrap();
+    third.send(&Message::Move { x: 1, y: 1 }).unwrap();
+    assert_eq!(server.state().cursor, (2, 2));
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn idle_connections_time_out() {
+    let server = server(1, Some(Duration::from_millis(100)));
+    let mut client = Client::connect(server.local_addr()).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    wait_for_connections(&server, 0);
+
+    assert!(matches!(client.read_reply(), Ok(Reply::TimedOut)));
+    assert!(matches!(client.read_reply(), Err(ClientError::Closed)));
+    // the slot has been given back
+    let mut client = Client::connect(server.local_addr()).unwrap();
+    client.send(&Message::Quit).unwrap();
+    server.shutdown().unwrap();
+}
+
+/// Reads the server's whole side of a raw connection.
+fn raw_exchange(server: &ServerHandle, input: &[u8]) -> Vec<u8> {
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream.write_all(input).unwrap();
+    stream.shutdown(std::net::Shutdown::Write).unwrap();
+    let mut output = Vec::new();
+    stream.read_to_end(&mut output).unwrap();
+    output
+}
+
+#[test]
+fn malformed_input_gets_an_error_reply() {
+    let server = server(4, None);
+
+    // header, ready, then malformed for the bad magic
+    let output = raw_exchange(&server, b"XYZ\x01");
+    assert_eq!(&output[..6], b"RBR\x01\x01\x00");
+    assert_eq!(output[7], 3);
+
+    // an unknown tag, a body that does not decode, then a good message
+    let output = raw_exchange(&server, b"RBM\x01\x01\x09\x02\x01\x02\x03\x01\x02\x04");
+    let replies = &output[6..];
+    assert_eq!(replies[1], 3, "{:?}", replies);
+    let second = 1 + replies[0] as usize;
+    assert_eq!(replies[second + 1], 3, "{:?}", replies);
+    let third = second + 1 + replies[second] as usize;
+    assert_eq!(&replies[third..], b"\x06\x01\x01\x00\x00\x02\x04");
+
+    // a frame cut short ends the connection
+    let output = raw_exchange(&server, b"RBM\x01\x05\x01");
+    assert_eq!(output[7], 3);
+    assert_eq!(output.len(), 7 + output[6] as usize);
+    server.shutdown().unwrap();
+}
+
+#[test]
+fn only_loopback_addresses_are_served() {
+    let error = Server::bind("0.0.0.0:0").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(Server::bind("localhost:0").is_ok());
+}
+
+#[test]
+fn shutdown_closes_open_connections() {
+    let server = server(4, None);
+    let mut client = Client::connect(server.local_addr()).unwrap();
+    wait_for_connections(&server, 1);
+    server.shutdown().unwrap();
+    assert!(client.send(&Message::Move { x: 1, y: 1 }).is_err());
+}
@@ -0,0 +1,225 @@
+//! `RoutingTable`: adding and removing routes, their order, and longest-prefix lookups.
+
+mod common;
+
+use rust_begin::enums::{IpAddr, IpAddrKind, Ipv6Addr};
+use rust_begin::net::{IpNetwork, NextHop, RoutingTable};
+
+use common::Rng;
+
+impl Rng {
+    /// An address near one of a few fixed ones, so that the prefixes nest and overlap.
+    fn addr(&mut self, kind: IpAddrKind) -> IpAddr {
+        const NEAR: [u128; 4] = [0, 0x0a00_0000, 0xc0a8_0100, !0];
+        let bits = u32::from(kind.bit_len());
+        let random = u128::from(self.next()) << 64 | u128::from(self.next());
+        let flip = random >> (128 - bits) >> self.below(u64::from(bits));
+        let near = NEAR[self.below(4) as usize] << (128 - bits) >> (128 - bits);
+        let addr = (near ^ flip) & (!0 >> (128 - bits));
+        match kind {
+            IpAddrKind::V4 => {
+                let [a, b, c, d] = (addr as u32).to_be_bytes();
+                IpAddr::V4(a, b, c, d)
+            }
+            IpAddrKind::V6 => IpAddr::V6(Ipv6Addr::from_bits(addr)),
+        }
+    }
+
+    fn network(&mut self, kind: IpAddrKind) -> IpNetwork {
+        let prefix_len = self.below(u64::from(kind.bit_len()) + 1) as u8;
+        IpNetwork::new(self.addr(kind), prefix_len).unwrap()
+    }
+}
+
+fn net(text: &str) -> IpNetwork {
+    text.parse().unwrap()
+}
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+/// The network `lookup` should find for `ip`, the slow way.
+fn scan<'a>(routes: &'a [(IpNetwork, usize)], ip: &IpAddr) -> Option<&'a (IpNetwork, usize)> {
+    routes
+        .iter()
+        .filter(|(network, _)| network.contains(ip))
+        .max_by_key(|(network, _)| network.prefix_len())
+}
+
+#[test]
+fn inserting_and_getting() {
+    let mut table = RoutingTable::new();
+    assert!(table.is_empty());
+    assert_eq!(table.insert(net("10.0.0.0/8"), "a"), None);
+    assert_eq!(table.insert(net("10.1.0.0/16"), "b"), None);
+    assert_eq!(table.insert(net("2001:db8::/32"), "c"), None);
+    assert_eq!(table.insert(net("10.0.0.0/8"), "d"), Some("a"));
+    assert_eq!(table.len(), 3);
+
+    assert_eq!(table.get(&net("10.0.0.0/8")), Some(&"d"));
+    assert_eq!(table.get(&net("10.0.0.0/9")), None);
+    assert_eq!(table.get(&net("10.1.0.0/24")), None);
+    assert_eq!(table.update(&net("10.1.0.0/16"), "e"), Some("b"));
+    assert_eq!(table.update(&net("10.2.0.0/16"), "f"), None);
+    assert_eq!(table.len(), 3);
+
+    assert_eq!(table.default_route(IpAddrKind::V4), None);
+    table.insert(net("0.0.0.0/0"), "g");
+    assert_eq!(table.default_route(IpAddrKind::V4), Some(&"g"));
+    assert_eq!(table.default_route(IpAddrKind::V6), None);
+    assert_eq!(
+        table.lookup(&ip("10.1.2.3")),
+        Some((net("10.1.0.0/16"), &"e"))
+    );
+    assert_eq!(
+        table.lookup(&ip("10.2.0.1")),
+        Some((net("10.0.0.0/8"), &"d"))
+    );
+    assert_eq!(
+        table.lookup(&ip("11.0.0.1")),
+        Some((net("0.0.0.0/0"), &"g"))
+    );
+    assert_eq!(table.lookup(&ip("2001:db9::1")), None);
+    // an IPv4 route never matches an IPv6 address
+    assert_eq!(table.lookup(&ip("::a01:203")), None);
+}
+
+#[test]
+fn removing_collapses_the_branches() {
+    let routes = [
+        "10.0.0.0/8",
+        "10.0.0.0/16",
+        "10.128.0.0/16",
+        "10.0.0.0/24",
+        "10.0.1.0/24",
+        "10.0.0.1/32",
+    ];
+    let mut table = RoutingTable::new();
+    for (i, route) in routes.iter().enumerate() {
+        table.insert(net(route), i);
+    }
+    assert_eq!(table.remove(&net("10.0.0.0/12")), None);
+    assert_eq!(table.remove(&net("10.64.0.0/16")), None);
+
+    // the /8 and the /16 sit between branches: without them every other route still resolves
+    for gone in ["10.0.0.0/8", "10.0.0.0/16"] {
+        let index = routes.iter().position(|&route| route == gone).unwrap();
+        assert_eq!(table.remove(&net(gone)), Some(index));
+        assert_eq!(table.remove(&net(gone)), None);
+    }
+    assert_eq!(table.len(), 4);
+    assert_eq!(
+        table.lookup(&ip("10.0.0.1")),
+        Some((net("10.0.0.1/32"), &5))
+    );
+    assert_eq!(
+        table.lookup(&ip("10.0.0.2")),
+        Some((net("10.0.0.0/24"), &3))
+    );
+    assert_eq!(
+        table.lookup(&ip("10.0.1.9")),
+        Some((net("10.0.1.0/24"), &4))
+    );
+    assert_eq!(
+        table.lookup(&ip("10.128.7.7")),
+        Some((net("10.128.0.0/16"), &2))
+    );
+    assert_eq!(table.lookup(&ip("10.0.2.0")), None);
+
+    for (i, route) in routes.iter().enumerate().skip(2) {
+        assert_eq!(table.remove(&net(route)), Some(i));
+    }
+    assert!(table.is_empty());
+    assert_eq!(table.iter().count(), 0);
+    assert_eq!(table.lookup(&ip("10.0.0.1")), None);
+}
+
+#[test]
+fn iteration_is_sorted() {
+    let mut table = RoutingTable::new();
+    for route in [
+        "2001:db8::/32",
+        "192.168.1.0/24",
+        "::/0",
+        "10.0.0.0/16",
+        "10.0.0.0/8",
+        "0.0.0.0/0",
+        "10.0.0.1/32",
+        "192.168.0.0/16",
+    ] {
+        table.insert(net(route), ());
+    }
+    let order: Vec<String> = table.iter().map(|(n, _)| n.to_string()).collect();
+    assert_eq!(
+        order,
+        [
+            "0.0.0.0/0",
+            "10.0.0.0/8",
+            "10.0.0.0/16",
+            "10.0.0.1/32",
+            "192.168.0.0/16",
+            "192.168.1.0/24",
+            "::/0",
+            "2001:db8::/32",
+        ]
+    );
+    let mut sorted: Vec<IpNetwork> = table.iter().map(|(n, _)| n).collect();
+    sorted.sort();
+    assert!(table.iter().map(|(n, _)| n).eq(sorted));
+}
+
+#[test]
+fn printing_like_ip_route() {
+    let mut table = RoutingTable::new();
+    table.insert(net("0.0.0.0/0"), NextHop::via(ip("192.168.1.1")));
+    table.insert(net("192.168.1.0/24"), NextHop::dev("eth0").with_metric(100));
+    table.insert(
+        net("10.0.0.7/32"),
+        NextHop::via(ip("10.0.0.1")).with_dev("tun0"),
+    );
+    assert_eq!(
+        table.to_string(),
+        "default via 192.168.1.1\n\
+         10.0.0.7 via 10.0.0.1 dev tun0\n\
+         192.168.1.0/24 dev eth0 metric 100\n"
+    );
+}
+
+#[test]
+fn lookups_match_a_linear_scan() {
+    let mut rng = Rng(0x7a11);
+    for kind in [IpAddrKind::V4, IpAddrKind::V6] {
+        for round in 0..20 {
+            let mut table = RoutingTable::new();
+            let mut routes: Vec<(IpNetwork, usize)> = Vec::new();
+            for i in 0..rng.below(200) as usize {
+                let network = rng.network(kind);
+                let old = table.insert(network, i);
+                let at = routes.iter().position(|(n, _)| *n == network);
+                assert_eq!(old, at.map(|at| routes[at].1));
+                match at {
+                    Some(at) => routes[at].1 = i,
+                    None => routes.push((network, i)),
+                }
+            }
+            // drop some again, so that lookups also run over collapsed branches
+            for _ in 0..rng.below(routes.len() as u64 + 1) {
+                let (network, value) = routes.swap_remove(rng.below(routes.len() as u64) as usize);
+                assert_eq!(table.remove(&network), Some(value));
+            }
+            assert_eq!(table.len(), routes.len(), "{:?} round {}", kind, round);
+
+            for _ in 0..500 {
+                let addr = rng.addr(kind);
+                let expected = scan(&routes, &addr).map(|(network, value)| (*network, value));
+                assert_eq!(table.lookup(&addr), expected, "{}", addr);
+            }
+            routes.sort();
+            assert!(table
+                .iter()
+                .map(|(network, &value)| (network, value))
+                .eq(routes.iter().copied()));
+        }
+    }
+}
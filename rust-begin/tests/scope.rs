@@ -0,0 +1,224 @@
+//! Address classification checked against the IANA special-purpose registries.
+
+use rust_begin::enums::IpAddr;
+use rust_begin::net::{IpNetwork, MulticastScope, Scope, SPECIAL_PURPOSE_BLOCKS};
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+/// (address, scope, globally reachable), with the edges of each registry block and the
+/// addresses just outside them.
+#[rustfmt::skip]
+const CASES: &[(&str, Scope, bool)] = &[
+    // IPv4 Special-Purpose Address Registry
+    ("0.0.0.0", Scope::Unspecified, false),
+    ("0.0.0.1", Scope::ThisNetwork, false),
+    ("0.255.255.255", Scope::ThisNetwork, false),
+    ("1.0.0.0", Scope::Global, true),
+    ("9.255.255.255", Scope::Global, true),
+    ("10.0.0.0", Scope::Private, false),
+    ("10.255.255.255", Scope::Private, false),
+    ("11.0.0.0", Scope::Global, true),
+    ("100.63.255.255", Scope::Global, true),
+    ("100.64.0.0", Scope::SharedAddressSpace, false),
+    ("100.127.255.255", Scope::SharedAddressSpace, false),
+    ("100.128.0.0", Scope::Global, true),
+    ("127.0.0.1", Scope::Loopback, false),
+    ("127.255.255.255", Scope::Loopback, false),
+    ("169.254.0.0", Scope::LinkLocal, false),
+    ("169.254.255.255", Scope::LinkLocal, false),
+    ("169.255.0.0", Scope::Global, true),
+    ("172.15.255.255", Scope::Global, true),
+    ("172.16.0.0", Scope::Private, false),
+    ("172.31.255.255", Scope::Private, false),
+    ("172.32.0.0", Scope::Global, true),
+    ("192.0.0.0", Scope::ProtocolAssignment, false),
+    ("192.0.0.7", Scope::ProtocolAssignment, false),
+    ("192.0.0.8", Scope::ProtocolAssignment, false),
+    ("192.0.0.9", Scope::Global, true),
+    ("192.0.0.10", Scope::Global, true),
+    ("192.0.0.11", Scope::ProtocolAssignment, false),
+    ("192.0.0.170", Scope::ProtocolAssignment, false),
+    ("192.0.0.171", Scope::ProtocolAssignment, false),
+    ("192.0.0.255", Scope::ProtocolAssignment, false),
+    ("192.0.2.0", Scope::Documentation, false),
+    ("192.0.2.255", Scope::Documentation, false),
+    ("192.0.3.0", Scope::Global, true),
+    ("192.31.196.1", Scope::Global, true),
+    ("192.52.193.1", Scope::Global, true),
+    ("192.88.99.1", Scope::Reserved, false),
+    ("192.167.255.255", Scope::Global, true),
+    ("192.168.0.0", Scope::Private, false),
+    ("192.168.255.255", Scope::Private, false),
+    ("192.169.0.0", Scope::Global, true),
+    ("192.175.48.1", Scope::Global, true),
+    ("198.17.255.255", Scope::Global, true),
+    ("198.18.0.0", Scope::Benchmarking, false),
+    ("198.19.255.255", Scope::Benchmarking, false),
+    ("198.20.0.0", Scope::Global, true),
+    ("198.51.100.1", Scope::Documentation, false),
+    ("203.0.113.1", Scope::Documentation, false),
+    ("239.255.255.254", Scope::Multicast(MulticastScope::SiteLocal), false),
+    ("240.0.0.0", Scope::Reserved, false),
+    ("255.255.255.254", Scope::Reserved, false),
+    ("255.255.255.255", Scope::Broadcast, false),
+    // IPv4 multicast
+    ("224.0.0.1", Scope::Multicast(MulticastScope::LinkLocal), false),
+    ("224.0.0.251", Scope::Multicast(MulticastScope::LinkLocal), false),
+    ("224.0.1.1", Scope::Multicast(MulticastScope::Global), true),
+    ("232.1.1.1", Scope::Multicast(MulticastScope::Global), true),
+    ("233.252.0.1", Scope::Multicast(MulticastScope::Global), true),
+    ("239.0.0.1", Scope::Multicast(MulticastScope::AdminLocal), false),
+    ("239.192.0.1", Scope::Multicast(MulticastScope::OrganizationLocal), false),
+    ("239.195.255.255", Scope::Multicast(MulticastScope::OrganizationLocal), false),
+    ("239.255.255.250", Scope::Multicast(MulticastScope::SiteLocal), false),
+    // IPv6 Special-Purpose Address Registry
+    ("::", Scope::Unspecified, false),
+    ("::1", Scope::Loopback, false),
+    ("::2", Scope::Ipv4Compatible, false),
+    ("::192.0.2.1", Scope::Ipv4Compatible, false),
+    ("::1:0:0", Scope::Global, true),
+    ("::ffff:0.0.0.0", Scope::Ipv4Mapped, false),
+    ("::ffff:192.168.1.1", Scope::Ipv4Mapped, false),
+    ("::ffff:255.255.255.255", Scope::Ipv4Mapped, false),
+    ("64:ff9b::192.0.2.1", Scope::Translation, true),
+    ("64:ff9b:1::1", Scope::Translation, false),
+    ("100::", Scope::DiscardOnly, false),
+    ("100::ffff:ffff:ffff:ffff", Scope::DiscardOnly, false),
+    ("100:0:0:1::", Scope::Global, true),
+    ("2001::", Scope::Teredo, false),
+    ("2001:0:ffff:ffff:ffff:ffff:ffff:ffff", Scope::Teredo, false),
+    ("2001:1::1", Scope::Global, true),
+    ("2001:1::2", Scope::Global, true),
+    ("2001:1::3", Scope::Global, true),
+    ("2001:1::4", Scope::ProtocolAssignment, false),
+    ("2001:2::", Scope::Benchmarking, false),
+    ("2001:2:0:ffff::1", Scope::Benchmarking, false),
+    ("2001:2:1::", Scope::ProtocolAssignment, false),
+    ("2001:3::1", Scope::Global, true),
+    ("2001:4:112::1", Scope::Global, true),
+    ("2001:10::1", Scope::Orchid, false),
+    ("2001:20::1", Scope::Orchid, true),
+    ("2001:30::1", Scope::Global, true),
+    ("2001:1ff:ffff::", Scope::ProtocolAssignment, false),
+    ("2001:200::", Scope::Global, true),
+    ("2001:db7:ffff::", Scope::Global, true),
+    ("2001:db8::", Scope::Documentation, false),
+    ("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff", Scope::Documentation, false),
+    ("2001:db9::", Scope::Global, true),
+    ("2002::1", Scope::SixToFour, true),
+    ("2620:4f:8000::1", Scope::Global, true),
+    ("3fff::", Scope::Documentation, false),
+    ("3fff:fff::1", Scope::Documentation, false),
+    ("3fff:1000::", Scope::Global, true),
+    ("5f00::1", Scope::SegmentRouting, false),
+    ("fbff:ffff::", Scope::Global, true),
+    ("fc00::", Scope::UniqueLocal, false),
+    ("fdff:ffff::1", Scope::UniqueLocal, false),
+    ("fe00::", Scope::Global, true),
+    ("fe80::", Scope::LinkLocal, false),
+    ("fe80::1%eth0", Scope::LinkLocal, false),
+    ("febf:ffff::1", Scope::LinkLocal, false),
+    ("fec0::", Scope::Global, true),
+    // IPv6 multicast scopes
+    ("ff01::1", Scope::Multicast(MulticastScope::InterfaceLocal), false),
+    ("ff02::1", Scope::Multicast(MulticastScope::LinkLocal), false),
+    ("ff03::1", Scope::Multicast(MulticastScope::RealmLocal), false),
+    ("ff04::1", Scope::Multicast(MulticastScope::AdminLocal), false),
+    ("ff05::1:3", Scope::Multicast(MulticastScope::SiteLocal), false),
+    ("ff08::1", Scope::Multicast(MulticastScope::OrganizationLocal), false),
+    ("ff0e::101", Scope::Multicast(MulticastScope::Global), true),
+    ("ff3e::8000:1", Scope::Multicast(MulticastScope::Global), true),
+    ("ff12::1", Scope::Multicast(MulticastScope::LinkLocal), false),
+    ("ff00::1", Scope::Multicast(MulticastScope::Other(0)), false),
+    ("ff0f::1", Scope::Multicast(MulticastScope::Other(15)), false),
+];
+
+#[test]
+fn scope_matches_registry_table() {
+    for &(text, scope, global) in CASES {
+        let addr = ip(text);
+        assert_eq!(addr.scope(), scope, "scope of {}", text);
+        assert_eq!(addr.is_global(), global, "is_global of {}", text);
+    }
+}
+
+#[test]
+fn every_registry_block_classifies_its_own_edges() {
+    for special in SPECIAL_PURPOSE_BLOCKS {
+        let network: IpNetwork = special.block.parse().unwrap();
+        for addr in [network.network(), network.last()] {
+            let found = addr
+                .special_purpose_block()
+                .unwrap_or_else(|| panic!("{} from {} has no block", addr, special.block));
+            // either this block, or a more specific one inside it
+            let found_network: IpNetwork = found.block.parse().unwrap();
+            assert!(
+                network.contains_network(&found_network),
+                "{} from {} matched {}",
+                addr,
+                special.block,
+                found.block
+            );
+        }
+    }
+}
+
+#[test]
+fn registry_blocks_are_unique_and_have_no_host_bits() {
+    let networks: Vec<IpNetwork> = SPECIAL_PURPOSE_BLOCKS
+        .iter()
+        .map(|special| special.block.parse().unwrap())
+        .collect();
+    for (i, (network, special)) in networks.iter().zip(SPECIAL_PURPOSE_BLOCKS).enumerate() {
+        let written: IpAddr = special.block.split('/').next().unwrap().parse().unwrap();
+        assert_eq!(
+            network.network(),
+            written,
+            "{} has host bits",
+            special.block
+        );
+        assert!(
+            !networks[i + 1..].contains(network),
+            "{} is listed twice",
+            special.block
+        );
+    }
+}
+
+#[test]
+fn predicates_follow_scope() {
+    assert!(ip("127.0.0.1").is_loopback());
+    assert!(ip("::1").is_loopback());
+    assert!(!ip("::2").is_loopback());
+    assert!(ip("10.1.2.3").is_private());
+    assert!(ip("172.20.0.1").is_private());
+    assert!(ip("192.168.1.1").is_private());
+    assert!(!ip("fd00::1").is_private());
+    assert!(ip("fd00::1").is_unique_local());
+    assert!(ip("100.100.100.100").is_shared());
+    assert!(ip("169.254.1.1").is_link_local());
+    assert!(ip("fe80::1").is_link_local());
+    assert!(ip("224.0.0.1").is_multicast());
+    assert!(ip("ff02::1").is_multicast());
+    assert!(ip("255.255.255.255").is_broadcast());
+    assert!(ip("198.51.100.7").is_documentation());
+    assert!(ip("2001:db8::1").is_documentation());
+    assert!(ip("198.18.0.1").is_benchmarking());
+    assert!(ip("250.0.0.1").is_reserved());
+    assert!(ip("0.0.0.0").is_unspecified());
+    assert!(ip("::").is_unspecified());
+    assert!(ip("::ffff:1.2.3.4").is_ipv4_mapped());
+    assert!(ip("::1.2.3.4").is_ipv4_compatible());
+    assert!(!ip("::ffff:1.2.3.4").is_ipv4_compatible());
+}
+
+#[test]
+fn unicast_global_needs_global_unicast_space() {
+    assert!(ip("8.8.8.8").is_unicast_global());
+    assert!(ip("2606:4700::1111").is_unicast_global());
+    assert!(!ip("4000::1").is_unicast_global());
+    assert!(!ip("ff0e::1").is_unicast_global());
+    assert!(!ip("10.0.0.1").is_unicast_global());
+}
@@ -0,0 +1,189 @@
+//! Socket addresses in text, and converting addresses to and from `std::net`.
+
+mod common;
+
+use std::net::{self, ToSocketAddrs};
+
+use rust_begin::enums::{IpAddr, Ipv4Addr, Ipv6Addr};
+use rust_begin::net::{AddrParseError, ConversionError, SocketAddr, SocketAddrParseError};
+
+use common::Rng;
+
+impl Rng {
+    /// Either kind of address, IPv6 ones with runs of zero groups and sometimes a numeric zone.
+    fn ip(&mut self) -> IpAddr {
+        if self.below(2) == 0 {
+            let [a, b, c, d] = (self.next() as u32).to_be_bytes();
+            return IpAddr::V4(a, b, c, d);
+        }
+        let mut segments = [0; 8];
+        for segment in &mut segments {
+            if self.below(2) == 0 {
+                *segment = self.next() as u16;
+            }
+        }
+        let ip = Ipv6Addr::from_segments(segments);
+        IpAddr::V6(match self.below(4) {
+            0 => ip.with_zone((1 + self.below(1000)).to_string()),
+            _ => ip,
+        })
+    }
+}
+
+fn socket(text: &str) -> SocketAddr {
+    text.parse().unwrap()
+}
+
+fn socket_error(text: &str) -> SocketAddrParseError {
+    text.parse::<SocketAddr>().unwrap_err()
+}
+
+#[test]
+fn parsing_and_printing() {
+    let addr = socket("192.0.2.1:80");
+    assert_eq!(*addr.ip(), IpAddr::V4(192, 0, 2, 1));
+    assert_eq!(addr.port(), 80);
+    assert_eq!(addr.to_string(), "192.0.2.1:80");
+
+    let addr = socket("[2001:DB8:0::1]:8080");
+    assert_eq!(*addr.ip(), IpAddr::V6("2001:db8::1".parse().unwrap()));
+    assert_eq!(addr.port(), 8080);
+    assert_eq!(addr.to_string(), "[2001:db8::1]:8080");
+
+    for text in [
+        "[::]:0",
+        "[::1]:65535",
+        "[fe80::1%eth0]:22",
+        "[::ffff:192.0.2.1]:443",
+        "0.0.0.0:0",
+    ] {
+        assert_eq!(socket(text).to_string(), text);
+    }
+    assert_eq!(socket("[fe80::1%eth0]:22").ip().to_string(), "fe80::1%eth0");
+
+    let mut addr = socket("10.0.0.1:1");
+    addr.set_port(2);
+    addr.set_ip(IpAddr::V6(Ipv6Addr::from_bits(1)));
+    assert_eq!(addr, SocketAddr::new("::1".parse().unwrap(), 2));
+    assert_eq!(addr.to_string(), "[::1]:2");
+
+    // widths and alignment apply to the whole address, port included
+    assert_eq!(format!("{:>14}", socket("10.0.0.1:80")), "   10.0.0.1:80");
+    assert_eq!(format!("{:-<10}|", socket("[::1]:2")), "[::1]:2---|");
+    assert_eq!(format!("{:^11}", socket("[::]:0")), "  [::]:0   ");
+    assert_eq!(
+        format!("{:>20}", socket("[fe80::1%lo]:22")),
+        "     [fe80::1%lo]:22"
+    );
+    assert_eq!(format!("{:3}", socket("1.2.3.4:5")), "1.2.3.4:5");
+}
+
+#[test]
+fn bad_socket_addresses() {
+    use SocketAddrParseError::*;
+    assert_eq!(socket_error("192.0.2.1"), MissingPort);
+    assert_eq!(socket_error("[::1]"), MissingPort);
+    assert_eq!(socket_error("[::1:80"), UnclosedBracket);
+    assert_eq!(socket_error("[::1]80"), UnclosedBracket);
+    assert_eq!(socket_error("::1:80"), UnbracketedIpv6);
+    assert_eq!(
+        socket_error("10.0.0.1:65536"),
+        InvalidPort("65536".to_string())
+    );
+    assert_eq!(socket_error("10.0.0.1:+80"), InvalidPort("+80".to_string()));
+    assert_eq!(socket_error("10.0.0.1:"), InvalidPort(String::new()));
+    assert_eq!(socket_error("[::1]:x"), InvalidPort("x".to_string()));
+    assert_eq!(
+        socket_error("10.0.0:80"),
+        Addr(AddrParseError::OctetCount(3))
+    );
+    assert_eq!(
+        socket_error("[10.0.0.1]:80"),
+        Addr(AddrParseError::GroupCount(2))
+    );
+    assert_eq!(
+        socket_error("::1:80").to_string(),
+        "IPv6 socket addresses need brackets, as in [::1]:8080"
+    );
+}
+
+#[test]
+fn addresses_convert_both_ways() {
+    let ip = Ipv4Addr::new(192, 0, 2, 1);
+    let std_ip = net::Ipv4Addr::from(ip);
+    assert_eq!(std_ip, net::Ipv4Addr::new(192, 0, 2, 1));
+    assert_eq!(Ipv4Addr::from(std_ip), ip);
+
+    let ip: Ipv6Addr = "2001:db8::ff00:42:8329".parse().unwrap();
+    let std_ip = net::Ipv6Addr::try_from(ip.clone()).unwrap();
+    assert_eq!(
+        std_ip,
+        "2001:db8::ff00:42:8329".parse::<net::Ipv6Addr>().unwrap()
+    );
+    assert_eq!(Ipv6Addr::from(std_ip), ip);
+
+    // std has no zones on bare addresses
+    let zoned: Ipv6Addr = "fe80::1%eth0".parse().unwrap();
+    assert_eq!(
+        net::Ipv6Addr::try_from(zoned.clone()),
+        Err(ConversionError::Zone("eth0".to_string()))
+    );
+    assert_eq!(
+        net::IpAddr::try_from(IpAddr::V6(zoned)),
+        Err(ConversionError::Zone("eth0".to_string()))
+    );
+
+    let mut rng = Rng(0x50c7);
+    for _ in 0..2000 {
+        let ip = rng.ip();
+        match net::IpAddr::try_from(ip.clone()) {
+            Ok(std_ip) => {
+                assert_eq!(IpAddr::from(std_ip), ip);
+                assert_eq!(std_ip.to_string(), ip.to_string());
+            }
+            Err(e) => {
+                let IpAddr::V6(v6) = &ip else {
+                    panic!("{} did not convert: {}", ip, e);
+                };
+                assert_eq!(e, ConversionError::Zone(v6.zone().unwrap().to_string()));
+            }
+        }
+    }
+}
+
+#[test]
+fn socket_addresses_convert_both_ways() {
+    let mut rng = Rng(0x50c8);
+    for _ in 0..2000 {
+        let addr = SocketAddr::new(rng.ip(), rng.next() as u16);
+        let std_addr = net::SocketAddr::try_from(addr.clone()).unwrap();
+        assert_eq!(SocketAddr::try_from(std_addr), Ok(addr.clone()));
+        // the two agree on the text too, numeric zones included
+        assert_eq!(std_addr.to_string(), addr.to_string());
+        assert_eq!(std_addr.to_string().parse(), Ok(addr.clone()));
+        assert_eq!(addr.to_string().parse(), Ok(std_addr));
+        assert_eq!(
+            addr.to_socket_addrs().unwrap().collect::<Vec<_>>(),
+            [std_addr]
+        );
+    }
+
+    let std_addr: net::SocketAddr = "[fe80::1%7]:22".parse().unwrap();
+    assert_eq!(SocketAddr::try_from(std_addr), Ok(socket("[fe80::1%7]:22")));
+    let flowing = net::SocketAddr::V6(net::SocketAddrV6::new(net::Ipv6Addr::LOCALHOST, 80, 5, 0));
+    assert_eq!(
+        SocketAddr::try_from(flowing),
+        Err(ConversionError::FlowInfo(5))
+    );
+
+    // named zones, and numbers that would not come back the same, stay on our side
+    for zone in ["eth0", "0", "007"] {
+        let addr = socket(&format!("[fe80::1%{}]:22", zone));
+        assert_eq!(
+            net::SocketAddr::try_from(addr.clone()),
+            Err(ConversionError::Zone(zone.to_string()))
+        );
+        let e = addr.to_socket_addrs().unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,185 @@
+//! `Message::call` against a `DrawState`: what each message does, and when it is refused.
+
+use rust_begin::enums::Message;
+use rust_begin::message::{CallError, DrawState, Outcome, ProgramError};
+use rust_begin::structs::Color;
+
+#[test]
+fn each_message_says_what_it_did() {
+    let mut state = DrawState::new();
+    assert_eq!(
+        Message::Move { x: 3, y: -4 }.call(&mut state),
+        Ok(Outcome::Moved {
+            from: (0, 0),
+            to: (3, -4)
+        })
+    );
+    assert_eq!(
+        Message::Move { x: -3, y: 0 }.call(&mut state),
+        Ok(Outcome::Moved {
+            from: (3, -4),
+            to: (0, -4)
+        })
+    );
+    assert_eq!(
+        Message::Write("hé".to_string()).call(&mut state),
+        Ok(Outcome::Wrote { len: 3 })
+    );
+    assert_eq!(
+        Message::Write(String::new()).call(&mut state),
+        Ok(Outcome::Wrote { len: 0 })
+    );
+    assert_eq!(
+        Message::ChangeColor(255, 0, 128).call(&mut state),
+        Ok(Outcome::ColorChanged {
+            from: Color(0, 0, 0),
+            to: Color(255, 0, 128)
+        })
+    );
+    assert_eq!(Message::Quit.call(&mut state), Ok(Outcome::Stopped));
+    assert_eq!(
+        state,
+        DrawState {
+            cursor: (0, -4),
+            color: Color(255, 0, 128),
+            text: "hé".to_string(),
+            running: false,
+        }
+    );
+}
+
+#[test]
+fn colors_out_of_range() {
+    for (message, component, value) in [
+        (Message::ChangeColor(256, 0, 0), 0, 256),
+        (Message::ChangeColor(0, -1, 0), 1, -1),
+        (Message::ChangeColor(0, 0, i32::MAX), 2, i32::MAX),
+        // the first bad component is the one reported
+        (Message::ChangeColor(0, 300, -5), 1, 300),
+    ] {
+        let mut state = DrawState::new();
+        assert_eq!(
+            message.call(&mut state),
+            Err(CallError::ColorOutOfRange { component, value }),
+            "{}",
+            message
+        );
+        assert_eq!(state, DrawState::new(), "{}", message);
+    }
+    assert_eq!(
+        Message::ChangeColor(0, 256, 0)
+            .call(&mut DrawState::new())
+            .unwrap_err()
+            .to_string(),
+        "green component 256 is outside 0..=255"
+    );
+}
+
+#[test]
+fn the_cursor_cannot_overflow() {
+    let mut state = DrawState::new();
+    Message::Move {
+        x: i32::MAX,
+        y: i32::MIN,
+    }
+    .call(&mut state)
+    .unwrap();
+    for (dx, dy) in [(1, 0), (0, -1), (1, 1), (-1, i32::MIN)] {
+        assert_eq!(
+            Message::Move { x: dx, y: dy }.call(&mut state),
+            Err(CallError::CursorOverflow { dx, dy }),
+            "({}, {})",
+            dx,
+            dy
+        );
+        assert_eq!(state.cursor, (i32::MAX, i32::MIN));
+    }
+    // right up to the edge is fine
+    assert_eq!(
+        Message::Move {
+            x: i32::MIN,
+            y: i32::MAX
+        }
+        .call(&mut state),
+        Ok(Outcome::Moved {
+            from: (i32::MAX, i32::MIN),
+            to: (-1, -1)
+        })
+    );
+    assert_eq!(
+        CallError::CursorOverflow { dx: 1, dy: 2 }.to_string(),
+        "moving by (1, 2) takes the cursor out of range"
+    );
+}
+
+#[test]
+fn nothing_runs_after_quit() {
+    let mut state = DrawState::new();
+    Message::Write("a".to_string()).call(&mut state).unwrap();
+    Message::Quit.call(&mut state).unwrap();
+    let stopped = state.clone();
+    for message in [
+        Message::Quit,
+        Message::Move { x: 1, y: 1 },
+        Message::Write("b".to_string()),
+        Message::ChangeColor(1, 2, 3),
+        // refused for stopping before the bad color is even looked at
+        Message::ChangeColor(-1, 0, 0),
+    ] {
+        assert_eq!(
+            message.call(&mut state),
+            Err(CallError::Stopped),
+            "{}",
+            message
+        );
+    }
+    assert_eq!(state, stopped);
+    assert_eq!(
+        CallError::Stopped.to_string(),
+        "the program has already quit"
+    );
+}
+
+#[test]
+fn running_a_program() {
+    let program = [
+        Message::Move { x: 1, y: 2 },
+        Message::Write("hi".to_string()),
+        Message::ChangeColor(1, 2, 3),
+    ];
+    let mut state = DrawState::new();
+    assert_eq!(
+        state.run(&program),
+        Ok(vec![
+            Outcome::Moved {
+                from: (0, 0),
+                to: (1, 2)
+            },
+            Outcome::Wrote { len: 2 },
+            Outcome::ColorChanged {
+                from: Color(0, 0, 0),
+                to: Color(1, 2, 3)
+            },
+        ])
+    );
+
+    // it stops at the first error; what ran before it stays done
+    let program = [
+        Message::Write("x".to_string()),
+        Message::Quit,
+        Message::Write("y".to_string()),
+        Message::Write("z".to_string()),
+    ];
+    let mut state = DrawState::new();
+    let e = state.run(&program).unwrap_err();
+    assert_eq!(
+        e,
+        ProgramError {
+            index: 2,
+            error: CallError::Stopped
+        }
+    );
+    assert_eq!(e.to_string(), "message 2: the program has already quit");
+    assert_eq!(state.text, "x");
+    assert!(!state.running);
+}
@@ -0,0 +1,112 @@
+//! `UsState`: every state, DC and territory, their codes, and the ways to name them.
+
+use std::collections::HashSet;
+
+use rust_begin::command::Command;
+use rust_begin::enums::{Coin, UsState};
+use rust_begin::states::{Date, ParseStateError, StateKind};
+
+#[test]
+fn fifty_states_and_six_others() {
+    assert_eq!(UsState::ALL.len(), 56);
+    assert_eq!(UsState::states().count(), 50);
+    assert!(UsState::states().all(|state| state.admitted().is_some()));
+    let others: Vec<UsState> = UsState::ALL
+        .into_iter()
+        .filter(|state| state.kind() != StateKind::State)
+        .collect();
+    assert_eq!(others.len(), 6);
+    assert!(others.iter().all(|state| state.admitted().is_none()));
+    assert_eq!(UsState::DistrictOfColumbia.kind(), StateKind::District);
+    assert_eq!(UsState::Guam.kind(), StateKind::Territory);
+
+    // no two share a postal code or a FIPS code
+    let postal: HashSet<&str> = UsState::ALL.iter().map(|s| s.abbreviation()).collect();
+    let fips: HashSet<u8> = UsState::ALL.iter().map(|s| s.fips()).collect();
+    assert_eq!((postal.len(), fips.len()), (56, 56));
+}
+
+#[test]
+fn parsing_names_codes_and_words() {
+    for (text, state) in [
+        ("New York", UsState::NewYork),
+        ("new york", UsState::NewYork),
+        ("NEW YORK", UsState::NewYork),
+        ("new   york", UsState::NewYork),
+        ("  New\tYork ", UsState::NewYork),
+        ("NY", UsState::NewYork),
+        ("ny", UsState::NewYork),
+        ("new_york", UsState::NewYork),
+        ("District of Columbia", UsState::DistrictOfColumbia),
+        ("dc", UsState::DistrictOfColumbia),
+        ("u.s. virgin islands", UsState::UsVirginIslands),
+        ("Alaska", UsState::Alaska),
+    ] {
+        assert_eq!(text.parse(), Ok(state), "{:?}", text);
+    }
+    for text in ["", "newyork", "new-york", "N Y", "Atlantis"] {
+        assert_eq!(
+            text.parse::<UsState>(),
+            Err(ParseStateError {
+                found: text.to_string()
+            })
+        );
+    }
+    assert_eq!(
+        "Atlantis".parse::<UsState>().unwrap_err().to_string(),
+        "no state or territory is called \"Atlantis\""
+    );
+
+    // every state parses back from each of its forms
+    for state in UsState::ALL {
+        assert_eq!(state.name().parse(), Ok(state));
+        assert_eq!(state.abbreviation().parse(), Ok(state));
+        assert_eq!(state.variant_name().parse(), Ok(state));
+        assert_eq!(
+            UsState::from_abbreviation(state.abbreviation()),
+            Some(state)
+        );
+    }
+    assert_eq!(UsState::from_abbreviation("oh"), Some(UsState::Ohio));
+    assert_eq!(UsState::from_abbreviation("Ohio"), None);
+}
+
+#[test]
+fn fips_codes() {
+    assert_eq!(UsState::from_fips(1), Some(UsState::Alabama));
+    assert_eq!(UsState::from_fips(36), Some(UsState::NewYork));
+    assert_eq!(UsState::from_fips(11), Some(UsState::DistrictOfColumbia));
+    assert_eq!(UsState::from_fips(78), Some(UsState::UsVirginIslands));
+    // the codes skip a few numbers
+    assert_eq!(UsState::from_fips(3), None);
+    assert_eq!(UsState::from_fips(0), None);
+    for state in UsState::ALL {
+        assert_eq!(UsState::from_fips(state.fips()), Some(state));
+    }
+}
+
+#[test]
+fn printing() {
+    assert_eq!(UsState::NewYork.to_string(), "New York");
+    assert_eq!(UsState::UsVirginIslands.to_string(), "U.S. Virgin Islands");
+    assert_eq!(UsState::NewYork.capital(), "Albany");
+    assert_eq!(
+        UsState::Delaware.admitted(),
+        Some(Date {
+            year: 1787,
+            month: 12,
+            day: 7
+        })
+    );
+    assert_eq!(
+        UsState::Delaware.admitted().unwrap().to_string(),
+        "1787-12-07"
+    );
+
+    // a quarter keeps its command form, not the name
+    let coin = Coin::Quarter(UsState::NewYork);
+    assert_eq!(coin.to_string(), "quarter new_york");
+    assert_eq!("quarter new_york".parse(), Ok(coin));
+    assert_eq!("quarter NY".parse(), Ok(coin));
+    assert_eq!(Coin::from_bytes(&coin.to_bytes()), Ok(coin));
+}
@@ -0,0 +1,198 @@
+//! The text syntax for `Message`: parsing scripts, where errors point, and printing back.
+
+mod common;
+
+use rust_begin::enums::Message;
+use rust_begin::message::{parse_script, ParseError, ParseErrorKind};
+
+use common::Rng;
+
+impl Rng {
+    fn int(&mut self) -> i32 {
+        match self.below(4) {
+            0 => i32::MIN,
+            1 => i32::MAX,
+            _ => self.next() as i32,
+        }
+    }
+
+    fn text(&mut self) -> String {
+        const CHARS: &[char] = &[
+            'a', 'Z', ' ', '"', '\\', '#', '\n', '\t', '\r', '\0', '\u{7f}', '\u{1b}', 'é', '🦀',
+            '{', '}',
+        ];
+        (0..self.below(12))
+            .map(|_| CHARS[self.below(CHARS.len() as u64) as usize])
+            .collect()
+    }
+}
+
+fn error(line: &str) -> ParseError {
+    line.parse::<Message>().unwrap_err()
+}
+
+/// Where the error is and what it is: column, width and kind.
+fn at(line: &str) -> (usize, usize, ParseErrorKind) {
+    let e = error(line);
+    (e.column, e.width, e.kind)
+}
+
+#[test]
+fn commands_and_scripts() {
+    assert_eq!("quit".parse(), Ok(Message::Quit));
+    assert_eq!(
+        "  move\t3   -4  # up and left".parse(),
+        Ok(Message::Move { x: 3, y: -4 })
+    );
+    assert_eq!("move +3 -0".parse(), Ok(Message::Move { x: 3, y: 0 }));
+    assert_eq!(
+        "color 255 0 128".parse(),
+        Ok(Message::ChangeColor(255, 0, 128))
+    );
+    assert_eq!(
+        r#"write "a \"b\" \\ \n\t\r\0 # \u{1F980}\u{e9}""#.parse(),
+        Ok(Message::Write("a \"b\" \\ \n\t\r\0 # 🦀é".to_string()))
+    );
+    assert_eq!(
+        r##"write "a"# b"##.parse(),
+        Ok(Message::Write("a".to_string()))
+    );
+
+    let script = "# a drawing\n\nmove 1 2\n   # indented comment\nwrite \"x\"\nquit\n";
+    assert_eq!(
+        parse_script(script),
+        Ok(vec![
+            Message::Move { x: 1, y: 2 },
+            Message::Write("x".to_string()),
+            Message::Quit
+        ])
+    );
+    assert_eq!(parse_script(""), Ok(vec![]));
+    let e = parse_script("quit\n\nmove 1\nquit\n").unwrap_err();
+    assert_eq!((e.line, e.column), (3, 7));
+    assert_eq!(e.kind, ParseErrorKind::MissingArgument("y"));
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+    use ParseErrorKind::*;
+    assert_eq!(at(""), (1, 1, Empty));
+    assert_eq!(at("   # nothing"), (13, 1, Empty));
+    assert_eq!(at("jump 1"), (1, 4, UnknownCommand("jump".to_string())));
+    assert_eq!(
+        at(r#""quit""#),
+        (1, 6, UnknownCommand(r#""quit""#.to_string()))
+    );
+    assert_eq!(at("move 3   "), (7, 1, MissingArgument("y")));
+    assert_eq!(at("move 3 x"), (8, 1, InvalidInteger("x".to_string())));
+    assert_eq!(at("move 3 -"), (8, 1, InvalidInteger("-".to_string())));
+    assert_eq!(
+        at(r#"move 3 "4""#),
+        (8, 3, InvalidInteger(r#""4""#.to_string()))
+    );
+    assert_eq!(
+        at("move 3 99999999999"),
+        (8, 11, OutOfRange("99999999999".to_string()))
+    );
+    assert_eq!(at("color 1 2 3 4"), (13, 1, TrailingInput));
+    assert_eq!(at("quit now please"), (6, 3, TrailingInput));
+    assert_eq!(at("write hello"), (7, 5, ExpectedString));
+    assert_eq!(at("write"), (6, 1, MissingArgument("text")));
+    assert_eq!(at(r#"write "abc  "#), (7, 4, UnterminatedString));
+    assert_eq!(at(r#"write "abc\"#), (11, 1, UnterminatedString));
+    // columns count characters, not bytes
+    assert_eq!(
+        at(r#"write "é🦀\q""#),
+        (10, 2, InvalidEscape(r"\q".to_string()))
+    );
+    assert_eq!(
+        at(r#"write "\é""#),
+        (8, 2, InvalidEscape(r"\é".to_string()))
+    );
+}
+
+#[test]
+fn unicode_escapes() {
+    use ParseErrorKind::*;
+    assert_eq!(
+        r#"write "\u{0}\u{10FFFF}""#.parse(),
+        Ok(Message::Write("\0\u{10FFFF}".to_string()))
+    );
+    for (escape, width) in [
+        (r"\u{110000}", 10),
+        (r"\u{D800}", 8),
+        (r"\u{}", 4),
+        (r"\u{1234567}", 11),
+        (r"\u{+41}", 7),
+        (r"\u{-41}", 7),
+        (r"\u{4 1}", 7),
+    ] {
+        let line = format!(r#"write "{}""#, escape);
+        assert_eq!(
+            at(&line),
+            (8, width, InvalidEscape(escape.to_string())),
+            "{}",
+            line
+        );
+    }
+    assert_eq!(
+        at(r#"write "\u41""#),
+        (8, 4, InvalidEscape(r"\u41".to_string()))
+    );
+    // the braces end with the string: the `}` after the closing quote is not part of it
+    assert_eq!(
+        at(r#"write "\u{41" x}""#),
+        (8, 5, InvalidEscape(r"\u{41".to_string()))
+    );
+    assert_eq!(
+        at(r#"write "\u{41"#),
+        (8, 5, InvalidEscape(r"\u{41".to_string()))
+    );
+}
+
+#[test]
+fn messages_and_carets() {
+    let e = parse_script("quit\nmove 3 x\n").unwrap_err();
+    assert_eq!(e.to_string(), "2:8: `x` is not an integer");
+    assert_eq!(e.caret(), "       ^");
+    assert_eq!(error("jump 1").caret(), "^^^^");
+    assert_eq!(error("move 1").caret(), "      ^");
+    assert_eq!(
+        error("jump").to_string(),
+        "1:1: unknown command `jump`, expected move, write, color or quit"
+    );
+    assert_eq!(
+        error("move 1 99999999999").to_string(),
+        "1:8: 99999999999 does not fit in a 32-bit integer"
+    );
+    // the caret sits under the character, however wide its bytes
+    let line = r#"write "ééé\x""#;
+    let e = error(line);
+    let under: String = line.chars().skip(e.column - 1).take(e.width).collect();
+    assert_eq!(under, r"\x");
+    assert_eq!(e.caret(), format!("{}^^", " ".repeat(10)));
+}
+
+#[test]
+fn printing_round_trips() {
+    assert_eq!(Message::Move { x: 3, y: -4 }.to_string(), "move 3 -4");
+    assert_eq!(
+        Message::Write("a \"b\"\n\u{1b}".to_string()).to_string(),
+        r#"write "a \"b\"\n\u{1b}""#
+    );
+    let mut rng = Rng(0x7e47);
+    for _ in 0..2000 {
+        let message = match rng.below(4) {
+            0 => Message::Quit,
+            1 => Message::Move {
+                x: rng.int(),
+                y: rng.int(),
+            },
+            2 => Message::Write(rng.text()),
+            _ => Message::ChangeColor(rng.int(), rng.int(), rng.int()),
+        };
+        let text = message.to_string();
+        assert!(!text.contains('\n'), "{:?}", text);
+        assert_eq!(text.parse(), Ok(message), "{}", text);
+    }
+}
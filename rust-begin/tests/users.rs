@@ -0,0 +1,464 @@
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+use rust_begin::credentials::{Lockout, Params};
+use rust_begin::users::{
+    inspect_store, normalize_username, read_store, repair_store, validate_email, EmailError,
+    FieldError, RegistryError, StoreError, StoreFix, StoreProblem, UserRegistry, UsernameError,
+};
+
+fn invalid(result: Result<impl std::fmt::Debug, RegistryError>) -> Vec<FieldError> {
+    match result {
+        Err(RegistryError::Invalid(errors)) => errors.errors().to_vec(),
+        other => panic!("expected validation errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn usernames() {
+    #[rustfmt::skip]
+    let cases = [
+        ("ferris", Ok("ferris")),
+        ("  Ferris_The.Crab-2 ", Ok("ferris_the.crab-2")),
+        ("abc", Ok("abc")),
+        ("", Err(UsernameError::Empty)),
+        ("   ", Err(UsernameError::Empty)),
+        ("ab", Err(UsernameError::TooShort { min: 3 })),
+        ("a234567890123456789012345678901234", Err(UsernameError::TooLong { max: 32 })),
+        ("fer ris", Err(UsernameError::InvalidChar(' '))),
+        ("fer@ris", Err(UsernameError::InvalidChar('@'))),
+        ("férris", Err(UsernameError::InvalidChar('é'))),
+        ("2ferris", Err(UsernameError::MustStartWithLetter)),
+        ("_ferris", Err(UsernameError::MustStartWithLetter)),
+        ("ferris_", Err(UsernameError::MisplacedSeparator)),
+        ("fer..ris", Err(UsernameError::MisplacedSeparator)),
+        ("fer-_ris", Err(UsernameError::MisplacedSeparator)),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            normalize_username(input),
+            expected.map(str::to_string),
+            "{:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn emails() {
+    let long_local = format!("{}@example.com", "a".repeat(65));
+    let long_label = format!("me@{}.com", "a".repeat(64));
+    let long_email = format!("{}@{}com", "a".repeat(64), "abcdefghi.".repeat(19));
+    #[rustfmt::skip]
+    let cases = [
+        ("ferris@example.com", Ok("ferris@example.com")),
+        (" Ferris@Example.COM ", Ok("Ferris@example.com")),
+        ("first.last+tag@mail.example.co.uk", Ok("first.last+tag@mail.example.co.uk")),
+        ("!#$%&'*+/=?^_`{|}~-@example.com", Ok("!#$%&'*+/=?^_`{|}~-@example.com")),
+        ("me@xn--bcher-kva.example", Ok("me@xn--bcher-kva.example")),
+        ("me@[192.0.2.1]", Ok("me@[192.0.2.1]")),
+        ("me@[IPv6:2001:db8::1]", Ok("me@[ipv6:2001:db8::1]")),
+        ("", Err(EmailError::Empty)),
+        ("ferris.example.com", Err(EmailError::MissingAt)),
+        ("@example.com", Err(EmailError::EmptyLocalPart)),
+        ("ferris@", Err(EmailError::EmptyDomain)),
+        (long_local.as_str(), Err(EmailError::LocalPartTooLong { max: 64 })),
+        (long_email.as_str(), Err(EmailError::TooLong { max: 254 })),
+        ("fer ris@example.com", Err(EmailError::InvalidChar(' '))),
+        ("a@b@example.com", Err(EmailError::InvalidChar('@'))),
+        ("\"ferris\"@example.com", Err(EmailError::InvalidChar('"'))),
+        (".ferris@example.com", Err(EmailError::MisplacedDot)),
+        ("ferris.@example.com", Err(EmailError::MisplacedDot)),
+        ("fer..ris@example.com", Err(EmailError::MisplacedDot)),
+        ("me@exa_mple.com", Err(EmailError::InvalidChar('_'))),
+        ("me@example..com", Err(EmailError::InvalidLabel(String::new()))),
+        ("me@-example.com", Err(EmailError::InvalidLabel("-example".to_string()))),
+        ("me@example.com.", Err(EmailError::InvalidLabel(String::new()))),
+        (long_label.as_str(), Err(EmailError::InvalidLabel("a".repeat(64)))),
+        ("me@example.123", Err(EmailError::InvalidLabel("123".to_string()))),
+        ("me@localhost", Err(EmailError::NoTopLevelDomain)),
+        ("me@[192.0.2.256]", Err(EmailError::InvalidAddressLiteral("192.0.2.256".to_string()))),
+        ("me@[2001:db8::1]", Err(EmailError::InvalidAddressLiteral("2001:db8::1".to_string()))),
+        ("me@[IPv6:fe80::1%eth0]", Err(EmailError::InvalidAddressLiteral("IPv6:fe80::1%eth0".to_string()))),
+    ];
+    for (input, expected) in cases {
+        let email = validate_email(input);
+        assert_eq!(email, expected.map(str::to_string), "{:?}", input);
+        if let Ok(email) = email {
+            assert_eq!(
+                validate_email(&email),
+                Ok(email.clone()),
+                "stored as {:?}",
+                email
+            );
+        }
+    }
+}
+
+#[test]
+fn registering_enforces_unique_usernames_and_emails() {
+    let mut users = UserRegistry::new();
+    let ferris = users.register(" Ferris ", "Ferris@Example.com").unwrap();
+    assert_eq!(ferris.username, "ferris");
+    assert_eq!(ferris.email, "Ferris@example.com");
+    assert!(ferris.active);
+    assert_eq!(ferris.sign_in_count, 1);
+
+    assert_eq!(
+        invalid(users.register("FERRIS", "other@example.com")),
+        [FieldError::Username(UsernameError::Taken)]
+    );
+    assert_eq!(
+        invalid(users.register("corro", "FERRIS@EXAMPLE.COM")),
+        [FieldError::Email(EmailError::Taken)]
+    );
+    assert_eq!(users.len(), 1);
+
+    users.register("corro", "corro@example.com").unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(
+        users.find_by_email("ferris@EXAMPLE.com").unwrap().username,
+        "ferris"
+    );
+    assert_eq!(users.get(" CORRO").unwrap().email, "corro@example.com");
+    assert!(users.get("nobody").is_none());
+    let names: Vec<&str> = users.iter().map(|u| u.username.as_str()).collect();
+    assert_eq!(names, ["corro", "ferris"]);
+}
+
+#[test]
+fn every_failing_field_is_reported() {
+    let mut users = UserRegistry::new();
+    let errors = invalid(users.register("x", "not-an-email"));
+    assert_eq!(
+        errors,
+        [
+            FieldError::Username(UsernameError::TooShort { min: 3 }),
+            FieldError::Email(EmailError::MissingAt),
+        ]
+    );
+    assert_eq!(errors[0].field(), "username");
+    assert_eq!(errors[1].field(), "email");
+    let Err(error) = users.register("x", "not-an-email") else {
+        unreachable!()
+    };
+    assert_eq!(
+        error.to_string(),
+        "invalid user: username is shorter than 3 characters; email has no @"
+    );
+    assert!(users.is_empty());
+
+    users.register("ferris", "ferris@example.com").unwrap();
+    let Err(RegistryError::Invalid(errors)) = users.register("ferris", "ferris@example.com") else {
+        unreachable!()
+    };
+    assert_eq!(errors.username(), Some(&UsernameError::Taken));
+    assert_eq!(errors.email(), Some(&EmailError::Taken));
+}
+
+#[test]
+fn deactivating_and_reactivating() {
+    let mut users = UserRegistry::new();
+    users.register("ferris", "ferris@example.com").unwrap();
+    users.register("corro", "corro@example.com").unwrap();
+
+    users.deactivate("Ferris").unwrap();
+    assert!(!users.get("ferris").unwrap().active);
+    users.deactivate("ferris").unwrap();
+    let active: Vec<&str> = users.active().map(|u| u.username.as_str()).collect();
+    assert_eq!(active, ["corro"]);
+    // an inactive user keeps their username and email
+    assert_eq!(
+        invalid(users.register("ferris", "ferris@example.com")).len(),
+        2
+    );
+
+    users.reactivate("ferris").unwrap();
+    assert!(users.get("ferris").unwrap().active);
+    assert_eq!(users.active().count(), 2);
+    assert_eq!(
+        users.deactivate("nobody"),
+        Err(RegistryError::UnknownUser("nobody".to_string()))
+    );
+}
+
+#[test]
+fn renaming_changing_email_and_removing() {
+    let mut users = UserRegistry::new();
+    users.register("ferris", "ferris@example.com").unwrap();
+    users.register("corro", "corro@example.com").unwrap();
+
+    assert_eq!(
+        invalid(users.rename("ferris", "Corro")),
+        [FieldError::Username(UsernameError::Taken)]
+    );
+    // the same name in another case is still ferris's own
+    users.rename("ferris", "FERRIS").unwrap();
+    let crab = users.rename("ferris", "crab").unwrap();
+    assert_eq!(crab.username, "crab");
+    assert!(users.get("ferris").is_none());
+    assert_eq!(
+        users.find_by_email("ferris@example.com").unwrap().username,
+        "crab"
+    );
+
+    assert_eq!(
+        invalid(users.change_email("crab", "corro@example.com")),
+        [FieldError::Email(EmailError::Taken)]
+    );
+    assert_eq!(
+        invalid(users.change_email("crab", "crab@")),
+        [FieldError::Email(EmailError::EmptyDomain)]
+    );
+    users.change_email("crab", "crab@example.com").unwrap();
+    assert!(users.find_by_email("ferris@example.com").is_none());
+    // the old address is free again
+    users.register("ferris", "ferris@example.com").unwrap();
+
+    let corro = users.remove("CORRO").unwrap();
+    assert_eq!(corro.username, "corro");
+    assert!(users.remove("corro").is_none());
+    users.register("corro", "corro@example.com").unwrap();
+    assert_eq!(
+        users.rename("nobody", "somebody"),
+        Err(RegistryError::UnknownUser("nobody".to_string()))
+    );
+}
+
+fn stored_registry() -> UserRegistry {
+    let lockout = Lockout {
+        threshold: 2,
+        ..Lockout::DEFAULT
+    };
+    let mut users = UserRegistry::new()
+        .with_params(Params::new(4, 1, 1).unwrap())
+        .with_lockout(lockout);
+    users.register("ferris", "Ferris@example.com").unwrap();
+    users.register("corro", "corro@example.com").unwrap();
+    users.register("crab", "crab@example.com").unwrap();
+    users.deactivate("corro").unwrap();
+    users.set_password("ferris", "hunter2hunter2").unwrap();
+    users.set_password("crab", "correct horse").unwrap();
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    users.sign_in("ferris", "hunter2hunter2", now).unwrap();
+    users.sign_in("crab", "wrong", now).unwrap_err();
+    users
+        .sign_in("crab", "wrong", now + Duration::new(1, 5))
+        .unwrap_err();
+    users
+}
+
+#[test]
+fn a_registry_survives_saving_and_loading() {
+    let users = stored_registry();
+    let text = users.to_store();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "version 2");
+    assert_eq!(
+        lines[1],
+        "user username=corro email=corro@example.com sign_in_count=1 active=false"
+    );
+    assert!(lines[2].ends_with(
+        " failed_attempts=2 locked_until=1700000031.000000005 last_failed_sign_in=1700000001.000000005"
+    ));
+    assert!(lines[3].starts_with(
+        "user username=ferris email=Ferris@example.com sign_in_count=2 active=true password=$scrypt$ln=4,r=1,p=1$"
+    ));
+
+    // the cost and lockout are settings, not data
+    let loaded = UserRegistry::from_store(&text)
+        .unwrap()
+        .with_params(users.params())
+        .with_lockout(*users.lockout());
+    assert_eq!(loaded, users);
+    assert_eq!(loaded.to_store(), text);
+}
+
+#[test]
+fn old_stores_are_migrated() {
+    let text = "\
+# written before the count was spelled right
+version 1
+
+user username=ferris email=ferris@example.com sign_in_acount=7 active=true
+user username=Corro email=corro@example.com sign_in_acount=1 active=false
+";
+    let report = read_store(text).unwrap();
+    assert_eq!(report.version, 1);
+    assert!(report.needs_migration());
+    assert_eq!(report.problems, []);
+    let users = UserRegistry::from_store(text).unwrap();
+    assert_eq!(users.get("ferris").unwrap().sign_in_count, 7);
+    assert!(!users.get("corro").unwrap().active);
+    assert!(users
+        .to_store()
+        .contains("user username=corro email=corro@example.com sign_in_count=1 active=false\n"));
+    // the misspelled field means nothing to version 2
+    let Err(StoreError::Parse { line, reason }) = UserRegistry::from_store(
+        "version 2\nuser username=ferris email=ferris@example.com sign_in_acount=7 active=true",
+    ) else {
+        panic!("sign_in_acount is not a field of version 2");
+    };
+    assert_eq!((line, reason.as_str()), (2, "no sign_in_count"));
+}
+
+#[test]
+fn broken_stores_are_reported_line_by_line() {
+    let text = "\
+version 2
+user username=ferris email=ferris@example.com sign_in_count=2 active=true
+user username=Ferris email=other@example.com sign_in_count=1 active=true
+user username=corro email=corro@example sign_in_count=1 active=true
+user username=crab email=crab@example.com sign_in_count=lots active=true
+user username=rusty email=rusty@example.com sign_in_count=1 active=true password=secret
+user username=gopher email=gopher@example.com sign_in_count=1 active=true colour=blue
+group admins
+user username=duke email
+user username=abc email=a@b.com sign_in_count=1 active=true last_sign_in=18446744073709551615.000000000
+";
+    let report = read_store(text).unwrap();
+    let problem = |line, reason: &str, fix| StoreProblem {
+        line,
+        reason: reason.to_string(),
+        fix,
+    };
+    assert_eq!(
+        report.problems,
+        [
+            problem(3, "username is taken", StoreFix::SkipLine),
+            problem(4, "email has a domain without a dot", StoreFix::SkipLine),
+            problem(
+                5,
+                "sign_in_count is not a number: \"lots\"",
+                StoreFix::SkipLine
+            ),
+            problem(
+                6,
+                "not an scrypt password hash: \"secret\"",
+                StoreFix::DropPassword
+            ),
+            problem(
+                7,
+                "unknown field \"colour\"",
+                StoreFix::DropField("colour".to_string())
+            ),
+            problem(8, "unknown entry \"group\"", StoreFix::SkipLine),
+            problem(9, "field \"email\" has no value", StoreFix::SkipLine),
+            problem(
+                10,
+                "a record of signing in but no password",
+                StoreFix::DropPassword
+            ),
+        ]
+    );
+    assert_eq!(
+        report.problems[3].to_string(),
+        "line 6: not an scrypt password hash: \"secret\" (password dropped)"
+    );
+    let names: Vec<&str> = report
+        .registry
+        .iter()
+        .map(|u| u.username.as_str())
+        .collect();
+    assert_eq!(names, ["abc", "ferris", "gopher", "rusty"]);
+    assert!(report.registry.credential("rusty").is_none());
+    // a time past what `SystemTime` holds is wrong, not a panic
+    let far = "\
+version 2
+user username=abc email=a@b.com sign_in_count=1 active=true password=$scrypt$ln=4,r=1,p=1$MDEyMzQ1Njc4OWFiY2RlZg$+sy13Bl5E/O6Nwgy523eXwxrh9SLg6D5JSA7JiFfqHw last_sign_in=18446744073709551615.000000000
+";
+    assert_eq!(
+        read_store(far).unwrap().problems,
+        [problem(
+            2,
+            "last_sign_in is not a time: \"18446744073709551615.000000000\"",
+            StoreFix::DropPassword
+        )]
+    );
+
+    let error = UserRegistry::from_store(text).unwrap_err();
+    assert_eq!(error.to_string(), "user store line 3: username is taken");
+    assert!(matches!(
+        UserRegistry::from_store("version 3\n"),
+        Err(StoreError::UnsupportedVersion(3))
+    ));
+    assert!(matches!(
+        UserRegistry::from_store("# nothing else\n"),
+        Err(StoreError::Parse { line: 1, .. })
+    ));
+    assert!(matches!(
+        UserRegistry::from_store("user username=ferris\n"),
+        Err(StoreError::Parse { line: 1, .. })
+    ));
+    assert_eq!(UserRegistry::from_store("version 2").unwrap().len(), 0);
+}
+
+#[test]
+fn saving_inspecting_and_repairing_files() {
+    let dir = std::env::temp_dir().join(format!("rust-begin-users-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("users.db");
+
+    let users = stored_registry();
+    users.save(&path).unwrap();
+    users.save(&path).unwrap();
+    // the temporary file is renamed into place, so none is left next to the store
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    let loaded = UserRegistry::load(&path).unwrap();
+    assert_eq!(loaded.to_store(), users.to_store());
+    let report = inspect_store(&path).unwrap();
+    assert!(report.is_clean());
+    let report = repair_store(&path).unwrap();
+    assert!(report.is_clean());
+    assert!(!dir.join("users.db.bak").exists());
+
+    // a save that died before the rename, over an old and broken file
+    let old = "\
+version 1
+user username=ferris email=ferris@example.com sign_in_acount=3 active=true
+user username=ferris email=again@example.com sign_in_acount=1 active=true
+";
+    fs::write(&path, old).unwrap();
+    fs::write(dir.join("users.db.tmp"), "version 2\nuser username=").unwrap();
+    fs::write(dir.join("users.db.17-0.tmp"), "version 2\n").unwrap();
+    // files that only look like ours are someone else's
+    let others = [
+        "users.db.bak.old",
+        "users.db.notes.tmp",
+        "users.db.archive.999-0.tmp",
+        "users.db.17-.tmp",
+        "users.db.-0.tmp",
+        "users.db.17-0a.tmp",
+        "users.db.17-0.tmp.old",
+    ];
+    for other in others {
+        fs::write(dir.join(other), "not a temporary file").unwrap();
+    }
+    let report = inspect_store(&path).unwrap();
+    assert_eq!(
+        report.temp_files,
+        [dir.join("users.db.17-0.tmp"), dir.join("users.db.tmp")]
+    );
+    assert_eq!(report.problems.len(), 1);
+    assert!(!report.is_clean());
+
+    let report = repair_store(&path).unwrap();
+    assert_eq!(report.problems.len(), 1);
+    assert!(!dir.join("users.db.tmp").exists());
+    assert!(!dir.join("users.db.17-0.tmp").exists());
+    for other in others {
+        assert!(dir.join(other).exists(), "{}", other);
+    }
+    assert_eq!(fs::read_to_string(dir.join("users.db.bak")).unwrap(), old);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "version 2\nuser username=ferris email=ferris@example.com sign_in_count=3 active=true\n"
+    );
+    assert!(inspect_store(&path).unwrap().is_clean());
+
+    assert!(matches!(
+        UserRegistry::load(dir.join("missing.db")),
+        Err(StoreError::Io(_))
+    ));
+    fs::remove_dir_all(&dir).unwrap();
+}
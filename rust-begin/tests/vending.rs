@@ -0,0 +1,404 @@
+//! Every event in every state of the vending machine, and what sales, refunds and refusals do
+//! to its slots, till and log.
+
+use rust_begin::change::CoinInventory;
+use rust_begin::enums::{Coin, CoinKind, UsState};
+use rust_begin::vending::{
+    Event, Outcome, Sale, State, TransactionKind, VendError, VendingMachine,
+};
+
+const ALABAMA: Coin = Coin::Quarter(UsState::Alabama);
+const ALASKA: Coin = Coin::Quarter(UsState::Alaska);
+
+/// Pretzels too dear for the credit used below, mints at exactly that credit, candy that
+/// needs 15 cents in change (the till has no nickels) and sold-out gum.
+fn machine() -> VendingMachine {
+    VendingMachine::builder()
+        .slot("A1", "Pretzels", 125, 3)
+        .slot("B1", "Mints", 50, 2)
+        .slot("B2", "Gum", 40, 0)
+        .slot("C1", "Candy", 35, 1)
+        .coins(ALABAMA, 2)
+        .coins(Coin::Dime, 2)
+        .reject(CoinKind::Penny)
+        .build()
+}
+
+fn in_state(state: State) -> VendingMachine {
+    let mut machine = machine();
+    match state {
+        State::Idle => {}
+        State::Paying { .. } => {
+            machine.insert(ALASKA).unwrap();
+            machine.insert(ALASKA).unwrap();
+        }
+        State::Service => machine.open_service().unwrap(),
+    }
+    assert_eq!(machine.state(), state);
+    machine
+}
+
+const IDLE: State = State::Idle;
+const PAYING: State = State::Paying { credit: 50 };
+const SERVICE: State = State::Service;
+
+fn select(slot: &str) -> Event {
+    Event::Select(slot.to_string())
+}
+
+fn restock() -> Event {
+    Event::Restock {
+        slot: "B2".to_string(),
+        count: 4,
+    }
+}
+
+fn load() -> Event {
+    Event::Load {
+        coin: Coin::Nickel,
+        count: 3,
+    }
+}
+
+fn mints() -> Outcome {
+    Outcome::Sold(Sale {
+        slot: "B1".to_string(),
+        product: "Mints".to_string(),
+        price: 50,
+        paid: vec![ALASKA, ALASKA],
+        change: vec![],
+    })
+}
+
+fn till_coins() -> CoinInventory {
+    [ALABAMA, ALABAMA, Coin::Dime, Coin::Dime]
+        .into_iter()
+        .collect()
+}
+
+#[test]
+fn every_event_in_every_state() {
+    use VendError::*;
+
+    let sold_out = Err(SoldOut("B2".to_string()));
+    let unknown = Err(UnknownSlot("Z9".to_string()));
+    #[rustfmt::skip]
+    let table: Vec<(State, Event, Result<Outcome, VendError>, State)> = vec![
+        (IDLE, Event::Insert(ALASKA), Ok(Outcome::Credited { credit: 25 }), State::Paying { credit: 25 }),
+        (IDLE, Event::Insert(Coin::Penny), Err(CoinRejected(Coin::Penny)), IDLE),
+        (IDLE, select("B1"), Err(InsufficientCredit { price: 50, credit: 0 }), IDLE),
+        (IDLE, select("B2"), sold_out.clone(), IDLE),
+        (IDLE, select("Z9"), unknown.clone(), IDLE),
+        (IDLE, Event::Refund, Ok(Outcome::Refunded(vec![])), IDLE),
+        (IDLE, Event::OpenService, Ok(Outcome::ServiceOpened), SERVICE),
+        (IDLE, Event::CloseService, Ok(Outcome::ServiceClosed), IDLE),
+        (IDLE, restock(), Err(NotInService), IDLE),
+        (IDLE, load(), Err(NotInService), IDLE),
+        (IDLE, Event::Collect, Err(NotInService), IDLE),
+        (PAYING, Event::Insert(Coin::Dime), Ok(Outcome::Credited { credit: 60 }), State::Paying { credit: 60 }),
+        (PAYING, Event::Insert(Coin::Penny), Err(CoinRejected(Coin::Penny)), PAYING),
+        (PAYING, select("B1"), Ok(mints()), IDLE),
+        (PAYING, select("b1"), Ok(mints()), IDLE),
+        (PAYING, select("A1"), Err(InsufficientCredit { price: 125, credit: 50 }), PAYING),
+        (PAYING, select("C1"), Err(CannotMakeChange { change: 15 }), PAYING),
+        (PAYING, select("B2"), sold_out, PAYING),
+        (PAYING, select("Z9"), unknown, PAYING),
+        (PAYING, Event::Refund, Ok(Outcome::Refunded(vec![ALASKA, ALASKA])), IDLE),
+        (PAYING, Event::OpenService, Err(CreditPending { credit: 50 }), PAYING),
+        (PAYING, Event::CloseService, Ok(Outcome::ServiceClosed), PAYING),
+        (PAYING, restock(), Err(NotInService), PAYING),
+        (PAYING, load(), Err(NotInService), PAYING),
+        (PAYING, Event::Collect, Err(NotInService), PAYING),
+        (SERVICE, Event::Insert(ALASKA), Err(InService), SERVICE),
+        (SERVICE, Event::Insert(Coin::Penny), Err(InService), SERVICE),
+        (SERVICE, select("B1"), Err(InService), SERVICE),
+        (SERVICE, select("Z9"), Err(InService), SERVICE),
+        (SERVICE, Event::Refund, Err(InService), SERVICE),
+        (SERVICE, Event::OpenService, Ok(Outcome::ServiceOpened), SERVICE),
+        (SERVICE, Event::CloseService, Ok(Outcome::ServiceClosed), IDLE),
+        (SERVICE, restock(), Ok(Outcome::Restocked { slot: "B2".to_string(), count: 4 }), SERVICE),
+        (SERVICE, load(), Ok(Outcome::Loaded { coin: Coin::Nickel, count: 3 }), SERVICE),
+        (SERVICE, Event::Collect, Ok(Outcome::Collected(till_coins())), SERVICE),
+    ];
+
+    // every kind of event is tried in every state
+    for state in [IDLE, PAYING, SERVICE] {
+        let tried = table.iter().filter(|(from, ..)| *from == state);
+        let mut kinds: Vec<_> = tried
+            .map(|(_, event, ..)| std::mem::discriminant(event))
+            .collect();
+        kinds.dedup();
+        assert_eq!(kinds.len(), 8, "{:?}", state);
+    }
+
+    for (from, event, expected, to) in table {
+        let mut machine = in_state(from);
+        let before = (machine.slots().to_vec(), machine.till().clone());
+        let result = machine.handle(event.clone());
+        assert_eq!(result, expected, "{:?} in {:?}", event, from);
+        assert_eq!(machine.state(), to, "{:?} in {:?}", event, from);
+        if result.is_err() {
+            assert_eq!(
+                (machine.slots().to_vec(), machine.till().clone()),
+                before,
+                "{:?} in {:?} changed the machine",
+                event,
+                from
+            );
+        }
+    }
+}
+
+#[test]
+fn refunds_give_back_the_very_coins_inserted() {
+    let mut machine = machine();
+    for coin in [ALASKA, Coin::Dime, ALABAMA, Coin::Nickel] {
+        machine.insert(coin).unwrap();
+    }
+    assert_eq!(machine.credit(), 65);
+    // a failed selection does not spend anything
+    assert!(machine.select("A1").is_err());
+    assert_eq!(
+        machine.refund(),
+        Ok(vec![ALASKA, Coin::Dime, ALABAMA, Coin::Nickel])
+    );
+    assert_eq!(machine.state(), State::Idle);
+    assert_eq!(machine.till(), &in_state(State::Idle).till().clone());
+    assert_eq!(machine.refund(), Ok(vec![]));
+}
+
+#[test]
+fn sales_take_the_coins_and_pay_change_from_the_till() {
+    let mut machine = machine();
+    for _ in 0..3 {
+        machine.insert(ALASKA).unwrap();
+    }
+    let sale = machine.select("C1").unwrap_err();
+    assert_eq!(sale, VendError::CannotMakeChange { change: 40 });
+
+    machine.insert(Coin::Dime).unwrap();
+    // 85 cents for 35: a 50 cent change, paid with the till's own quarters first
+    let sale = machine.select("C1").unwrap();
+    assert_eq!(sale.change, vec![ALABAMA, ALABAMA]);
+    assert_eq!(sale.paid, vec![ALASKA, ALASKA, ALASKA, Coin::Dime]);
+    assert_eq!(machine.slot("C1").unwrap().count, 0);
+    assert_eq!(machine.till().count(ALASKA), 3);
+    assert_eq!(machine.till().count(ALABAMA), 0);
+    assert_eq!(machine.till().count(Coin::Dime), 3);
+    assert_eq!(machine.till().total_cents(), 105);
+
+    machine.insert(ALASKA).unwrap();
+    machine.insert(ALASKA).unwrap();
+    assert_eq!(
+        machine.select("C1"),
+        Err(VendError::SoldOut("C1".to_string()))
+    );
+    // the customer's own coins can come back as change
+    let sale = machine.select("B1").unwrap();
+    assert_eq!(sale.change, vec![]);
+}
+
+#[test]
+fn sold_out_slots_sell_again_after_a_restock() {
+    let mut machine = machine();
+    for _ in 0..2 {
+        machine.insert(ALASKA).unwrap();
+        machine.insert(ALASKA).unwrap();
+        machine.select("B1").unwrap();
+    }
+    machine.insert(ALASKA).unwrap();
+    machine.insert(ALASKA).unwrap();
+    assert_eq!(
+        machine.select("B1"),
+        Err(VendError::SoldOut("B1".to_string()))
+    );
+    assert_eq!(
+        machine.open_service(),
+        Err(VendError::CreditPending { credit: 50 })
+    );
+    let refund = machine.refund().unwrap();
+
+    machine.open_service().unwrap();
+    machine.restock("b1", 1).unwrap();
+    machine.close_service().unwrap();
+    for coin in refund {
+        machine.insert(coin).unwrap();
+    }
+    assert_eq!(
+        machine.select("B1").map(|sale| sale.product),
+        Ok("Mints".to_string())
+    );
+    assert_eq!(machine.slot("B1").unwrap().count, 0);
+}
+
+#[test]
+fn a_slot_cannot_overflow() {
+    let mut machine = machine();
+    machine.open_service().unwrap();
+    let count = machine.slot("B1").unwrap().count;
+    let logged = machine.log().len();
+    machine.restock("B1", u32::MAX - count).unwrap();
+    assert_eq!(
+        machine.restock("b1", 1),
+        Err(VendError::SlotFull {
+            slot: "B1".to_string(),
+            count: 1
+        })
+    );
+    assert_eq!(machine.slot("B1").unwrap().count, u32::MAX);
+    assert_eq!(machine.log().len(), logged + 1);
+    assert_eq!(
+        machine.restock("B1", 7).unwrap_err().to_string(),
+        "slot B1 cannot take 7 more"
+    );
+}
+
+#[test]
+fn the_till_cannot_overflow() {
+    let mut machine = machine();
+    machine.open_service().unwrap();
+    machine.load(Coin::Dime, u32::MAX - 2).unwrap();
+    let logged = machine.log().len();
+    assert_eq!(
+        machine.load(Coin::Dime, 1),
+        Err(VendError::TillFull {
+            coin: Coin::Dime,
+            count: 1
+        })
+    );
+    assert_eq!(machine.till().count(Coin::Dime), u32::MAX);
+    assert_eq!(machine.log().len(), logged);
+    assert_eq!(
+        machine.load(Coin::Dime, 7).unwrap_err().to_string(),
+        "the till cannot take 7 more dime"
+    );
+
+    // a sale that would put one more dime in the till is refused, and the credit kept
+    machine.close_service().unwrap();
+    for coin in [ALASKA, ALASKA, Coin::Dime] {
+        machine.insert(coin).unwrap();
+    }
+    assert_eq!(
+        machine.select("B1"),
+        Err(VendError::TillFull {
+            coin: Coin::Dime,
+            count: 1
+        })
+    );
+    assert_eq!(machine.state(), State::Paying { credit: 60 });
+    assert_eq!(machine.refund(), Ok(vec![ALASKA, ALASKA, Coin::Dime]));
+
+    // collecting hands the coins over counted, however many there are
+    machine.open_service().unwrap();
+    let collected = machine.collect().unwrap();
+    assert_eq!(collected.count(Coin::Dime), u32::MAX);
+    assert_eq!(collected.count(ALABAMA), 2);
+    assert!(machine.till().is_empty());
+    assert_eq!(
+        machine.log().last().unwrap().to_string(),
+        format!(
+            "{:>4}  collected [2 x quarter alabama, {} x dime] ($429496730.00)",
+            machine.log().len(),
+            u32::MAX
+        )
+    );
+}
+
+#[test]
+fn exact_change_only_until_coins_are_loaded() {
+    let mut machine = machine();
+    machine.insert(ALASKA).unwrap();
+    machine.insert(ALASKA).unwrap();
+    assert_eq!(
+        machine.select("C1"),
+        Err(VendError::CannotMakeChange { change: 15 })
+    );
+    let refund = machine.refund().unwrap();
+
+    machine.open_service().unwrap();
+    machine.load(Coin::Nickel, 1).unwrap();
+    machine.close_service().unwrap();
+    for coin in refund {
+        machine.insert(coin).unwrap();
+    }
+    let sale = machine.select("C1").unwrap();
+    assert_eq!(sale.change, vec![Coin::Dime, Coin::Nickel]);
+}
+
+#[test]
+fn collecting_empties_the_till() {
+    let mut machine = in_state(State::Service);
+    assert_eq!(machine.collect(), Ok(till_coins()));
+    assert_eq!(machine.till(), &CoinInventory::new());
+    assert_eq!(machine.collect(), Ok(CoinInventory::new()));
+    machine.close_service().unwrap();
+
+    // nothing to give change from but the customer's coins
+    machine.insert(ALASKA).unwrap();
+    machine.insert(Coin::Dime).unwrap();
+    machine.insert(Coin::Dime).unwrap();
+    assert_eq!(machine.select("C1").unwrap().change, vec![Coin::Dime]);
+}
+
+#[test]
+fn everything_goes_in_the_log() {
+    let mut machine = machine();
+    machine.insert(ALASKA).unwrap();
+    machine.insert(ALASKA).unwrap();
+    machine.select("Z9").unwrap_err();
+    machine.select("B1").unwrap();
+    machine.insert(Coin::Dime).unwrap();
+    machine.insert(Coin::Penny).unwrap_err();
+    machine.refund().unwrap();
+    machine.refund().unwrap();
+    machine.open_service().unwrap();
+    machine.select("B1").unwrap_err();
+    machine.restock("B2", 4).unwrap();
+    machine.load(Coin::Nickel, 3).unwrap();
+    machine.collect().unwrap();
+
+    let kinds: Vec<&TransactionKind> = machine.log().iter().map(|t| &t.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            &TransactionKind::Refused {
+                slot: "Z9".to_string(),
+                error: VendError::UnknownSlot("Z9".to_string())
+            },
+            &TransactionKind::Sale(match mints() {
+                Outcome::Sold(sale) => sale,
+                _ => unreachable!(),
+            }),
+            &TransactionKind::Refund(vec![Coin::Dime]),
+            &TransactionKind::Restock {
+                slot: "B2".to_string(),
+                count: 4
+            },
+            &TransactionKind::Load {
+                coin: Coin::Nickel,
+                count: 3
+            },
+            &TransactionKind::Collect(
+                [
+                    ALABAMA,
+                    ALABAMA,
+                    Coin::Dime,
+                    Coin::Dime,
+                    ALASKA,
+                    ALASKA,
+                    Coin::Nickel,
+                    Coin::Nickel,
+                    Coin::Nickel
+                ]
+                .into_iter()
+                .collect()
+            ),
+        ]
+    );
+    let seqs: Vec<u64> = machine.log().iter().map(|t| t.seq).collect();
+    assert_eq!(seqs, [1, 2, 3, 4, 5, 6]);
+    assert_eq!(
+        machine.log()[1].to_string(),
+        "   2  sold Mints from B1 for $0.50, paid [quarter alaska, quarter alaska] ($0.50), change [] ($0.00)"
+    );
+}
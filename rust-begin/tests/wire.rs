@@ -0,0 +1,242 @@
+//! Round trips of the `Message` wire format, and decoding of random, malformed and truncated
+//! input.
+
+mod common;
+
+use rust_begin::enums::Message;
+use rust_begin::message::wire::{
+    decode, encode, DecodeError, Frame, MessageReader, MessageWriter, WireError,
+};
+
+use common::Rng;
+
+impl Rng {
+    /// Mostly small numbers, with the extremes and varint boundaries mixed in.
+    fn int(&mut self) -> i32 {
+        match self.below(4) {
+            0 => [0, -1, 1, 63, -64, 64, -65, i32::MIN, i32::MAX][self.below(9) as usize],
+            1 => self.below(256) as i32 - 128,
+            _ => self.next() as i32,
+        }
+    }
+
+    fn text(&mut self) -> String {
+        let pieces = [
+            "",
+            "a",
+            "hello",
+            " ",
+            "é",
+            "日本",
+            "🦀",
+            "\0",
+            "\"quoted\"",
+            "\n",
+        ];
+        (0..self.below(8))
+            .map(|_| pieces[self.below(pieces.len() as u64) as usize])
+            .collect()
+    }
+
+    fn message(&mut self) -> Message {
+        match self.below(4) {
+            0 => Message::Quit,
+            1 => Message::Move {
+                x: self.int(),
+                y: self.int(),
+            },
+            2 => Message::Write(self.text()),
+            _ => Message::ChangeColor(self.int(), self.int(), self.int()),
+        }
+    }
+
+    fn bytes(&mut self, max: u64) -> Vec<u8> {
+        (0..self.below(max + 1))
+            .map(|_| self.next() as u8)
+            .collect()
+    }
+}
+
+fn stream(messages: &[Message]) -> Vec<u8> {
+    let mut writer = MessageWriter::new(Vec::new()).unwrap();
+    for message in messages {
+        writer.write(message).unwrap();
+    }
+    writer.into_inner()
+}
+
+fn read_all(bytes: &[u8]) -> Result<Vec<Message>, WireError> {
+    MessageReader::new(bytes)?.collect()
+}
+
+#[test]
+fn known_encodings() {
+    assert_eq!(encode(&Message::Quit), [0]);
+    assert_eq!(encode(&Message::Move { x: 3, y: -4 }), [1, 6, 7]);
+    assert_eq!(
+        encode(&Message::Write("hi".to_string())),
+        [2, 2, b'h', b'i']
+    );
+    assert_eq!(
+        encode(&Message::ChangeColor(255, 0, 128)),
+        [3, 0xfe, 0x03, 0, 0x80, 0x02]
+    );
+    assert_eq!(
+        encode(&Message::Move {
+            x: i32::MIN,
+            y: i32::MAX
+        }),
+        [1, 0xff, 0xff, 0xff, 0xff, 0x0f, 0xfe, 0xff, 0xff, 0xff, 0x0f]
+    );
+    assert_eq!(stream(&[Message::Quit]), [b'R', b'B', b'M', 1, 1, 0]);
+}
+
+#[test]
+fn random_messages_round_trip() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..20_000 {
+        let message = rng.message();
+        let body = encode(&message);
+        assert_eq!(
+            decode(&body),
+            Ok(Frame::Message(message.clone())),
+            "{:?}",
+            body
+        );
+    }
+}
+
+#[test]
+fn random_streams_round_trip() {
+    let mut rng = Rng(42);
+    for _ in 0..500 {
+        let messages: Vec<Message> = (0..rng.below(20)).map(|_| rng.message()).collect();
+        assert_eq!(read_all(&stream(&messages)).unwrap(), messages);
+    }
+}
+
+#[test]
+fn every_truncated_body_is_an_error() {
+    let mut rng = Rng(7);
+    for _ in 0..2_000 {
+        let body = encode(&rng.message());
+        for len in 0..body.len() {
+            assert_eq!(
+                decode(&body[..len]),
+                Err(DecodeError::Truncated),
+                "{:?} cut to {}",
+                body,
+                len
+            );
+        }
+    }
+}
+
+#[test]
+fn every_truncated_stream_fails_or_ends_between_frames() {
+    let mut rng = Rng(11);
+    for _ in 0..200 {
+        let messages: Vec<Message> = (1..=rng.below(6) + 1).map(|_| rng.message()).collect();
+        let bytes = stream(&messages);
+        // where each complete frame ends, and how many messages come before it
+        let mut boundaries = vec![(4, 0)];
+        for (i, message) in messages.iter().enumerate() {
+            let len = stream(&messages[..=i]).len();
+            assert!(len > boundaries.last().unwrap().0, "{:?}", message);
+            boundaries.push((len, i + 1));
+        }
+        for len in 0..bytes.len() {
+            let cut = &bytes[..len];
+            match boundaries.iter().find(|&&(end, _)| end == len) {
+                Some(&(_, count)) => assert_eq!(read_all(cut).unwrap(), messages[..count]),
+                None => assert!(read_all(cut).is_err(), "{:?} cut to {}", bytes, len),
+            }
+        }
+    }
+}
+
+#[test]
+fn random_bytes_never_panic() {
+    let mut rng = Rng(0xdead_beef);
+    for _ in 0..50_000 {
+        let body = rng.bytes(24);
+        if let Ok(Frame::Message(message)) = decode(&body) {
+            // overlong varints decode too, so compare messages rather than bytes
+            assert_eq!(decode(&encode(&message)), Ok(Frame::Message(message)));
+        }
+        let mut bytes = b"RBM\x01".to_vec();
+        bytes.extend_from_slice(&body);
+        let _ = read_all(&bytes);
+    }
+}
+
+#[test]
+fn malformed_input_is_reported() {
+    assert_eq!(decode(&[]), Err(DecodeError::Truncated));
+    assert_eq!(decode(&[0x80]), Err(DecodeError::Truncated));
+    assert_eq!(decode(&[0xff; 11]), Err(DecodeError::VarintOverflow));
+    assert_eq!(
+        decode(&[1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]),
+        Err(DecodeError::VarintOverflow)
+    );
+    assert_eq!(
+        decode(&[1, 0x80, 0x80, 0x80, 0x80, 0x10, 0]),
+        Err(DecodeError::OutOfRange(1 << 31))
+    );
+    assert_eq!(decode(&[2, 2, 0xc3, 0x28]), Err(DecodeError::InvalidUtf8));
+    assert_eq!(decode(&[2, 5, b'a']), Err(DecodeError::Truncated));
+    assert_eq!(
+        decode(&[2, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        Err(DecodeError::Truncated)
+    );
+
+    assert!(matches!(
+        MessageReader::new(&b"XYZ\x01"[..]),
+        Err(WireError::BadMagic(_))
+    ));
+    assert!(matches!(
+        MessageReader::new(&b"RBM\x00"[..]),
+        Err(WireError::UnsupportedVersion(0))
+    ));
+    assert!(matches!(
+        MessageReader::new(&b"RB"[..]),
+        Err(WireError::Io(_))
+    ));
+    assert!(matches!(
+        read_all(b"RBM\x01\xff\xff\xff\xff\x7f"),
+        Err(WireError::Decode(DecodeError::FrameTooLarge(_)))
+    ));
+}
+
+#[test]
+fn unknown_variants_and_extra_fields_are_skipped() {
+    // version 2 stream: an unknown tag 9 between two messages, and a Move with a third field
+    let bytes = b"RBM\x02\x01\x00\x04\x09\xaa\xbb\xcc\x04\x01\x02\x04\x06\x01\x00";
+    let mut reader = MessageReader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.version(), 2);
+    assert_eq!(
+        reader.read_frame().unwrap(),
+        Some(Frame::Message(Message::Quit))
+    );
+    assert_eq!(
+        reader.read_frame().unwrap(),
+        Some(Frame::Unknown {
+            tag: 9,
+            body: vec![0xaa, 0xbb, 0xcc]
+        })
+    );
+    assert_eq!(
+        reader.read_frame().unwrap(),
+        Some(Frame::Message(Message::Move { x: 1, y: 2 }))
+    );
+    assert_eq!(
+        reader.read_frame().unwrap(),
+        Some(Frame::Message(Message::Quit))
+    );
+    assert_eq!(reader.read_frame().unwrap(), None);
+
+    assert_eq!(
+        read_all(bytes).unwrap(),
+        [Message::Quit, Message::Move { x: 1, y: 2 }, Message::Quit]
+    );
+}